@@ -0,0 +1,30 @@
+//! Benchmarks of rendering tables and wrapped text.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use mycli::output::{display_width, Columns, DetailView, Wrap};
+
+fn columns(c: &mut Criterion) {
+    let names: Vec<String> = (0..500).map(|i| format!("file-{i}.txt")).collect();
+    c.bench_function("columns 500 items", |b| b.iter(|| Columns::new().width(120).render(black_box(&names))));
+}
+
+fn detail(c: &mut Criterion) {
+    c.bench_function("detail view 20 fields", |b| {
+        b.iter(|| {
+            (0..20)
+                .fold(DetailView::new().width(80), |view, i| view.field(format!("field {i}"), "some value"))
+                .render()
+        })
+    });
+}
+
+fn text(c: &mut Criterion) {
+    let paragraph = "Lorem ipsum dolor sit amet, 日本語のテキスト, consectetur adipiscing elit. ".repeat(40);
+    c.bench_function("display width", |b| b.iter(|| display_width(black_box(&paragraph))));
+    c.bench_function("wrap 80 columns", |b| b.iter(|| Wrap::new().width(80).render(black_box(&paragraph))));
+}
+
+criterion_group!(benches, columns, detail, text);
+criterion_main!(benches);
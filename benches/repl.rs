@@ -0,0 +1,62 @@
+//! Benchmarks of the line-processing path: tokenizing, dispatch through a
+//! REPL and history lookups.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use mycli::output::{BufferSink, Output};
+use mycli::repl::{tokenize, CommandHandler, Context, LazyHistory, Repl};
+use rustyline::history::{History, SearchDirection};
+
+struct Count(usize);
+
+impl CommandHandler for Count {
+    fn handle(&mut self, command: &str, _: &Context) -> bool {
+        self.0 += tokenize(command).count();
+        true
+    }
+}
+
+fn repl() -> Repl<Count> {
+    Repl::new("> ", Count(0)).unwrap().with_output(Output::new(BufferSink::new()))
+}
+
+fn tokenizer(c: &mut Criterion) {
+    let line = r#"deploy --env production --tag "release 2024-06" 'web-01' web-02 web-03"#;
+    c.bench_function("tokenize", |b| b.iter(|| tokenize(black_box(line)).count()));
+}
+
+fn dispatch(c: &mut Criterion) {
+    let script = "status\nlist users --limit 10\n\nquery 'select 1'\n".repeat(250);
+    c.bench_function("run_batch 1000 lines", |b| {
+        b.iter_batched(repl, |mut repl| repl.run_batch(black_box(script.as_bytes())).unwrap(), BatchSize::SmallInput)
+    });
+}
+
+fn history(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history");
+    let mut text = String::from("#V2\n");
+    for i in 0..100_000 {
+        text.push_str(&format!("select * from table_{i} where id = {i}\n"));
+    }
+    std::fs::write(&path, text).unwrap();
+    let config = rustyline::Config::builder().max_history_size(100_000).unwrap().build();
+
+    c.bench_function("history load 100k", |b| {
+        b.iter(|| {
+            let mut history = LazyHistory::with_config(&config);
+            history.load(&path).unwrap();
+            history.len()
+        })
+    });
+
+    let mut history = LazyHistory::with_config(&config);
+    history.load(&path).unwrap();
+    c.bench_function("history reverse search 100k", |b| {
+        b.iter(|| history.search(black_box("table_42 "), history.len() - 1, SearchDirection::Reverse).unwrap().map(|r| r.idx))
+    });
+}
+
+criterion_group!(benches, tokenizer, dispatch, history);
+criterion_main!(benches);
@@ -0,0 +1,18 @@
+//! Records the version of the compiler building the crate, which plugins
+//! must share with the program that loads them.
+
+use std::env;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=RUSTC");
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=MYCLI_RUSTC_VERSION={version}");
+}
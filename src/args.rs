@@ -0,0 +1,160 @@
+//! Checking command lines against their [`clap`] definitions.
+
+use std::ops::Range;
+
+use clap::{Arg, Command};
+
+use crate::locale::text;
+use crate::output::Diagnostic;
+use crate::suggest::closest;
+
+/// Checks the flags, values and subcommands in `line` against `command`,
+/// and returns a [`Diagnostic`] suggesting the nearest correct spelling
+/// for the first one that is wrong.
+///
+/// The first word of `line` is the command's own name. Words are split at
+/// whitespace and checked as follows:
+///
+/// * `--name` and `--name=value` must be long flags of the command;
+/// * values of arguments with [possible values](Arg::get_possible_values),
+///   given after their flag, as `name=value` or as positional arguments,
+///   must be one of them;
+/// * a command with subcommands but no positional arguments must be given
+///   one of its subcommands, whose arguments are then checked in turn.
+///
+/// Short flags and words after `--` are not checked. Lines that pass may
+/// still be rejected by clap, for instance for a missing argument.
+///
+/// # Examples
+///
+/// ```
+/// use clap::{Arg, ArgAction, Command};
+///
+/// let deploy = Command::new("deploy")
+///     .arg(Arg::new("env").long("env").value_parser(["staging", "production"]))
+///     .arg(Arg::new("force").long("force").action(ArgAction::SetTrue));
+///
+/// let error = mycli::args::check(&deploy, "deploy --forze").unwrap_err();
+/// assert_eq!(error.message(), "unknown flag `--forze`");
+/// assert_eq!(
+///     error.to_string(),
+///     "Error: unknown flag `--forze`\n \
+///      | deploy --forze\n \
+///      |        ^^^^^^^ not a flag of `deploy`\n \
+///      = help: did you mean `--force`?"
+/// );
+///
+/// let error = mycli::args::check(&deploy, "deploy env=stagin").unwrap_err();
+/// assert!(error.to_string().ends_with("= help: did you mean `staging`?"));
+///
+/// assert!(mycli::args::check(&deploy, "deploy --env production --force").is_ok());
+/// ```
+pub fn check(command: &Command, line: &str) -> Result<(), Diagnostic> {
+    let mut command = command.clone();
+    command.build();
+    let mut current = &command;
+    let mut position = 0;
+    let mut words = words(line).skip(1).peekable();
+    while let Some((span, word)) = words.next() {
+        if word == "--" {
+            break;
+        }
+        if let Some(flag) = word.strip_prefix("--") {
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some((span.end - value.len()..span.end, value))),
+                None => (flag, None),
+            };
+            let Some(arg) = find_long(current, name) else {
+                return Err(unknown_flag(current, name, line, span.start..span.start + 2 + name.len()));
+            };
+            if !arg.get_action().takes_values() {
+                continue;
+            }
+            let value = match value {
+                Some(value) => Some(value),
+                None => words.next_if(|(_, next)| !next.starts_with('-')),
+            };
+            if let Some((span, value)) = value {
+                check_value(arg, &format!("--{name}"), value, line, span)?;
+            }
+        } else if word.starts_with('-') {
+            continue;
+        } else if let Some((name, value)) = word.split_once('=')
+            && let Some(arg) = find_long(current, name).filter(|arg| arg.get_action().takes_values())
+        {
+            check_value(arg, name, value, line, span.end - value.len()..span.end)?;
+        } else if let Some(subcommand) = current.find_subcommand(word) {
+            current = subcommand;
+            position = 0;
+        } else if current.has_subcommands() && current.get_positionals().next().is_none() {
+            return Err(unknown_subcommand(current, word, line, span));
+        } else {
+            if let Some(arg) = current.get_positionals().nth(position) {
+                check_value(arg, arg.get_id().as_str(), word, line, span)?;
+            }
+            position += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the argument of `command` with the long flag or alias `name`.
+fn find_long<'a>(command: &'a Command, name: &str) -> Option<&'a Arg> {
+    command.get_arguments().find(|arg| {
+        arg.get_long() == Some(name) || arg.get_all_aliases().is_some_and(|aliases| aliases.contains(&name))
+    })
+}
+
+/// Checks that `value` is one of the possible values of `arg`, if it has
+/// any.
+fn check_value(arg: &Arg, name: &str, value: &str, line: &str, span: Range<usize>) -> Result<(), Diagnostic> {
+    let possible = arg.get_possible_values();
+    if possible.is_empty() || possible.iter().any(|p| p.matches(value, arg.is_ignore_case_set())) {
+        return Ok(());
+    }
+    let known: Vec<&str> = possible.iter().filter(|p| !p.is_hide_set()).map(|p| p.get_name()).collect();
+    let error = Diagnostic::new(text("args.invalid_value", &[("value", &value), ("name", &name)]), line, span)
+        .label(text("args.expected", &[("known", &list(&known))]));
+    Err(match closest(value, &known) {
+        Some(guess) => error.help(text("did-you-mean", &[("guess", &guess)])),
+        None => error,
+    })
+}
+
+fn unknown_flag(command: &Command, name: &str, line: &str, span: Range<usize>) -> Diagnostic {
+    let known: Vec<&str> = command.get_arguments().filter(|arg| !arg.is_hide_set()).filter_map(Arg::get_long).collect();
+    let error = Diagnostic::new(text("args.unknown_flag", &[("name", &name)]), line, span)
+        .label(text("args.not_a_flag", &[("command", &command.get_name())]));
+    match closest(name, &known) {
+        Some(guess) => error.help(text("did-you-mean", &[("guess", &format!("--{guess}"))])),
+        None => error,
+    }
+}
+
+fn unknown_subcommand(command: &Command, name: &str, line: &str, span: Range<usize>) -> Diagnostic {
+    let known: Vec<&str> = command.get_subcommands().filter(|c| !c.is_hide_set()).map(Command::get_name).collect();
+    let error = Diagnostic::new(text("args.unknown_subcommand", &[("name", &name)]), line, span)
+        .label(text("args.not_a_subcommand", &[("command", &command.get_name())]));
+    match closest(name, &known) {
+        Some(guess) => error.help(text("did-you-mean", &[("guess", &guess)])),
+        None => error.help(text("args.expected", &[("known", &list(&known))])),
+    }
+}
+
+/// Splits `line` at whitespace, with the bytes each word covers.
+fn words(line: &str) -> impl Iterator<Item = (Range<usize>, &str)> {
+    line.split_whitespace().map(move |word| {
+        let start = word.as_ptr() as usize - line.as_ptr() as usize;
+        (start..start + word.len(), word)
+    })
+}
+
+fn list(names: &[&str]) -> String {
+    match names {
+        [only] => format!("`{only}`"),
+        _ => {
+            let names = names.iter().map(|n| format!("`{n}`")).collect::<Vec<_>>().join(", ");
+            text("args.one_of", &[("names", &names)])
+        }
+    }
+}
@@ -0,0 +1,381 @@
+//! An audit log of the commands run, for compliance records.
+//!
+//! Unlike a transcript, which captures what a session printed, an
+//! [`AuditLog`] records one entry per command: when it ran, who ran it,
+//! the command line after alias expansion, with secrets masked by the
+//! current [redactor](crate::redact), and its result code, `0` when it
+//! succeeded and `1` when it wrote error output. Entries are JSON objects,
+//! written one per line to a file or writer, or sent to the local syslog
+//! daemon on Unix.
+//!
+//! Entries are chained: each one ends with `prev`, the hash of the entry
+//! before it, and `hash`, the SHA-256 hash of the entry's own text up to
+//! and including `prev`, in lowercase hexadecimal. The first entry follows
+//! [`GENESIS`]. Changing, removing or reordering entries in a file breaks
+//! the chain, which [`verify`] detects. Removing entries from the end
+//! cannot be detected from the file alone; keep the
+//! [`last_hash`](AuditLog::last_hash) somewhere else to check it against.
+//!
+//! Give the log to a REPL with `Repl::with_audit_log`. The user defaults to
+//! the one running the program; for remote sessions, give each REPL a
+//! clone naming the client with [`AuditLog::with_user`].
+//!
+//! # Examples
+//!
+//! ```
+//! use mycli::audit::AuditLog;
+//!
+//! let path = std::env::temp_dir().join("mycli-audit-example.log");
+//! # let _ = std::fs::remove_file(&path);
+//! let log = AuditLog::file(&path)?.with_user("alice");
+//! log.record("deploy web", 0)?;
+//!
+//! let entry = std::fs::read_to_string(&path)?;
+//! assert!(entry.starts_with(r#"{"timestamp":""#));
+//! assert!(entry.contains(r#""user":"alice","command":"deploy web","code":0,"prev":"000000"#));
+//!
+//! let verified = mycli::audit::verify_file(&path)?;
+//! assert_eq!(verified.entries, 1);
+//! assert_eq!(verified.last_hash, log.last_hash());
+//! # std::fs::remove_file(&path)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fmt::{self, Write as _};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sha2::{Digest, Sha256};
+
+/// The `prev` hash of the first entry in a log: 64 zeros.
+pub const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+enum Target {
+    Writer(Box<dyn Write + Send>),
+    #[cfg(unix)]
+    Syslog { socket: std::os::unix::net::UnixDatagram, ident: String },
+}
+
+struct Chain {
+    target: Target,
+    // The hash of the last entry written, or `GENESIS`.
+    last: String,
+}
+
+/// A cloneable handle to an audit log; clones write to the same place and
+/// continue the same chain.
+#[derive(Clone)]
+pub struct AuditLog {
+    chain: Arc<Mutex<Chain>>,
+    user: String,
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog").field("user", &self.user).finish_non_exhaustive()
+    }
+}
+
+impl AuditLog {
+    /// Appends entries to the file at `path`, created if needed. On Unix,
+    /// a new file is only accessible to the user running the program.
+    ///
+    /// The chain continues from the last entry already in the file, or
+    /// starts at [`GENESIS`] if it has no chained entries.
+    pub fn file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let file = options.open(path)?;
+        let mut last = None;
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                last = Some(line);
+            }
+        }
+        let log = Self::writer(file);
+        match last.as_deref().and_then(split_entry) {
+            Some((_, _, hash)) => Ok(log.chained_to(hash)),
+            None => Ok(log),
+        }
+    }
+
+    /// Writes entries to `writer`, flushing after each one.
+    pub fn writer(writer: impl Write + Send + 'static) -> Self {
+        Self::with_target(Target::Writer(Box::new(writer)))
+    }
+
+    /// Sends entries to the local syslog daemon, through `/dev/log`, as
+    /// `authpriv.info` messages tagged `ident`.
+    #[cfg(unix)]
+    pub fn syslog(ident: &str) -> io::Result<Self> {
+        let socket = std::os::unix::net::UnixDatagram::unbound()?;
+        socket.connect("/dev/log")?;
+        Ok(Self::with_target(Target::Syslog { socket, ident: ident.to_string() }))
+    }
+
+    fn with_target(target: Target) -> Self {
+        let user = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_else(|_| "unknown".into());
+        Self { chain: Arc::new(Mutex::new(Chain { target, last: GENESIS.to_string() })), user }
+    }
+
+    /// Continues the chain from the entry whose hash is `hash`, such as
+    /// the last entry of the previous file when logs are rotated.
+    pub fn chained_to(self, hash: impl Into<String>) -> Self {
+        self.lock().last = hash.into();
+        self
+    }
+
+    /// Returns the hash of the last entry recorded, or [`GENESIS`] if
+    /// there is none yet.
+    pub fn last_hash(&self) -> String {
+        self.lock().last.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Chain> {
+        self.chain.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Records entries as run by `user` instead of the user running the
+    /// program, such as the peer of a remote session.
+    pub fn with_user(mut self, user: impl Into<String>) -> Self {
+        self.user = user.into();
+        self
+    }
+
+    /// Returns the user entries are recorded for.
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// Records that `command` ran now with the result `code`. REPLs given
+    /// the log call this for every command.
+    pub fn record(&self, command: &str, code: i32) -> io::Result<()> {
+        let mut chain = self.lock();
+        let mut entry = String::new();
+        let _ = write!(
+            entry,
+            "{{\"timestamp\":{},\"user\":{},\"command\":{},\"code\":{code},\"prev\":\"{}\"",
+            quote(&timestamp(SystemTime::now())),
+            quote(&self.user),
+            quote(&crate::redact::current().redact(command)),
+            chain.last
+        );
+        let hash = hash(&entry);
+        let _ = write!(entry, ",\"hash\":\"{hash}\"}}");
+        let Chain { target, last } = &mut *chain;
+        let written = match target {
+            Target::Writer(writer) => {
+                entry.push('\n');
+                writer.write_all(entry.as_bytes())?;
+                writer.flush()
+            }
+            #[cfg(unix)]
+            Target::Syslog { socket, ident } => {
+                // Facility authpriv (10), severity info (6).
+                let message = format!("<86>{ident}[{}]: {entry}", std::process::id());
+                socket.send(message.as_bytes()).map(|_| ())
+            }
+        };
+        // Entries that could not be written are not part of the chain.
+        if written.is_ok() {
+            *last = hash;
+        }
+        written
+    }
+}
+
+/// What [`verify`] found in an intact log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verified {
+    /// The number of entries.
+    pub entries: usize,
+    /// The `prev` hash of the first entry: [`GENESIS`] for a log that was
+    /// started afresh, or the last hash of the log it continues.
+    pub first_previous: String,
+    /// The hash of the last entry, to compare with one kept elsewhere.
+    pub last_hash: String,
+}
+
+/// Why [`verify`] rejected a log.
+#[derive(Debug)]
+pub enum VerifyError {
+    /// The log could not be read.
+    Io(io::Error),
+    /// A line is not a chained entry.
+    Malformed {
+        /// The line, starting at 1.
+        line: usize,
+    },
+    /// An entry does not match its hash: it was changed.
+    Altered {
+        /// The line, starting at 1.
+        line: usize,
+    },
+    /// An entry does not follow the one before it: entries were removed,
+    /// added or reordered before it.
+    Broken {
+        /// The line, starting at 1.
+        line: usize,
+    },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Io(err) => write!(f, "cannot read the audit log: {err}"),
+            VerifyError::Malformed { line } => write!(f, "line {line} is not a chained audit entry"),
+            VerifyError::Altered { line } => write!(f, "the entry on line {line} was altered"),
+            VerifyError::Broken { line } => write!(f, "the chain is broken before line {line}"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VerifyError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for VerifyError {
+    fn from(err: io::Error) -> Self {
+        VerifyError::Io(err)
+    }
+}
+
+/// Checks that every entry read from `input` matches its hash and follows
+/// the entry before it. Blank lines are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use std::sync::{Arc, Mutex};
+/// use mycli::audit::{self, AuditLog, VerifyError};
+///
+/// #[derive(Clone, Default)]
+/// struct Shared(Arc<Mutex<Vec<u8>>>);
+///
+/// impl std::io::Write for Shared {
+///     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+///         self.0.lock().unwrap().write(buf)
+///     }
+///     fn flush(&mut self) -> std::io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let buffer = Shared::default();
+/// let log = AuditLog::writer(buffer.clone()).with_user("alice");
+/// log.record("status", 0)?;
+/// log.record("restart web", 0)?;
+/// let text = String::from_utf8(buffer.0.lock().unwrap().clone())?;
+///
+/// assert_eq!(audit::verify(Cursor::new(&text))?.entries, 2);
+///
+/// let tampered = text.replace("restart web", "status");
+/// assert!(matches!(audit::verify(Cursor::new(&tampered)), Err(VerifyError::Altered { line: 2 })));
+///
+/// let second = text.lines().nth(1).unwrap();
+/// assert!(matches!(audit::verify(Cursor::new(second)), Ok(v) if v.first_previous != audit::GENESIS));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn verify(input: impl BufRead) -> Result<Verified, VerifyError> {
+    let mut verified = Verified { entries: 0, first_previous: GENESIS.to_string(), last_hash: GENESIS.to_string() };
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        let number = i + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (body, prev, recorded) = split_entry(&line).ok_or(VerifyError::Malformed { line: number })?;
+        if hash(body) != recorded {
+            return Err(VerifyError::Altered { line: number });
+        }
+        if verified.entries == 0 {
+            verified.first_previous = prev.to_string();
+        } else if prev != verified.last_hash {
+            return Err(VerifyError::Broken { line: number });
+        }
+        verified.entries += 1;
+        verified.last_hash = recorded.to_string();
+    }
+    Ok(verified)
+}
+
+/// Checks the log file at `path` as [`verify`] does.
+pub fn verify_file(path: impl AsRef<Path>) -> Result<Verified, VerifyError> {
+    verify(BufReader::new(File::open(path)?))
+}
+
+/// Splits an entry into the text its hash covers, its `prev` hash and its
+/// `hash`.
+fn split_entry(line: &str) -> Option<(&str, &str, &str)> {
+    let (body, hash) = line.trim_end().strip_suffix("\"}")?.rsplit_once(",\"hash\":\"")?;
+    let prev = body.strip_suffix('"')?.rsplit_once(",\"prev\":\"")?.1;
+    let is_hash = |s: &str| s.len() == 64 && s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+    (is_hash(prev) && is_hash(hash)).then_some((body, prev, hash))
+}
+
+/// Returns the SHA-256 hash of `text` in lowercase hexadecimal.
+fn hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes()).iter().fold(String::with_capacity(64), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+/// Formats `time` as an RFC 3339 timestamp in UTC, with milliseconds.
+fn timestamp(time: SystemTime) -> String {
+    let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since.as_secs();
+    let (days, secs) = ((secs / 86_400) as i64, secs % 86_400);
+    // Days since the epoch to a civil date, after Howard Hinnant's
+    // `civil_from_days`.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        since.subsec_millis()
+    )
+}
+
+/// Returns `s` as a JSON string.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
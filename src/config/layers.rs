@@ -0,0 +1,250 @@
+//! Configuration assembled from several sources with recorded origins.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use super::{Config, ConfigError};
+use crate::dirs::AppDirs;
+
+/// Where a configuration value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// The application's built-in defaults.
+    Default,
+    /// The system-wide configuration file.
+    System(PathBuf),
+    /// The user's configuration file.
+    User(PathBuf),
+    /// Another configuration file added with [`ConfigLayers::file`].
+    File(PathBuf),
+    /// An environment variable.
+    Env(String),
+    /// A call to [`ConfigLayers::set`], typically from a command-line flag.
+    Explicit,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => f.write_str("default"),
+            Origin::System(path) => write!(f, "system file {}", path.display()),
+            Origin::User(path) => write!(f, "user file {}", path.display()),
+            Origin::File(path) => write!(f, "file {}", path.display()),
+            Origin::Env(name) => write!(f, "environment variable {name}"),
+            Origin::Explicit => f.write_str("command line"),
+        }
+    }
+}
+
+/// Builds a [`Config`] from layered sources, later layers overriding earlier
+/// ones.
+///
+/// The usual order is built-in defaults, the system file, the user file,
+/// environment variables and finally explicit settings such as flags. Each
+/// method adds a layer; call them in increasing order of precedence.
+/// Missing files are skipped, but files that exist and do not parse are
+/// errors.
+///
+/// Environment variables are named after the dotted keys of [`Config::set`]
+/// in uppercase with `_` in place of `.`, after a prefix: with the prefix
+/// `MYAPP`, `MYAPP_PROMPT` sets `prompt`, `MYAPP_HISTORY_MAX_SIZE` sets
+/// `history.max_size`, `MYAPP_COLORS_ACCENT` sets `colors.accent` and
+/// `MYAPP_ALIASES_LL` sets `aliases.ll`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mycli::config::{Config, ConfigLayers, Origin};
+///
+/// let resolved = ConfigLayers::new("myapp")
+///     .defaults(Config::parse("prompt = \"myapp> \"")?)
+///     .system()?
+///     .user()?
+///     .env("MYAPP")?
+///     .set("history.max_size", "100")?
+///     .resolve();
+///
+/// assert_eq!(resolved.origin("history.max_size"), Some(&Origin::Explicit));
+/// print!("{}", resolved.show(true));
+/// # Ok::<(), mycli::config::ConfigError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConfigLayers {
+    name: String,
+    layers: Vec<(Config, Origin)>,
+}
+
+impl ConfigLayers {
+    /// Starts resolving the configuration of the application `name`, which
+    /// names its configuration files.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            layers: Vec::new(),
+        }
+    }
+
+    /// Adds the application's built-in defaults.
+    pub fn defaults(mut self, config: Config) -> Self {
+        self.layers.push((config, Origin::Default));
+        self
+    }
+
+    /// Adds the system-wide file, `/etc/<name>/<name>.toml` on Unix and
+    /// `%PROGRAMDATA%\<name>\<name>.toml` on Windows.
+    pub fn system(self) -> Result<Self, ConfigError> {
+        let dir = if cfg!(windows) {
+            std::env::var_os("PROGRAMDATA").map(PathBuf::from)
+        } else {
+            Some(PathBuf::from("/etc"))
+        };
+        match dir {
+            Some(dir) => {
+                let path = dir.join(&self.name).join(format!("{}.toml", self.name));
+                self.layer_file(path.clone(), Origin::System(path))
+            }
+            None => Ok(self),
+        }
+    }
+
+    /// Adds the user's file, `<config dir>/<name>/<name>.toml`, where the
+    /// config directory is `$XDG_CONFIG_HOME`, `~/.config` or `%APPDATA%`.
+    pub fn user(self) -> Result<Self, ConfigError> {
+        match AppDirs::new(&self.name).config_dir() {
+            Some(dir) => {
+                let path = dir.join(format!("{}.toml", self.name));
+                self.layer_file(path.clone(), Origin::User(path))
+            }
+            None => Ok(self),
+        }
+    }
+
+    /// Adds the file at `path`, such as one named by a `--config` flag.
+    pub fn file(self, path: impl Into<PathBuf>) -> Result<Self, ConfigError> {
+        let path = path.into();
+        self.layer_file(path.clone(), Origin::File(path))
+    }
+
+    /// Adds the environment variables starting with `prefix` and `_`.
+    /// Variables that do not name a setting are ignored, but invalid values
+    /// for known settings are errors.
+    pub fn env(mut self, prefix: &str) -> Result<Self, ConfigError> {
+        let prefix = format!("{prefix}_");
+        let mut vars: Vec<(String, String)> = std::env::vars()
+            .filter(|(name, _)| name.starts_with(&prefix))
+            .collect();
+        vars.sort();
+        for (name, value) in vars {
+            let Some(key) = env_key(&name[prefix.len()..]) else {
+                continue;
+            };
+            let mut config = Config::default();
+            config.set(&key, &value)?;
+            self.layers.push((config, Origin::Env(name)));
+        }
+        Ok(self)
+    }
+
+    /// Sets one value by its dotted key, overriding all other layers added
+    /// before it.
+    pub fn set(mut self, key: &str, value: &str) -> Result<Self, ConfigError> {
+        let mut config = Config::default();
+        config.set(key, value)?;
+        self.layers.push((config, Origin::Explicit));
+        Ok(self)
+    }
+
+    /// Merges the layers into the final configuration.
+    pub fn resolve(self) -> Resolved {
+        let mut config = Config::default();
+        let mut origins = BTreeMap::new();
+        for (layer, origin) in self.layers {
+            for (key, _) in layer.entries() {
+                origins.insert(key, origin.clone());
+            }
+            config.merge(layer);
+        }
+        Resolved { config, origins }
+    }
+
+    fn layer_file(mut self, path: PathBuf, origin: Origin) -> Result<Self, ConfigError> {
+        match Config::load(&path) {
+            Ok(config) => self.layers.push((config, origin)),
+            Err(ConfigError::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        Ok(self)
+    }
+}
+
+/// Maps the part of an environment variable name after the prefix to a
+/// dotted key.
+fn env_key(rest: &str) -> Option<String> {
+    let lower = rest.to_ascii_lowercase();
+    if lower == "prompt" || lower == "timing" || lower == "report_time" || lower == "dry_run" || lower == "command_timeout" || lower == "title" || lower == "theme" {
+        return Some(lower);
+    }
+    for section in ["history", "colors", "aliases", "abbreviations", "snippets", "keybindings", "variables", "connection"] {
+        if let Some(name) = lower.strip_prefix(section).and_then(|r| r.strip_prefix('_')) {
+            if name.is_empty() {
+                return None;
+            }
+            // Key bindings are written `ctrl-l`, which cannot appear in a
+            // variable name.
+            let name = if section == "keybindings" { name.replace('_', "-") } else { name.to_string() };
+            return Some(format!("{section}.{name}"));
+        }
+    }
+    None
+}
+
+/// A configuration together with where each of its values came from.
+#[derive(Debug, Clone, Default)]
+pub struct Resolved {
+    /// The merged configuration.
+    pub config: Config,
+    origins: BTreeMap<String, Origin>,
+}
+
+impl Resolved {
+    /// Returns where the value of the dotted key `key` came from, or `None`
+    /// if it is not set.
+    pub fn origin(&self, key: &str) -> Option<&Origin> {
+        self.origins.get(key)
+    }
+
+    /// Returns the configuration with the profile `name` applied, as
+    /// [`Config::profile`] does, keeping track of where the profile's values
+    /// came from. Use it for a `--profile` flag.
+    pub fn profile(&self, name: &str) -> Result<Resolved, ConfigError> {
+        let config = self.config.profile(name)?;
+        let prefix = format!("profiles.{name}.");
+        let mut origins: BTreeMap<String, Origin> =
+            self.origins.iter().filter(|(key, _)| !key.starts_with("profiles.")).map(|(k, v)| (k.clone(), v.clone())).collect();
+        for (key, origin) in &self.origins {
+            if let Some(key) = key.strip_prefix(&prefix) {
+                origins.insert(key.to_string(), origin.clone());
+            }
+        }
+        Ok(Resolved { config, origins })
+    }
+
+    /// Renders the configuration as `key = value` lines, each followed by
+    /// its origin as a comment when `with_origin` is set, for a
+    /// `config show --origin` command.
+    pub fn show(&self, with_origin: bool) -> String {
+        let entries = self.config.entries();
+        let width = entries.iter().map(|(k, v)| k.len() + v.len() + 3).max().unwrap_or(0);
+        let mut out = String::new();
+        for (key, value) in entries {
+            let line = format!("{key} = {value}");
+            match self.origins.get(&key).filter(|_| with_origin) {
+                Some(origin) => out.push_str(&format!("{line:width$}  # {origin}\n")),
+                None => out.push_str(&format!("{line}\n")),
+            }
+        }
+        out
+    }
+}
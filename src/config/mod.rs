@@ -0,0 +1,716 @@
+//! REPL settings loaded from a TOML file.
+//!
+//! A configuration file lets users change the prompt, colors, history
+//! behaviour, aliases, abbreviations, snippets and key bindings without
+//! recompiling:
+//!
+//! ```toml
+//! prompt = "db> "
+//! timing = true
+//! report_time = 5
+//! dry_run = false
+//! command_timeout = 60
+//! title = "db"
+//! theme = "high-contrast"
+//!
+//! [colors]
+//! accent = "bold cyan"
+//! error = "bright-red"
+//!
+//! [history]
+//! file = "~/.myapp_history"
+//! max_size = 5000
+//! ignore_duplicates = true
+//! ignore_space = true
+//!
+//! [aliases]
+//! ll = "list --long"
+//!
+//! [abbreviations]
+//! k = "kubectl"
+//!
+//! [snippets]
+//! deploy = "deploy <env:staging> <version>"
+//!
+//! [keybindings]
+//! ctrl-l = "clear-screen"
+//! alt-s = "insert:status"
+//!
+//! [profiles.staging]
+//! prompt = "staging> "
+//! history.file = "~/.myapp_staging_history"
+//! variables.region = "eu-west-1"
+//! connection.url = "https://staging.example.com"
+//! connection.timeout = 30
+//! ```
+//!
+//! Pass the loaded [`Config`] to [`Repl::with_config`] to apply it. Each
+//! `[profiles.<name>]` section holds settings that replace the top-level
+//! ones while that profile is in use; see [`Config::profile`] and
+//! [`Repl::use_profile`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use mycli::config::Config;
+//! use mycli::repl::{CommandHandler, Context, Repl};
+//!
+//! struct App;
+//! impl CommandHandler for App {
+//!     fn handle(&mut self, command: &str, _: &Context) -> bool { command != "quit" }
+//! }
+//!
+//! let config = Config::find("myapp")?.unwrap_or_default();
+//! let mut repl = Repl::new("myapp> ", App)?.with_config(&config)?;
+//! repl.run()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Repl::with_config`]: crate::repl::Repl::with_config
+//! [`Repl::use_profile`]: crate::repl::Repl::use_profile
+
+mod layers;
+mod schema;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::dirs::{self, AppDirs};
+use crate::locale::text;
+use crate::output::theme::{Style, Theme, Tone};
+#[cfg(feature = "rustyline")]
+use crate::repl::keys;
+
+pub use layers::{ConfigLayers, Origin, Resolved};
+pub use schema::{Diagnostic, Severity};
+
+/// Settings read from a configuration file.
+///
+/// Every field is optional; anything left out keeps the application's own
+/// setting.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The prompt shown before each line.
+    pub prompt: Option<String>,
+    /// Whether the time each command took is printed after it.
+    pub timing: Option<bool>,
+    /// How many seconds a command must take for the time it took to be
+    /// printed after it; see
+    /// [`Repl::with_report_time`](crate::repl::Repl::with_report_time).
+    /// `0` for never.
+    pub report_time: Option<u64>,
+    /// Whether commands only describe what they would do; see
+    /// [`DryRun`](crate::repl::DryRun).
+    pub dry_run: Option<bool>,
+    /// How many seconds commands may run before they are cancelled; see
+    /// [`Repl::with_timeout`](crate::repl::Repl::with_timeout). `0` for no
+    /// limit.
+    pub command_timeout: Option<u64>,
+    /// The window title, usually the application's name; the profile in use
+    /// and the running command are added to it. Empty for none.
+    pub title: Option<String>,
+    /// The built-in [theme](crate::output::theme) to start from, such as
+    /// `deuteranopia`, `high-contrast` or `monochrome`.
+    pub theme: Option<String>,
+    /// Styles for each output tone, such as `accent = "bold cyan"`,
+    /// replacing those of the theme.
+    pub colors: BTreeMap<String, String>,
+    /// How commands are recorded in the history. Ignored without the
+    /// `rustyline` feature, which keeps no history.
+    pub history: HistoryConfig,
+    /// Words that are replaced by a command line when they start a line.
+    pub aliases: BTreeMap<String, String>,
+    /// Words that are replaced by their expansion as soon as space is
+    /// typed after them at the start of a line, such as `k = "kubectl"`.
+    /// Unlike aliases, the expansion is shown and can be edited before it
+    /// runs. Ignored without the `rustyline` feature.
+    pub abbreviations: BTreeMap<String, String>,
+    /// Command templates inserted with the `snippet` built-in, whose
+    /// fields are written `<name>` or `<name:default>`. Ignored without the
+    /// `rustyline` feature.
+    pub snippets: BTreeMap<String, String>,
+    /// Editing actions bound to keys, such as `ctrl-l = "clear-screen"`.
+    /// Ignored, and not checked, without the `rustyline` feature.
+    pub keybindings: BTreeMap<String, String>,
+    /// Values the application makes available to its commands, such as a
+    /// default region.
+    pub variables: BTreeMap<String, String>,
+    /// How the application connects to its service, such as a URL and
+    /// timeout. The framework does not interpret these.
+    pub connection: BTreeMap<String, toml::Value>,
+    /// Named sets of settings that replace the ones above while in use.
+    pub profiles: BTreeMap<String, Config>,
+}
+
+/// The `[history]` section of a [`Config`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    /// The file history is loaded from at startup and saved to on exit. A
+    /// leading `~` stands for the home directory.
+    pub file: Option<PathBuf>,
+    /// The maximum number of entries kept. Also accepted as the deprecated
+    /// `size`.
+    #[serde(alias = "size")]
+    pub max_size: Option<usize>,
+    /// Whether a line repeating the previous entry is left out. Also
+    /// accepted as the deprecated `ignore_dups`.
+    #[serde(alias = "ignore_dups")]
+    pub ignore_duplicates: Option<bool>,
+    /// Whether lines starting with a space are left out.
+    pub ignore_space: Option<bool>,
+}
+
+/// An error loading a configuration file.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file could not be read.
+    Io {
+        /// The file being read.
+        path: PathBuf,
+        /// The underlying error.
+        source: io::Error,
+    },
+    /// The file is not valid TOML or has unexpected keys or types.
+    Parse {
+        /// The file being parsed, if the text came from a file.
+        path: Option<PathBuf>,
+        /// The parser's description of the problem.
+        message: String,
+    },
+    /// The file does not match the settings the framework knows, such as
+    /// an unknown key or a value of the wrong type.
+    Schema {
+        /// The file being checked, if the text came from a file.
+        path: Option<PathBuf>,
+        /// Every problem found, errors and warnings alike.
+        diagnostics: Vec<Diagnostic>,
+    },
+    /// A value has the right type but cannot be used, such as an unknown
+    /// color.
+    Invalid {
+        /// The dotted key of the value, such as `colors.accent`.
+        key: String,
+        /// Why the value was rejected.
+        message: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => write!(f, "cannot read {}: {source}", path.display()),
+            ConfigError::Parse { path: Some(path), message } => write!(f, "{}: {message}", path.display()),
+            ConfigError::Parse { path: None, message } => f.write_str(message),
+            ConfigError::Schema { path, diagnostics } => {
+                let errors = diagnostics.iter().filter(|d| d.severity == Severity::Error);
+                for (i, diagnostic) in errors.enumerate() {
+                    if i > 0 {
+                        f.write_str("\n")?;
+                    }
+                    if let Some(path) = path {
+                        write!(f, "{}:", path.display())?;
+                    }
+                    write!(f, "{diagnostic}")?;
+                }
+                Ok(())
+            }
+            ConfigError::Invalid { key, message } => f.write_str(&text("config.invalid_value", &[("key", key), ("message", message)])),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl Config {
+    /// Parses and checks configuration text.
+    ///
+    /// Unknown keys, values of the wrong type and unusable values are all
+    /// reported together in [`ConfigError::Schema`], each with its line and
+    /// column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::config::Config;
+    ///
+    /// let config = Config::parse("prompt = \"db> \"\n[aliases]\nll = \"list --long\"").unwrap();
+    /// assert_eq!(config.prompt.as_deref(), Some("db> "));
+    /// assert_eq!(config.aliases["ll"], "list --long");
+    ///
+    /// let err = Config::parse("[colors]\naccent = \"sparkly\"\n[history]\nmax_sise = 10").unwrap_err();
+    /// assert_eq!(err.to_string(), "\
+    /// 2:1: error: invalid value for `colors.accent`: unknown color `sparkly`
+    /// 4:1: error: unknown setting `history.max_sise`; did you mean `max_size`?");
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        Self::parse_with_warnings(text).map(|(config, _)| config)
+    }
+
+    /// Parses and checks configuration text like [`parse`](Config::parse),
+    /// also returning warnings about settings that still work but should be
+    /// changed, such as deprecated keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::config::Config;
+    ///
+    /// let (config, warnings) = Config::parse_with_warnings("[history]\nsize = 100").unwrap();
+    /// assert_eq!(config.history.max_size, Some(100));
+    /// assert_eq!(warnings[0].to_string(), "2:1: warning: `history.size` is deprecated; use `max_size` instead");
+    /// ```
+    pub fn parse_with_warnings(text: &str) -> Result<(Self, Vec<Diagnostic>), ConfigError> {
+        let parse_error = |message: String| ConfigError::Parse { path: None, message: message.trim_end().to_string() };
+        let doc = toml_edit::Document::parse(text).map_err(|err| parse_error(err.to_string()))?;
+        let diagnostics = schema::check(&doc, text);
+        if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+            return Err(ConfigError::Schema { path: None, diagnostics });
+        }
+        let config = toml::from_str(text).map_err(|err| parse_error(err.to_string()))?;
+        Ok((config, diagnostics))
+    }
+
+    /// Checks configuration text and returns every problem found, for a
+    /// `config check` command. Text that is not valid TOML yields a single
+    /// error describing the syntax problem.
+    pub fn validate(text: &str) -> Vec<Diagnostic> {
+        schema::validate(text)
+    }
+
+    /// Reads and checks the configuration file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::load_with_warnings(path).map(|(config, _)| config)
+    }
+
+    /// Reads and checks the configuration file at `path`, also returning
+    /// warnings as [`parse_with_warnings`](Config::parse_with_warnings) does.
+    pub fn load_with_warnings(path: impl AsRef<Path>) -> Result<(Self, Vec<Diagnostic>), ConfigError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::parse_with_warnings(&text).map_err(|err| match err {
+            ConfigError::Parse { message, .. } => ConfigError::Parse {
+                path: Some(path.to_path_buf()),
+                message,
+            },
+            ConfigError::Schema { diagnostics, .. } => ConfigError::Schema {
+                path: Some(path.to_path_buf()),
+                diagnostics,
+            },
+            err => err,
+        })
+    }
+
+    /// Loads the first of `./<name>.toml` and `<config dir>/<name>/<name>.toml`
+    /// that exists, where the config directory is `$XDG_CONFIG_HOME`,
+    /// `~/.config` or `%APPDATA%`. Returns `None` when neither exists.
+    pub fn find(name: &str) -> Result<Option<Self>, ConfigError> {
+        let file = format!("{name}.toml");
+        let mut candidates = vec![PathBuf::from(&file)];
+        if let Some(dir) = AppDirs::new(name).config_dir() {
+            candidates.push(dir.join(&file));
+        }
+        match candidates.into_iter().find(|path| path.is_file()) {
+            Some(path) => Self::load(path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns `base` with the configured colors applied, or the configured
+    /// theme with them applied if one is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::config::Config;
+    /// use mycli::output::theme::{Theme, Tone};
+    ///
+    /// let config = Config::parse("theme = \"monochrome\"\ncolors.accent = \"underline\"").unwrap();
+    /// let theme = config.theme(Theme::default()).unwrap();
+    /// assert_eq!(theme.style(Tone::Muted), Theme::monochrome().style(Tone::Muted));
+    /// assert_eq!(theme.paint(Tone::Accent, "title"), "\x1b[4mtitle\x1b[0m");
+    /// ```
+    pub fn theme(&self, base: Theme) -> Result<Theme, ConfigError> {
+        let mut theme = match &self.theme {
+            Some(name) => name
+                .parse()
+                .map_err(|message| ConfigError::Invalid { key: "theme".to_string(), message })?,
+            None => base,
+        };
+        for (name, spec) in &self.colors {
+            let key = || format!("colors.{name}");
+            let tone: Tone = name.parse().map_err(|message| ConfigError::Invalid { key: key(), message })?;
+            let style: Style = spec.parse().map_err(|message| ConfigError::Invalid { key: key(), message })?;
+            theme.set(tone, style);
+        }
+        Ok(theme)
+    }
+
+    /// Returns the history file with a leading `~` expanded.
+    pub fn history_file(&self) -> Option<PathBuf> {
+        self.history.file.as_deref().map(dirs::expand_home)
+    }
+
+    /// Overlays `other` on this configuration: values set in `other` replace
+    /// these, and its colors, aliases, abbreviations, snippets and key
+    /// bindings are added entry by entry.
+    pub fn merge(&mut self, other: Config) {
+        if other.prompt.is_some() {
+            self.prompt = other.prompt;
+        }
+        if other.timing.is_some() {
+            self.timing = other.timing;
+        }
+        if other.report_time.is_some() {
+            self.report_time = other.report_time;
+        }
+        if other.dry_run.is_some() {
+            self.dry_run = other.dry_run;
+        }
+        if other.command_timeout.is_some() {
+            self.command_timeout = other.command_timeout;
+        }
+        if other.title.is_some() {
+            self.title = other.title;
+        }
+        if other.theme.is_some() {
+            self.theme = other.theme;
+        }
+        self.colors.extend(other.colors);
+        let history = other.history;
+        if history.file.is_some() {
+            self.history.file = history.file;
+        }
+        if history.max_size.is_some() {
+            self.history.max_size = history.max_size;
+        }
+        if history.ignore_duplicates.is_some() {
+            self.history.ignore_duplicates = history.ignore_duplicates;
+        }
+        if history.ignore_space.is_some() {
+            self.history.ignore_space = history.ignore_space;
+        }
+        self.aliases.extend(other.aliases);
+        self.abbreviations.extend(other.abbreviations);
+        self.snippets.extend(other.snippets);
+        self.keybindings.extend(other.keybindings);
+        self.variables.extend(other.variables);
+        self.connection.extend(other.connection);
+        for (name, profile) in other.profiles {
+            self.profiles.entry(name).or_default().merge(profile);
+        }
+    }
+
+    /// Returns these settings with those of the profile `name` applied on
+    /// top, and no profiles of their own.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::config::Config;
+    ///
+    /// let config = Config::parse(r#"
+    ///     prompt = "myapp> "
+    ///     variables.region = "us-east-1"
+    ///
+    ///     [profiles.staging]
+    ///     prompt = "staging> "
+    ///     connection.url = "https://staging.example.com"
+    /// "#).unwrap();
+    ///
+    /// let staging = config.profile("staging").unwrap();
+    /// assert_eq!(staging.prompt.as_deref(), Some("staging> "));
+    /// assert_eq!(staging.variables["region"], "us-east-1");
+    /// assert_eq!(staging.connection["url"].as_str(), Some("https://staging.example.com"));
+    ///
+    /// assert!(config.profile("prod").is_err());
+    /// ```
+    pub fn profile(&self, name: &str) -> Result<Config, ConfigError> {
+        let profile = self.profiles.get(name).ok_or_else(|| ConfigError::Invalid {
+            key: format!("profiles.{name}"),
+            message: "no such profile".to_string(),
+        })?;
+        let mut config = Config { profiles: BTreeMap::new(), ..self.clone() };
+        config.merge(profile.clone());
+        Ok(config)
+    }
+
+    /// Sets one value by its dotted key, such as `prompt`, `history.max_size`,
+    /// `aliases.ll` or `profiles.staging.prompt`, parsing `value` as the
+    /// setting requires.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::config::Config;
+    ///
+    /// let mut config = Config::default();
+    /// config.set("history.max_size", "500").unwrap();
+    /// config.set("colors.accent", "bold cyan").unwrap();
+    /// assert_eq!(config.history.max_size, Some(500));
+    ///
+    /// assert!(config.set("history.max_size", "lots").is_err());
+    /// assert!(config.set("pager", "on").is_err());
+    /// ```
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        let invalid = |message: String| ConfigError::Invalid { key: key.to_string(), message };
+        let flag = || parse_bool(value).ok_or_else(|| invalid(format!("expected true or false, got `{value}`")));
+        match key.split_once('.') {
+            None if key == "prompt" => self.prompt = Some(value.to_string()),
+            None if key == "timing" => self.timing = Some(flag()?),
+            None if key == "report_time" => {
+                let secs = value.parse().map_err(|_| invalid(format!("expected a number of seconds, got `{value}`")))?;
+                self.report_time = Some(secs);
+            }
+            None if key == "dry_run" => self.dry_run = Some(flag()?),
+            None if key == "command_timeout" => {
+                let secs = value.parse().map_err(|_| invalid(format!("expected a number of seconds, got `{value}`")))?;
+                self.command_timeout = Some(secs);
+            }
+            None if key == "title" => self.title = Some(value.to_string()),
+            None if key == "theme" => {
+                value.parse::<Theme>().map_err(invalid)?;
+                self.theme = Some(value.to_string());
+            }
+            Some(("history", "file")) => self.history.file = Some(PathBuf::from(value)),
+            Some(("history", "max_size")) => {
+                let size = value.parse().map_err(|_| invalid(format!("expected a number, got `{value}`")))?;
+                self.history.max_size = Some(size);
+            }
+            Some(("history", "ignore_duplicates")) => self.history.ignore_duplicates = Some(flag()?),
+            Some(("history", "ignore_space")) => self.history.ignore_space = Some(flag()?),
+            Some(("colors", tone)) => {
+                tone.parse::<Tone>().map_err(invalid)?;
+                value.parse::<Style>().map_err(invalid)?;
+                self.colors.insert(tone.to_string(), value.to_string());
+            }
+            Some(("aliases", name)) if !name.is_empty() && !name.contains(char::is_whitespace) => {
+                self.aliases.insert(name.to_string(), value.to_string());
+            }
+            Some(("abbreviations", name)) if !name.is_empty() && !name.contains(char::is_whitespace) => {
+                self.abbreviations.insert(name.to_string(), value.to_string());
+            }
+            Some(("snippets", name)) if !name.is_empty() && !name.contains(char::is_whitespace) => {
+                self.snippets.insert(name.to_string(), value.to_string());
+            }
+            Some(("keybindings", binding)) => {
+                #[cfg(feature = "rustyline")]
+                {
+                    keys::parse_key(binding).map_err(invalid)?;
+                    keys::parse_action(value).map_err(invalid)?;
+                }
+                self.keybindings.insert(binding.to_string(), value.to_string());
+            }
+            Some(("variables", name)) if !name.is_empty() => {
+                self.variables.insert(name.to_string(), value.to_string());
+            }
+            Some(("connection", name)) if !name.is_empty() => {
+                // Written like a TOML value, or taken as a plain string.
+                let parsed = value.parse().unwrap_or_else(|_| toml::Value::String(value.to_string()));
+                self.connection.insert(name.to_string(), parsed);
+            }
+            Some(("profiles", rest)) => {
+                let (name, setting) = rest.split_once('.').ok_or_else(|| invalid("expected profiles.<name>.<setting>".to_string()))?;
+                if name.is_empty() || setting.starts_with("profiles.") {
+                    return Err(invalid("unknown setting".to_string()));
+                }
+                let mut profile = self.profiles.get(name).cloned().unwrap_or_default();
+                profile.set(setting, value).map_err(|err| match err {
+                    ConfigError::Invalid { message, .. } => invalid(message),
+                    err => err,
+                })?;
+                self.profiles.insert(name.to_string(), profile);
+            }
+            _ => return Err(invalid("unknown setting".to_string())),
+        }
+        Ok(())
+    }
+
+    /// Returns every value that is set, as dotted keys and values written
+    /// the way they would appear in a TOML file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::config::Config;
+    ///
+    /// let config = Config::parse("prompt = \"db> \"\n[history]\nmax_size = 500").unwrap();
+    /// assert_eq!(config.entries(), [
+    ///     ("prompt".to_string(), "\"db> \"".to_string()),
+    ///     ("history.max_size".to_string(), "500".to_string()),
+    /// ]);
+    /// ```
+    pub fn entries(&self) -> Vec<(String, String)> {
+        let mut entries = Vec::new();
+        if let Some(prompt) = &self.prompt {
+            entries.push(("prompt".to_string(), quote(prompt)));
+        }
+        if let Some(timing) = self.timing {
+            entries.push(("timing".to_string(), timing.to_string()));
+        }
+        if let Some(secs) = self.report_time {
+            entries.push(("report_time".to_string(), secs.to_string()));
+        }
+        if let Some(dry_run) = self.dry_run {
+            entries.push(("dry_run".to_string(), dry_run.to_string()));
+        }
+        if let Some(secs) = self.command_timeout {
+            entries.push(("command_timeout".to_string(), secs.to_string()));
+        }
+        if let Some(title) = &self.title {
+            entries.push(("title".to_string(), quote(title)));
+        }
+        if let Some(theme) = &self.theme {
+            entries.push(("theme".to_string(), quote(theme)));
+        }
+        for (tone, style) in &self.colors {
+            entries.push((format!("colors.{tone}"), quote(style)));
+        }
+        let history = &self.history;
+        if let Some(file) = &history.file {
+            entries.push(("history.file".to_string(), quote(&file.display().to_string())));
+        }
+        if let Some(size) = history.max_size {
+            entries.push(("history.max_size".to_string(), size.to_string()));
+        }
+        if let Some(ignore) = history.ignore_duplicates {
+            entries.push(("history.ignore_duplicates".to_string(), ignore.to_string()));
+        }
+        if let Some(ignore) = history.ignore_space {
+            entries.push(("history.ignore_space".to_string(), ignore.to_string()));
+        }
+        for (name, expansion) in &self.aliases {
+            entries.push((format!("aliases.{name}"), quote(expansion)));
+        }
+        for (name, expansion) in &self.abbreviations {
+            entries.push((format!("abbreviations.{name}"), quote(expansion)));
+        }
+        for (name, template) in &self.snippets {
+            entries.push((format!("snippets.{name}"), quote(template)));
+        }
+        for (key, action) in &self.keybindings {
+            entries.push((format!("keybindings.{key}"), quote(action)));
+        }
+        for (name, value) in &self.variables {
+            entries.push((format!("variables.{name}"), quote(value)));
+        }
+        for (name, value) in &self.connection {
+            entries.push((format!("connection.{name}"), value.to_string()));
+        }
+        for (name, profile) in &self.profiles {
+            for (key, value) in profile.entries() {
+                entries.push((format!("profiles.{name}.{key}"), value));
+            }
+        }
+        entries
+    }
+
+    /// Sets one value by its dotted key in the configuration file at `path`,
+    /// creating the file if it does not exist.
+    ///
+    /// The rest of the file, including comments and formatting, is left as
+    /// it was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::config::Config;
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let path = dir.path().join("myapp.toml");
+    /// std::fs::write(&path, "# my settings\nprompt = \"db> \"\n").unwrap();
+    ///
+    /// Config::save_setting(&path, "history.max_size", "500").unwrap();
+    /// let config = Config::load(&path).unwrap();
+    /// assert_eq!(config.history.max_size, Some(500));
+    /// assert!(std::fs::read_to_string(&path).unwrap().starts_with("# my settings"));
+    /// ```
+    pub fn save_setting(path: impl AsRef<Path>, key: &str, value: &str) -> Result<(), ConfigError> {
+        let path = path.as_ref();
+        let io_error = |source| ConfigError::Io { path: path.to_path_buf(), source };
+        let mut single = Config::default();
+        single.set(key, value)?;
+        let Some((_, literal)) = single.entries().into_iter().next() else {
+            return Ok(());
+        };
+
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(io_error(err)),
+        };
+        let parse_error = |message: String| ConfigError::Parse { path: Some(path.to_path_buf()), message };
+        let mut doc: toml_edit::DocumentMut = text.parse().map_err(|err: toml_edit::TomlError| parse_error(err.to_string()))?;
+        let value: toml_edit::Value = literal.parse().map_err(|err: toml_edit::TomlError| parse_error(err.to_string()))?;
+        // A profile's settings nest inside its own table.
+        let (mut sections, setting) = match key.strip_prefix("profiles.").and_then(|rest| rest.split_once('.')) {
+            Some((profile, setting)) => (vec!["profiles", profile], setting),
+            None => (Vec::new(), key),
+        };
+        let name = match setting.split_once('.') {
+            Some((section, name)) => {
+                sections.push(section);
+                name
+            }
+            None => setting,
+        };
+        let mut table = doc.as_table_mut() as &mut dyn toml_edit::TableLike;
+        for (depth, section) in sections.iter().enumerate() {
+            table = table
+                .entry(section)
+                .or_insert_with(|| {
+                    // `[profiles.x]` rather than an empty `[profiles]`, and
+                    // `history.max_size = ...` inside a profile.
+                    let mut table = toml_edit::Table::new();
+                    table.set_implicit(depth == 0 && *section == "profiles");
+                    table.set_dotted(depth == 2);
+                    toml_edit::Item::Table(table)
+                })
+                .as_table_like_mut()
+                .ok_or_else(|| parse_error(format!("`{section}` is not a table")))?;
+        }
+        table.insert(name, toml_edit::value(value));
+        std::fs::write(path, doc.to_string()).map_err(io_error)
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Writes `s` as a TOML basic string.
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,318 @@
+//! Checking configuration text against the settings the framework knows.
+
+use std::fmt;
+use std::ops::Range;
+
+use toml_edit::{Item, TableLike, Value};
+
+use crate::output::theme::{Style, Theme, Tone};
+use crate::locale::text;
+#[cfg(feature = "rustyline")]
+use crate::repl::keys;
+use crate::suggest::closest;
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The setting is still accepted but should be changed, such as a
+    /// renamed key.
+    Warning,
+    /// The file cannot be used until the problem is fixed.
+    Error,
+}
+
+/// A problem found in a configuration file, with where it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// The dotted key the problem is about, such as `history.max_size`.
+    pub key: String,
+    /// What is wrong and how to fix it.
+    pub message: String,
+    /// The line of the problem, starting at 1.
+    pub line: usize,
+    /// The column of the problem, starting at 1.
+    pub column: usize,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}:{}: {severity}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// The type a setting's value must have.
+enum Kind {
+    String,
+    Bool,
+    Count,
+    /// The name of a built-in theme.
+    Theme,
+    /// Any value; the application interprets it.
+    Any,
+    /// A table with fixed keys.
+    Table(&'static [Field]),
+    /// A table whose keys are chosen by the user, all holding strings.
+    Map(Check),
+}
+
+/// Extra checks on the entries of a [`Kind::Map`].
+#[derive(Clone, Copy)]
+enum Check {
+    None,
+    Color,
+    KeyBinding,
+    /// A single-word name, and a non-empty expansion; the text says what
+    /// the names are.
+    Word(&'static str),
+}
+
+struct Field {
+    name: &'static str,
+    kind: Kind,
+    /// The key that replaces this one, for settings that were renamed.
+    renamed: Option<&'static str>,
+}
+
+const fn field(name: &'static str, kind: Kind) -> Field {
+    Field { name, kind, renamed: None }
+}
+
+const fn renamed(name: &'static str, kind: Kind, to: &'static str) -> Field {
+    Field { name, kind, renamed: Some(to) }
+}
+
+const HISTORY: &[Field] = &[
+    field("file", Kind::String),
+    field("max_size", Kind::Count),
+    field("ignore_duplicates", Kind::Bool),
+    field("ignore_space", Kind::Bool),
+    // The names rustyline uses.
+    renamed("size", Kind::Count, "max_size"),
+    renamed("ignore_dups", Kind::Bool, "ignore_duplicates"),
+];
+
+/// Every setting except `profiles`, which is checked separately because a
+/// profile holds these same settings.
+const SETTINGS: &[Field] = &[
+    field("prompt", Kind::String),
+    field("timing", Kind::Bool),
+    field("report_time", Kind::Count),
+    field("dry_run", Kind::Bool),
+    field("command_timeout", Kind::Count),
+    field("title", Kind::String),
+    field("theme", Kind::Theme),
+    field("colors", Kind::Map(Check::Color)),
+    field("history", Kind::Table(HISTORY)),
+    field("aliases", Kind::Map(Check::Word("alias"))),
+    field("abbreviations", Kind::Map(Check::Word("abbreviation"))),
+    field("snippets", Kind::Map(Check::Word("snippet"))),
+    field("keybindings", Kind::Map(Check::KeyBinding)),
+    field("variables", Kind::Map(Check::None)),
+    field("connection", Kind::Any),
+];
+
+/// Checks `text` and returns every problem found, in file order.
+///
+/// Text that is not valid TOML yields a single error for the syntax
+/// problem.
+pub(crate) fn validate(text: &str) -> Vec<Diagnostic> {
+    match toml_edit::Document::parse(text) {
+        Ok(doc) => check(&doc, text),
+        Err(err) => {
+            let mut checker = Checker { text, found: Vec::new() };
+            checker.error(String::new(), err.message().to_string(), err.span());
+            checker.found
+        }
+    }
+}
+
+/// Checks the parsed document `doc` of `text` and returns every problem
+/// found, in file order.
+pub(crate) fn check(doc: &toml_edit::Document<&str>, text: &str) -> Vec<Diagnostic> {
+    let mut checker = Checker { text, found: Vec::new() };
+    if let Some(root) = doc.as_item().as_table_like() {
+        checker.settings(root, "", true);
+    }
+    checker.found.sort_by_key(|d| (d.line, d.column));
+    checker.found
+}
+
+struct Checker<'a> {
+    text: &'a str,
+    found: Vec<Diagnostic>,
+}
+
+impl Checker<'_> {
+    fn settings(&mut self, table: &dyn TableLike, prefix: &str, top: bool) {
+        for (name, item) in table.iter() {
+            let key = format!("{prefix}{name}");
+            let span = span_of(table, name, item);
+            if name == "profiles" {
+                // Profiles inside a profile were already reported.
+                if top {
+                    self.profiles(item, span);
+                }
+                continue;
+            }
+            self.field(SETTINGS, table, name, item, &key);
+        }
+    }
+
+    fn profiles(&mut self, item: &Item, span: Option<Range<usize>>) {
+        let Some(profiles) = item.as_table_like() else {
+            return self.error("profiles".to_string(), text("config.not_profiles", &[]), span);
+        };
+        for (name, profile) in profiles.iter() {
+            let key = format!("profiles.{name}");
+            match profile.as_table_like() {
+                Some(settings) => {
+                    if let Some(nested) = settings.get("profiles") {
+                        let span = span_of(settings, "profiles", nested);
+                        self.error(format!("{key}.profiles"), text("config.nested_profiles", &[]), span);
+                    }
+                    self.settings(settings, &format!("{key}."), false);
+                }
+                None => {
+                    let span = span_of(profiles, name, profile);
+                    self.error(key.clone(), text("config.not_settings", &[("key", &key)]), span);
+                }
+            }
+        }
+    }
+
+    fn field(&mut self, fields: &[Field], table: &dyn TableLike, name: &str, item: &Item, key: &str) {
+        let span = span_of(table, name, item);
+        let Some(field) = fields.iter().find(|f| f.name == name) else {
+            let known: Vec<&str> = fields.iter().filter(|f| f.renamed.is_none()).map(|f| f.name).collect();
+            let message = match closest(name, &known) {
+                Some(guess) => text("config.unknown_setting.guess", &[("key", &key), ("guess", &guess)]),
+                None => text("config.unknown_setting", &[("key", &key), ("known", &list(&known))]),
+            };
+            return self.error(key.to_string(), message, span);
+        };
+        if let Some(to) = field.renamed {
+            let message = text("config.deprecated", &[("key", &key), ("to", &to)]);
+            self.report(Severity::Warning, key.to_string(), message, span.clone());
+            if table.contains_key(to) {
+                let message = text("config.both_set", &[("key", &key), ("to", &to)]);
+                self.error(key.to_string(), message, span.clone());
+            }
+        }
+        self.value(&field.kind, item, key, span);
+    }
+
+    fn value(&mut self, kind: &Kind, item: &Item, key: &str, span: Option<Range<usize>>) {
+        let value = item.as_value();
+        match kind {
+            Kind::String if value.is_some_and(Value::is_str) => {}
+            Kind::String => self.mismatch(key, "config.type.string", item, span),
+            Kind::Bool if value.is_some_and(Value::is_bool) => {}
+            Kind::Bool => self.mismatch(key, "config.type.bool", item, span),
+            Kind::Count => match value.and_then(Value::as_integer) {
+                Some(n) if n >= 0 => {}
+                Some(_) => self.error(key.to_string(), text("config.negative", &[("key", &key)]), span),
+                None => self.mismatch(key, "config.type.count", item, span),
+            },
+            Kind::Theme => match value.and_then(Value::as_str) {
+                Some(name) => {
+                    if let Err(message) = name.parse::<Theme>() {
+                        self.error(key.to_string(), text("config.invalid_value", &[("key", &key), ("message", &message)]), span);
+                    }
+                }
+                None => self.mismatch(key, "config.type.theme", item, span),
+            },
+            Kind::Any => {}
+            Kind::Table(fields) => match item.as_table_like() {
+                Some(table) => {
+                    for (name, item) in table.iter() {
+                        self.field(fields, table, name, item, &format!("{key}.{name}"));
+                    }
+                }
+                None => self.mismatch(key, "config.type.table", item, span),
+            },
+            Kind::Map(check) => match item.as_table_like() {
+                Some(table) => {
+                    for (name, entry) in table.iter() {
+                        let entry_span = span_of(table, name, entry);
+                        let entry_key = format!("{key}.{name}");
+                        match entry.as_str() {
+                            Some(text) => self.entry(*check, name, text, &entry_key, entry_span),
+                            None => self.mismatch(&entry_key, "config.type.string", entry, entry_span),
+                        }
+                    }
+                }
+                None => self.mismatch(key, "config.type.table", item, span),
+            },
+        }
+    }
+
+    fn entry(&mut self, check: Check, name: &str, text: &str, key: &str, span: Option<Range<usize>>) {
+        let problem = match check {
+            Check::None => Ok(()),
+            Check::Color => name.parse::<Tone>().and_then(|_| text.parse::<Style>()).map(drop),
+            #[cfg(feature = "rustyline")]
+            Check::KeyBinding => keys::parse_key(name).and_then(|_| keys::parse_action(text)).map(drop),
+            // Without a line editor, key bindings are kept but not used.
+            #[cfg(not(feature = "rustyline"))]
+            Check::KeyBinding => Ok(()),
+            Check::Word(what) if name.is_empty() || name.contains(char::is_whitespace) => {
+                Err(format!("{what} names must be a single word"))
+            }
+            Check::Word(_) if text.trim().is_empty() => Err("expansion is empty".to_string()),
+            Check::Word(_) => Ok(()),
+        };
+        if let Err(message) = problem {
+            self.error(key.to_string(), crate::locale::text("config.invalid_value", &[("key", &key), ("message", &message)]), span);
+        }
+    }
+
+    /// Reports that `key` holds `item` instead of a value of the type
+    /// whose catalog id is `expected`.
+    fn mismatch(&mut self, key: &str, expected: &str, item: &Item, span: Option<Range<usize>>) {
+        let found = match item {
+            Item::None => "config.type.nothing",
+            Item::Value(Value::String(_)) => "config.type.string",
+            Item::Value(Value::Integer(_)) => "config.type.integer",
+            Item::Value(Value::Float(_)) => "config.type.float",
+            Item::Value(Value::Boolean(_)) => "config.type.boolean",
+            Item::Value(Value::Datetime(_)) => "config.type.date",
+            Item::Value(Value::Array(_)) | Item::ArrayOfTables(_) => "config.type.array",
+            Item::Value(Value::InlineTable(_)) | Item::Table(_) => "config.type.table",
+        };
+        let message = text("config.mismatch", &[("key", &key), ("expected", &text(expected, &[])), ("found", &text(found, &[]))]);
+        self.error(key.to_string(), message, span);
+    }
+
+    fn error(&mut self, key: String, message: String, span: Option<Range<usize>>) {
+        self.report(Severity::Error, key, message, span);
+    }
+
+    fn report(&mut self, severity: Severity, key: String, message: String, span: Option<Range<usize>>) {
+        let (line, column) = position(self.text, span.map_or(0, |span| span.start));
+        self.found.push(Diagnostic { severity, key, message, line, column });
+    }
+}
+
+/// Returns where the key `name` of `table` is written, falling back to its
+/// value.
+fn span_of(table: &dyn TableLike, name: &str, item: &Item) -> Option<Range<usize>> {
+    table.key(name).and_then(|key| key.span()).or_else(|| item.span())
+}
+
+/// Converts a byte offset into a line and column, both starting at 1.
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |s| s.chars().count()) + 1;
+    (line, column)
+}
+
+fn list(names: &[&str]) -> String {
+    names.iter().map(|n| format!("`{n}`")).collect::<Vec<_>>().join(", ")
+}
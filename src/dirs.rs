@@ -0,0 +1,139 @@
+//! Per-application configuration, state, data and cache directories.
+//!
+//! [`AppDirs`] finds where an application keeps its files so that history,
+//! configuration and cached data land in the same places as other programs
+//! on the platform:
+//!
+//! | Kind   | Unix                                  | Windows                        |
+//! |--------|---------------------------------------|--------------------------------|
+//! | config | `$XDG_CONFIG_HOME` or `~/.config`     | `%APPDATA%\<name>`             |
+//! | data   | `$XDG_DATA_HOME` or `~/.local/share`  | `%APPDATA%\<name>\data`        |
+//! | state  | `$XDG_STATE_HOME` or `~/.local/state` | `%LOCALAPPDATA%\<name>\state`  |
+//! | cache  | `$XDG_CACHE_HOME` or `~/.cache`       | `%LOCALAPPDATA%\<name>\cache`  |
+//!
+//! On Unix each directory is followed by `/<name>`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use mycli::dirs::AppDirs;
+//!
+//! let dirs = AppDirs::new("myapp");
+//! // Creates ~/.local/state/myapp if needed.
+//! let history = dirs.state_file("history")?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The directories belonging to one application.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppDirs {
+    name: String,
+}
+
+impl AppDirs {
+    /// Creates the directory set for the application `name`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+
+    /// Returns the application's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the directory for settings the user edits.
+    pub fn config_dir(&self) -> Option<PathBuf> {
+        self.dir("XDG_CONFIG_HOME", ".config", "APPDATA", None)
+    }
+
+    /// Returns the directory for data the application keeps, such as saved
+    /// snippets.
+    pub fn data_dir(&self) -> Option<PathBuf> {
+        self.dir("XDG_DATA_HOME", ".local/share", "APPDATA", Some("data"))
+    }
+
+    /// Returns the directory for state that survives restarts but is not
+    /// worth backing up, such as history and session state.
+    pub fn state_dir(&self) -> Option<PathBuf> {
+        self.dir("XDG_STATE_HOME", ".local/state", "LOCALAPPDATA", Some("state"))
+    }
+
+    /// Returns the directory for files that can be recreated at any time.
+    pub fn cache_dir(&self) -> Option<PathBuf> {
+        self.dir("XDG_CACHE_HOME", ".cache", "LOCALAPPDATA", Some("cache"))
+    }
+
+    /// Returns the path of `file` in the config directory, creating the
+    /// directory if it does not exist.
+    pub fn config_file(&self, file: impl AsRef<Path>) -> io::Result<PathBuf> {
+        create_in(self.config_dir(), file.as_ref())
+    }
+
+    /// Returns the path of `file` in the data directory, creating the
+    /// directory if it does not exist.
+    pub fn data_file(&self, file: impl AsRef<Path>) -> io::Result<PathBuf> {
+        create_in(self.data_dir(), file.as_ref())
+    }
+
+    /// Returns the path of `file` in the state directory, creating the
+    /// directory if it does not exist.
+    pub fn state_file(&self, file: impl AsRef<Path>) -> io::Result<PathBuf> {
+        create_in(self.state_dir(), file.as_ref())
+    }
+
+    /// Returns the path of `file` in the cache directory, creating the
+    /// directory if it does not exist.
+    pub fn cache_file(&self, file: impl AsRef<Path>) -> io::Result<PathBuf> {
+        create_in(self.cache_dir(), file.as_ref())
+    }
+
+    fn dir(&self, xdg: &str, home: &str, windows: &str, sub: Option<&str>) -> Option<PathBuf> {
+        if let Some(dir) = var(xdg) {
+            return Some(PathBuf::from(dir).join(&self.name));
+        }
+        if cfg!(windows) {
+            let dir = PathBuf::from(var(windows)?).join(&self.name);
+            return Some(match sub {
+                Some(sub) => dir.join(sub),
+                None => dir,
+            });
+        }
+        Some(home_dir()?.join(home).join(&self.name))
+    }
+}
+
+/// Returns the user's home directory.
+pub fn home_dir() -> Option<PathBuf> {
+    var("HOME").or_else(|| var("USERPROFILE")).map(PathBuf::from)
+}
+
+/// Replaces a leading `~` in `path` with the home directory.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::dirs::expand_home;
+/// use std::path::Path;
+///
+/// assert_eq!(expand_home(Path::new("/etc/hosts")), Path::new("/etc/hosts"));
+/// ```
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn var(name: &str) -> Option<OsString> {
+    std::env::var_os(name).filter(|value| !value.is_empty())
+}
+
+fn create_in(dir: Option<PathBuf>, file: &Path) -> io::Result<PathBuf> {
+    let dir = dir.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))?;
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join(file))
+}
@@ -0,0 +1,347 @@
+//! Running REPL commands over HTTP.
+//!
+//! An [`HttpServer`] runs the commands of one [`Repl`] for HTTP clients,
+//! through the same aliases, built-ins and command handler as typed lines.
+//! A command is run by posting its arguments to its path:
+//!
+//! ```text
+//! POST /commands/deploy HTTP/1.1
+//! Content-Type: application/json
+//!
+//! {"args": ["web", "--env", "staging"]}
+//! ```
+//!
+//! runs `deploy web --env staging`. The command name in the path may be
+//! percent-encoded. The body may also be the array of arguments alone, or
+//! empty when there are none. Arguments are passed as they are: those
+//! containing whitespace are quoted for the command, and so are `--dry-run`
+//! and arguments ending in `&`, which would otherwise change how the line
+//! runs. The response is a JSON object
+//! with what the command printed, as described for the
+//! [`rpc`](crate::rpc) module:
+//!
+//! ```text
+//! {"errors":"","exit":false,"failures":[],"output":"deployed web to staging\n"}
+//! ```
+//!
+//! Requests are answered one at a time, so commands never run
+//! concurrently. Errors are answered with a status code and a JSON object
+//! with an `error` message: 403 for commands that are not allowed, 404 for
+//! other paths, 405 for methods other than `POST` and 400 for malformed
+//! bodies or command names.
+//!
+//! With the `metrics` feature, a server given metrics with
+//! `HttpServer::with_metrics` also answers `GET /metrics` for Prometheus.
+//!
+//! There is no authentication: anyone who can connect can run commands.
+//! Listen on a loopback address and limit what can be run with
+//! [`HttpServer::allow`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use mycli::http::HttpServer;
+//! use mycli::output::Output;
+//! use mycli::repl::{CommandHandler, Context, Repl};
+//!
+//! struct Daemon {
+//!     out: Output,
+//! }
+//!
+//! impl CommandHandler for Daemon {
+//!     fn handle(&mut self, command: &str, _: &Context) -> bool {
+//!         let _ = self.out.println(&format!("ran {command}"));
+//!         true
+//!     }
+//! }
+//!
+//! let server = HttpServer::bind("127.0.0.1:8080", |out| Repl::new("", Daemon { out }))?
+//!     .allow(["status", "deploy"]);
+//! std::thread::spawn(move || server.serve());
+//! // curl -d '{"args": ["web"]}' http://127.0.0.1:8080/commands/deploy
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::output::Output;
+use crate::repl::{quote, CommandHandler, Repl};
+use crate::rpc::RpcSession;
+
+// Requests larger than this are refused instead of read into memory.
+const MAX_BODY: usize = 1 << 20;
+
+// Request and header lines longer than this are refused the same way.
+const MAX_LINE: usize = 8 << 10;
+
+/// Serves the commands of a REPL over HTTP.
+///
+/// See the [module documentation](self) for the protocol.
+pub struct HttpServer<F> {
+    listener: TcpListener,
+    factory: F,
+    rules: Rules,
+}
+
+impl<F, H> HttpServer<F>
+where
+    F: FnOnce(Output) -> crate::repl::Result<Repl<H>>,
+    H: CommandHandler,
+{
+    /// Listens on `addr`. When serving starts, `factory` creates the REPL
+    /// that runs the commands, given the [`Output`] whose text ends up in
+    /// responses.
+    pub fn bind(addr: impl ToSocketAddrs, factory: F) -> io::Result<Self> {
+        let rules = Rules {
+            allowed: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        };
+        Ok(Self { listener: TcpListener::bind(addr)?, factory, rules })
+    }
+
+    /// Returns the address the server listens on, for instance to find the
+    /// port chosen when binding to port 0.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Lets clients run only `commands`. Others are answered with 403.
+    pub fn allow<I>(mut self, commands: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.rules.allowed = Some(commands.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Records the commands run in `metrics`, and answers `GET /metrics`
+    /// with them in the Prometheus text format.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: crate::metrics::Metrics) -> Self {
+        self.rules.metrics = Some(metrics);
+        self
+    }
+
+    /// Answers requests until accepting a connection fails.
+    pub fn serve(self) -> io::Result<()> {
+        let factory = self.factory;
+        #[cfg(feature = "metrics")]
+        let factory = {
+            let metrics = self.rules.metrics.clone();
+            move |out| match metrics {
+                Some(metrics) => factory(out).map(|repl| repl.with_metrics(metrics)),
+                None => factory(out),
+            }
+        };
+        let mut session = RpcSession::new(factory).map_err(io::Error::other)?;
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) if is_transient(&err) => continue,
+                Err(err) => return Err(err),
+            };
+            // A client that stops sending would otherwise hold up everyone.
+            let _ = stream.set_read_timeout(Some(Duration::from_secs(10)));
+            let _ = answer(&mut session, &self.rules, stream);
+        }
+        Ok(())
+    }
+}
+
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset | io::ErrorKind::Interrupted
+    )
+}
+
+/// What clients of a server may do.
+struct Rules {
+    allowed: Option<HashSet<String>>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<crate::metrics::Metrics>,
+}
+
+/// Reads one request from `stream` and answers it.
+fn answer<H: CommandHandler>(session: &mut RpcSession<H>, rules: &Rules, mut stream: TcpStream) -> io::Result<()> {
+    let request = read_request(&mut stream)?;
+    #[cfg(feature = "metrics")]
+    if let (Ok(request), Some(metrics)) = (&request, &rules.metrics)
+        && request.path == "/metrics"
+    {
+        let (status, body) = match request.method.as_str() {
+            "GET" => ("200 OK", metrics.render()),
+            _ => ("405 Method Not Allowed", "metrics are read with GET\n".to_string()),
+        };
+        return respond(&mut stream, status, "text/plain; version=0.0.4", &body);
+    }
+    let (status, body) = match request {
+        Ok(request) => handle(session, rules.allowed.as_ref(), &request),
+        Err(reply) => reply,
+    };
+    respond(&mut stream, status, "application/json", &body.to_string())
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+type Reply = (&'static str, Value);
+
+fn failure(status: &'static str, message: impl Into<String>) -> Reply {
+    (status, json!({ "error": message.into() }))
+}
+
+fn read_request(stream: &mut TcpStream) -> io::Result<Result<Request, Reply>> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    if !read_line(&mut reader, &mut line)? {
+        return Ok(Err(failure("414 URI Too Long", "the request line is too long")));
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(Err(failure("400 Bad Request", "malformed request line")));
+    };
+    let (method, path) = (method.to_string(), target.split('?').next().unwrap_or(target).to_string());
+
+    let mut length = 0;
+    loop {
+        if !read_line(&mut reader, &mut line)? {
+            return Ok(Err(failure("431 Request Header Fields Too Large", "a header line is too long")));
+        }
+        if line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            let Ok(value) = value.trim().parse() else {
+                return Ok(Err(failure("400 Bad Request", "malformed Content-Length")));
+            };
+            length = value;
+        }
+    }
+    if length > MAX_BODY {
+        return Ok(Err(failure("413 Content Too Large", "the body is too large")));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Ok(Request { method, path, body }))
+}
+
+/// Reads a line into `line`, replacing what it held, reading at most
+/// `MAX_LINE` bytes. Returns `false` if the line is longer.
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> io::Result<bool> {
+    line.clear();
+    reader.by_ref().take(MAX_LINE as u64 + 1).read_line(line)?;
+    Ok(line.len() <= MAX_LINE)
+}
+
+fn handle<H: CommandHandler>(
+    session: &mut RpcSession<H>,
+    allowed: Option<&HashSet<String>>,
+    request: &Request,
+) -> Reply {
+    let Some(name) = request.path.strip_prefix("/commands/") else {
+        return failure("404 Not Found", format!("no such path `{}`", request.path));
+    };
+    if request.method != "POST" {
+        return failure("405 Method Not Allowed", "commands are run with POST");
+    }
+    let Some(name) = percent_decode(name) else {
+        return failure("400 Bad Request", format!("the command `{name}` is not valid percent-encoded UTF-8"));
+    };
+    if name.is_empty() || name.contains(|c: char| c.is_whitespace() || matches!(c, '/' | '&' | '"' | '\'')) {
+        return failure("404 Not Found", format!("no such command `{name}`"));
+    }
+    if allowed.is_some_and(|allowed| !allowed.contains(&name)) {
+        return failure("403 Forbidden", format!("`{name}` cannot be run over HTTP"));
+    }
+    let args = match args(&request.body) {
+        Ok(args) => args,
+        Err(message) => return failure("400 Bad Request", message),
+    };
+
+    let mut command = name;
+    for arg in &args {
+        command.push(' ');
+        match quote_arg(arg) {
+            Some(arg) => command.push_str(&arg),
+            None => return failure("400 Bad Request", format!("cannot quote the argument `{arg}`")),
+        }
+    }
+    let outcome = session.run_command(&command);
+    ("200 OK", json!(outcome))
+}
+
+/// Returns `path` with `%XX` escapes decoded, or `None` if an escape is
+/// malformed or the result is not UTF-8.
+fn percent_decode(path: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = after.get(..2).filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))?;
+            bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            rest = &after[2..];
+        } else {
+            bytes.push(byte);
+            rest = after;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Quotes `arg` for the command line like [`quote`], also quoting an
+/// argument the REPL would otherwise take as the `&` that runs a line in
+/// the background or as the `--dry-run` flag.
+fn quote_arg(arg: &str) -> Option<Cow<'_, str>> {
+    if !arg.ends_with('&') && arg != "--dry-run" {
+        return quote(arg);
+    }
+    let quote = ['"', '\''].into_iter().find(|&q| !arg.contains(q))?;
+    Some(Cow::Owned(format!("{quote}{arg}{quote}")))
+}
+
+/// Returns the arguments given in `body`.
+fn args(body: &[u8]) -> Result<Vec<String>, String> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(Vec::new());
+    }
+    let body: Value = serde_json::from_slice(body).map_err(|err| format!("the body is not JSON: {err}"))?;
+    let args = match &body {
+        Value::Array(args) => args,
+        Value::Object(fields) => match fields.get("args") {
+            Some(Value::Array(args)) => args,
+            None => return Ok(Vec::new()),
+            Some(_) => return Err("`args` must be an array".to_string()),
+        },
+        _ => return Err("expected an array of arguments or an object with `args`".to_string()),
+    };
+    args.iter()
+        .map(|arg| match arg {
+            Value::String(arg) => Ok(arg.clone()),
+            Value::Number(_) | Value::Bool(_) => Ok(arg.to_string()),
+            _ => Err(format!("the argument {arg} is not a string, number or boolean")),
+        })
+        .collect()
+}
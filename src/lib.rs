@@ -1,7 +1,64 @@
-#[cfg(feature = "repl")]
+/// Emits a `tracing` debug event with the `tracing` feature, and nothing
+/// without it.
+#[allow(unused_macros)]
+macro_rules! debug_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
+#[cfg(feature = "repl-core")]
 pub mod repl;
 
 #[cfg(feature = "args")]
 pub mod args;
 
-pub mod mods;
\ No newline at end of file
+#[cfg(feature = "prompt-core")]
+pub mod prompt;
+
+#[cfg(feature = "config")]
+pub mod config;
+
+#[cfg(feature = "secrets")]
+pub mod secrets;
+
+#[cfg(feature = "remote")]
+pub mod remote;
+
+#[cfg(feature = "rpc")]
+pub mod rpc;
+
+#[cfg(feature = "http")]
+pub mod http;
+
+#[cfg(feature = "update")]
+pub mod update;
+
+#[cfg(feature = "plugins")]
+pub mod plugin;
+
+#[cfg(feature = "metrics")]
+pub mod metrics;
+
+#[cfg(feature = "audit")]
+pub mod audit;
+
+#[cfg(feature = "tui")]
+pub mod tui;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+pub mod dirs;
+
+pub mod locale;
+
+pub mod redact;
+
+#[cfg(any(feature = "repl-core", feature = "args"))]
+mod suggest;
+
+pub mod mods;
+
+pub mod output;
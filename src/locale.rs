@@ -0,0 +1,484 @@
+//! Translations of the text the framework shows.
+//!
+//! Every message the framework prints itself, such as `Error: ` before
+//! errors, the REPL's built-in commands' replies and the prompt widgets'
+//! hints, is looked up by id in the current [`Catalog`]. Catalogs hold
+//! text with `{name}` placeholders; ids missing from a catalog fall back to
+//! English.
+//!
+//! The catalog is English unless replaced with [`set_catalog`], for
+//! instance with the user's language:
+//!
+//! ```
+//! use mycli::locale::{self, Catalog};
+//!
+//! locale::set_catalog(Catalog::japanese());
+//! assert_eq!(locale::text("job.not_found", &[("id", &3)]), "ジョブ 3 はありません");
+//!
+//! // Ids the catalog does not translate are shown in English.
+//! let mut catalog = Catalog::parse("ja", "error = エラー: {message}").unwrap();
+//! catalog.set("warning", "警告: {message}");
+//! locale::set_catalog(catalog);
+//! assert_eq!(locale::text("job.not_found", &[("id", &3)]), "no job 3");
+//! # locale::set_catalog(Catalog::english());
+//! ```
+//!
+//! Applications can keep their own messages in the same catalog and print
+//! them with [`text`].
+//!
+//! Ids ending in `.one` and `.other` are the singular and plural forms of
+//! a message with a count; see [`Catalog::format_count`]. The ids the
+//! framework uses, with their English text, are listed by
+//! [`Catalog::ids`].
+
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::sync::{Arc, RwLock};
+
+/// The framework's messages in English.
+const ENGLISH: &[(&str, &str)] = &[
+    ("error", "Error: {message}"),
+    ("warning", "Warning: {message}"),
+    ("help", "help"),
+    ("usage", "Usage: {usage}"),
+    ("did-you-mean", "did you mean `{guess}`?"),
+    ("args.unknown_flag", "unknown flag `--{name}`"),
+    ("args.not_a_flag", "not a flag of `{command}`"),
+    ("args.invalid_value", "invalid value `{value}` for `{name}`"),
+    ("args.expected", "expected {known}"),
+    ("args.one_of", "one of {names}"),
+    ("args.unknown_subcommand", "unknown subcommand `{name}`"),
+    ("args.not_a_subcommand", "not a subcommand of `{command}`"),
+    ("audit.write_failed", "cannot write the audit log: {error}"),
+    ("clipboard.copied.one", "Copied {count} line to the clipboard"),
+    ("clipboard.copied.other", "Copied {count} lines to the clipboard"),
+    ("clipboard.empty", "the previous command printed nothing to copy"),
+    ("clipboard.failed", "cannot copy: {error}"),
+    ("clipboard.no_terminal", "copying needs a terminal"),
+    ("clipboard.would_copy.one", "Would copy {count} line to the clipboard"),
+    ("clipboard.would_copy.other", "Would copy {count} lines to the clipboard"),
+    ("command.failed", "`{command}` failed: {error}"),
+    ("command.failed_after", "{command} failed after {elapsed}"),
+    ("command.finished_after", "{command} finished after {elapsed}"),
+    ("command.interrupted", "Interrupted"),
+    ("command.not_allowed", "`{command}` is not allowed here"),
+    ("command.panicked", "`{command}` failed unexpectedly: {panic}"),
+    ("command.timed_out", "`{command}` timed out after {timeout}"),
+    ("command.took", "took {elapsed}"),
+    ("config.both_set", "`{key}` and `{to}` are both set; remove `{key}`"),
+    ("config.deprecated", "`{key}` is deprecated; use `{to}` instead"),
+    ("config.invalid_value", "invalid value for `{key}`: {message}"),
+    ("config.mismatch", "`{key}` must be {expected}, found {found}"),
+    ("config.negative", "`{key}` cannot be negative"),
+    ("config.nested_profiles", "profiles cannot contain profiles"),
+    ("config.no_file", "no configuration file to save to"),
+    ("config.no_profiles", "No profiles are configured"),
+    ("config.not_profiles", "expected a table of profiles"),
+    ("config.not_set", "`{key}` is not set"),
+    ("config.not_settings", "`{key}` must be a table of settings"),
+    ("config.reloaded", "Reloaded {path}"),
+    ("config.type.array", "an array"),
+    ("config.type.bool", "true or false"),
+    ("config.type.boolean", "a boolean"),
+    ("config.type.count", "a whole number"),
+    ("config.type.date", "a date"),
+    ("config.type.float", "a float"),
+    ("config.type.integer", "an integer"),
+    ("config.type.nothing", "nothing"),
+    ("config.type.string", "a string"),
+    ("config.type.table", "a table"),
+    ("config.type.theme", "a theme name"),
+    ("config.unknown_setting", "unknown setting `{key}`; expected one of {known}"),
+    ("config.unknown_setting.guess", "unknown setting `{key}`; did you mean `{guess}`?"),
+    ("config.using_profile", "Using profile {name}"),
+    ("config.would_save", "Would save {key} = {value} to {path}"),
+    ("confirm.retry", "please answer {yes} or {no}"),
+    ("dir.change_failed", "cannot change to {path}: {error}"),
+    ("dir.no_home", "there is no home directory to change to"),
+    ("dir.no_previous", "there is no previous directory"),
+    ("form.confirm", "Is this correct?"),
+    ("help.examples", "Examples:"),
+    ("help.no_example", "`{name}` has no example {number}"),
+    ("help.none", "No commands are described"),
+    ("help.run_example", "Run one in dry-run mode with `help {name} --run-example <n>`"),
+    ("help.running_example", "Running `{command}` in dry-run mode"),
+    ("help.unknown_command", "there is no help for `{name}`"),
+    ("help.unknown_command.guess", "there is no help for `{name}`; did you mean `{guess}`?"),
+    ("history.search", "Search history"),
+    ("input.unterminated_quote", "unterminated {quote}"),
+    ("job.cannot_background", "`{command}` cannot run in the background"),
+    ("job.cannot_parallel", "`{command}` cannot run in parallel"),
+    ("job.done", "Done"),
+    ("job.failed", "Failed"),
+    ("job.killed", "Killed"),
+    ("job.none", "No jobs are running"),
+    ("job.none_running", "no jobs are running"),
+    ("job.not_found", "no job {id}"),
+    ("job.parallel_failed", "{failed} of {total} commands failed"),
+    ("job.running", "[{id}] Running {command}"),
+    ("job.stopping", "[{id}] Stopping {command}"),
+    ("job.would_stop", "Would stop [{id}] {command}"),
+    ("menu.choose", "Type a number or a name"),
+    ("menu.choose_many", "Type numbers or names, separated by commas"),
+    ("menu.no_choice", "no choice named `{name}`"),
+    ("menu.selected", "(selected)"),
+    ("number.at_least", "must be at least {min}"),
+    ("number.at_most", "must be at most {max}"),
+    ("number.expected_duration", "expected a duration like 90s, 1m30s or 250ms"),
+    ("number.expected_float", "expected a number"),
+    ("number.expected_integer", "expected a whole number"),
+    ("number.expected_size", "expected a size like 512MiB or 2G"),
+    ("number.invalid_size", "invalid size: {size}"),
+    ("number.size_too_large", "size too large: {size}"),
+    ("number.unknown_unit", "unknown size unit: {unit}"),
+    ("plugin.failed", "plugin {plugin} failed: {error}"),
+    ("prompt.back", "returned to the previous step"),
+    ("prompt.cancelled", "prompt cancelled"),
+    ("prompt.invalid_answer", "invalid answer for `{key}`: {message}"),
+    ("prompt.no_items", "there is nothing to choose from"),
+    ("prompt.not_interactive", "cannot ask \"{prompt}\": not running in an interactive terminal (supply an answer for `{key}`)"),
+    ("prompt.required", "a value is required"),
+    ("prompt.terminal_error", "terminal error: {error}"),
+    ("remote.auth_failed", "authentication failed"),
+    ("remote.line_too_long", "the command is longer than {limit} bytes"),
+    ("remote.not_allowed", "`{command}` cannot be run remotely"),
+    ("schedule.bad_interval", "`{interval}` is not a duration such as 30s, 5m or 1h"),
+    ("schedule.none", "No commands are scheduled"),
+    ("schedule.not_repeatable", "`{command}` cannot run repeatedly"),
+    ("script.bad_signature", "refusing to run {path}: {error}"),
+    ("script.unreadable", "cannot read {path}: {error}"),
+    ("script.unsigned", "refusing to run {path}, which has no readable signature: {error}"),
+    ("select.at_least.one", "select at least {count} item"),
+    ("select.at_least.other", "select at least {count} items"),
+    ("select.at_most.one", "select at most {count} item"),
+    ("select.at_most.other", "select at most {count} items"),
+    ("select.filter_hint", "(type to filter)"),
+    ("select.no_matches", "no matches"),
+    ("session.save_failed", "cannot save session: {error}"),
+    ("snippet.none", "No snippets are configured"),
+    ("snippet.not_found", "there is no snippet named `{name}`"),
+    ("status", "Status: {status}"),
+    ("summary.commands", "Commands run: {count}"),
+    ("summary.failures", "Failed: {count}"),
+    ("summary.slowest", "Slowest commands:"),
+    ("summary.time", "Time in commands: {elapsed}"),
+    ("update.available", "Version {version} is available; you have {current}"),
+    ("update.failed", "cannot update: {error}"),
+    ("update.installed", "Installed version {version}; restart to use it"),
+    ("update.latest", "Version {current} is the latest"),
+    ("update.would_install", "Would install version {version}"),
+];
+
+/// The framework's messages in Japanese.
+const JAPANESE: &[(&str, &str)] = &[
+    ("error", "エラー: {message}"),
+    ("warning", "警告: {message}"),
+    ("help", "ヒント"),
+    ("usage", "使い方: {usage}"),
+    ("did-you-mean", "`{guess}` のことですか?"),
+    ("args.unknown_flag", "不明なフラグ `--{name}`"),
+    ("args.not_a_flag", "`{command}` のフラグではありません"),
+    ("args.invalid_value", "`{name}` に無効な値 `{value}`"),
+    ("args.expected", "{known} を指定してください"),
+    ("args.one_of", "{names} のいずれか"),
+    ("args.unknown_subcommand", "不明なサブコマンド `{name}`"),
+    ("args.not_a_subcommand", "`{command}` のサブコマンドではありません"),
+    ("audit.write_failed", "監査ログに書き込めません: {error}"),
+    ("clipboard.copied.other", "{count} 行をクリップボードにコピーしました"),
+    ("clipboard.empty", "直前のコマンドはコピーするものを出力していません"),
+    ("clipboard.failed", "コピーできません: {error}"),
+    ("clipboard.no_terminal", "コピーには端末が必要です"),
+    ("clipboard.would_copy.other", "{count} 行をクリップボードにコピーします"),
+    ("command.failed", "`{command}` が失敗しました: {error}"),
+    ("command.failed_after", "{command} は {elapsed} 後に失敗しました"),
+    ("command.finished_after", "{command} は {elapsed} で完了しました"),
+    ("command.interrupted", "中断しました"),
+    ("command.not_allowed", "`{command}` はここでは実行できません"),
+    ("command.panicked", "`{command}` が予期せず失敗しました: {panic}"),
+    ("command.timed_out", "`{command}` は {timeout} でタイムアウトしました"),
+    ("command.took", "{elapsed} かかりました"),
+    ("config.both_set", "`{key}` と `{to}` の両方が設定されています。`{key}` を削除してください"),
+    ("config.deprecated", "`{key}` は非推奨です。代わりに `{to}` を使ってください"),
+    ("config.invalid_value", "`{key}` の値が無効です: {message}"),
+    ("config.mismatch", "`{key}` は{expected}でなければなりませんが、{found}です"),
+    ("config.negative", "`{key}` に負の値は指定できません"),
+    ("config.nested_profiles", "プロファイルの中にプロファイルは置けません"),
+    ("config.no_file", "保存先の設定ファイルがありません"),
+    ("config.no_profiles", "プロファイルが設定されていません"),
+    ("config.not_profiles", "プロファイルのテーブルが必要です"),
+    ("config.not_set", "`{key}` は設定されていません"),
+    ("config.not_settings", "`{key}` は設定のテーブルでなければなりません"),
+    ("config.reloaded", "{path} を再読み込みしました"),
+    ("config.type.array", "配列"),
+    ("config.type.bool", "true か false"),
+    ("config.type.boolean", "真偽値"),
+    ("config.type.count", "0 以上の整数"),
+    ("config.type.date", "日付"),
+    ("config.type.float", "浮動小数点数"),
+    ("config.type.integer", "整数"),
+    ("config.type.nothing", "値なし"),
+    ("config.type.string", "文字列"),
+    ("config.type.table", "テーブル"),
+    ("config.type.theme", "テーマ名"),
+    ("config.unknown_setting", "不明な設定 `{key}`。{known} のいずれかを指定してください"),
+    ("config.unknown_setting.guess", "不明な設定 `{key}`。`{guess}` のことですか?"),
+    ("config.using_profile", "プロファイル {name} を使用します"),
+    ("config.would_save", "{key} = {value} を {path} に保存します"),
+    ("confirm.retry", "{yes} か {no} で答えてください"),
+    ("dir.change_failed", "{path} に移動できません: {error}"),
+    ("dir.no_home", "移動先のホームディレクトリがありません"),
+    ("dir.no_previous", "前のディレクトリがありません"),
+    ("form.confirm", "これでよろしいですか?"),
+    ("help.examples", "例:"),
+    ("help.no_example", "`{name}` に例 {number} はありません"),
+    ("help.none", "説明のあるコマンドはありません"),
+    ("help.run_example", "`help {name} --run-example <n>` で例をドライランで実行できます"),
+    ("help.running_example", "`{command}` をドライランで実行します"),
+    ("help.unknown_command", "`{name}` のヘルプはありません"),
+    ("help.unknown_command.guess", "`{name}` のヘルプはありません。`{guess}` のことですか?"),
+    ("history.search", "履歴を検索"),
+    ("input.unterminated_quote", "{quote} が閉じられていません"),
+    ("job.cannot_background", "`{command}` はバックグラウンドで実行できません"),
+    ("job.cannot_parallel", "`{command}` は並列に実行できません"),
+    ("job.done", "完了"),
+    ("job.failed", "失敗"),
+    ("job.killed", "停止"),
+    ("job.none", "実行中のジョブはありません"),
+    ("job.none_running", "実行中のジョブはありません"),
+    ("job.not_found", "ジョブ {id} はありません"),
+    ("job.parallel_failed", "{total} 個中 {failed} 個のコマンドが失敗しました"),
+    ("job.running", "[{id}] 実行中 {command}"),
+    ("job.stopping", "[{id}] 停止中 {command}"),
+    ("job.would_stop", "[{id}] {command} を停止します"),
+    ("menu.choose", "番号か名前を入力してください"),
+    ("menu.choose_many", "番号か名前をカンマ区切りで入力してください"),
+    ("menu.no_choice", "`{name}` という選択肢はありません"),
+    ("menu.selected", "(選択済み)"),
+    ("number.at_least", "{min} 以上にしてください"),
+    ("number.at_most", "{max} 以下にしてください"),
+    ("number.expected_duration", "90s、1m30s、250ms のような時間を入力してください"),
+    ("number.expected_float", "数値を入力してください"),
+    ("number.expected_integer", "整数を入力してください"),
+    ("number.expected_size", "512MiB や 2G のようなサイズを入力してください"),
+    ("number.invalid_size", "無効なサイズ: {size}"),
+    ("number.size_too_large", "サイズが大きすぎます: {size}"),
+    ("number.unknown_unit", "不明なサイズの単位: {unit}"),
+    ("plugin.failed", "プラグイン {plugin} が失敗しました: {error}"),
+    ("prompt.back", "前の手順に戻りました"),
+    ("prompt.cancelled", "入力が取り消されました"),
+    ("prompt.invalid_answer", "`{key}` の回答が無効です: {message}"),
+    ("prompt.no_items", "選択肢がありません"),
+    ("prompt.not_interactive", "\"{prompt}\" を尋ねられません: 対話型の端末で実行されていません (`{key}` の回答を指定してください)"),
+    ("prompt.required", "値を入力してください"),
+    ("prompt.terminal_error", "端末のエラー: {error}"),
+    ("remote.auth_failed", "認証に失敗しました"),
+    ("remote.line_too_long", "コマンドが {limit} バイトを超えています"),
+    ("remote.not_allowed", "`{command}` はリモートから実行できません"),
+    ("schedule.bad_interval", "`{interval}` は 30s、5m、1h のような時間ではありません"),
+    ("schedule.none", "予定されたコマンドはありません"),
+    ("schedule.not_repeatable", "`{command}` は繰り返し実行できません"),
+    ("script.bad_signature", "{path} は実行できません: {error}"),
+    ("script.unreadable", "{path} を読み込めません: {error}"),
+    ("script.unsigned", "{path} には読み込める署名がないため実行できません: {error}"),
+    ("select.at_least.other", "{count} 個以上選択してください"),
+    ("select.at_most.other", "{count} 個まで選択してください"),
+    ("select.filter_hint", "(入力して絞り込み)"),
+    ("select.no_matches", "一致する項目はありません"),
+    ("session.save_failed", "セッションを保存できません: {error}"),
+    ("snippet.none", "スニペットが設定されていません"),
+    ("snippet.not_found", "`{name}` という名前のスニペットはありません"),
+    ("status", "状態: {status}"),
+    ("summary.commands", "実行したコマンド: {count}"),
+    ("summary.failures", "失敗: {count}"),
+    ("summary.slowest", "時間のかかったコマンド:"),
+    ("summary.time", "コマンドの実行時間: {elapsed}"),
+    ("update.available", "バージョン {version} が利用できます (現在は {current})"),
+    ("update.failed", "更新できません: {error}"),
+    ("update.installed", "バージョン {version} をインストールしました。再起動すると使用されます"),
+    ("update.latest", "バージョン {current} が最新です"),
+    ("update.would_install", "バージョン {version} をインストールします"),
+];
+
+/// Message texts by id, for one language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Catalog {
+    language: String,
+    messages: HashMap<String, String>,
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self::english()
+    }
+}
+
+impl Catalog {
+    /// Returns the framework's messages in English.
+    pub fn english() -> Self {
+        Self::from_table("en", ENGLISH)
+    }
+
+    /// Returns the framework's messages in Japanese.
+    pub fn japanese() -> Self {
+        Self::from_table("ja", JAPANESE)
+    }
+
+    /// Returns the built-in catalog for the two-letter `language`, such as
+    /// `ja`, if there is one.
+    pub fn for_language(language: &str) -> Option<Self> {
+        match language {
+            "en" => Some(Self::english()),
+            "ja" => Some(Self::japanese()),
+            _ => None,
+        }
+    }
+
+    /// Returns the built-in catalog for the user's language, from
+    /// `LC_ALL`, `LC_MESSAGES` or `LANG`, or English if there is none.
+    pub fn from_env() -> Self {
+        Self::for_language(&language()).unwrap_or_default()
+    }
+
+    /// Parses a catalog for `language` from `id = text` lines.
+    ///
+    /// Blank lines and lines starting with `#` are ignored, and texts may
+    /// be wrapped in double quotes to keep surrounding spaces.
+    pub fn parse(language: &str, text: &str) -> Result<Self, String> {
+        let mut catalog = Self { language: language.to_string(), messages: HashMap::new() };
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (id, text) = line.split_once('=').ok_or_else(|| format!("line {}: expected `id = text`", n + 1))?;
+            let text = text.trim();
+            let text = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')).unwrap_or(text);
+            catalog.set(id.trim(), text);
+        }
+        Ok(catalog)
+    }
+
+    /// Returns the ids the framework uses, with their English text.
+    pub fn ids() -> impl Iterator<Item = (&'static str, &'static str)> {
+        ENGLISH.iter().copied()
+    }
+
+    /// Returns the two-letter language of the catalog.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Sets the text for `id`.
+    pub fn set(&mut self, id: impl Into<String>, text: impl Into<String>) {
+        self.messages.insert(id.into(), text.into());
+    }
+
+    /// Returns the text for `id`, in English if the catalog does not have
+    /// it, or `id` itself if no catalog does.
+    pub fn get<'a>(&'a self, id: &'a str) -> &'a str {
+        self.messages
+            .get(id)
+            .map(String::as_str)
+            .or_else(|| ENGLISH.iter().find(|(known, _)| *known == id).map(|(_, text)| *text))
+            .unwrap_or(id)
+    }
+
+    /// Returns the text for `id` with each `{name}` replaced by the value
+    /// given for `name` in `args`.
+    pub fn format(&self, id: &str, args: &[(&str, &dyn Display)]) -> String {
+        fill(self.get(id), args)
+    }
+
+    /// Returns the text for `id.one` if `count` is 1 and `id.other`
+    /// otherwise, with `{count}` and the placeholders in `args` filled in.
+    /// Catalogs for languages without plural forms only need `id.other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::locale::Catalog;
+    ///
+    /// let english = Catalog::english();
+    /// assert_eq!(english.format_count("select.at_least", 1, &[]), "select at least 1 item");
+    /// assert_eq!(english.format_count("select.at_least", 2, &[]), "select at least 2 items");
+    /// ```
+    pub fn format_count(&self, id: &str, count: usize, args: &[(&str, &dyn Display)]) -> String {
+        let one = format!("{id}.one");
+        let other = format!("{id}.other");
+        let text = match (self.messages.get(&one), self.messages.get(&other)) {
+            (Some(text), _) if count == 1 => text.as_str(),
+            (_, Some(text)) => text.as_str(),
+            _ => self.get(if count == 1 { &one } else { &other }),
+        };
+        let mut args = args.to_vec();
+        args.push(("count", &count));
+        fill(text, &args)
+    }
+
+    fn from_table(language: &str, table: &[(&str, &str)]) -> Self {
+        let messages = table.iter().map(|(id, text)| (id.to_string(), text.to_string())).collect();
+        Self { language: language.to_string(), messages }
+    }
+}
+
+/// Replaces each `{name}` in `text` with the value for `name` in `args`,
+/// leaving unknown placeholders as they are.
+fn fill(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after
+            .find('}')
+            .and_then(|end| args.iter().find(|(name, _)| *name == &after[..end]).map(|(_, value)| (end, value)));
+        match value {
+            Some((end, value)) => {
+                let _ = fmt::write(&mut out, format_args!("{value}"));
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Returns the user's two-letter language from `LC_ALL`, `LC_MESSAGES` or
+/// `LANG`, or an empty string if none is set.
+pub(crate) fn language() -> String {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|v| !v.is_empty())
+        .unwrap_or_default();
+    locale.get(..2).unwrap_or("").to_string()
+}
+
+static CURRENT: RwLock<Option<Arc<Catalog>>> = RwLock::new(None);
+
+/// Returns the catalog the framework's messages are taken from.
+pub fn current() -> Arc<Catalog> {
+    let current = CURRENT.read().unwrap_or_else(|e| e.into_inner());
+    current.clone().unwrap_or_default()
+}
+
+/// Replaces the catalog the framework's messages are taken from.
+pub fn set_catalog(catalog: Catalog) {
+    *CURRENT.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(catalog));
+}
+
+/// Returns the text for `id` in the [current] catalog, with each
+/// `{name}` replaced by the value given for `name` in `args`.
+pub fn text(id: &str, args: &[(&str, &dyn Display)]) -> String {
+    current().format(id, args)
+}
+
+/// Returns the singular or plural text for `id` in the
+/// [current] catalog; see [`Catalog::format_count`].
+pub fn text_count(id: &str, count: usize, args: &[(&str, &dyn Display)]) -> String {
+    current().format_count(id, count, args)
+}
@@ -0,0 +1,176 @@
+//! Counting what REPLs do, in the Prometheus text format.
+//!
+//! A [`Metrics`] handle collects how many times each command ran, how many
+//! of those reported an error, how long they took and how many remote
+//! sessions are connected. Give clones of it to the REPLs to measure with
+//! `Repl::with_metrics` and to the servers with `with_metrics`, then
+//! expose it: an `HttpServer` with metrics answers `GET /metrics`, and
+//! [`Metrics::push`] sends the values to a Prometheus push gateway.
+//!
+//! Commands are labeled with their first word, after alias expansion. A
+//! command counts as failed when it writes error output.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::Duration;
+//! use mycli::metrics::Metrics;
+//!
+//! let metrics = Metrics::new("myapp");
+//! metrics.observe("deploy web", Duration::from_millis(120), false);
+//! metrics.observe("deploy db", Duration::from_secs(3), true);
+//!
+//! let text = metrics.render();
+//! assert!(text.contains(r#"myapp_commands_total{command="deploy"} 2"#));
+//! assert!(text.contains(r#"myapp_command_failures_total{command="deploy"} 1"#));
+//! assert!(text.contains(r#"myapp_command_duration_seconds_bucket{command="deploy",le="0.25"} 1"#));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+// The upper bounds of the duration histogram buckets, in seconds; the
+// defaults of the Prometheus client libraries.
+const BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+#[derive(Default)]
+struct CommandMetrics {
+    total: u64,
+    failures: u64,
+    // Runs that took at most each bucket's bound; not cumulative.
+    buckets: [u64; BUCKETS.len()],
+    seconds: f64,
+}
+
+#[derive(Default)]
+struct State {
+    commands: BTreeMap<String, CommandMetrics>,
+    sessions: u64,
+}
+
+/// A cloneable handle to the metrics of a program; clones share the same
+/// values.
+#[derive(Clone)]
+pub struct Metrics {
+    namespace: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl std::fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Metrics").field("namespace", &self.namespace).finish_non_exhaustive()
+    }
+}
+
+impl Metrics {
+    /// Creates empty metrics, named with the prefix `namespace`, such as
+    /// `myapp` for `myapp_commands_total`.
+    pub fn new(namespace: &str) -> Self {
+        Self { namespace: namespace.to_string(), state: Arc::default() }
+    }
+
+    /// Records that `command` ran for `took`, and whether it failed. REPLs
+    /// given the metrics call this for every command.
+    pub fn observe(&self, command: &str, took: Duration, failed: bool) {
+        let name = command.split_whitespace().next().unwrap_or(command);
+        let mut state = self.lock();
+        let metrics = state.commands.entry(name.to_string()).or_default();
+        metrics.total += 1;
+        metrics.failures += u64::from(failed);
+        let seconds = took.as_secs_f64();
+        metrics.seconds += seconds;
+        if let Some(bucket) = BUCKETS.iter().position(|&bound| seconds <= bound) {
+            metrics.buckets[bucket] += 1;
+        }
+    }
+
+    /// Counts a remote session as connected until the returned guard is
+    /// dropped.
+    pub fn session(&self) -> SessionGuard {
+        self.lock().sessions += 1;
+        SessionGuard { metrics: self.clone() }
+    }
+
+    /// Returns the metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let ns = &self.namespace;
+        let state = self.lock();
+        let mut text = String::new();
+        let family = |text: &mut String, name: &str, kind: &str, help: &str| {
+            let _ = writeln!(text, "# HELP {ns}_{name} {help}\n# TYPE {ns}_{name} {kind}");
+        };
+
+        family(&mut text, "commands_total", "counter", "Commands run, by name.");
+        for (command, metrics) in &state.commands {
+            let _ = writeln!(text, "{ns}_commands_total{{command=\"{}\"}} {}", escape(command), metrics.total);
+        }
+        family(&mut text, "command_failures_total", "counter", "Commands that reported an error, by name.");
+        for (command, metrics) in &state.commands {
+            let _ = writeln!(text, "{ns}_command_failures_total{{command=\"{}\"}} {}", escape(command), metrics.failures);
+        }
+        family(&mut text, "command_duration_seconds", "histogram", "How long commands took, by name.");
+        for (command, metrics) in &state.commands {
+            let name = format!("{ns}_command_duration_seconds");
+            let label = escape(command);
+            let mut cumulative = 0;
+            for (bound, count) in BUCKETS.iter().zip(metrics.buckets) {
+                cumulative += count;
+                let _ = writeln!(text, "{name}_bucket{{command=\"{label}\",le=\"{bound}\"}} {cumulative}");
+            }
+            let _ = writeln!(text, "{name}_bucket{{command=\"{label}\",le=\"+Inf\"}} {}", metrics.total);
+            let _ = writeln!(text, "{name}_sum{{command=\"{label}\"}} {}", metrics.seconds);
+            let _ = writeln!(text, "{name}_count{{command=\"{label}\"}} {}", metrics.total);
+        }
+        family(&mut text, "remote_sessions", "gauge", "Remote sessions currently connected.");
+        let _ = writeln!(text, "{ns}_remote_sessions {}", state.sessions);
+        text
+    }
+
+    /// Sends the metrics to the Prometheus push gateway at `gateway`, such
+    /// as `localhost:9091`, under the job name `job`, replacing what was
+    /// pushed for the job before.
+    pub fn push(&self, gateway: impl ToSocketAddrs, job: &str) -> io::Result<()> {
+        let body = self.render();
+        let mut stream = TcpStream::connect(gateway)?;
+        let host = stream.peer_addr()?;
+        write!(
+            stream,
+            "PUT /metrics/job/{job} HTTP/1.1\r\nHost: {host}\r\nContent-Type: text/plain; version=0.0.4\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+        stream.flush()?;
+        let mut status = String::new();
+        BufReader::new(stream).read_line(&mut status)?;
+        match status.split_whitespace().nth(1) {
+            Some(code) if code.starts_with('2') => Ok(()),
+            _ => Err(io::Error::other(format!("the push gateway answered {}", status.trim()))),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Keeps a remote session counted by [`Metrics::session`] while it is alive.
+#[derive(Debug)]
+pub struct SessionGuard {
+    metrics: Metrics,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        let mut state = self.metrics.lock();
+        state.sessions = state.sessions.saturating_sub(1);
+    }
+}
+
+/// Escapes a label value.
+fn escape(value: &str) -> String {
+    value.replace('\\', r"\\").replace('"', "\\\"").replace('\n', r"\n")
+}
@@ -0,0 +1,53 @@
+//! A mode for screen readers and other assistive technology.
+//!
+//! Screen readers read text as it is written, so output that redraws
+//! itself, such as arrow-key menus and status lines pinned to the bottom
+//! of the terminal, is read out over and over or not at all. With the
+//! accessible mode on:
+//!
+//! * menus from [`prompt`](crate::prompt) list their items with numbers
+//!   and read the answer as a line of text, and number and path prompts
+//!   read a plain line instead of reacting to single keys;
+//! * validation errors in prompts say `Error:` instead of showing a red
+//!   mark;
+//! * a [`StatusBar`](crate::repl::StatusBar) is not redrawn; its text is
+//!   printed as an ordinary line whenever it changes.
+//!
+//! The mode is on when the `ACCESSIBLE` environment variable is set to
+//! anything but an empty string or `0`, unless [`set_enabled`] says
+//! otherwise.
+//!
+//! # Examples
+//!
+//! ```
+//! use mycli::output::accessible;
+//!
+//! accessible::set_enabled(true);
+//! assert!(accessible::is_enabled());
+//! ```
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+const UNSET: u8 = 0;
+const OFF: u8 = 1;
+const ON: u8 = 2;
+
+static MODE: AtomicU8 = AtomicU8::new(UNSET);
+
+/// Returns `true` if the accessible mode is on.
+pub fn is_enabled() -> bool {
+    match MODE.load(Ordering::Relaxed) {
+        UNSET => {
+            let on = std::env::var_os("ACCESSIBLE").is_some_and(|v| !v.is_empty() && v != "0");
+            MODE.store(if on { ON } else { OFF }, Ordering::Relaxed);
+            on
+        }
+        mode => mode == ON,
+    }
+}
+
+/// Turns the accessible mode on or off, overriding the `ACCESSIBLE`
+/// environment variable.
+pub fn set_enabled(on: bool) {
+    MODE.store(if on { ON } else { OFF }, Ordering::Relaxed);
+}
@@ -0,0 +1,128 @@
+//! Recognition and removal of ANSI escape sequences embedded in output.
+
+use std::borrow::Cow;
+
+/// A piece of a string: either printable text or an escape sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Segment<'a> {
+    Text(&'a str),
+    Escape(&'a str),
+}
+
+/// Splits `s` into runs of printable text and complete escape sequences.
+///
+/// CSI sequences (`ESC [ ... final`), OSC sequences (`ESC ] ... BEL` or
+/// `ESC ] ... ESC \`) and two-byte escapes are recognized. A truncated
+/// sequence at the end of the input is returned as an escape as well, so it
+/// is never counted as visible text.
+pub(crate) fn segments(s: &str) -> impl Iterator<Item = Segment<'_>> {
+    let mut rest = s;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let len = match rest.find('\x1b') {
+            Some(0) => {
+                let len = escape_len(rest);
+                let (seq, tail) = rest.split_at(len);
+                rest = tail;
+                return Some(Segment::Escape(seq));
+            }
+            Some(pos) => pos,
+            None => rest.len(),
+        };
+        let (text, tail) = rest.split_at(len);
+        rest = tail;
+        Some(Segment::Text(text))
+    })
+}
+
+/// Returns the byte length of the escape sequence at the start of `s`.
+fn escape_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    match bytes.get(1) {
+        Some(b'[') => bytes[2..]
+            .iter()
+            .position(|b| (0x40..=0x7e).contains(b))
+            .map_or(bytes.len(), |i| i + 3),
+        Some(b']') => {
+            let mut i = 2;
+            while i < bytes.len() {
+                match bytes[i] {
+                    0x07 => return i + 1,
+                    0x1b if bytes.get(i + 1) == Some(&b'\\') => return i + 2,
+                    _ => i += 1,
+                }
+            }
+            bytes.len()
+        }
+        Some(_) => 1 + s[1..].chars().next().map_or(0, char::len_utf8),
+        None => 1,
+    }
+}
+
+
+/// Removes all escape sequences from `s`, leaving only the printable text.
+///
+/// Borrows the input unchanged when it contains no escapes.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::ansi::strip;
+///
+/// assert_eq!(strip("\x1b[1;31merror\x1b[0m: not found"), "error: not found");
+/// assert_eq!(strip("plain"), "plain");
+/// ```
+pub fn strip(s: &str) -> Cow<'_, str> {
+    if !s.contains('\x1b') {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len());
+    for seg in segments(s) {
+        if let Segment::Text(text) = seg {
+            out.push_str(text);
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Controls whether escape sequences are kept in output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    /// Keep escapes only when the output goes to a terminal.
+    #[default]
+    Auto,
+    /// Always keep escapes, even when output is redirected.
+    Always,
+    /// Always strip escapes.
+    Never,
+}
+
+impl ColorMode {
+    /// Reads the mode from the conventional environment variables.
+    ///
+    /// A non-empty `NO_COLOR` selects [`ColorMode::Never`] and a
+    /// `CLICOLOR_FORCE` other than `0` selects [`ColorMode::Always`];
+    /// otherwise the mode is [`ColorMode::Auto`].
+    pub fn from_env() -> Self {
+        let set = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+        if set("NO_COLOR").is_some() {
+            ColorMode::Never
+        } else if set("CLICOLOR_FORCE").is_some_and(|v| v != "0") {
+            ColorMode::Always
+        } else {
+            ColorMode::Auto
+        }
+    }
+
+    /// Returns `true` if escapes should be removed, given whether the
+    /// destination is a terminal.
+    pub fn strips(self, is_terminal: bool) -> bool {
+        match self {
+            ColorMode::Auto => !is_terminal,
+            ColorMode::Always => false,
+            ColorMode::Never => true,
+        }
+    }
+}
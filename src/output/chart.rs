@@ -0,0 +1,184 @@
+//! Small inline charts: sparklines and labelled horizontal bars.
+//!
+//! # Examples
+//!
+//! ```
+//! use mycli::output::chart::sparkline;
+//!
+//! assert_eq!(sparkline(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]), "▁▂▃▄▅▆▇█");
+//! ```
+
+use std::sync::Arc;
+
+use super::term;
+use super::theme::{self, Theme, Tone};
+use super::width::{display_width, pad, Align};
+
+const TICKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Renders `values` as a sparkline scaled between their minimum and maximum.
+///
+/// Non-finite values are shown as blanks. A series of identical values is
+/// drawn at mid height.
+pub fn sparkline(values: &[f64]) -> String {
+    let finite = values.iter().copied().filter(|v| v.is_finite());
+    let min = finite.clone().fold(f64::INFINITY, f64::min);
+    let max = finite.fold(f64::NEG_INFINITY, f64::max);
+    sparkline_range(values, min, max)
+}
+
+/// Renders `values` as a sparkline on a fixed scale from `min` to `max`.
+///
+/// Values outside the range are clamped, which keeps several sparklines
+/// comparable with each other.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::chart::sparkline_range;
+///
+/// assert_eq!(sparkline_range(&[0.0, 50.0, 100.0, 150.0], 0.0, 100.0), "▁▅██");
+/// ```
+pub fn sparkline_range(values: &[f64], min: f64, max: f64) -> String {
+    values
+        .iter()
+        .map(|&v| {
+            if !v.is_finite() {
+                return ' ';
+            }
+            if max <= min {
+                return TICKS[TICKS.len() / 2 - 1];
+            }
+            let ratio = ((v - min) / (max - min)).clamp(0.0, 1.0);
+            TICKS[(ratio * (TICKS.len() - 1) as f64).round() as usize]
+        })
+        .collect()
+}
+
+/// A horizontal bar chart with one labelled bar per row.
+///
+/// Bars are drawn with eighth-block characters for sub-cell precision and
+/// styled with the theme's info style.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::chart::BarChart;
+/// use mycli::output::theme::Theme;
+///
+/// let out = BarChart::new()
+///     .bar("cpu", 25.0)
+///     .bar("memory", 100.0)
+///     .max(100.0)
+///     .width(24)
+///     .theme(Theme::plain())
+///     .render();
+/// assert_eq!(out, "\
+/// cpu     ███          25
+/// memory  ████████████ 100
+/// ");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BarChart {
+    bars: Vec<(String, f64)>,
+    max: Option<f64>,
+    width: Option<usize>,
+    unit: String,
+    theme: Option<Arc<Theme>>,
+}
+
+impl BarChart {
+    /// Creates an empty chart.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a bar.
+    pub fn bar(mut self, label: impl Into<String>, value: f64) -> Self {
+        self.bars.push((label.into(), value));
+        self
+    }
+
+    /// Sets the value of a full-length bar. Defaults to the largest value.
+    pub fn max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Sets the total width, overriding the terminal width.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Sets a suffix printed after each value, such as `"ms"` or `"%"`.
+    pub fn unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = unit.into();
+        self
+    }
+
+    /// Uses `theme` instead of the current theme.
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = Some(Arc::new(theme));
+        self
+    }
+
+    /// Renders the chart, each line terminated by a newline.
+    pub fn render(&self) -> String {
+        let theme = self.theme.clone().unwrap_or_else(theme::current);
+        let width = self.width.unwrap_or_else(term::width);
+
+        let values: Vec<String> = self
+            .bars
+            .iter()
+            .map(|(_, value)| format!("{}{}", format_value(*value), self.unit))
+            .collect();
+        let label_width = self.bars.iter().map(|(l, _)| display_width(l)).max().unwrap_or(0);
+        let value_width = values.iter().map(|v| display_width(v)).max().unwrap_or(0);
+        let bar_width = width.saturating_sub(label_width + value_width + 3).max(1);
+        let max = self.max.unwrap_or_else(|| {
+            self.bars.iter().map(|(_, v)| *v).fold(0.0, f64::max)
+        });
+
+        let mut out = String::new();
+        for ((label, value), text) in self.bars.iter().zip(&values) {
+            let ratio = if max > 0.0 { (value / max).clamp(0.0, 1.0) } else { 0.0 };
+            let bar = bar(ratio, bar_width);
+            let bar = bar.trim_end();
+            out.push_str(&pad(label, label_width, Align::Left));
+            out.push_str("  ");
+            out.push_str(&theme.paint(Tone::Info, bar));
+            out.push_str(&" ".repeat(bar_width - display_width(bar) + 1));
+            out.push_str(text);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl std::fmt::Display for BarChart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.render())
+    }
+}
+
+/// Draws a bar filling `ratio` of `width` cells, padded with spaces.
+fn bar(ratio: f64, width: usize) -> String {
+    let eighths = (ratio * width as f64 * 8.0).round() as usize;
+    let full = eighths / 8;
+    let mut out = "█".repeat(full);
+    if full < width {
+        out.push(EIGHTHS[eighths % 8]);
+        out.push_str(&" ".repeat(width - full - 1));
+    }
+    out
+}
+
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{value:.1}")
+    }
+}
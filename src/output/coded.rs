@@ -0,0 +1,70 @@
+//! Errors with stable codes, for programs reading the output.
+
+use std::fmt;
+
+/// An error with a stable code and details, reported with
+/// [`Output::report`](super::Output::report).
+///
+/// People read the message; programs driving the REPL, such as through
+/// [`rpc`](crate::rpc), branch on the code, which stays the same when the
+/// wording changes. Codes are short dotted names such as `job.not_found`;
+/// the ones the REPL uses for its own errors are listed in the
+/// [`rpc`](crate::rpc) module.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::{BufferSink, CodedError, Output};
+///
+/// let buffer = BufferSink::new();
+/// let out = Output::new(buffer.clone());
+///
+/// let error = CodedError::new("deploy.locked", "production is locked").detail("environment", "production");
+/// out.report(&error).unwrap();
+///
+/// assert_eq!(buffer.take(), "Error: production is locked\n");
+/// assert_eq!(error.code(), "deploy.locked");
+/// assert_eq!(error.details(), [("environment".to_string(), "production".to_string())]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodedError {
+    code: String,
+    message: String,
+    details: Vec<(String, String)>,
+}
+
+impl CodedError {
+    /// Creates an error with `code` saying `message`.
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { code: code.into(), message: message.into(), details: Vec::new() }
+    }
+
+    /// Adds a detail, such as the job or file the error is about.
+    pub fn detail(mut self, key: impl Into<String>, value: impl ToString) -> Self {
+        self.details.push((key.into(), value.to_string()));
+        self
+    }
+
+    /// Returns the code.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Returns the message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the details in the order they were added.
+    pub fn details(&self) -> &[(String, String)] {
+        &self.details
+    }
+}
+
+impl fmt::Display for CodedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CodedError {}
@@ -0,0 +1,174 @@
+//! Columnar layout of short strings, similar to `ls`.
+//!
+//! # Examples
+//!
+//! ```
+//! use mycli::output::Columns;
+//!
+//! let names = ["alpha", "beta", "gamma", "delta", "epsilon"];
+//! let out = Columns::new().width(30).render(&names);
+//! assert_eq!(out, "alpha  gamma  epsilon\nbeta   delta\n");
+//! ```
+
+use super::term;
+use super::width::display_width;
+
+/// The order in which items fill the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Order {
+    /// Fill each column top to bottom before moving right (like `ls`).
+    #[default]
+    Vertical,
+    /// Fill each row left to right before moving down (like `ls -x`).
+    Horizontal,
+}
+
+/// Lays out a list of strings into as many columns as fit the terminal width.
+///
+/// Each column is as wide as its longest entry, so short names pack tightly
+/// while a single long name only widens its own column.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::columns::{Columns, Order};
+///
+/// let out = Columns::new()
+///     .width(20)
+///     .order(Order::Horizontal)
+///     .render(&["a", "b", "c", "d", "e"]);
+/// assert_eq!(out, "a  b  c  d  e\n");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Columns {
+    width: Option<usize>,
+    spacing: usize,
+    order: Order,
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Columns {
+    /// Creates a layout using the current terminal width and two spaces
+    /// between columns.
+    pub fn new() -> Self {
+        Self {
+            width: None,
+            spacing: 2,
+            order: Order::Vertical,
+        }
+    }
+
+    /// Sets the total width available, overriding the terminal width.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Sets the number of spaces between columns.
+    pub fn spacing(mut self, spacing: usize) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Sets the fill order of the grid.
+    pub fn order(mut self, order: Order) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Renders the items as a grid, one line per row, each line terminated
+    /// by a newline.
+    ///
+    /// Returns an empty string when `items` is empty. Items wider than the
+    /// available width are placed one per line.
+    pub fn render<S: AsRef<str>>(&self, items: &[S]) -> String {
+        let lines = self.lines(items);
+        let mut out = String::new();
+        for line in lines {
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Renders the items as a grid, returning each row without a trailing
+    /// newline.
+    pub fn lines<S: AsRef<str>>(&self, items: &[S]) -> Vec<String> {
+        if items.is_empty() {
+            return Vec::new();
+        }
+
+        let widths: Vec<usize> = items.iter().map(|s| display_width(s.as_ref())).collect();
+        let total = self.width.unwrap_or_else(term::width);
+        let (rows, col_widths) = self.fit(&widths, total);
+        let cols = col_widths.len();
+
+        let mut lines = Vec::with_capacity(rows);
+        for row in 0..rows {
+            let mut line = String::new();
+            for (col, col_width) in col_widths.iter().enumerate() {
+                let Some(index) = self.index(row, col, rows, cols, items.len()) else {
+                    continue;
+                };
+                if col > 0 {
+                    line.push_str(&" ".repeat(self.spacing));
+                }
+                line.push_str(items[index].as_ref());
+                line.push_str(&" ".repeat(col_width - widths[index]));
+            }
+            lines.push(line.trim_end().to_string());
+        }
+        lines
+    }
+
+    /// Finds the layout with the most columns that fits in `total`, returning
+    /// the row count and the width of each column.
+    fn fit(&self, widths: &[usize], total: usize) -> (usize, Vec<usize>) {
+        let n = widths.len();
+        for cols in (1..=n).rev() {
+            let rows = n.div_ceil(cols);
+            // With vertical fill, fewer columns may be needed than requested.
+            let cols = n.div_ceil(rows);
+            let mut col_widths = vec![0; cols];
+            for (i, w) in widths.iter().enumerate() {
+                let col = match self.order {
+                    Order::Vertical => i / rows,
+                    Order::Horizontal => i % cols,
+                };
+                col_widths[col] = col_widths[col].max(*w);
+            }
+            let used = col_widths.iter().sum::<usize>() + self.spacing * (cols - 1);
+            if used <= total || cols == 1 {
+                return (rows, col_widths);
+            }
+        }
+        unreachable!("a single column always fits")
+    }
+
+    fn index(&self, row: usize, col: usize, rows: usize, cols: usize, len: usize) -> Option<usize> {
+        let index = match self.order {
+            Order::Vertical => col * rows + row,
+            Order::Horizontal => row * cols + col,
+        };
+        (index < len).then_some(index)
+    }
+}
+
+/// Lays out `items` in columns fitting the current terminal width.
+///
+/// This is a shorthand for `Columns::new().render(items)`.
+///
+/// # Examples
+///
+/// ```
+/// let out = mycli::output::columns(&["help", "quit"]);
+/// assert!(out.contains("help"));
+/// ```
+pub fn columns<S: AsRef<str>>(items: &[S]) -> String {
+    Columns::new().render(items)
+}
@@ -0,0 +1,169 @@
+//! Aligned `Field: value` views of a single record, for `describe`-style
+//! commands.
+//!
+//! # Examples
+//!
+//! ```
+//! use mycli::output::detail::DetailView;
+//! use mycli::output::theme::Theme;
+//!
+//! let out = DetailView::new()
+//!     .field("Name", "web-1")
+//!     .field("Status", "running")
+//!     .group("Network")
+//!     .field("Address", "10.0.0.12")
+//!     .theme(Theme::plain())
+//!     .width(40)
+//!     .render();
+//! assert_eq!(out, "\
+//! Name:       web-1
+//! Status:     running
+//!
+//! Network
+//!   Address:  10.0.0.12
+//! ");
+//! ```
+
+use std::sync::Arc;
+
+use super::term;
+use super::theme::{self, Theme, Tone};
+use super::width::display_width;
+use super::wrap::Wrap;
+
+#[derive(Debug, Clone)]
+enum Entry {
+    Field { label: String, value: String },
+    Group(String),
+}
+
+/// A record rendered as labelled values in an aligned column.
+///
+/// Values that do not fit the width are wrapped, with continuation lines
+/// starting under the value column. Fields added after [`group`] are
+/// indented under the group's heading.
+///
+/// [`group`]: DetailView::group
+#[derive(Debug, Clone, Default)]
+pub struct DetailView {
+    entries: Vec<Entry>,
+    width: Option<usize>,
+    theme: Option<Arc<Theme>>,
+}
+
+impl DetailView {
+    /// Creates an empty view.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a field. `value` may contain newlines to force line breaks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::output::detail::DetailView;
+    /// use mycli::output::theme::Theme;
+    ///
+    /// let out = DetailView::new().field("Notes", "\nsecond line").theme(Theme::plain()).width(40).render();
+    /// assert_eq!(out, "Notes:\n        second line\n");
+    /// ```
+    pub fn field(mut self, label: impl Into<String>, value: impl ToString) -> Self {
+        self.entries.push(Entry::Field {
+            label: label.into(),
+            value: value.to_string(),
+        });
+        self
+    }
+
+    /// Adds a field only if `value` is `Some`.
+    pub fn field_opt(self, label: impl Into<String>, value: Option<impl ToString>) -> Self {
+        match value {
+            Some(value) => self.field(label, value),
+            None => self,
+        }
+    }
+
+    /// Starts a group of fields under the heading `title`.
+    pub fn group(mut self, title: impl Into<String>) -> Self {
+        self.entries.push(Entry::Group(title.into()));
+        self
+    }
+
+    /// Sets the total width, overriding the terminal width.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Uses `theme` instead of the current theme.
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = Some(Arc::new(theme));
+        self
+    }
+
+    /// Renders the view, each line terminated by a newline.
+    pub fn render(&self) -> String {
+        let theme = self.theme.clone().unwrap_or_else(theme::current);
+        let width = self.width.unwrap_or_else(term::width);
+
+        // Align every value to the same column, including grouped fields.
+        let mut indent = 0;
+        let mut column = 0;
+        for entry in &self.entries {
+            match entry {
+                Entry::Group(_) => indent = 2,
+                Entry::Field { label, .. } => {
+                    column = column.max(indent + display_width(label) + 1);
+                }
+            }
+        }
+        let column = column + 2;
+
+        let mut out = String::new();
+        let mut indent = 0;
+        for (i, entry) in self.entries.iter().enumerate() {
+            match entry {
+                Entry::Group(title) => {
+                    if i > 0 {
+                        out.push('\n');
+                    }
+                    out.push_str(&theme.paint(Tone::Accent, title));
+                    out.push('\n');
+                    indent = 2;
+                }
+                Entry::Field { label, value } => {
+                    let label = format!("{}{label}:", " ".repeat(indent));
+                    if value.trim().is_empty() {
+                        out.push_str(&theme.paint(Tone::Muted, &label));
+                        out.push('\n');
+                        continue;
+                    }
+                    let padding = " ".repeat(column - display_width(&label));
+                    let lines = Wrap::new().width(width.max(column + 10) - column).lines(value);
+                    for (n, line) in lines.iter().enumerate() {
+                        // The wrapped value has no indent, so blank lines
+                        // get none either.
+                        if n == 0 {
+                            out.push_str(&theme.paint(Tone::Muted, &label));
+                            if !line.is_empty() {
+                                out.push_str(&padding);
+                            }
+                        } else if !line.is_empty() {
+                            out.push_str(&" ".repeat(column));
+                        }
+                        out.push_str(line);
+                        out.push('\n');
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+impl std::fmt::Display for DetailView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.render())
+    }
+}
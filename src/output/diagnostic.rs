@@ -0,0 +1,128 @@
+//! Error messages that point at the part of the input they are about.
+
+use std::fmt;
+use std::ops::Range;
+
+use super::theme::{self, Tone};
+use super::width::display_width;
+use crate::locale::text;
+
+/// An error about a command line, shown with the offending part of the
+/// line underlined.
+///
+/// ```text
+/// Error: unknown flag `--forze`
+///  | deploy prod --forze
+///  |             ^^^^^^^ not a flag of `deploy`
+///  = help: did you mean `--force`?
+/// ```
+///
+/// [`Display`](fmt::Display) writes it without colors; [`render`] paints
+/// the underline and the help with the current [theme](theme::current).
+///
+/// [`render`]: Diagnostic::render
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::Diagnostic;
+///
+/// let line = "deploy prod --forze";
+/// let error = Diagnostic::new("unknown flag `--forze`", line, 12..19)
+///     .label("not a flag of `deploy`")
+///     .help("did you mean `--force`?");
+///
+/// assert_eq!(
+///     error.to_string(),
+///     "Error: unknown flag `--forze`\n \
+///      | deploy prod --forze\n \
+///      |             ^^^^^^^ not a flag of `deploy`\n \
+///      = help: did you mean `--force`?"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    message: String,
+    line: String,
+    span: Range<usize>,
+    label: Option<String>,
+    help: Option<String>,
+}
+
+impl Diagnostic {
+    /// Creates an error saying `message` about the bytes `span` of `line`.
+    ///
+    /// A span that is empty, such as one at the end of the line for
+    /// missing input, is shown as a single caret.
+    pub fn new(message: impl Into<String>, line: impl Into<String>, span: Range<usize>) -> Self {
+        let line = line.into();
+        let end = clamp(&line, span.end);
+        let span = clamp(&line, span.start).min(end)..end;
+        Self { message: message.into(), line, span, label: None, help: None }
+    }
+
+    /// Sets the text shown after the underline.
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Sets a hint shown below the line, such as how to fix the error.
+    pub fn help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Returns the message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the bytes of the line the error is about.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Returns the error with its underline and help painted.
+    pub fn render(&self) -> String {
+        self.format(true)
+    }
+
+    fn format(&self, color: bool) -> String {
+        let theme = theme::current();
+        let paint = |tone, s: &str| if color { theme.paint(tone, s) } else { s.to_string() };
+        // Tabs are one byte, like the space shown instead.
+        let line = self.line.replace('\t', " ");
+        let indent = display_width(&line[..self.span.start]);
+        let width = display_width(&line[self.span.clone()]).max(1);
+        let mut underline = "^".repeat(width);
+        if let Some(label) = &self.label {
+            underline = format!("{underline} {label}");
+        }
+        let gutter = paint(Tone::Muted, " |");
+        let heading = text("error", &[("message", &self.message)]);
+        let mut out = format!("{heading}\n{gutter} {line}\n{gutter} {}{}", " ".repeat(indent), paint(Tone::Error, &underline));
+        if let Some(help) = &self.help {
+            let label = format!("= {}:", text("help", &[]));
+            out.push_str(&format!("\n {} {help}", paint(Tone::Muted, &label)));
+        }
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(false))
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Moves `at` back onto a character boundary within `line`.
+fn clamp(line: &str, at: usize) -> usize {
+    let mut at = at.min(line.len());
+    while !line.is_char_boundary(at) {
+        at -= 1;
+    }
+    at
+}
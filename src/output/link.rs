@@ -0,0 +1,72 @@
+//! Terminal hyperlinks using the OSC 8 escape sequence.
+//!
+//! # Examples
+//!
+//! ```
+//! use mycli::output::link::link_with;
+//!
+//! assert_eq!(
+//!     link_with("TICKET-42", "https://tracker.example/42", true),
+//!     "\x1b]8;;https://tracker.example/42\x1b\\TICKET-42\x1b]8;;\x1b\\",
+//! );
+//! assert_eq!(
+//!     link_with("TICKET-42", "https://tracker.example/42", false),
+//!     "TICKET-42 (https://tracker.example/42)",
+//! );
+//! ```
+
+use std::io::{self, IsTerminal};
+
+/// Formats `text` as a hyperlink to `url` if the terminal supports it.
+///
+/// Terminals without hyperlink support get the URL in parentheses after the
+/// text instead, or just the URL when it is the same as the text.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::link;
+///
+/// println!("See {}", link("the runbook", "https://wiki.example/runbook"));
+/// ```
+pub fn link(text: &str, url: &str) -> String {
+    link_with(text, url, supports_hyperlinks())
+}
+
+/// Formats a hyperlink, choosing explicitly between the OSC 8 sequence and
+/// the plain-text fallback.
+pub fn link_with(text: &str, url: &str, supported: bool) -> String {
+    if supported {
+        format!("\x1b]8;;{url}\x1b\\{text}\x1b]8;;\x1b\\")
+    } else if text == url {
+        url.to_string()
+    } else {
+        format!("{text} ({url})")
+    }
+}
+
+/// Returns `true` if stdout is a terminal known to render OSC 8 hyperlinks.
+///
+/// Setting `FORCE_HYPERLINK` to `1` or `0` overrides the detection.
+pub fn supports_hyperlinks() -> bool {
+    if let Ok(force) = std::env::var("FORCE_HYPERLINK") {
+        return force != "0";
+    }
+    if !io::stdout().is_terminal() {
+        return false;
+    }
+
+    let var = |name| std::env::var(name).unwrap_or_default();
+    let program = var("TERM_PROGRAM");
+    let term = var("TERM");
+    matches!(
+        program.as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "Hyper" | "ghostty" | "rio"
+    ) || ["xterm-kitty", "alacritty", "foot", "wezterm", "xterm-ghostty"]
+        .iter()
+        .any(|t| term.starts_with(t))
+        || var("VTE_VERSION").parse::<u32>().is_ok_and(|v| v >= 5000)
+        || !var("WT_SESSION").is_empty()
+        || !var("KONSOLE_VERSION").is_empty()
+        || !var("DOMTERM").is_empty()
+}
@@ -0,0 +1,34 @@
+//! Helpers for formatting command output in the terminal.
+//!
+//! These utilities are independent of the REPL itself and can be used by
+//! command handlers to present their results.
+
+pub mod accessible;
+pub mod ansi;
+pub mod chart;
+pub mod coded;
+pub mod columns;
+pub mod detail;
+pub mod diagnostic;
+pub mod link;
+pub mod panel;
+pub mod sink;
+pub mod term;
+pub mod theme;
+pub mod verbosity;
+pub mod width;
+pub mod wrap;
+
+pub use ansi::ColorMode;
+pub use chart::{sparkline, BarChart};
+pub use coded::CodedError;
+pub use columns::{columns, Columns};
+pub use detail::DetailView;
+pub use diagnostic::Diagnostic;
+pub use link::link;
+pub use panel::Panel;
+pub use sink::{BufferSink, FileSink, Output, OutputSink, StdoutSink, TeeSink, WriterSink};
+pub use theme::{Style, Theme, Tone};
+pub use verbosity::Verbosity;
+pub use width::{display_width, pad, truncate, Align};
+pub use wrap::{indent, wrap, Wrap};
@@ -0,0 +1,187 @@
+//! Bordered panels for emphasizing warnings, summaries and errors.
+//!
+//! # Examples
+//!
+//! ```
+//! use mycli::output::panel::{Border, Panel};
+//! use mycli::output::theme::Theme;
+//!
+//! let out = Panel::new("3 hosts unreachable")
+//!     .title("Warning")
+//!     .border(Border::Ascii)
+//!     .theme(Theme::plain())
+//!     .render();
+//! assert_eq!(out, "\
+//! +- Warning -----------+
+//! | 3 hosts unreachable |
+//! +---------------------+
+//! ");
+//! ```
+
+use std::sync::Arc;
+
+use super::term;
+use super::theme::{self, Theme, Tone};
+use super::width::{display_width, pad, truncate, Align};
+use super::wrap::Wrap;
+
+/// The characters used to draw a panel border.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Border {
+    /// Light lines with rounded corners: `╭─╮`.
+    #[default]
+    Rounded,
+    /// Light lines with square corners: `┌─┐`.
+    Square,
+    /// Double lines: `╔═╗`.
+    Double,
+    /// Plain ASCII: `+-+`, for terminals without box-drawing glyphs.
+    Ascii,
+}
+
+struct Glyphs {
+    top_left: &'static str,
+    top_right: &'static str,
+    bottom_left: &'static str,
+    bottom_right: &'static str,
+    horizontal: &'static str,
+    vertical: &'static str,
+}
+
+impl Border {
+    fn glyphs(self) -> Glyphs {
+        let (top_left, top_right, bottom_left, bottom_right, horizontal, vertical) = match self {
+            Border::Rounded => ("╭", "╮", "╰", "╯", "─", "│"),
+            Border::Square => ("┌", "┐", "└", "┘", "─", "│"),
+            Border::Double => ("╔", "╗", "╚", "╝", "═", "║"),
+            Border::Ascii => ("+", "+", "+", "+", "-", "|"),
+        };
+        Glyphs { top_left, top_right, bottom_left, bottom_right, horizontal, vertical }
+    }
+}
+
+/// A block of text surrounded by a border, with an optional title.
+///
+/// The panel is as wide as its content needs, but never wider than the
+/// terminal; longer lines are wrapped. The border is styled with the theme's
+/// style for the panel's [`Tone`] and the title with its accent style.
+#[derive(Debug, Clone)]
+pub struct Panel {
+    body: String,
+    title: Option<String>,
+    border: Border,
+    tone: Tone,
+    width: Option<usize>,
+    max_width: Option<usize>,
+    theme: Option<Arc<Theme>>,
+}
+
+impl Panel {
+    /// Creates a panel around `body`.
+    pub fn new(body: impl Into<String>) -> Self {
+        Self {
+            body: body.into(),
+            title: None,
+            border: Border::Rounded,
+            tone: Tone::Muted,
+            width: None,
+            max_width: None,
+            theme: None,
+        }
+    }
+
+    /// Sets the title shown in the top border.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Sets the border characters.
+    pub fn border(mut self, border: Border) -> Self {
+        self.border = border;
+        self
+    }
+
+    /// Sets the tone used to style the border, such as [`Tone::Warning`].
+    pub fn tone(mut self, tone: Tone) -> Self {
+        self.tone = tone;
+        self
+    }
+
+    /// Makes the panel exactly `width` columns wide, borders included.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Limits the panel width, overriding the terminal width.
+    pub fn max_width(mut self, width: usize) -> Self {
+        self.max_width = Some(width);
+        self
+    }
+
+    /// Uses `theme` instead of the current theme.
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = Some(Arc::new(theme));
+        self
+    }
+
+    /// Renders the panel, each line terminated by a newline.
+    pub fn render(&self) -> String {
+        let theme = self.theme.clone().unwrap_or_else(theme::current);
+        let border = theme.style(self.tone);
+        let glyphs = self.border.glyphs();
+
+        let max = self.max_width.unwrap_or_else(term::width).max(5);
+        let title_width = self.title.as_deref().map_or(0, |t| display_width(t) + 4);
+        let width = match self.width {
+            Some(width) => width.max(5),
+            None => {
+                let content = self.body.lines().map(display_width).max().unwrap_or(0);
+                (content + 4).max(title_width + 2).min(max)
+            }
+        };
+        let inner = width - 4;
+
+        let mut out = String::new();
+
+        // Top border, with the title embedded after the corner.
+        let lead = format!("{}{}", glyphs.top_left, glyphs.horizontal);
+        out.push_str(&border.paint(&lead));
+        let mut used = 2;
+        if let Some(title) = &self.title {
+            let title = truncate(title, width.saturating_sub(6));
+            used += display_width(&title) + 2;
+            out.push(' ');
+            out.push_str(&theme.paint(Tone::Accent, &title));
+            out.push(' ');
+        }
+        let rest = glyphs.horizontal.repeat(width.saturating_sub(used + 1));
+        out.push_str(&border.paint(&format!("{rest}{}", glyphs.top_right)));
+        out.push('\n');
+
+        let side = border.paint(glyphs.vertical);
+        for line in Wrap::new().width(inner).lines(&self.body) {
+            out.push_str(&side);
+            out.push(' ');
+            out.push_str(&pad(&line, inner, Align::Left));
+            out.push(' ');
+            out.push_str(&side);
+            out.push('\n');
+        }
+
+        let bottom = glyphs.horizontal.repeat(width - 2);
+        out.push_str(&border.paint(&format!(
+            "{}{bottom}{}",
+            glyphs.bottom_left, glyphs.bottom_right
+        )));
+        out.push('\n');
+        out
+    }
+}
+
+impl std::fmt::Display for Panel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.render())
+    }
+}
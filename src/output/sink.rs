@@ -0,0 +1,548 @@
+//! Destinations for framework and command output.
+//!
+//! Everything printed by the REPL and by command handlers goes through an
+//! [`Output`] handle wrapping an [`OutputSink`]. Swapping the sink lets tests
+//! assert on what was printed and transcripts capture a session without
+//! touching the process-wide stdout.
+//!
+//! # Examples
+//!
+//! ```
+//! use mycli::output::{BufferSink, Output};
+//!
+//! let buffer = BufferSink::new();
+//! let out = Output::new(buffer.clone());
+//!
+//! writeln!(out, "{} records", 3).unwrap();
+//! out.warn("cache is stale").unwrap();
+//!
+//! assert_eq!(buffer.contents(), "3 records\nWarning: cache is stale\n");
+//! ```
+//!
+//! Escape sequences are stripped automatically when the sink is not a
+//! terminal, so output redirected to a file or pipe stays free of color
+//! codes. Use [`Output::with_color`] to force them on or off.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::ansi::{self, ColorMode};
+use super::{CodedError, Verbosity};
+use crate::locale::text;
+
+/// A destination for output text.
+///
+/// Regular output is written with [`write_str`](OutputSink::write_str) and
+/// diagnostics with [`write_err`](OutputSink::write_err). Sinks without a
+/// separate error stream can rely on the default, which writes errors to the
+/// same place as regular output.
+pub trait OutputSink: Send {
+    /// Writes regular output.
+    fn write_str(&mut self, s: &str) -> io::Result<()>;
+
+    /// Writes error output. Defaults to [`write_str`](OutputSink::write_str).
+    fn write_err(&mut self, s: &str) -> io::Result<()> {
+        self.write_str(s)
+    }
+
+    /// Writes `error`, reported with [`Output::report`], shown as `text`.
+    /// Defaults to writing `text` and a newline with
+    /// [`write_err`](OutputSink::write_err); sinks feeding other programs
+    /// can keep the code and details instead.
+    fn write_error(&mut self, error: &CodedError, text: &str) -> io::Result<()> {
+        let _ = error;
+        self.write_err(text)?;
+        self.write_err("\n")
+    }
+
+    /// Flushes any buffered output.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Returns `true` if the output ends up on an interactive terminal.
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+/// Writes regular output to stdout and errors to stderr.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        io::stdout().write_all(s.as_bytes())
+    }
+
+    fn write_err(&mut self, s: &str) -> io::Result<()> {
+        io::stderr().write_all(s.as_bytes())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()?;
+        io::stderr().flush()
+    }
+
+    fn is_terminal(&self) -> bool {
+        io::stdout().is_terminal()
+    }
+}
+
+/// Collects output in memory.
+///
+/// Clones share the same buffer, so a clone can be handed to an [`Output`]
+/// while the original is kept to inspect what was written.
+#[derive(Debug, Clone, Default)]
+pub struct BufferSink {
+    buffer: Arc<Mutex<String>>,
+}
+
+impl BufferSink {
+    /// Creates an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of everything written so far.
+    pub fn contents(&self) -> String {
+        self.lock().clone()
+    }
+
+    /// Returns everything written so far and empties the buffer.
+    pub fn take(&self) -> String {
+        std::mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, String> {
+        self.buffer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl OutputSink for BufferSink {
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        self.lock().push_str(s);
+        Ok(())
+    }
+}
+
+/// Writes output, including errors, to any [`Write`], such as a socket, a
+/// pipe or the master side of a PTY.
+///
+/// Nothing is buffered beyond what the writer does itself, and the writer
+/// is not treated as a terminal, so colors are stripped unless forced with
+/// [`Output::with_color`].
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::{Output, WriterSink};
+///
+/// let (mut reader, writer) = std::io::pipe()?;
+/// let out = Output::new(WriterSink::new(writer));
+/// out.println("hello")?;
+/// drop(out);
+///
+/// let mut text = String::new();
+/// std::io::Read::read_to_string(&mut reader, &mut text)?;
+/// assert_eq!(text, "hello\n");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct WriterSink<W> {
+    writer: W,
+}
+
+impl<W: Write + Send> WriterSink<W> {
+    /// Creates a sink writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: Write + Send> OutputSink for WriterSink<W> {
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        self.writer.write_all(s.as_bytes())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes output, including errors, to a file, with secrets masked by the
+/// current [redactor](crate::redact).
+#[derive(Debug)]
+pub struct FileSink {
+    file: BufWriter<File>,
+}
+
+impl FileSink {
+    /// Creates the file at `path`, truncating it if it exists.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::from_file(File::create(path)?))
+    }
+
+    /// Opens the file at `path` for appending, creating it if needed.
+    pub fn append(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::from_file(file))
+    }
+
+    /// Wraps an already opened file.
+    pub fn from_file(file: File) -> Self {
+        Self { file: BufWriter::new(file) }
+    }
+}
+
+impl OutputSink for FileSink {
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        self.file.write_all(crate::redact::current().redact(s).as_bytes())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Duplicates output to two sinks, for example the terminal and a
+/// transcript file.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::{BufferSink, Output, StdoutSink, TeeSink};
+///
+/// let transcript = BufferSink::new();
+/// let out = Output::new(TeeSink::new(StdoutSink, transcript.clone()));
+/// out.println("hello").unwrap();
+/// assert_eq!(transcript.contents(), "hello\n");
+/// ```
+#[derive(Debug, Clone)]
+pub struct TeeSink<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: OutputSink, B: OutputSink> TeeSink<A, B> {
+    /// Creates a sink writing to both `first` and `second`.
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<A: OutputSink, B: OutputSink> OutputSink for TeeSink<A, B> {
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        self.first.write_str(s)?;
+        self.second.write_str(s)
+    }
+
+    fn write_err(&mut self, s: &str) -> io::Result<()> {
+        self.first.write_err(s)?;
+        self.second.write_err(s)
+    }
+
+    fn write_error(&mut self, error: &CodedError, text: &str) -> io::Result<()> {
+        self.first.write_error(error, text)?;
+        self.second.write_error(error, text)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.first.flush()?;
+        self.second.flush()
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.first.is_terminal() || self.second.is_terminal()
+    }
+}
+
+/// A cloneable, thread-safe handle to an [`OutputSink`].
+///
+/// Implements `write_fmt`, so it can be used with `write!` and `writeln!`.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::{BufferSink, ColorMode, Output};
+///
+/// let buffer = BufferSink::new();
+/// let out = Output::new(buffer.clone());
+/// out.println("\x1b[32mok\x1b[0m").unwrap();
+/// assert_eq!(buffer.take(), "ok\n");
+///
+/// let out = out.with_color(ColorMode::Always);
+/// out.println("\x1b[32mok\x1b[0m").unwrap();
+/// assert_eq!(buffer.take(), "\x1b[32mok\x1b[0m\n");
+/// ```
+#[derive(Clone)]
+pub struct Output {
+    sink: Arc<Mutex<Box<dyn OutputSink>>>,
+    color: ColorMode,
+    errors: Arc<AtomicUsize>,
+    verbosity: Arc<AtomicU8>,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self::stdout()
+    }
+}
+
+impl fmt::Debug for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Output").finish_non_exhaustive()
+    }
+}
+
+impl Output {
+    /// Creates a handle writing to `sink`.
+    pub fn new(sink: impl OutputSink + 'static) -> Self {
+        Self {
+            sink: Arc::new(Mutex::new(Box::new(sink))),
+            color: ColorMode::from_env(),
+            errors: Arc::new(AtomicUsize::new(0)),
+            verbosity: Arc::new(AtomicU8::new(Verbosity::Normal as u8)),
+        }
+    }
+
+    /// Returns a handle to the same sink using the given color mode.
+    ///
+    /// The default mode is read from the environment with
+    /// [`ColorMode::from_env`].
+    pub fn with_color(mut self, color: ColorMode) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Returns the color mode of this handle.
+    pub fn color(&self) -> ColorMode {
+        self.color
+    }
+
+    /// Sets which messages besides errors this handle and its clones show.
+    pub fn set_verbosity(&self, verbosity: Verbosity) {
+        self.verbosity.store(verbosity as u8, Ordering::Relaxed);
+    }
+
+    /// Returns which messages besides errors are shown. Defaults to
+    /// [`Verbosity::Normal`].
+    pub fn verbosity(&self) -> Verbosity {
+        Verbosity::from_u8(self.verbosity.load(Ordering::Relaxed))
+    }
+
+    /// Creates a handle writing to the process stdout and stderr.
+    pub fn stdout() -> Self {
+        Self::new(StdoutSink)
+    }
+
+    /// Writes `s` as regular output.
+    pub fn print(&self, s: &str) -> io::Result<()> {
+        let mut sink = self.lock();
+        let s = self.filter(&**sink, s);
+        sink.write_str(&s)
+    }
+
+    /// Writes `s` followed by a newline as regular output.
+    pub fn println(&self, s: &str) -> io::Result<()> {
+        let mut sink = self.lock();
+        let s = self.filter(&**sink, s);
+        sink.write_str(&s)?;
+        sink.write_str("\n")
+    }
+
+    /// Writes `s` as error output.
+    pub fn eprint(&self, s: &str) -> io::Result<()> {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        let mut sink = self.lock();
+        let s = self.filter(&**sink, s);
+        sink.write_err(&s)
+    }
+
+    /// Writes `s` followed by a newline as error output.
+    pub fn eprintln(&self, s: &str) -> io::Result<()> {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        let mut sink = self.lock();
+        let s = self.filter(&**sink, s);
+        sink.write_err(&s)?;
+        sink.write_err("\n")
+    }
+
+    /// Writes `message` after `Warning: ` as error output, unless the
+    /// [verbosity](Self::verbosity) is quiet. Warnings are not counted by
+    /// [`errors_written`](Self::errors_written).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::output::{BufferSink, Output, Verbosity};
+    ///
+    /// let buffer = BufferSink::new();
+    /// let out = Output::new(buffer.clone());
+    /// out.warn("cache is stale").unwrap();
+    /// out.debug("read 3 records from the cache").unwrap();
+    /// assert_eq!(buffer.take(), "Warning: cache is stale\n");
+    ///
+    /// out.set_verbosity(Verbosity::Quiet);
+    /// out.warn("cache is stale").unwrap();
+    /// assert_eq!(buffer.take(), "");
+    /// assert_eq!(out.errors_written(), 0);
+    /// ```
+    pub fn warn(&self, message: &str) -> io::Result<()> {
+        self.note(Verbosity::Normal, &text("warning", &[("message", &message)]))
+    }
+
+    /// Writes `message` as error output, unless the
+    /// [verbosity](Self::verbosity) is quiet. Use it for progress and
+    /// status messages that should not end up in piped output.
+    pub fn info(&self, message: &str) -> io::Result<()> {
+        self.note(Verbosity::Normal, message)
+    }
+
+    /// Writes `message` as error output if the
+    /// [verbosity](Self::verbosity) is verbose.
+    pub fn debug(&self, message: &str) -> io::Result<()> {
+        self.note(Verbosity::Verbose, message)
+    }
+
+    fn note(&self, level: Verbosity, line: &str) -> io::Result<()> {
+        if self.verbosity() < level {
+            return Ok(());
+        }
+        let mut sink = self.lock();
+        let line = self.filter(&**sink, line);
+        sink.write_err(&line)?;
+        sink.write_err("\n")
+    }
+
+    /// Writes `error` as error output, after `Error: `. Sinks that keep
+    /// errors for other programs, like the one behind
+    /// [`rpc`](crate::rpc), also record its code and details.
+    pub fn report(&self, error: &CodedError) -> io::Result<()> {
+        self.report_as(error, &text("error", &[("message", &error.message())]))
+    }
+
+    /// Writes `error` as error output, shown as `text`.
+    pub(crate) fn report_as(&self, error: &CodedError, text: &str) -> io::Result<()> {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        let mut sink = self.lock();
+        let text = self.filter(&**sink, text);
+        sink.write_error(error, &text)
+    }
+
+    /// Writes formatted regular output; used by `write!` and `writeln!`.
+    pub fn write_fmt(&self, args: fmt::Arguments<'_>) -> io::Result<()> {
+        match args.as_str() {
+            Some(s) => self.print(s),
+            None => self.print(&args.to_string()),
+        }
+    }
+
+    /// Flushes the underlying sink.
+    pub fn flush(&self) -> io::Result<()> {
+        self.lock().flush()
+    }
+
+    /// Returns `true` if the sink writes to an interactive terminal.
+    pub fn is_terminal(&self) -> bool {
+        self.lock().is_terminal()
+    }
+
+    /// Returns how many times error output was written through this handle
+    /// or its clones. Comparing the count before and after a command tells
+    /// whether it reported an error.
+    pub fn errors_written(&self) -> usize {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    /// Runs `f` and returns the regular and error output written during it
+    /// through any handle to this sink, without escape sequences. With
+    /// `echo` the output is also written to the sink as usual.
+    #[cfg(any(feature = "script", feature = "clipboard"))]
+    pub(crate) fn record<R>(&self, echo: bool, f: impl FnOnce() -> R) -> (R, String, String) {
+        let recorded = Arc::new(Mutex::new((String::new(), String::new())));
+        let original = {
+            let mut sink = self.lock();
+            let original = Arc::new(Mutex::new(std::mem::replace(&mut *sink, Box::new(StdoutSink))));
+            let terminal = original.lock().unwrap_or_else(|e| e.into_inner()).is_terminal();
+            let recorder = Recorder { original: original.clone(), echo, terminal, recorded: recorded.clone() };
+            *sink = Box::new(recorder);
+            original
+        };
+        let result = f();
+        {
+            let mut sink = self.lock();
+            // Dropping the recorder leaves `original` as the only reference.
+            *sink = Box::new(StdoutSink);
+            let original = Arc::try_unwrap(original).ok().expect("the recorder was dropped");
+            *sink = original.into_inner().unwrap_or_else(|e| e.into_inner());
+        }
+        let (out, err) = std::mem::take(&mut *recorded.lock().unwrap_or_else(|e| e.into_inner()));
+        (result, out, err)
+    }
+
+    fn filter<'a>(&self, sink: &dyn OutputSink, s: &'a str) -> std::borrow::Cow<'a, str> {
+        if self.color.strips(sink.is_terminal()) {
+            ansi::strip(s)
+        } else {
+            s.into()
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Box<dyn OutputSink>> {
+        self.sink.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Stands in for a sink during [`Output::record`].
+#[cfg(any(feature = "script", feature = "clipboard"))]
+struct Recorder {
+    original: Arc<Mutex<Box<dyn OutputSink>>>,
+    echo: bool,
+    terminal: bool,
+    recorded: Arc<Mutex<(String, String)>>,
+}
+
+#[cfg(any(feature = "script", feature = "clipboard"))]
+impl Recorder {
+    fn original(&self) -> MutexGuard<'_, Box<dyn OutputSink>> {
+        self.original.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn recorded(&self) -> MutexGuard<'_, (String, String)> {
+        self.recorded.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(any(feature = "script", feature = "clipboard"))]
+impl OutputSink for Recorder {
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        self.recorded().0.push_str(&ansi::strip(s));
+        if self.echo { self.original().write_str(s) } else { Ok(()) }
+    }
+
+    fn write_err(&mut self, s: &str) -> io::Result<()> {
+        self.recorded().1.push_str(&ansi::strip(s));
+        if self.echo { self.original().write_err(s) } else { Ok(()) }
+    }
+
+    fn write_error(&mut self, error: &CodedError, text: &str) -> io::Result<()> {
+        let recorded = &mut self.recorded().1;
+        recorded.push_str(&ansi::strip(text));
+        recorded.push('\n');
+        if self.echo { self.original().write_error(error, text) } else { Ok(()) }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.echo { self.original().flush() } else { Ok(()) }
+    }
+
+    fn is_terminal(&self) -> bool {
+        // Keeps colors when echoing to a terminal; recorded text is stripped.
+        self.echo && self.terminal
+    }
+}
@@ -0,0 +1,227 @@
+//! Terminal size detection.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// Width assumed when the terminal size cannot be determined.
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// Returns the width of the terminal attached to stdout, in columns.
+///
+/// The `COLUMNS` environment variable takes precedence when set. Otherwise
+/// the size is queried from the terminal, falling back to [`DEFAULT_WIDTH`]
+/// when stdout is not a terminal.
+///
+/// # Examples
+///
+/// ```
+/// let width = mycli::output::term::width();
+/// assert!(width > 0);
+/// ```
+pub fn width() -> usize {
+    let env = std::env::var("COLUMNS").ok().and_then(|v| v.parse().ok());
+    if let Some(cols) = env.filter(|&cols: &usize| cols > 0) {
+        return cols;
+    }
+    size().map_or(DEFAULT_WIDTH, |(cols, _)| cols)
+}
+
+/// Returns the size of the terminal attached to stdout as `(columns, rows)`.
+///
+/// Returns `None` when stdout is not a terminal or the size is unknown.
+///
+/// # Examples
+///
+/// ```
+/// if let Some((cols, rows)) = mycli::output::term::size() {
+///     println!("{cols}x{rows}");
+/// }
+/// ```
+pub fn size() -> Option<(usize, usize)> {
+    query_size()
+}
+
+#[cfg(unix)]
+fn query_size() -> Option<(usize, usize)> {
+    // SAFETY: `winsize` is plain data and `ioctl` only writes into it.
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let ret = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    if ret == 0 && size.ws_col > 0 && size.ws_row > 0 {
+        Some((size.ws_col as usize, size.ws_row as usize))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn query_size() -> Option<(usize, usize)> {
+    None
+}
+
+/// Calls `callback` with the new `(columns, rows)` whenever the terminal
+/// attached to stdout is resized, until the returned [`ResizeWatch`] is
+/// dropped.
+///
+/// Resizes are noticed through `SIGWINCH`, so callbacks never run on
+/// platforms other than Unix. They run on a thread of their own, one after
+/// the other, and only when the size really changed, and must not call
+/// `on_resize` or drop a watch themselves. The signal handler calls the one
+/// that was installed before it, so a line editor keeps redrawing its line,
+/// and a [`Repl`](crate::repl::Repl) installs it again when it starts
+/// running, after its line editor replaced it with its own.
+///
+/// Formatting helpers such as [`Columns`](super::Columns) read the size each
+/// time they render, so they need no callback to fit a resized terminal.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+///
+/// let width = Arc::new(AtomicUsize::new(mycli::output::term::width()));
+/// let seen = width.clone();
+/// let _watch = mycli::output::term::on_resize(move |cols, _rows| {
+///     seen.store(cols, Ordering::Relaxed);
+/// });
+/// ```
+pub fn on_resize(callback: impl Fn(usize, usize) + Send + 'static) -> ResizeWatch {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let id = NEXT.fetch_add(1, Ordering::Relaxed);
+    lock_callbacks().push((id, Box::new(callback)));
+    #[cfg(unix)]
+    sigwinch::watch();
+    ResizeWatch { id }
+}
+
+/// Keeps a callback given to [`on_resize`] registered until dropped.
+#[derive(Debug)]
+#[must_use = "the callback is removed when the watch is dropped"]
+pub struct ResizeWatch {
+    id: u64,
+}
+
+impl Drop for ResizeWatch {
+    fn drop(&mut self) {
+        lock_callbacks().retain(|(id, _)| *id != self.id);
+    }
+}
+
+type Callbacks = Vec<(u64, Box<dyn Fn(usize, usize) + Send>)>;
+
+/// Installs the `SIGWINCH` handler again if callbacks are registered and
+/// another handler replaced it, as a line editor does when it is created.
+#[cfg(feature = "repl-core")]
+pub(crate) fn keep_watching() {
+    #[cfg(unix)]
+    if !lock_callbacks().is_empty() {
+        sigwinch::watch();
+    }
+}
+
+fn lock_callbacks() -> MutexGuard<'static, Callbacks> {
+    static CALLBACKS: Mutex<Callbacks> = Mutex::new(Vec::new());
+    CALLBACKS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Waiting for `SIGWINCH` on a thread, which the signal handler wakes up
+/// through a pipe.
+#[cfg(unix)]
+mod sigwinch {
+    use std::ffi::{c_int, c_void};
+    use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+    use std::sync::Once;
+
+    type Handler = extern "C" fn(c_int, *mut libc::siginfo_t, *mut c_void);
+
+    /// The write end of the pipe, or -1 before it is created.
+    static PIPE: AtomicI32 = AtomicI32::new(-1);
+    /// The handler in place before ours, and its flags.
+    static PREVIOUS: AtomicUsize = AtomicUsize::new(libc::SIG_DFL);
+    static PREVIOUS_FLAGS: AtomicI32 = AtomicI32::new(0);
+
+    /// Starts the thread the first time, and installs the handler unless
+    /// it is already the one in place.
+    pub(super) fn watch() {
+        static START: Once = Once::new();
+        START.call_once(|| {
+            let mut fds = [0; 2];
+            // SAFETY: `pipe` writes two descriptors into `fds`, which are
+            // then only used by this module.
+            if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+                return;
+            }
+            let [read, write] = fds;
+            unsafe {
+                libc::fcntl(read, libc::F_SETFD, libc::FD_CLOEXEC);
+                libc::fcntl(write, libc::F_SETFD, libc::FD_CLOEXEC);
+                libc::fcntl(write, libc::F_SETFL, libc::O_NONBLOCK);
+            }
+            if std::thread::Builder::new().name("mycli-resize".into()).spawn(move || wait(read)).is_ok() {
+                PIPE.store(write, Ordering::Relaxed);
+            }
+        });
+        if PIPE.load(Ordering::Relaxed) < 0 {
+            return;
+        }
+        // SAFETY: `handle` only does async-signal-safe work, and the
+        // action in place is kept before ours replaces it.
+        unsafe {
+            let mut previous: libc::sigaction = std::mem::zeroed();
+            libc::sigaction(libc::SIGWINCH, std::ptr::null(), &mut previous);
+            if previous.sa_sigaction == handle as Handler as usize {
+                return;
+            }
+            PREVIOUS_FLAGS.store(previous.sa_flags, Ordering::Relaxed);
+            PREVIOUS.store(previous.sa_sigaction, Ordering::Relaxed);
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handle as Handler as usize;
+            action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(libc::SIGWINCH, &action, std::ptr::null_mut());
+        }
+    }
+
+    extern "C" fn handle(signal: c_int, info: *mut libc::siginfo_t, context: *mut c_void) {
+        // SAFETY: `write` is async-signal-safe, and the previous handler
+        // is called the way it was installed to be.
+        unsafe {
+            libc::write(PIPE.load(Ordering::Relaxed), [0u8].as_ptr().cast(), 1);
+            let previous = PREVIOUS.load(Ordering::Relaxed);
+            if previous == libc::SIG_DFL || previous == libc::SIG_IGN {
+                return;
+            }
+            if PREVIOUS_FLAGS.load(Ordering::Relaxed) & libc::SA_SIGINFO != 0 {
+                let previous: Handler = std::mem::transmute(previous);
+                previous(signal, info, context);
+            } else {
+                let previous: extern "C" fn(c_int) = std::mem::transmute(previous);
+                previous(signal);
+            }
+        }
+    }
+
+    /// Calls the callbacks each time the handler writes to the pipe and
+    /// the size has changed.
+    fn wait(read: c_int) {
+        let mut last = super::size();
+        let mut buf = [0u8; 16];
+        loop {
+            // SAFETY: `buf` is valid for its length.
+            let n = unsafe { libc::read(read, buf.as_mut_ptr().cast(), buf.len()) };
+            if n == 0 || (n < 0 && std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted) {
+                return;
+            }
+            let size = super::size();
+            if size == last {
+                continue;
+            }
+            last = size;
+            if let Some((cols, rows)) = size {
+                for (_, callback) in super::lock_callbacks().iter() {
+                    callback(cols, rows);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,524 @@
+//! Colors and text styles shared by the output helpers.
+//!
+//! A process-wide [`Theme`] decides how each [`Tone`] of output is styled.
+//! Helpers such as panels consult the current theme, so switching it once
+//! restyles all built-in formatting.
+//!
+//! Besides the default theme there are [`Theme::deuteranopia`], which tells
+//! success from failure without relying on red and green,
+//! [`Theme::high_contrast`] and [`Theme::monochrome`]. Each can also be
+//! chosen by name, as in the `theme` setting of a
+//! [configuration file](crate::config).
+//!
+//! # Examples
+//!
+//! ```
+//! use mycli::output::theme::{self, Color, Style, Theme, Tone};
+//!
+//! let mut custom = Theme::default();
+//! custom.set(Tone::Warning, Style::new().fg(Color::Magenta).bold());
+//! theme::set_theme(custom);
+//!
+//! let warning = theme::current().paint(Tone::Warning, "disk almost full");
+//! assert_eq!(warning, "\x1b[1;35mdisk almost full\x1b[0m");
+//! ```
+
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+/// A terminal color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+    /// One of the 256 indexed colors.
+    Ansi256(u8),
+    /// A 24-bit color.
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    fn sgr(self, background: bool) -> String {
+        let base = if background { 40 } else { 30 };
+        let bright = if background { 100 } else { 90 };
+        let ext = if background { 48 } else { 38 };
+        match self {
+            Color::Black => (base).to_string(),
+            Color::Red => (base + 1).to_string(),
+            Color::Green => (base + 2).to_string(),
+            Color::Yellow => (base + 3).to_string(),
+            Color::Blue => (base + 4).to_string(),
+            Color::Magenta => (base + 5).to_string(),
+            Color::Cyan => (base + 6).to_string(),
+            Color::White => (base + 7).to_string(),
+            Color::BrightBlack => (bright).to_string(),
+            Color::BrightRed => (bright + 1).to_string(),
+            Color::BrightGreen => (bright + 2).to_string(),
+            Color::BrightYellow => (bright + 3).to_string(),
+            Color::BrightBlue => (bright + 4).to_string(),
+            Color::BrightMagenta => (bright + 5).to_string(),
+            Color::BrightCyan => (bright + 6).to_string(),
+            Color::BrightWhite => (bright + 7).to_string(),
+            Color::Ansi256(n) => format!("{ext};5;{n}"),
+            Color::Rgb(r, g, b) => format!("{ext};2;{r};{g};{b}"),
+        }
+    }
+}
+
+const COLOR_NAMES: [(&str, Color); 16] = [
+    ("black", Color::Black),
+    ("red", Color::Red),
+    ("green", Color::Green),
+    ("yellow", Color::Yellow),
+    ("blue", Color::Blue),
+    ("magenta", Color::Magenta),
+    ("cyan", Color::Cyan),
+    ("white", Color::White),
+    ("bright-black", Color::BrightBlack),
+    ("bright-red", Color::BrightRed),
+    ("bright-green", Color::BrightGreen),
+    ("bright-yellow", Color::BrightYellow),
+    ("bright-blue", Color::BrightBlue),
+    ("bright-magenta", Color::BrightMagenta),
+    ("bright-cyan", Color::BrightCyan),
+    ("bright-white", Color::BrightWhite),
+];
+
+impl FromStr for Color {
+    type Err = String;
+
+    /// Parses a color name such as `red` or `bright-blue` (`gray` and `grey`
+    /// mean `bright-black`), an indexed color `0`–`255`, or `#rrggbb`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase().replace('_', "-");
+        if let Some((_, color)) = COLOR_NAMES.iter().find(|(n, _)| *n == name) {
+            return Ok(*color);
+        }
+        if name == "gray" || name == "grey" {
+            return Ok(Color::BrightBlack);
+        }
+        if let Ok(n) = name.parse::<u8>() {
+            return Ok(Color::Ansi256(n));
+        }
+        if let Some(hex) = name.strip_prefix('#').filter(|h| h.len() == 6) {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+            if let (Ok(r), Ok(g), Ok(b)) = (channel(0), channel(2), channel(4)) {
+                return Ok(Color::Rgb(r, g, b));
+            }
+        }
+        Err(format!("unknown color `{}`", s.trim()))
+    }
+}
+
+/// A combination of colors and text attributes.
+///
+/// The default style leaves text unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    fg: Option<Color>,
+    bg: Option<Color>,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    reverse: bool,
+}
+
+impl Style {
+    /// Creates a style with no colors or attributes.
+    pub const fn new() -> Self {
+        Self {
+            fg: None,
+            bg: None,
+            bold: false,
+            dim: false,
+            italic: false,
+            underline: false,
+            reverse: false,
+        }
+    }
+
+    /// Sets the foreground color.
+    pub const fn fg(mut self, color: Color) -> Self {
+        self.fg = Some(color);
+        self
+    }
+
+    /// Sets the background color.
+    pub const fn bg(mut self, color: Color) -> Self {
+        self.bg = Some(color);
+        self
+    }
+
+    /// Makes the text bold.
+    pub const fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    /// Makes the text dim.
+    pub const fn dim(mut self) -> Self {
+        self.dim = true;
+        self
+    }
+
+    /// Makes the text italic.
+    pub const fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+
+    /// Underlines the text.
+    pub const fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    /// Swaps the foreground and background colors.
+    pub const fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    /// Returns `true` if the style does not change the text.
+    pub fn is_plain(&self) -> bool {
+        *self == Style::new()
+    }
+
+    /// Returns the escape sequence that switches to this style, or an empty
+    /// string for a plain style.
+    pub fn prefix(&self) -> String {
+        let mut codes = Vec::new();
+        for (on, code) in [
+            (self.bold, "1"),
+            (self.dim, "2"),
+            (self.italic, "3"),
+            (self.underline, "4"),
+            (self.reverse, "7"),
+        ] {
+            if on {
+                codes.push(code.to_string());
+            }
+        }
+        codes.extend(self.fg.map(|c| c.sgr(false)));
+        codes.extend(self.bg.map(|c| c.sgr(true)));
+        if codes.is_empty() {
+            String::new()
+        } else {
+            format!("\x1b[{}m", codes.join(";"))
+        }
+    }
+
+    /// Wraps `text` in this style, resetting all attributes afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::output::theme::{Color, Style};
+    ///
+    /// assert_eq!(Style::new().fg(Color::Green).paint("ok"), "\x1b[32mok\x1b[0m");
+    /// assert_eq!(Style::new().paint("ok"), "ok");
+    /// ```
+    pub fn paint(&self, text: &str) -> String {
+        if self.is_plain() {
+            text.to_string()
+        } else {
+            format!("{}{text}\x1b[0m", self.prefix())
+        }
+    }
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    /// Parses a space-separated style such as `bold red`, `cyan on black` or
+    /// `underline #ff8800`. Colors are parsed as by [`Color`]; a color after
+    /// `on` is the background. `plain` or an empty string is the default
+    /// style.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::output::theme::{Color, Style};
+    ///
+    /// let style: Style = "bold yellow on blue".parse().unwrap();
+    /// assert_eq!(style, Style::new().fg(Color::Yellow).bg(Color::Blue).bold());
+    /// assert!("blinking".parse::<Style>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut style = Style::new();
+        let mut words = s.split_whitespace();
+        while let Some(word) = words.next() {
+            match word.to_ascii_lowercase().as_str() {
+                "plain" | "none" => {}
+                "bold" => style = style.bold(),
+                "dim" => style = style.dim(),
+                "italic" => style = style.italic(),
+                "underline" => style = style.underline(),
+                "reverse" => style = style.reverse(),
+                "on" => {
+                    let color = words.next().ok_or("expected a color after `on`")?;
+                    style = style.bg(color.parse()?);
+                }
+                _ => style = style.fg(word.parse()?),
+            }
+        }
+        Ok(style)
+    }
+}
+
+/// The purpose of a piece of output, used to pick its style from the theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Tone {
+    /// Ordinary text.
+    #[default]
+    Plain,
+    /// Highlighted text such as titles and headings.
+    Accent,
+    /// Secondary text such as hints and borders.
+    Muted,
+    /// Informational messages.
+    Info,
+    /// Successful outcomes.
+    Success,
+    /// Warnings.
+    Warning,
+    /// Errors.
+    Error,
+}
+
+impl Tone {
+    /// All tones, in declaration order.
+    pub const ALL: [Tone; 7] = [
+        Tone::Plain,
+        Tone::Accent,
+        Tone::Muted,
+        Tone::Info,
+        Tone::Success,
+        Tone::Warning,
+        Tone::Error,
+    ];
+
+    /// Returns the lowercase name of the tone, as accepted by its
+    /// [`FromStr`] implementation.
+    pub fn name(self) -> &'static str {
+        match self {
+            Tone::Plain => "plain",
+            Tone::Accent => "accent",
+            Tone::Muted => "muted",
+            Tone::Info => "info",
+            Tone::Success => "success",
+            Tone::Warning => "warning",
+            Tone::Error => "error",
+        }
+    }
+}
+
+impl FromStr for Tone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Tone::ALL
+            .into_iter()
+            .find(|tone| tone.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("unknown tone `{}`", s.trim()))
+    }
+}
+
+/// The styles used for each [`Tone`] of output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    styles: [Style; 7],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            styles: [
+                Style::new(),
+                Style::new().bold(),
+                Style::new().fg(Color::BrightBlack),
+                Style::new().fg(Color::Cyan),
+                Style::new().fg(Color::Green),
+                Style::new().fg(Color::Yellow).bold(),
+                Style::new().fg(Color::Red).bold(),
+            ],
+        }
+    }
+}
+
+impl Theme {
+    /// Creates a theme that leaves all output unstyled.
+    pub fn plain() -> Self {
+        Self {
+            styles: [Style::new(); 7],
+        }
+    }
+
+    /// Creates a theme for red-green color blindness, which uses blue for
+    /// success and orange for errors.
+    pub fn deuteranopia() -> Self {
+        Self {
+            styles: [
+                Style::new(),
+                Style::new().bold(),
+                Style::new().fg(Color::BrightBlack),
+                Style::new().fg(Color::Cyan),
+                Style::new().fg(Color::Ansi256(33)),
+                Style::new().fg(Color::Ansi256(220)).bold(),
+                Style::new().fg(Color::Ansi256(208)).bold(),
+            ],
+        }
+    }
+
+    /// Creates a theme that uses bright colors only, with warnings and
+    /// errors shown on a solid background.
+    pub fn high_contrast() -> Self {
+        Self {
+            styles: [
+                Style::new(),
+                Style::new().fg(Color::BrightWhite).bold(),
+                Style::new().fg(Color::White),
+                Style::new().fg(Color::BrightCyan).bold(),
+                Style::new().fg(Color::BrightGreen).bold(),
+                Style::new().fg(Color::Black).bg(Color::BrightYellow).bold(),
+                Style::new().fg(Color::BrightWhite).bg(Color::Red).bold(),
+            ],
+        }
+    }
+
+    /// Creates a theme without colors, which tells tones apart by text
+    /// attributes alone.
+    pub fn monochrome() -> Self {
+        Self {
+            styles: [
+                Style::new(),
+                Style::new().bold(),
+                Style::new().dim(),
+                Style::new(),
+                Style::new().bold(),
+                Style::new().underline(),
+                Style::new().bold().underline(),
+            ],
+        }
+    }
+
+    /// Returns the style used for `tone`.
+    pub fn style(&self, tone: Tone) -> Style {
+        self.styles[tone as usize]
+    }
+
+    /// Sets the style used for `tone`.
+    pub fn set(&mut self, tone: Tone, style: Style) {
+        self.styles[tone as usize] = style;
+    }
+
+    /// Returns `text` styled for `tone`.
+    pub fn paint(&self, tone: Tone, text: &str) -> String {
+        self.style(tone).paint(text)
+    }
+}
+
+/// The names of the built-in themes, as accepted by the [`FromStr`]
+/// implementation of [`Theme`].
+pub const NAMES: [&str; 5] = ["default", "plain", "deuteranopia", "high-contrast", "monochrome"];
+
+impl FromStr for Theme {
+    type Err = String;
+
+    /// Returns the built-in theme called `s`, one of [`NAMES`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::output::theme::{Theme, Tone};
+    ///
+    /// let theme: Theme = "monochrome".parse().unwrap();
+    /// assert_eq!(theme.paint(Tone::Error, "failed"), "\x1b[1;4mfailed\x1b[0m");
+    /// assert!("neon".parse::<Theme>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "default" => Ok(Theme::default()),
+            "plain" => Ok(Theme::plain()),
+            "deuteranopia" => Ok(Theme::deuteranopia()),
+            "high-contrast" => Ok(Theme::high_contrast()),
+            "monochrome" => Ok(Theme::monochrome()),
+            _ => Err(format!("unknown theme `{}`; expected one of {}", s.trim(), NAMES.join(", "))),
+        }
+    }
+}
+
+#[cfg(feature = "tui")]
+impl From<Style> for ratatui::style::Style {
+    fn from(style: Style) -> Self {
+        use ratatui::style::Modifier;
+
+        let color = |color: Color| match color {
+            Color::Black => ratatui::style::Color::Black,
+            Color::Red => ratatui::style::Color::Red,
+            Color::Green => ratatui::style::Color::Green,
+            Color::Yellow => ratatui::style::Color::Yellow,
+            Color::Blue => ratatui::style::Color::Blue,
+            Color::Magenta => ratatui::style::Color::Magenta,
+            Color::Cyan => ratatui::style::Color::Cyan,
+            Color::White => ratatui::style::Color::Gray,
+            Color::BrightBlack => ratatui::style::Color::DarkGray,
+            Color::BrightRed => ratatui::style::Color::LightRed,
+            Color::BrightGreen => ratatui::style::Color::LightGreen,
+            Color::BrightYellow => ratatui::style::Color::LightYellow,
+            Color::BrightBlue => ratatui::style::Color::LightBlue,
+            Color::BrightMagenta => ratatui::style::Color::LightMagenta,
+            Color::BrightCyan => ratatui::style::Color::LightCyan,
+            Color::BrightWhite => ratatui::style::Color::White,
+            Color::Ansi256(n) => ratatui::style::Color::Indexed(n),
+            Color::Rgb(r, g, b) => ratatui::style::Color::Rgb(r, g, b),
+        };
+        let mut converted = ratatui::style::Style::new();
+        if let Some(fg) = style.fg {
+            converted = converted.fg(color(fg));
+        }
+        if let Some(bg) = style.bg {
+            converted = converted.bg(color(bg));
+        }
+        for (on, modifier) in [
+            (style.bold, Modifier::BOLD),
+            (style.dim, Modifier::DIM),
+            (style.italic, Modifier::ITALIC),
+            (style.underline, Modifier::UNDERLINED),
+            (style.reverse, Modifier::REVERSED),
+        ] {
+            if on {
+                converted = converted.add_modifier(modifier);
+            }
+        }
+        converted
+    }
+}
+
+static CURRENT: RwLock<Option<Arc<Theme>>> = RwLock::new(None);
+
+/// Returns the theme currently used by the output helpers.
+pub fn current() -> Arc<Theme> {
+    let current = CURRENT.read().unwrap_or_else(|e| e.into_inner());
+    current.clone().unwrap_or_default()
+}
+
+/// Replaces the theme used by the output helpers.
+pub fn set_theme(theme: Theme) {
+    *CURRENT.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(theme));
+}
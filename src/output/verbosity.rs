@@ -0,0 +1,83 @@
+//! How much beyond errors to show.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Which messages besides errors an [`Output`](super::Output) shows.
+///
+/// Errors are always shown. [Warnings](super::Output::warn) and
+/// [notes](super::Output::info) are shown unless the output is quiet, and
+/// [details](super::Output::debug) only when it is verbose. All of them go
+/// to the error stream, so they never mix with output piped to another
+/// program.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::Verbosity;
+///
+/// // `-q` and `-vv` counted by the argument parser.
+/// assert_eq!(Verbosity::from_counts(1, 0), Verbosity::Quiet);
+/// assert_eq!(Verbosity::from_counts(0, 2), Verbosity::Verbose);
+/// assert_eq!("quiet".parse(), Ok(Verbosity::Quiet));
+/// assert!(Verbosity::Verbose > Verbosity::Normal);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Verbosity {
+    /// Only errors.
+    Quiet,
+    /// Errors, warnings and notes.
+    #[default]
+    Normal,
+    /// Everything, including details meant for troubleshooting.
+    Verbose,
+}
+
+impl Verbosity {
+    /// Returns the verbosity asked for by `quiet` times `-q` and `verbose`
+    /// times `-v`. Each `-q` cancels out a `-v`.
+    pub fn from_counts(quiet: u8, verbose: u8) -> Self {
+        match i16::from(verbose) - i16::from(quiet) {
+            ..0 => Verbosity::Quiet,
+            0 => Verbosity::Normal,
+            _ => Verbosity::Verbose,
+        }
+    }
+
+    /// Returns the lowercase name of the verbosity, as accepted by its
+    /// [`FromStr`] implementation.
+    pub fn name(self) -> &'static str {
+        match self {
+            Verbosity::Quiet => "quiet",
+            Verbosity::Normal => "normal",
+            Verbosity::Verbose => "verbose",
+        }
+    }
+
+    pub(super) fn from_u8(level: u8) -> Self {
+        match level {
+            0 => Verbosity::Quiet,
+            1 => Verbosity::Normal,
+            _ => Verbosity::Verbose,
+        }
+    }
+}
+
+impl fmt::Display for Verbosity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Verbosity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "quiet" => Ok(Verbosity::Quiet),
+            "normal" => Ok(Verbosity::Normal),
+            "verbose" => Ok(Verbosity::Verbose),
+            _ => Err(format!("unknown verbosity `{s}`; expected `quiet`, `normal` or `verbose`")),
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Display-width aware measuring, truncation and padding.
+//!
+//! Terminal columns do not correspond to bytes or `char`s: CJK characters and
+//! most emoji take two columns, combining marks take none, and escape
+//! sequences are invisible. These helpers measure strings by grapheme cluster
+//! and display width so aligned output stays aligned.
+//!
+//! # Examples
+//!
+//! ```
+//! use mycli::output::width::{display_width, pad, truncate, Align};
+//!
+//! assert_eq!(display_width("日本語"), 6);
+//! assert_eq!(truncate("日本語のテキスト", 7), "日本語…");
+//! assert_eq!(pad("名前", 6, Align::Left), "名前  ");
+//! ```
+
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use super::ansi::{self, Segment};
+
+/// Horizontal alignment used when padding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    /// Text on the left, padding on the right.
+    #[default]
+    Left,
+    /// Text on the right, padding on the left.
+    Right,
+    /// Text centered, with any odd column of padding on the right.
+    Center,
+}
+
+/// Returns the number of terminal columns `s` occupies.
+///
+/// Escape sequences are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::width::display_width;
+///
+/// assert_eq!(display_width("abc"), 3);
+/// assert_eq!(display_width("\x1b[1mabc\x1b[0m"), 3);
+/// assert_eq!(display_width("👍"), 2);
+/// ```
+pub fn display_width(s: &str) -> usize {
+    ansi::segments(s)
+        .map(|seg| match seg {
+            Segment::Text(text) => text_width(text),
+            Segment::Escape(_) => 0,
+        })
+        .sum()
+}
+
+/// Returns the display width of a single grapheme cluster.
+pub fn grapheme_width(g: &str) -> usize {
+    g.width()
+}
+
+fn text_width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+/// Shortens `s` to at most `width` columns, ending with `…` if anything was
+/// cut off.
+///
+/// See [`truncate_with`] to use a different marker.
+pub fn truncate(s: &str, width: usize) -> Cow<'_, str> {
+    truncate_with(s, width, "…")
+}
+
+/// Shortens `s` to at most `width` columns, ending with `marker` if anything
+/// was cut off.
+///
+/// Wide characters are never split: if one does not fit it is dropped
+/// entirely. Escape sequences are kept, so styling that is reset at the end
+/// of `s` is still reset after truncation.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::width::truncate_with;
+///
+/// assert_eq!(truncate_with("deployment", 7, "..."), "depl...");
+/// assert_eq!(truncate_with("short", 7, "..."), "short");
+/// ```
+pub fn truncate_with<'a>(s: &'a str, width: usize, marker: &str) -> Cow<'a, str> {
+    if display_width(s) <= width {
+        return Cow::Borrowed(s);
+    }
+
+    // The marker goes right where the text is cut, before any trailing
+    // escapes, so it is styled like the text it replaces.
+    let marker_width = display_width(marker);
+    let budget = width.saturating_sub(marker_width);
+    let mut out = String::with_capacity(s.len());
+    let mut used = 0;
+    let mut full = false;
+    for seg in ansi::segments(s) {
+        match seg {
+            Segment::Escape(seq) => out.push_str(seq),
+            Segment::Text(_) if full => {}
+            Segment::Text(text) => {
+                for g in text.graphemes(true) {
+                    let w = grapheme_width(g);
+                    if used + w > budget {
+                        full = true;
+                        if marker_width <= width {
+                            out.push_str(marker);
+                        }
+                        break;
+                    }
+                    out.push_str(g);
+                    used += w;
+                }
+            }
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Pads `s` with spaces to `width` columns using the given alignment.
+///
+/// Strings already at least `width` columns wide are returned unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::width::{pad, Align};
+///
+/// assert_eq!(pad("42", 5, Align::Right), "   42");
+/// assert_eq!(pad("ok", 6, Align::Center), "  ok  ");
+/// ```
+pub fn pad(s: &str, width: usize, align: Align) -> Cow<'_, str> {
+    let len = display_width(s);
+    if len >= width {
+        return Cow::Borrowed(s);
+    }
+    let fill = width - len;
+    let (left, right) = match align {
+        Align::Left => (0, fill),
+        Align::Right => (fill, 0),
+        Align::Center => (fill / 2, fill - fill / 2),
+    };
+    let mut out = String::with_capacity(s.len() + fill);
+    out.extend(std::iter::repeat_n(' ', left));
+    out.push_str(s);
+    out.extend(std::iter::repeat_n(' ', right));
+    Cow::Owned(out)
+}
+
+/// Truncates or pads `s` so that it occupies exactly `width` columns.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::width::{fit, Align};
+///
+/// assert_eq!(fit("production", 6, Align::Left), "produ…");
+/// assert_eq!(fit("dev", 6, Align::Left), "dev   ");
+/// ```
+pub fn fit(s: &str, width: usize, align: Align) -> String {
+    let truncated = truncate(s, width);
+    pad(&truncated, width, align).into_owned()
+}
+
+/// Splits `s` after at most `width` columns without breaking escapes or
+/// grapheme clusters, returning both halves and the width of the first.
+///
+/// At least one grapheme is always placed in the first half, even if it is
+/// wider than `width`, so that callers breaking long words make progress.
+pub(crate) fn split_at_width(s: &str, width: usize) -> (&str, &str, usize) {
+    let mut used = 0;
+    let mut offset = 0;
+    for seg in ansi::segments(s) {
+        match seg {
+            Segment::Escape(seq) => {
+                if used >= width && used > 0 {
+                    break;
+                }
+                offset += seq.len();
+            }
+            Segment::Text(text) => {
+                for (i, g) in text.grapheme_indices(true) {
+                    let w = grapheme_width(g);
+                    if used + w > width && used > 0 {
+                        let (head, tail) = s.split_at(offset + i);
+                        return (head, tail, used);
+                    }
+                    used += w;
+                }
+                offset += text.len();
+            }
+        }
+    }
+    let (head, tail) = s.split_at(offset);
+    (head, tail, used)
+}
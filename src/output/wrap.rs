@@ -0,0 +1,211 @@
+//! Width-aware word wrapping and indentation.
+//!
+//! Escape sequences (colors, hyperlinks) are treated as zero-width and are
+//! never split, so styled help text wraps the same way as plain text.
+//!
+//! # Examples
+//!
+//! ```
+//! use mycli::output::Wrap;
+//!
+//! let out = Wrap::new()
+//!     .width(26)
+//!     .indent("  --force  ")
+//!     .hanging("           ")
+//!     .render("Overwrite existing files without asking");
+//! assert_eq!(out, "  --force  Overwrite\n           existing files\n           without asking\n");
+//! ```
+
+use super::term;
+use super::width::{display_width, split_at_width};
+
+/// Configurable word wrapper with optional first-line and hanging indents.
+///
+/// Runs of whitespace between words collapse into a single space, while
+/// explicit newlines in the input start a new paragraph. Words longer than
+/// the available width are broken across lines.
+#[derive(Debug, Clone, Default)]
+pub struct Wrap {
+    width: Option<usize>,
+    initial_indent: String,
+    subsequent_indent: String,
+}
+
+impl Wrap {
+    /// Creates a wrapper using the current terminal width and no indentation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the total line width, overriding the terminal width.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Sets the prefix of the first line.
+    ///
+    /// When no hanging indent is set, the same prefix is used for all lines.
+    /// A prefix as wide as the line is left out, so there is always room
+    /// for text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::output::Wrap;
+    ///
+    /// assert_eq!(Wrap::new().width(12).indent("> ").render("one two three"), "> one two\n> three\n");
+    /// assert_eq!(Wrap::new().width(5).indent("longprefix ").render("ab"), "ab\n");
+    /// ```
+    pub fn indent(mut self, prefix: impl Into<String>) -> Self {
+        self.initial_indent = prefix.into();
+        self
+    }
+
+    /// Sets the prefix of every line after the first.
+    pub fn hanging(mut self, prefix: impl Into<String>) -> Self {
+        self.subsequent_indent = prefix.into();
+        self
+    }
+
+    /// Wraps `text`, returning the lines joined with newlines, each line
+    /// terminated by a newline.
+    pub fn render(&self, text: &str) -> String {
+        let mut out = String::new();
+        for line in self.lines(text) {
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Wraps `text`, returning each line without a trailing newline.
+    pub fn lines(&self, text: &str) -> Vec<String> {
+        let width = self.width.unwrap_or_else(term::width);
+        let mut out = Vec::new();
+        for paragraph in text.split('\n') {
+            self.wrap_paragraph(paragraph, width, &mut out);
+        }
+        out
+    }
+
+    /// Returns the indent of a line, or nothing if it would leave no room
+    /// for text in `width` columns.
+    fn prefix(&self, first: bool, width: usize) -> &str {
+        let prefix = if first || self.subsequent_indent.is_empty() {
+            &self.initial_indent
+        } else {
+            &self.subsequent_indent
+        };
+        if display_width(prefix) < width { prefix } else { "" }
+    }
+
+    fn wrap_paragraph(&self, paragraph: &str, width: usize, out: &mut Vec<String>) {
+        if paragraph.trim().is_empty() {
+            out.push(String::new());
+            return;
+        }
+
+        let mut line = Line::start(self.prefix(out.is_empty(), width));
+        for word in paragraph.split_whitespace() {
+            let mut word = word;
+            let mut word_len = display_width(word);
+
+            if !line.empty && line.len + 1 + word_len > width {
+                out.push(line.finish());
+                line = Line::start(self.prefix(false, width));
+            }
+
+            if !line.empty {
+                line.push(" ", 1);
+            }
+
+            // Break words that cannot fit even on a line of their own.
+            while line.len + word_len > width {
+                let room = width.saturating_sub(line.len).max(1);
+                let (head, tail, used) = split_at_width(word, room);
+                if used == 0 {
+                    // Not even one character fits; a line of its own is as
+                    // good as it gets.
+                    if line.empty {
+                        break;
+                    }
+                    out.push(line.finish());
+                    line = Line::start(self.prefix(false, width));
+                    continue;
+                }
+                line.push(head, used);
+                out.push(line.finish());
+                line = Line::start(self.prefix(false, width));
+                word = tail;
+                word_len -= used;
+            }
+
+            if !word.is_empty() {
+                line.push(word, word_len);
+            }
+        }
+        out.push(line.finish());
+    }
+}
+
+struct Line {
+    text: String,
+    len: usize,
+    empty: bool,
+}
+
+impl Line {
+    fn start(prefix: &str) -> Self {
+        Self {
+            text: prefix.to_string(),
+            len: display_width(prefix),
+            empty: true,
+        }
+    }
+
+    fn push(&mut self, s: &str, len: usize) {
+        self.text.push_str(s);
+        self.len += len;
+        self.empty = false;
+    }
+
+    fn finish(self) -> String {
+        self.text.trim_end().to_string()
+    }
+}
+
+/// Wraps `text` to the current terminal width.
+///
+/// This is a shorthand for `Wrap::new().render(text)`.
+///
+/// # Examples
+///
+/// ```
+/// let out = mycli::output::wrap("short text");
+/// assert_eq!(out, "short text\n");
+/// ```
+pub fn wrap(text: &str) -> String {
+    Wrap::new().render(text)
+}
+
+/// Prefixes every non-empty line of `text` with `prefix`.
+///
+/// Empty lines are left empty so the result has no trailing whitespace.
+///
+/// # Examples
+///
+/// ```
+/// let out = mycli::output::indent("one\n\ntwo\n", "    ");
+/// assert_eq!(out, "    one\n\n    two\n");
+/// ```
+pub fn indent(text: &str, prefix: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        if !line.trim_end_matches(['\n', '\r']).is_empty() {
+            out.push_str(prefix);
+        }
+        out.push_str(line);
+    }
+    out
+}
@@ -0,0 +1,486 @@
+//! Commands loaded from dynamic libraries at startup.
+//!
+//! A plugin is a library built with `crate-type = ["cdylib"]` against the
+//! same version of this crate, which declares itself with
+//! [`export_plugin!`](crate::export_plugin) and adds its commands,
+//! completers and output formatters to the [`Registrar`] it is given. The
+//! program loads plugins into [`Plugins`] and puts them in front of its
+//! command handler with [`Plugins::handler`]:
+//!
+//! ```no_run
+//! use mycli::plugin::Plugins;
+//! use mycli::repl::{CommandHandler, Repl};
+//! # struct App;
+//! # impl CommandHandler for App {
+//! #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+//! # }
+//!
+//! let mut plugins = Plugins::new();
+//! // SAFETY: the directory only holds plugins built with the same compiler,
+//! // installed by the administrator.
+//! for err in unsafe { plugins.load_dir("/usr/lib/myapp/plugins") } {
+//!     eprintln!("warning: {err}");
+//! }
+//! let out = mycli::output::Output::stdout();
+//! let mut repl = Repl::new("> ", plugins.handler(App, out.clone()))?.with_output(out);
+//! repl.run()?;
+//! # Ok::<(), mycli::repl::ReadlineError>(())
+//! ```
+//!
+//! A plugin, in its own crate:
+//!
+//! ```no_run
+//! use mycli::output::Output;
+//! use mycli::plugin::{PluginCommand, Registrar};
+//!
+//! struct Uptime;
+//!
+//! impl PluginCommand for Uptime {
+//!     fn run(&mut self, _args: &str, out: &Output) {
+//!         let _ = out.println("up 3 days");
+//!     }
+//! }
+//!
+//! fn register(registrar: &mut Registrar) {
+//!     registrar.command("uptime", Uptime);
+//!     registrar.completer("uptime", |args| ["--since", "--pretty"].iter().filter(|flag| flag.starts_with(args)).map(|flag| flag.to_string()).collect());
+//!     registrar.formatter("upper", |text| text.to_uppercase());
+//! }
+//!
+//! mycli::export_plugin!("uptime", register);
+//! ```
+//!
+//! Rust has no stable ABI, so plugins must be built with the same compiler
+//! as the program. The library declares the plugin API version, the
+//! version of the compiler and the version of this crate it was built
+//! with, and libraries that do not match are refused instead of loaded.
+//! Loading a library runs its code with the program's permissions, and a
+//! library that lies about its versions can do anything, which is why
+//! [`Plugins::load`] is `unsafe`: only load plugins from trusted locations.
+//!
+//! With the `wasm` feature, plugins compiled to WebAssembly can be loaded
+//! with `Plugins::load_wasm` instead. They run in a sandbox, can only do
+//! what their allowed capabilities permit, and are safe to load from
+//! untrusted sources.
+
+#[cfg(feature = "wasm")]
+mod wasm;
+
+use std::collections::HashMap;
+use std::ffi::{CStr, c_char};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use libloading::Library;
+
+use crate::output::Output;
+use crate::repl::{CommandHandler, Context, Execution, Job};
+
+#[cfg(feature = "wasm")]
+pub use wasm::{Capability, WasmOptions};
+
+/// The version of the plugin interface. Plugins built for another version
+/// are refused.
+pub const API_VERSION: u32 = 2;
+
+#[doc(hidden)]
+pub const CORE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// The output of `rustc --version` for the compiler that built this crate,
+// with a NUL so plugins can declare it as a C string.
+#[doc(hidden)]
+pub const RUSTC_VERSION: &str = concat!(env!("MYCLI_RUSTC_VERSION"), "\0");
+
+// The symbol `export_plugin!` defines in a plugin.
+const DECLARATION: &[u8] = b"MYCLI_PLUGIN\0";
+
+/// A command provided by a plugin.
+pub trait PluginCommand: Send {
+    /// Runs the command with the rest of the line, writing to `out`.
+    fn run(&mut self, args: &str, out: &Output);
+
+    /// Returns a one-line description of the command. Empty by default.
+    fn summary(&self) -> &str {
+        ""
+    }
+}
+
+// Returns the completions of the last word of a command's arguments.
+type Completer = dyn Fn(&str) -> Vec<String> + Send;
+
+// Returns text reformatted.
+type Formatter = dyn Fn(&str) -> String + Send;
+
+/// Collects the commands, completers and output formatters of a plugin
+/// while it is loaded.
+pub struct Registrar {
+    commands: Vec<(String, Box<dyn PluginCommand>)>,
+    completers: Vec<(String, Box<Completer>)>,
+    formatters: Vec<(String, Box<Formatter>)>,
+}
+
+impl Registrar {
+    fn new() -> Self {
+        Self { commands: Vec::new(), completers: Vec::new(), formatters: Vec::new() }
+    }
+
+    /// Adds the command `name`, matched on the first word of a line.
+    pub fn command(&mut self, name: &str, command: impl PluginCommand + 'static) {
+        self.commands.push((name.to_string(), Box::new(command)));
+    }
+
+    /// Completes the arguments of the command `name`, which may be provided
+    /// by the plugin or by the program. `complete` is given the arguments
+    /// typed so far and returns the words that can replace the last one.
+    pub fn completer(&mut self, name: &str, complete: impl Fn(&str) -> Vec<String> + Send + 'static) {
+        self.completers.push((name.to_string(), Box::new(complete)));
+    }
+
+    /// Adds the output format `name`, such as `yaml`, which `format` turns
+    /// a command's text into. See [`Plugins::format`].
+    pub fn formatter(&mut self, name: &str, format: impl Fn(&str) -> String + Send + 'static) {
+        self.formatters.push((name.to_string(), Box::new(format)));
+    }
+}
+
+/// What a plugin library exports, created by
+/// [`export_plugin!`](crate::export_plugin).
+///
+/// The versions come first, in a C layout, so they can be read whichever
+/// compiler built the library; the rest is only read once they match.
+#[doc(hidden)]
+#[repr(C)]
+pub struct PluginDeclaration {
+    pub api_version: u32,
+    pub rustc_version: *const c_char,
+    pub core_version: &'static str,
+    pub name: &'static str,
+    pub register: fn(&mut Registrar),
+}
+
+// SAFETY: `rustc_version` points to a string literal, which is immutable.
+unsafe impl Sync for PluginDeclaration {}
+
+/// Declares the plugin `name` of a library, whose commands are added by
+/// the function `register`.
+///
+/// See the [module documentation](crate::plugin) for an example.
+#[macro_export]
+macro_rules! export_plugin {
+    ($name:expr, $register:expr) => {
+        #[doc(hidden)]
+        #[unsafe(no_mangle)]
+        pub static MYCLI_PLUGIN: $crate::plugin::PluginDeclaration = $crate::plugin::PluginDeclaration {
+            api_version: $crate::plugin::API_VERSION,
+            rustc_version: $crate::plugin::RUSTC_VERSION.as_ptr().cast(),
+            core_version: $crate::plugin::CORE_VERSION,
+            name: $name,
+            register: $register,
+        };
+    };
+}
+
+/// An error loading a plugin.
+#[derive(Debug)]
+pub enum PluginError {
+    /// The library could not be opened, or does not declare a plugin.
+    Load {
+        /// The library.
+        path: PathBuf,
+        /// Why loading failed.
+        message: String,
+    },
+    /// The plugin was built for another plugin API, compiler or version of
+    /// this crate.
+    Incompatible {
+        /// The library.
+        path: PathBuf,
+        /// The versions the plugin was built for.
+        found: String,
+    },
+    /// A command, completer or output format of the plugin is already
+    /// provided by another plugin.
+    Duplicate {
+        /// The plugin that was not loaded.
+        plugin: String,
+        /// The command or output format both plugins provide.
+        command: String,
+    },
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginError::Load { path, message } => write!(f, "cannot load {}: {message}", path.display()),
+            PluginError::Incompatible { path, found } => write!(
+                f,
+                "{} was built for {found}, not plugin API {API_VERSION}, {} and version {CORE_VERSION}",
+                path.display(),
+                rustc_version()
+            ),
+            PluginError::Duplicate { plugin, command } => {
+                write!(f, "plugin {plugin} provides `{command}`, which is already provided")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PluginError {}
+
+/// Returns the version of the compiler that built this crate.
+fn rustc_version() -> &'static str {
+    RUSTC_VERSION.trim_end_matches('\0')
+}
+
+struct Loaded {
+    plugin: String,
+    command: Box<dyn PluginCommand>,
+}
+
+/// The plugins loaded by a program and their commands.
+#[derive(Default)]
+pub struct Plugins {
+    // Declared before `libraries` so the commands, completers and
+    // formatters are dropped before the code they point into is unloaded.
+    commands: HashMap<String, Loaded>,
+    completers: HashMap<String, Box<Completer>>,
+    formatters: HashMap<String, Box<Formatter>>,
+    libraries: Vec<Library>,
+    names: Vec<String>,
+}
+
+impl fmt::Debug for Plugins {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Plugins").field("plugins", &self.names()).finish_non_exhaustive()
+    }
+}
+
+impl Plugins {
+    /// Creates an empty set of plugins.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the plugin library at `path` and returns the plugin's name.
+    ///
+    /// The library is refused if it was built for another plugin API,
+    /// compiler or version of this crate, or if it provides a command,
+    /// completer or output format that is already provided.
+    ///
+    /// # Safety
+    ///
+    /// Loading the library runs its initializers, and its commands run in
+    /// the program, so it must be trusted code. Its versions are read from
+    /// the symbol `export_plugin!` defines, so the library must define
+    /// that symbol with `export_plugin!` or not at all.
+    pub unsafe fn load(&mut self, path: impl AsRef<Path>) -> Result<String, PluginError> {
+        let path = path.as_ref();
+        let load_error = |err: libloading::Error| PluginError::Load { path: path.to_path_buf(), message: err.to_string() };
+        // SAFETY: the caller trusts the library.
+        let library = unsafe { Library::new(path) }.map_err(load_error)?;
+        let declaration = unsafe { library.get::<*const PluginDeclaration>(DECLARATION) }.map_err(load_error)?;
+        // SAFETY: the symbol is the static defined by `export_plugin!`,
+        // which lives as long as the library. Its fields up to
+        // `rustc_version` have a C layout whichever compiler built it.
+        let declaration = unsafe { &**declaration };
+        let incompatible = |found: String| PluginError::Incompatible { path: path.to_path_buf(), found };
+        if declaration.api_version != API_VERSION {
+            return Err(incompatible(format!("plugin API {}", declaration.api_version)));
+        }
+        // SAFETY: the same plugin API declares a NUL-terminated string.
+        let rustc = unsafe { CStr::from_ptr(declaration.rustc_version) }.to_string_lossy();
+        if rustc != rustc_version() {
+            return Err(incompatible(format!("plugin API {}, {rustc}", declaration.api_version)));
+        }
+        // Built by the same compiler, the rest of the declaration has the
+        // layout this crate gives it.
+        if declaration.core_version != CORE_VERSION {
+            return Err(incompatible(format!("plugin API {}, {rustc} and version {}", declaration.api_version, declaration.core_version)));
+        }
+
+        let plugin = declaration.name.to_string();
+        let mut registrar = Registrar::new();
+        (declaration.register)(&mut registrar);
+        self.add(&plugin, registrar)?;
+        self.libraries.push(library);
+        Ok(plugin)
+    }
+
+    /// Adds the commands, completers and formatters of `plugin`, unless
+    /// one is already provided.
+    fn add(&mut self, plugin: &str, registrar: Registrar) -> Result<(), PluginError> {
+        let Registrar { commands, completers, formatters } = registrar;
+        let duplicate = commands
+            .iter()
+            .map(|(name, _)| name)
+            .find(|name| self.commands.contains_key(*name))
+            .or_else(|| completers.iter().map(|(name, _)| name).find(|name| self.completers.contains_key(*name)))
+            .or_else(|| formatters.iter().map(|(name, _)| name).find(|name| self.formatters.contains_key(*name)));
+        if let Some(command) = duplicate {
+            return Err(PluginError::Duplicate { plugin: plugin.to_string(), command: command.clone() });
+        }
+        for (name, command) in commands {
+            self.commands.insert(name, Loaded { plugin: plugin.to_string(), command });
+        }
+        self.completers.extend(completers);
+        self.formatters.extend(formatters);
+        self.names.push(plugin.to_string());
+        Ok(())
+    }
+
+    /// Loads every library in `dir`, such as `*.so` files on Linux, and
+    /// returns the errors of those that could not be loaded. A missing
+    /// directory has no plugins.
+    ///
+    /// # Safety
+    ///
+    /// Every library in `dir` is loaded with [`load`](Self::load), so the
+    /// directory must only be writable by trusted users.
+    pub unsafe fn load_dir(&mut self, dir: impl AsRef<Path>) -> Vec<PluginError> {
+        let Ok(entries) = std::fs::read_dir(dir.as_ref()) else {
+            return Vec::new();
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION))
+            .collect();
+        paths.sort();
+        // SAFETY: the caller trusts the libraries in `dir`.
+        paths.into_iter().filter_map(|path| unsafe { self.load(path) }.err()).collect()
+    }
+
+    /// Returns the names of the loaded plugins, in the order they were
+    /// loaded.
+    pub fn names(&self) -> Vec<&str> {
+        self.names.iter().map(String::as_str).collect()
+    }
+
+    /// Returns the name, plugin and summary of each plugin command, sorted
+    /// by name.
+    pub fn commands(&self) -> Vec<(&str, &str, &str)> {
+        let mut commands: Vec<_> = self
+            .commands
+            .iter()
+            .map(|(name, loaded)| (name.as_str(), loaded.plugin.as_str(), loaded.command.summary()))
+            .collect();
+        commands.sort();
+        commands
+    }
+
+    /// Returns the completions of the last word of `line`, the text before
+    /// the cursor: the plugin commands starting with it when it is the
+    /// first word, or else what the plugin completer of the line's command
+    /// returns.
+    pub fn complete(&self, line: &str) -> Vec<String> {
+        let line = line.trim_start();
+        match line.split_once(char::is_whitespace) {
+            Some((word, args)) => self.completers.get(word).map_or_else(Vec::new, |complete| complete(args.trim_start())),
+            None => {
+                let mut names: Vec<String> = self.commands.keys().filter(|name| name.starts_with(line)).cloned().collect();
+                names.sort();
+                names
+            }
+        }
+    }
+
+    /// Returns the names of the output formats added by plugins, sorted.
+    pub fn formats(&self) -> Vec<&str> {
+        let mut formats: Vec<&str> = self.formatters.keys().map(String::as_str).collect();
+        formats.sort();
+        formats
+    }
+
+    /// Returns `text` in the output format `name` added by a plugin, or
+    /// `None` if no plugin adds it.
+    pub fn format(&self, name: &str, text: &str) -> Option<String> {
+        self.formatters.get(name).map(|format| format(text))
+    }
+
+    /// Returns a command handler that runs plugin commands, writing to
+    /// `out`, and passes every other command to `handler`.
+    pub fn handler<H: CommandHandler>(self, handler: H, out: Output) -> PluginHandler<H> {
+        PluginHandler { plugins: self, handler, out }
+    }
+
+    fn find<'a>(&mut self, command: &'a str) -> Option<(&mut Loaded, &'a str)> {
+        let (word, rest) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+        self.commands.get_mut(word).map(|loaded| (loaded, rest.trim_start()))
+    }
+}
+
+/// Runs plugin commands in front of another command handler, created by
+/// [`Plugins::handler`].
+#[derive(Debug)]
+pub struct PluginHandler<H> {
+    plugins: Plugins,
+    handler: H,
+    out: Output,
+}
+
+impl<H> PluginHandler<H> {
+    /// Returns the loaded plugins.
+    pub fn plugins(&self) -> &Plugins {
+        &self.plugins
+    }
+
+    /// Returns the handler commands are passed to.
+    pub fn inner(&self) -> &H {
+        &self.handler
+    }
+
+    /// Returns the handler commands are passed to, mutably.
+    pub fn inner_mut(&mut self) -> &mut H {
+        &mut self.handler
+    }
+}
+
+impl<H: CommandHandler> CommandHandler for PluginHandler<H> {
+    fn handle(&mut self, command: &str, ctx: &Context) -> bool {
+        match self.plugins.find(command) {
+            Some((loaded, args)) => {
+                loaded.command.run(args, &self.out);
+                true
+            }
+            None => self.handler.handle(command, ctx),
+        }
+    }
+
+    fn execution(&self, command: &str) -> Execution {
+        let word = command.split_whitespace().next().unwrap_or(command);
+        if self.plugins.commands.contains_key(word) {
+            return Execution::Blocking;
+        }
+        self.handler.execution(command)
+    }
+
+    fn background(&mut self, command: &str) -> Option<Job> {
+        if self.plugins.find(command).is_some() {
+            return None;
+        }
+        self.handler.background(command)
+    }
+
+    fn timeout(&self, command: &str) -> Option<Duration> {
+        let word = command.split_whitespace().next().unwrap_or(command);
+        if self.plugins.commands.contains_key(word) {
+            return None;
+        }
+        self.handler.timeout(command)
+    }
+
+    #[cfg(feature = "config")]
+    fn save_session(&self, state: &mut crate::repl::SessionState) {
+        self.handler.save_session(state);
+    }
+
+    #[cfg(feature = "config")]
+    fn restore_session(&mut self, state: &crate::repl::SessionState) {
+        self.handler.restore_session(state);
+    }
+
+    #[cfg(feature = "config")]
+    fn profile_changed(&mut self, name: &str, config: &crate::config::Config) {
+        self.handler.profile_changed(name, config);
+    }
+}
@@ -0,0 +1,349 @@
+//! Plugins compiled to WebAssembly, run in a sandbox.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use wasmtime::{Caller, Config, Engine, Extern, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+use super::{PluginCommand, PluginError, Plugins, Registrar};
+use crate::locale::text;
+use crate::output::ansi::{segments, Segment};
+use crate::output::Output;
+
+/// Something a WebAssembly plugin may do besides printing, if it is
+/// allowed with [`WasmOptions::allow`].
+///
+/// A plugin asks for a capability by importing its functions from the
+/// `mycli` module; plugins importing functions they are not allowed are
+/// refused when they are loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Capability {
+    /// Reading the time: `now() -> i64` returns the milliseconds since the
+    /// Unix epoch.
+    Clock,
+    /// Reading environment variables: `env(name_ptr, name_len) -> i64`
+    /// returns a value written with `mycli_alloc`, as a packed pointer and
+    /// length, or -1 if the variable is not set.
+    Env,
+}
+
+impl Capability {
+    /// Returns the capability needed to import `mycli.<function>`, or an
+    /// error if there is no such function.
+    fn of(function: &str) -> Result<Option<Self>, ()> {
+        match function {
+            "print" | "eprint" => Ok(None),
+            "now" => Ok(Some(Capability::Clock)),
+            "env" => Ok(Some(Capability::Env)),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Capability::Clock => "clock",
+            Capability::Env => "env",
+        })
+    }
+}
+
+/// What WebAssembly plugins loaded with [`Plugins::load_wasm`] may do.
+///
+/// By default plugins may only print, each command may run for 100 million
+/// units of fuel, roughly as many instructions, and a plugin may use 64 MiB
+/// of memory.
+#[derive(Debug, Clone)]
+pub struct WasmOptions {
+    capabilities: HashSet<Capability>,
+    fuel: u64,
+    memory: usize,
+}
+
+impl Default for WasmOptions {
+    fn default() -> Self {
+        Self { capabilities: HashSet::new(), fuel: 100_000_000, memory: 64 << 20 }
+    }
+}
+
+impl WasmOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lets plugins use `capability`.
+    pub fn allow(mut self, capability: Capability) -> Self {
+        self.capabilities.insert(capability);
+        self
+    }
+
+    /// Sets how much fuel each command may use before it is stopped.
+    pub fn fuel(mut self, fuel: u64) -> Self {
+        self.fuel = fuel;
+        self
+    }
+
+    /// Sets how many bytes of memory a plugin may use.
+    pub fn memory_limit(mut self, bytes: usize) -> Self {
+        self.memory = bytes;
+        self
+    }
+}
+
+/// The state of a plugin's store.
+struct Host {
+    // The output of the command that is running.
+    out: Option<Output>,
+    limits: StoreLimits,
+}
+
+/// A loaded plugin, shared by its commands.
+struct Sandbox {
+    plugin: String,
+    store: Store<Host>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    run: TypedFunc<(i32, i32, i32, i32), i32>,
+    fuel: u64,
+}
+
+impl Sandbox {
+    fn write(&mut self, text: &str) -> wasmtime::Result<(i32, i32)> {
+        let len = i32::try_from(text.len())?;
+        let ptr = self.alloc.call(&mut self.store, len)?;
+        self.memory.write(&mut self.store, usize::try_from(ptr)?, text.as_bytes())?;
+        Ok((ptr, len))
+    }
+
+    fn call(&mut self, command: &str, args: &str, out: &Output) -> wasmtime::Result<()> {
+        self.store.set_fuel(self.fuel)?;
+        self.store.data_mut().out = Some(out.clone());
+        let (command_ptr, command_len) = self.write(command)?;
+        let (args_ptr, args_len) = self.write(args)?;
+        let status = self.run.call(&mut self.store, (command_ptr, command_len, args_ptr, args_len));
+        self.store.data_mut().out = None;
+        match status? {
+            0 => Ok(()),
+            status => Err(wasmtime::Error::msg(format!("exited with status {status}"))),
+        }
+    }
+}
+
+struct WasmCommand {
+    name: String,
+    summary: String,
+    sandbox: Arc<Mutex<Sandbox>>,
+}
+
+impl PluginCommand for WasmCommand {
+    fn run(&mut self, args: &str, out: &Output) {
+        let mut sandbox = self.sandbox.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(err) = sandbox.call(&self.name, args, out) {
+            let message = text("plugin.failed", &[("plugin", &sandbox.plugin), ("error", &err.root_cause())]);
+            let _ = out.eprintln(&text("error", &[("message", &message)]));
+        }
+    }
+
+    fn summary(&self) -> &str {
+        &self.summary
+    }
+}
+
+impl Plugins {
+    /// Loads the WebAssembly plugin at `path`, a `.wasm` module or its
+    /// text format, and returns its name, the file name without extension.
+    ///
+    /// The plugin runs in a sandbox: it can only call the functions its
+    /// capabilities allow, and each command is stopped when it runs out of
+    /// fuel or memory. Unlike [`load`](Plugins::load), this is safe for
+    /// untrusted plugins.
+    ///
+    /// A plugin exports its `memory` and these functions:
+    ///
+    /// * `mycli_alloc(len: i32) -> i32` returns space for `len` bytes,
+    ///   where the host writes strings for the plugin.
+    /// * `mycli_commands() -> i64` returns the plugin's commands, as one
+    ///   `name<TAB>summary` line per command. Strings are returned as a
+    ///   pointer in the high 32 bits and a length in the low 32 bits.
+    /// * `mycli_run(name_ptr, name_len, args_ptr, args_len: i32) -> i32`
+    ///   runs a command with the rest of its line and returns 0 on success.
+    ///
+    /// It prints by importing `print(ptr, len: i32)` and `eprint` from the
+    /// `mycli` module, along with the functions of its [`Capability`]s.
+    /// What it prints keeps its colors, but other escape sequences and
+    /// control characters besides newlines and tabs are removed, so that
+    /// it cannot move the cursor, retitle the terminal or write to the
+    /// clipboard.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::output::{BufferSink, ColorMode, Output};
+    /// use mycli::plugin::{Plugins, WasmOptions};
+    /// use mycli::repl::{CommandHandler, Context};
+    /// # struct App;
+    /// # impl CommandHandler for App {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let path = dir.path().join("hello.wat");
+    /// std::fs::write(&path, r#"(module
+    ///     (import "mycli" "print" (func $print (param i32 i32)))
+    ///     (memory (export "memory") 1)
+    ///     (global $next (mut i32) (i32.const 1024))
+    ///     (data (i32.const 0) "hello\tsays hello")
+    ///     (data (i32.const 32) "\1b]0;pwned\07hello \1b[1mfrom\1b[0m wasm")
+    ///     (func (export "mycli_alloc") (param $len i32) (result i32)
+    ///         (global.get $next)
+    ///         (global.set $next (i32.add (global.get $next) (local.get $len))))
+    ///     (func (export "mycli_commands") (result i64)
+    ///         (i64.const 16))
+    ///     (func (export "mycli_run") (param i32 i32 i32 i32) (result i32)
+    ///         (call $print (i32.const 32) (i32.const 33))
+    ///         (i32.const 0)))"#).unwrap();
+    ///
+    /// let mut plugins = Plugins::new();
+    /// assert_eq!(plugins.load_wasm(&path, &WasmOptions::new()).unwrap(), "hello");
+    /// assert_eq!(plugins.commands(), [("hello", "hello", "says hello")]);
+    ///
+    /// let buffer = BufferSink::new();
+    /// let output = Output::new(buffer.clone()).with_color(ColorMode::Always);
+    /// let mut handler = plugins.handler(App, output.clone());
+    /// handler.handle("hello", &Context::new(output));
+    /// // The title the plugin tried to set is removed, its bold kept.
+    /// assert_eq!(buffer.contents(), "hello \x1b[1mfrom\x1b[0m wasm");
+    /// ```
+    pub fn load_wasm(&mut self, path: impl AsRef<Path>, options: &WasmOptions) -> Result<String, PluginError> {
+        let path = path.as_ref();
+        let plugin = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        let commands = instantiate(path, &plugin, options)
+            .map_err(|err| PluginError::Load { path: path.to_path_buf(), message: format!("{err:#}") })?;
+        let mut registrar = Registrar::new();
+        registrar.commands = commands;
+        self.add(&plugin, registrar)?;
+        Ok(plugin)
+    }
+}
+
+fn instantiate(
+    path: &Path,
+    plugin: &str,
+    options: &WasmOptions,
+) -> wasmtime::Result<Vec<(String, Box<dyn PluginCommand>)>> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config)?;
+    let module = Module::from_file(&engine, path)?;
+    for import in module.imports() {
+        let (module, name) = (import.module(), import.name());
+        let capability = match module {
+            "mycli" => Capability::of(name).map_err(|_| wasmtime::Error::msg(format!("there is no function mycli.{name}")))?,
+            _ => return Err(wasmtime::Error::msg(format!("imports {module}.{name}, which plugins cannot use"))),
+        };
+        if let Some(capability) = capability.filter(|capability| !options.capabilities.contains(capability)) {
+            return Err(wasmtime::Error::msg(format!("needs the {capability} capability, which is not allowed")));
+        }
+    }
+
+    let limits = StoreLimitsBuilder::new().memory_size(options.memory).instances(1).build();
+    let mut store = Store::new(&engine, Host { out: None, limits });
+    store.limiter(|host| &mut host.limits);
+    store.set_fuel(options.fuel)?;
+    let instance = linker(&engine)?.instantiate(&mut store, &module)?;
+    let memory = instance
+        .get_memory(&mut store, "memory")
+        .ok_or_else(|| wasmtime::Error::msg("the plugin does not export its memory"))?;
+    let alloc = instance.get_typed_func(&mut store, "mycli_alloc")?;
+    let run = instance.get_typed_func(&mut store, "mycli_run")?;
+    let list: TypedFunc<(), i64> = instance.get_typed_func(&mut store, "mycli_commands")?;
+    let packed = list.call(&mut store, ())?;
+    let list = read(memory.data(&store), (packed >> 32) as i32, packed as i32)?;
+
+    let sandbox = Sandbox { plugin: plugin.to_string(), store, memory, alloc, run, fuel: options.fuel };
+    let sandbox = Arc::new(Mutex::new(sandbox));
+    let commands = list.lines().filter(|line| !line.trim().is_empty()).map(|line| {
+        let (name, summary) = line.split_once('\t').unwrap_or((line, ""));
+        let command = WasmCommand { name: name.trim().to_string(), summary: summary.trim().to_string(), sandbox: sandbox.clone() };
+        (command.name.clone(), Box::new(command) as Box<dyn PluginCommand>)
+    });
+    Ok(commands.collect())
+}
+
+/// Removes escape sequences other than SGR ones, which only set colors and
+/// styles, and control characters other than newlines and tabs from text
+/// printed by a plugin.
+fn sanitize(text: &str) -> String {
+    let mut clean = String::with_capacity(text.len());
+    for segment in segments(text) {
+        match segment {
+            Segment::Text(text) => clean.extend(text.chars().filter(|&c| !c.is_control() || c == '\n' || c == '\t')),
+            Segment::Escape(escape) => {
+                let sgr = escape.strip_prefix("\x1b[").and_then(|escape| escape.strip_suffix('m'));
+                if sgr.is_some_and(|params| params.bytes().all(|b| b.is_ascii_digit() || b == b';')) {
+                    clean.push_str(escape);
+                }
+            }
+        }
+    }
+    clean
+}
+
+/// Returns the functions plugins can import. Those of capabilities that
+/// were not allowed are refused before linking.
+fn linker(engine: &Engine) -> wasmtime::Result<Linker<Host>> {
+    let mut linker = Linker::new(engine);
+    linker.func_wrap("mycli", "print", |mut caller: Caller<'_, Host>, ptr: i32, len: i32| {
+        let text = read(memory(&mut caller)?.data(&caller), ptr, len)?;
+        if let Some(out) = &caller.data().out {
+            out.print(&sanitize(&text))?;
+        }
+        Ok(())
+    })?;
+    linker.func_wrap("mycli", "eprint", |mut caller: Caller<'_, Host>, ptr: i32, len: i32| {
+        let text = read(memory(&mut caller)?.data(&caller), ptr, len)?;
+        if let Some(out) = &caller.data().out {
+            out.eprint(&sanitize(&text))?;
+        }
+        Ok(())
+    })?;
+    linker.func_wrap("mycli", "now", || {
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.as_millis() as i64)
+    })?;
+    linker.func_wrap("mycli", "env", |mut caller: Caller<'_, Host>, ptr: i32, len: i32| {
+        let memory = memory(&mut caller)?;
+        let name = read(memory.data(&caller), ptr, len)?;
+        let Ok(value) = std::env::var(name) else { return Ok(-1) };
+        let alloc = caller
+            .get_export("mycli_alloc")
+            .and_then(Extern::into_func)
+            .ok_or_else(|| wasmtime::Error::msg("the plugin does not export mycli_alloc"))?
+            .typed::<i32, i32>(&caller)?;
+        let len = i32::try_from(value.len())?;
+        let ptr = alloc.call(&mut caller, len)?;
+        memory.write(&mut caller, usize::try_from(ptr)?, value.as_bytes())?;
+        Ok((i64::from(ptr) << 32) | i64::from(len))
+    })?;
+    Ok(linker)
+}
+
+fn memory(caller: &mut Caller<'_, Host>) -> wasmtime::Result<Memory> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| wasmtime::Error::msg("the plugin does not export its memory"))
+}
+
+/// Reads the string of `len` bytes at `ptr` in a plugin's memory.
+fn read(memory: &[u8], ptr: i32, len: i32) -> wasmtime::Result<String> {
+    let start = usize::try_from(ptr)?;
+    let end = start.checked_add(usize::try_from(len)?).filter(|&end| end <= memory.len());
+    let end = end.ok_or_else(|| wasmtime::Error::msg("the plugin passed a string outside its memory"))?;
+    Ok(String::from_utf8_lossy(&memory[start..end]).into_owned())
+}
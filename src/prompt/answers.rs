@@ -0,0 +1,209 @@
+//! Pre-supplied answers that let scripts drive prompts.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use super::Error;
+
+/// Answers given ahead of time, keyed by prompt.
+///
+/// When an answer is set for a widget's key, the widget returns it without
+/// asking, whether or not there is a terminal, so the same command can be
+/// used interactively and from scripts. Each widget parses its answer the
+/// way it would parse typed input: `yes`/`no` for [`Confirm`], the item text
+/// or its index for [`Select`], comma-separated items for [`MultiSelect`],
+/// and so on. An answer that does not parse is reported as
+/// [`Error::InvalidAnswer`] rather than asked again.
+///
+/// A widget's key defaults to its prompt in lowercase with runs of other
+/// characters replaced by `_`, so `Project name` becomes `project_name`; set
+/// it explicitly with the widget's `key` method.
+///
+/// Answers are installed for the whole process with [`set_answers`].
+///
+/// [`Confirm`]: super::Confirm
+/// [`Select`]: super::Select
+/// [`MultiSelect`]: super::MultiSelect
+///
+/// # Examples
+///
+/// ```
+/// use mycli::prompt::{self, Answers, Input};
+///
+/// // e.g. from `--answer name=demo` flags
+/// let flags = ["name=demo"];
+/// let answers = Answers::from_env("MYAPP").merge(Answers::from_pairs(flags).unwrap());
+/// prompt::set_answers(answers);
+///
+/// let name = Input::new("Name").interact()?;
+/// assert_eq!(name, "demo");
+/// # Ok::<(), prompt::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Answers {
+    values: HashMap<String, String>,
+}
+
+impl Answers {
+    /// Creates an empty set of answers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the answer for `key`.
+    pub fn set(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.values.insert(key.into(), value.into());
+        self
+    }
+
+    /// Returns the answer for `key`, if there is one.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Returns `true` if no answers are set.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Adds the answers in `other`, replacing any with the same key. Merge
+    /// sources from least to most specific, such as file, then environment,
+    /// then flags.
+    pub fn merge(mut self, other: Answers) -> Self {
+        self.values.extend(other.values);
+        self
+    }
+
+    /// Collects answers from environment variables starting with `prefix`
+    /// and `_`. The rest of the name, in lowercase, is the key, so with the
+    /// prefix `MYAPP`, `MYAPP_PROJECT_NAME` answers `project_name`.
+    pub fn from_env(prefix: &str) -> Self {
+        let prefix = format!("{prefix}_");
+        let values = std::env::vars()
+            .filter_map(|(name, value)| {
+                let key = name.strip_prefix(&prefix)?;
+                Some((key.to_lowercase(), value))
+            })
+            .collect();
+        Self { values }
+    }
+
+    /// Parses `key=value` pairs, such as the values of repeated `--answer`
+    /// flags. Returns the first pair without an `=` as the error.
+    pub fn from_pairs<I, S>(pairs: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut answers = Self::new();
+        for pair in pairs {
+            let pair = pair.as_ref();
+            let (key, value) = pair.split_once('=').ok_or_else(|| pair.to_string())?;
+            answers.values.insert(key.trim().to_string(), value.to_string());
+        }
+        Ok(answers)
+    }
+
+    /// Parses an answers file of `key = value` lines.
+    ///
+    /// Blank lines and lines starting with `#` are ignored, and values may
+    /// be wrapped in double quotes to keep surrounding spaces.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut answers = Self::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", n + 1))?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+            answers.values.insert(key.trim().to_string(), value.to_string());
+        }
+        Ok(answers)
+    }
+
+    /// Reads an answers file in the format accepted by [`parse`].
+    ///
+    /// [`parse`]: Answers::parse
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|msg| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}: {msg}", path.display()))
+        })
+    }
+}
+
+static CURRENT: RwLock<Option<Arc<Answers>>> = RwLock::new(None);
+
+/// Installs the answers used by all widgets from now on.
+pub fn set_answers(answers: Answers) {
+    *CURRENT.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(answers));
+}
+
+/// Returns the answers installed with [`set_answers`], or an empty set.
+pub fn answers() -> Arc<Answers> {
+    CURRENT
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
+
+/// Returns the pre-supplied answer for `key`, if there is one.
+pub(crate) fn lookup(key: &str) -> Option<String> {
+    CURRENT
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .and_then(|answers| answers.get(key).map(String::from))
+}
+
+/// Derives a widget's default answer key from its prompt.
+pub(crate) fn key_for(prompt: &str) -> String {
+    let mut key = String::with_capacity(prompt.len());
+    for c in prompt.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            key.push(c);
+        } else if !key.is_empty() && !key.ends_with('_') {
+            key.push('_');
+        }
+    }
+    key.trim_end_matches('_').to_string()
+}
+
+/// Returns the answer key for a widget with the given prompt and explicit
+/// key.
+pub(crate) fn key(prompt: &str, key: Option<&str>) -> String {
+    key.map_or_else(|| key_for(prompt), String::from)
+}
+
+/// Finds the item named by an answer, either by its text, ignoring case, or
+/// by its index.
+#[cfg(feature = "menu")]
+pub(crate) fn find_item(items: &[String], answer: &str) -> Option<usize> {
+    let answer = answer.trim();
+    items
+        .iter()
+        .position(|item| item.eq_ignore_ascii_case(answer))
+        .or_else(|| answer.parse().ok().filter(|&i| i < items.len()))
+}
+
+/// Builds the error for a prompt that needs an answer but cannot ask.
+pub(crate) fn not_interactive(prompt: &str, key: &str) -> Error {
+    Error::NotInteractive { prompt: prompt.to_string(), key: key.to_string() }
+}
+
+/// Builds the error for a pre-supplied answer that does not parse.
+pub(crate) fn invalid(key: &str, message: impl Into<String>) -> Error {
+    Error::InvalidAnswer { key: key.to_string(), message: message.into() }
+}
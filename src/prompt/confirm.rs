@@ -0,0 +1,154 @@
+//! Yes/no confirmation prompt.
+
+use crate::locale::text;
+
+use super::answers;
+use super::{error_line, question, Result, Terminal};
+
+/// Accepted answers for one language.
+struct Answers {
+    lang: &'static str,
+    yes: &'static [&'static str],
+    no: &'static [&'static str],
+    hint: (&'static str, &'static str),
+}
+
+const ENGLISH: Answers = Answers {
+    lang: "en",
+    yes: &["y", "yes"],
+    no: &["n", "no"],
+    hint: ("y", "n"),
+};
+
+const LOCALES: &[Answers] = &[
+    Answers { lang: "ja", yes: &["はい", "うん", "ええ"], no: &["いいえ", "いや", "いえ"], hint: ("y", "n") },
+    Answers { lang: "de", yes: &["j", "ja"], no: &["n", "nein"], hint: ("j", "n") },
+    Answers { lang: "fr", yes: &["o", "oui"], no: &["n", "non"], hint: ("o", "n") },
+    Answers { lang: "es", yes: &["s", "sí", "si"], no: &["n", "no"], hint: ("s", "n") },
+    Answers { lang: "it", yes: &["s", "sì", "si"], no: &["n", "no"], hint: ("s", "n") },
+    Answers { lang: "pt", yes: &["s", "sim"], no: &["n", "não", "nao"], hint: ("s", "n") },
+    Answers { lang: "nl", yes: &["j", "ja"], no: &["n", "nee"], hint: ("j", "n") },
+];
+
+/// Returns the answers for the user's language, from `LC_ALL`,
+/// `LC_MESSAGES` or `LANG`.
+fn locale_answers() -> &'static Answers {
+    let lang = crate::locale::language();
+    LOCALES.iter().find(|a| a.lang == lang).unwrap_or(&ENGLISH)
+}
+
+/// Parses a yes/no answer in the user's language or in English.
+///
+/// Matching ignores case and surrounding whitespace. Returns `None` for an
+/// unrecognized answer.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::prompt::parse_yes_no;
+///
+/// assert_eq!(parse_yes_no("Yes"), Some(true));
+/// assert_eq!(parse_yes_no(" n "), Some(false));
+/// assert_eq!(parse_yes_no("maybe"), None);
+/// ```
+pub fn parse_yes_no(answer: &str) -> Option<bool> {
+    let answer = answer.trim().to_lowercase();
+    for answers in [locale_answers(), &ENGLISH] {
+        if answers.yes.contains(&answer.as_str()) {
+            return Some(true);
+        }
+        if answers.no.contains(&answer.as_str()) {
+            return Some(false);
+        }
+    }
+    None
+}
+
+/// A yes/no question.
+///
+/// Pressing Enter without typing selects the default, if one is set.
+/// Without a terminal the default is returned without asking, and
+/// [`Error::NotInteractive`](super::Error::NotInteractive) is returned
+/// when there is no default.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mycli::prompt::Confirm;
+///
+/// let proceed = Confirm::new("Deploy to production?")
+///     .default(false)
+///     .interact()?;
+/// # Ok::<(), mycli::prompt::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Confirm {
+    prompt: String,
+    key: Option<String>,
+    default: Option<bool>,
+}
+
+impl Confirm {
+    /// Creates a confirmation prompt asking `prompt`.
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            key: None,
+            default: None,
+        }
+    }
+
+    /// Sets the key of the pre-supplied [`Answers`] entry that answers this
+    /// prompt. Defaults to a key derived from the prompt.
+    ///
+    /// [`Answers`]: super::Answers
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Sets the answer chosen when the user just presses Enter.
+    pub fn default(mut self, default: bool) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Asks the question and waits for a valid answer.
+    pub fn interact(&self) -> Result<bool> {
+        let key = answers::key(&self.prompt, self.key.as_deref());
+        if let Some(answer) = answers::lookup(&key) {
+            return parse_yes_no(&answer).ok_or_else(|| answers::invalid(&key, "expected yes or no"));
+        }
+        let term = Terminal::stderr();
+        if !term.is_interactive() {
+            return self.default.ok_or_else(|| answers::not_interactive(&self.prompt, &key));
+        }
+
+        let (yes, no) = locale_answers().hint;
+        let hint = match self.default {
+            Some(true) => format!("[{}/{no}]", yes.to_uppercase()),
+            Some(false) => format!("[{yes}/{}]", no.to_uppercase()),
+            None => format!("[{yes}/{no}]"),
+        };
+
+        loop {
+            term.write_str(&format!("{} {hint} ", question(&self.prompt)))?;
+            let answer = term.read_line()?;
+            if answer.trim().is_empty() {
+                if let Some(default) = self.default {
+                    return Ok(default);
+                }
+            } else if let Some(answer) = parse_yes_no(&answer) {
+                return Ok(answer);
+            }
+            term.write_line(&error_line(&text("confirm.retry", &[("yes", &yes), ("no", &no)])))?;
+        }
+    }
+}
+
+/// Asks a yes/no question with no default.
+///
+/// This is a shorthand for `Confirm::new(prompt).interact()`.
+pub fn confirm(prompt: impl Into<String>) -> Result<bool> {
+    Confirm::new(prompt).interact()
+}
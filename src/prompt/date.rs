@@ -0,0 +1,259 @@
+//! Date and time input with natural formats.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Weekday};
+
+use crate::locale;
+use crate::output::theme::{self, Tone};
+
+use super::answers;
+use super::{error_line, question, Numeric, Result, Terminal};
+
+/// Parses a date and time relative to `now`.
+///
+/// Accepted forms, ignoring case:
+///
+/// - `now`
+/// - a date: `2024-06-01`, `today`, `tomorrow`, `yesterday`, a weekday such
+///   as `friday` (the next one, or today) or `next friday` (a week later if
+///   today is Friday)
+/// - a time: `14:00`, `14:00:30`, `2pm`, `2:30 pm`, alone for today or after
+///   a date, as in `tomorrow 14:00` or `2024-06-01T09:00`
+/// - an offset: `+2h`, `-30m`, `+1d12h`, `in 90m` or `15m ago`, using the
+///   units of [`Duration`]'s [`Numeric`] parsing
+///
+/// A date without a time means midnight. Returns `None` for anything else.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::{Local, TimeZone};
+/// use mycli::prompt::parse_datetime;
+///
+/// let now = Local.with_ymd_and_hms(2024, 6, 1, 9, 30, 0).unwrap();
+/// let at = |y, m, d, h, min| Local.with_ymd_and_hms(y, m, d, h, min, 0).unwrap();
+///
+/// assert_eq!(parse_datetime("2024-06-10", now), Some(at(2024, 6, 10, 0, 0)));
+/// assert_eq!(parse_datetime("tomorrow 14:00", now), Some(at(2024, 6, 2, 14, 0)));
+/// assert_eq!(parse_datetime("+2h", now), Some(at(2024, 6, 1, 11, 30)));
+/// assert_eq!(parse_datetime("monday 9am", now), Some(at(2024, 6, 3, 9, 0)));
+/// assert_eq!(parse_datetime("soon", now), None);
+/// ```
+pub fn parse_datetime(text: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let text = text.trim().to_lowercase();
+    if text == "now" {
+        return Some(now);
+    }
+    if let Some(offset) = text.strip_prefix('+').or_else(|| text.strip_prefix("in ")) {
+        return now.checked_add_signed(delta(offset)?);
+    }
+    if let Some(offset) = text.strip_prefix('-').or_else(|| text.strip_suffix(" ago")) {
+        return now.checked_sub_signed(delta(offset)?);
+    }
+
+    // `2024-06-01T09:00`, lowercased above, reads like `2024-06-01 09:00`.
+    let text = match text.split_once('t') {
+        Some((date, time)) if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok() => format!("{date} {time}"),
+        _ => text,
+    };
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.is_empty() {
+        return None;
+    }
+    let today = now.date_naive();
+    let (date, rest) = match parse_date(&words, today) {
+        Some((date, used)) => (date, &words[used..]),
+        None => (today, &words[..]),
+    };
+    let time = if rest.is_empty() { NaiveTime::MIN } else { parse_time(&rest.concat())? };
+    local(date.and_time(time))
+}
+
+fn delta(text: &str) -> Option<TimeDelta> {
+    let duration = <Duration as Numeric>::parse(text)?;
+    TimeDelta::from_std(duration).ok()
+}
+
+/// Parses the date at the start of `words`, returning it and how many words
+/// it used.
+fn parse_date(words: &[&str], today: NaiveDate) -> Option<(NaiveDate, usize)> {
+    let first = *words.first()?;
+    let date = match first {
+        "today" => today,
+        "tomorrow" => today.succ_opt()?,
+        "yesterday" => today.pred_opt()?,
+        "next" => {
+            let weekday: Weekday = words.get(1)?.parse().ok()?;
+            let ahead = days_until(today, weekday);
+            return Some((today.checked_add_days(Days::new(if ahead == 0 { 7 } else { ahead }))?, 2));
+        }
+        _ => match first.parse::<Weekday>() {
+            Ok(weekday) => today.checked_add_days(Days::new(days_until(today, weekday)))?,
+            Err(_) => NaiveDate::parse_from_str(first, "%Y-%m-%d").ok()?,
+        },
+    };
+    Some((date, 1))
+}
+
+fn days_until(today: NaiveDate, weekday: Weekday) -> u64 {
+    let from = today.weekday().num_days_from_monday();
+    let to = weekday.num_days_from_monday();
+    u64::from((to + 7 - from) % 7)
+}
+
+/// Parses `14:00`, `14:00:30`, `2pm` or `2:30pm`.
+fn parse_time(text: &str) -> Option<NaiveTime> {
+    let (clock, offset) = if let Some(clock) = text.strip_suffix("am") {
+        (clock, Some(0))
+    } else if let Some(clock) = text.strip_suffix("pm") {
+        (clock, Some(12))
+    } else {
+        (text, None)
+    };
+    let mut parts = clock.split(':').map(|p| p.parse::<u32>().ok());
+    let hour = parts.next()??;
+    let minute = parts.next().unwrap_or(Some(0))?;
+    let second = parts.next().unwrap_or(Some(0))?;
+    if parts.next().is_some() || (offset.is_none() && !clock.contains(':')) {
+        return None;
+    }
+    let hour = match offset {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some(offset) => hour % 12 + offset,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, second)
+}
+
+/// Resolves a wall-clock time in the local time zone, taking the earlier
+/// instant when a clock change makes it ambiguous.
+fn local(datetime: NaiveDateTime) -> Option<DateTime<Local>> {
+    Local.from_local_datetime(&datetime).earliest()
+}
+
+/// A prompt for a date and time, in the formats accepted by
+/// [`parse_datetime`].
+///
+/// The resolved date and time is shown after a valid answer so the user can
+/// see how it was understood. Answers outside the bounds, or that do not
+/// parse, are reported and asked again. Without a terminal the default is
+/// returned, or [`Error::NotInteractive`] when there is none.
+///
+/// [`Error::NotInteractive`]: super::Error::NotInteractive
+///
+/// # Examples
+///
+/// ```no_run
+/// use chrono::Local;
+/// use mycli::prompt::DateInput;
+///
+/// let when = DateInput::new("Run at").min(Local::now()).interact()?;
+/// println!("scheduled for {}", when.format("%Y-%m-%d %H:%M"));
+/// # Ok::<(), mycli::prompt::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct DateInput {
+    prompt: String,
+    key: Option<String>,
+    default: Option<DateTime<Local>>,
+    min: Option<DateTime<Local>>,
+    max: Option<DateTime<Local>>,
+}
+
+impl DateInput {
+    /// Creates a prompt asking for `prompt`.
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            key: None,
+            default: None,
+            min: None,
+            max: None,
+        }
+    }
+
+    /// Sets the key of the pre-supplied [`Answers`] entry that answers this
+    /// prompt. Defaults to a key derived from the prompt.
+    ///
+    /// [`Answers`]: super::Answers
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Sets the value used when the user just presses Enter.
+    pub fn default(mut self, default: DateTime<Local>) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Rejects dates before `min`.
+    pub fn min(mut self, min: DateTime<Local>) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// Rejects dates after `max`.
+    pub fn max(mut self, max: DateTime<Local>) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Asks for the date and returns it once it is valid.
+    pub fn interact(&self) -> Result<DateTime<Local>> {
+        let key = answers::key(&self.prompt, self.key.as_deref());
+        if let Some(answer) = answers::lookup(&key) {
+            return self.accept(&answer).map_err(|message| answers::invalid(&key, message));
+        }
+        let term = Terminal::stderr();
+        if !term.is_interactive() {
+            return match self.default {
+                Some(default) if self.check(default).is_none() => Ok(default),
+                _ => Err(answers::not_interactive(&self.prompt, &key)),
+            };
+        }
+
+        let theme = theme::current();
+        let hint = match self.default {
+            Some(default) => format!(" ({})", format(default)),
+            None => String::new(),
+        };
+        loop {
+            term.write_str(&format!("{}{} ", question(&self.prompt), theme.paint(Tone::Muted, &hint)))?;
+            match self.accept(&term.read_line()?) {
+                Ok(when) => {
+                    term.write_line(&theme.paint(Tone::Muted, &format!("  → {}", format(when))))?;
+                    return Ok(when);
+                }
+                Err(message) => term.write_line(&error_line(&message))?,
+            }
+        }
+    }
+
+    fn accept(&self, text: &str) -> std::result::Result<DateTime<Local>, String> {
+        let when = match self.default {
+            Some(default) if text.trim().is_empty() => default,
+            _ if text.trim().is_empty() => return Err(locale::text("prompt.required", &[])),
+            _ => parse_datetime(text, Local::now())
+                .ok_or("expected a date like 2024-06-01, tomorrow 14:00 or +2h")?,
+        };
+        match self.check(when) {
+            Some(message) => Err(message),
+            None => Ok(when),
+        }
+    }
+
+    /// Returns why `when` is out of bounds, if it is.
+    fn check(&self, when: DateTime<Local>) -> Option<String> {
+        match (self.min, self.max) {
+            (Some(min), _) if when < min => Some(format!("must be {} or later", format(min))),
+            (_, Some(max)) if when > max => Some(format!("must be {} or earlier", format(max))),
+            _ => None,
+        }
+    }
+}
+
+fn format(when: DateTime<Local>) -> String {
+    when.format("%a %Y-%m-%d %H:%M").to_string()
+}
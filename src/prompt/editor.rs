@@ -0,0 +1,182 @@
+//! Long-text input through the user's text editor.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::locale;
+use crate::output::theme::{self, Tone};
+
+use super::answers;
+use super::{answered, question, Error, Result, Terminal};
+
+/// Numbers the temporary files within this process.
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A prompt that opens `$VISUAL` or `$EDITOR` on a temporary file and returns
+/// what the user saved, like `git commit` does for commit messages.
+///
+/// The editor command may include arguments, such as `code --wait`. When
+/// neither variable is set, `vi` is used (`notepad` on Windows). If the
+/// saved text is empty, after removing comment lines when a comment prefix is
+/// set, the prompt is treated as cancelled and [`Error::Interrupted`] is
+/// returned unless [`allow_empty`] was set. Without a terminal
+/// [`Error::NotInteractive`] is returned.
+///
+/// [`allow_empty`]: Editor::allow_empty
+///
+/// # Examples
+///
+/// ```no_run
+/// use mycli::prompt::Editor;
+///
+/// let message = Editor::new("Commit message")
+///     .template("\n# Lines starting with # are ignored.\n")
+///     .comment_prefix("#")
+///     .interact()?;
+/// # Ok::<(), mycli::prompt::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Editor {
+    prompt: String,
+    key: Option<String>,
+    template: String,
+    extension: String,
+    comment_prefix: Option<String>,
+    allow_empty: bool,
+}
+
+impl Editor {
+    /// Creates a prompt asking for `prompt`.
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            key: None,
+            template: String::new(),
+            extension: "txt".to_string(),
+            comment_prefix: None,
+            allow_empty: false,
+        }
+    }
+
+    /// Sets the key of the pre-supplied [`Answers`] entry that answers this
+    /// prompt. Defaults to a key derived from the prompt.
+    ///
+    /// [`Answers`]: super::Answers
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Sets the text the file starts with.
+    pub fn template(mut self, template: impl Into<String>) -> Self {
+        self.template = template.into();
+        self
+    }
+
+    /// Sets the temporary file's extension, so editors pick the right syntax
+    /// highlighting. Defaults to `txt`.
+    pub fn extension(mut self, extension: impl Into<String>) -> Self {
+        self.extension = extension.into().trim_start_matches('.').to_string();
+        self
+    }
+
+    /// Removes lines starting with `prefix` from the saved text, so the
+    /// template can carry instructions.
+    pub fn comment_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.comment_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Accepts empty text instead of treating it as cancelled.
+    pub fn allow_empty(mut self, allow: bool) -> Self {
+        self.allow_empty = allow;
+        self
+    }
+
+    /// Opens the editor and returns the saved text, with trailing whitespace
+    /// removed.
+    pub fn interact(&self) -> Result<String> {
+        let key = answers::key(&self.prompt, self.key.as_deref());
+        if let Some(answer) = answers::lookup(&key) {
+            let text = answer.trim_end().to_string();
+            if text.is_empty() && !self.allow_empty {
+                return Err(answers::invalid(&key, locale::text("prompt.required", &[])));
+            }
+            return Ok(text);
+        }
+        let term = Terminal::stderr();
+        if !term.is_interactive() {
+            return Err(answers::not_interactive(&self.prompt, &key));
+        }
+
+        let command = editor_command();
+        let hint = theme::current().paint(Tone::Muted, &format!("(waiting for {})", command[0]));
+        term.write_line(&format!("{} {hint}", question(&self.prompt)))?;
+
+        let path = self.temp_path();
+        // `create_new` refuses to follow a file planted at the predictable
+        // path in a shared temporary directory.
+        fs::OpenOptions::new().write(true).create_new(true).open(&path)?.write_all(self.template.as_bytes())?;
+        let result = run(&command, &path).and_then(|()| fs::read_to_string(&path));
+        let _ = fs::remove_file(&path);
+        let text = self.clean(&result?);
+
+        term.clear_last_lines(1)?;
+        if text.is_empty() && !self.allow_empty {
+            return Err(Error::Interrupted);
+        }
+        let lines = text.lines().count();
+        let summary = if lines == 1 { "1 line".to_string() } else { format!("{lines} lines") };
+        term.write_line(&answered(&self.prompt, &summary))?;
+        Ok(text)
+    }
+
+    fn temp_path(&self) -> PathBuf {
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let name = format!("mycli-edit-{}-{n}.{}", std::process::id(), self.extension);
+        std::env::temp_dir().join(name)
+    }
+
+    fn clean(&self, text: &str) -> String {
+        let kept: Vec<&str> = match &self.comment_prefix {
+            Some(prefix) => text.lines().filter(|line| !line.starts_with(prefix.as_str())).collect(),
+            None => text.lines().collect(),
+        };
+        kept.join("\n").trim_end().to_string()
+    }
+}
+
+/// Returns the user's editor command split into the program and its
+/// arguments.
+fn editor_command() -> Vec<String> {
+    let configured = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .map(|value| value.split_whitespace().map(String::from).collect::<Vec<_>>())
+        .find(|parts| !parts.is_empty());
+    configured.unwrap_or_else(|| {
+        let fallback = if cfg!(windows) { "notepad" } else { "vi" };
+        vec![fallback.to_string()]
+    })
+}
+
+fn run(command: &[String], path: &Path) -> io::Result<()> {
+    let status = Command::new(&command[0]).args(&command[1..]).arg(path).status().map_err(|err| {
+        io::Error::new(err.kind(), format!("cannot start editor `{}`: {err}", command[0]))
+    })?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("editor `{}` exited with {status}", command[0])))
+    }
+}
+
+/// Opens the user's editor on an empty file and returns the saved text.
+///
+/// This is a shorthand for `Editor::new(prompt).interact()`.
+pub fn editor(prompt: impl Into<String>) -> Result<String> {
+    Editor::new(prompt).interact()
+}
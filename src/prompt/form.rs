@@ -0,0 +1,194 @@
+//! Multi-step wizards built from the other widgets.
+
+use std::cell::Cell;
+
+use crate::locale::text;
+use crate::output::detail::DetailView;
+use crate::output::theme::{self, Tone};
+
+use super::{Confirm, Error, Result, Terminal};
+
+thread_local! {
+    /// Whether a form is running on this thread, so Escape means "back".
+    static IN_FORM: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Returns the error a widget reports when the user presses Escape:
+/// [`Error::Back`] inside a form, [`Error::Interrupted`] otherwise.
+pub(crate) fn escaped() -> Error {
+    if IN_FORM.with(Cell::get) { Error::Back } else { Error::Interrupted }
+}
+
+/// Marks the current thread as running a form until dropped.
+struct FormScope(bool);
+
+impl FormScope {
+    fn enter() -> Self {
+        Self(IN_FORM.with(|f| f.replace(true)))
+    }
+}
+
+impl Drop for FormScope {
+    fn drop(&mut self) {
+        IN_FORM.with(|f| f.set(self.0));
+    }
+}
+
+type Ask<S> = Box<dyn FnMut(&mut S) -> Result<()>>;
+type Condition<S> = Box<dyn Fn(&S) -> bool>;
+type Summary<S> = Box<dyn Fn(&S) -> DetailView>;
+
+struct Step<S> {
+    ask: Ask<S>,
+    condition: Option<Condition<S>>,
+}
+
+/// A sequence of prompts that fills in a struct of answers.
+///
+/// Each step is a closure that asks one or more widgets and stores the
+/// answers in the state. Steps added with [`step_if`] are skipped unless
+/// their condition holds for the answers so far. Pressing Escape in a
+/// key-driven widget goes back to the previous step, whose closure runs
+/// again with the state as it was left, so it can offer the earlier answer
+/// as the default; Ctrl+C cancels the whole form.
+///
+/// With a [`summary`], the answers are shown once all steps are done and the
+/// user is asked to confirm them. Declining starts again from the first
+/// step.
+///
+/// [`step_if`]: Form::step_if
+/// [`summary`]: Form::summary
+///
+/// # Examples
+///
+/// ```no_run
+/// use mycli::output::detail::DetailView;
+/// use mycli::prompt::{Confirm, Form, Input};
+///
+/// #[derive(Default)]
+/// struct Project {
+///     name: String,
+///     public: bool,
+///     license: String,
+/// }
+///
+/// let project = Form::new()
+///     .title("New project")
+///     .step(|p: &mut Project| {
+///         p.name = Input::new("Name").interact()?;
+///         Ok(())
+///     })
+///     .step(|p| {
+///         p.public = Confirm::new("Publish it?").default(p.public).interact()?;
+///         Ok(())
+///     })
+///     .step_if(|p| p.public, |p| {
+///         p.license = Input::new("License").default("MIT").interact()?;
+///         Ok(())
+///     })
+///     .summary(|p| {
+///         DetailView::new()
+///             .field("Name", &p.name)
+///             .field("Public", p.public)
+///             .field_opt("License", p.public.then_some(&p.license))
+///     })
+///     .interact(Project::default())?;
+/// # Ok::<(), mycli::prompt::Error>(())
+/// ```
+pub struct Form<S> {
+    title: Option<String>,
+    steps: Vec<Step<S>>,
+    summary: Option<Summary<S>>,
+}
+
+impl<S> Default for Form<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Form<S> {
+    /// Creates a form with no steps.
+    pub fn new() -> Self {
+        Self {
+            title: None,
+            steps: Vec::new(),
+            summary: None,
+        }
+    }
+
+    /// Sets a heading shown before the first step.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Adds a step that is always asked.
+    pub fn step(mut self, ask: impl FnMut(&mut S) -> Result<()> + 'static) -> Self {
+        self.steps.push(Step { ask: Box::new(ask), condition: None });
+        self
+    }
+
+    /// Adds a step that is only asked when `condition` holds for the answers
+    /// given so far.
+    pub fn step_if(
+        mut self,
+        condition: impl Fn(&S) -> bool + 'static,
+        ask: impl FnMut(&mut S) -> Result<()> + 'static,
+    ) -> Self {
+        self.steps.push(Step {
+            ask: Box::new(ask),
+            condition: Some(Box::new(condition)),
+        });
+        self
+    }
+
+    /// Shows the answers as a [`DetailView`] once all steps are done and asks
+    /// the user to confirm them.
+    pub fn summary(mut self, summary: impl Fn(&S) -> DetailView + 'static) -> Self {
+        self.summary = Some(Box::new(summary));
+        self
+    }
+
+    /// Runs the steps starting from `state` and returns the filled-in state.
+    pub fn interact(&mut self, mut state: S) -> Result<S> {
+        let term = Terminal::stderr();
+        let _scope = FormScope::enter();
+        if let Some(title) = &self.title {
+            term.write_line(&theme::current().paint(Tone::Accent, title))?;
+        }
+
+        loop {
+            // Indices of the steps that were asked, so Back returns to the
+            // last one that was actually shown.
+            let mut asked: Vec<usize> = Vec::new();
+            let mut next = 0;
+            while next < self.steps.len() {
+                let step = &mut self.steps[next];
+                if step.condition.as_ref().is_some_and(|holds| !holds(&state)) {
+                    next += 1;
+                    continue;
+                }
+                match (step.ask)(&mut state) {
+                    Ok(()) => {
+                        asked.push(next);
+                        next += 1;
+                    }
+                    Err(Error::Back) => next = asked.pop().unwrap_or(0),
+                    Err(err) => return Err(err),
+                }
+            }
+
+            let Some(summary) = &self.summary else {
+                return Ok(state);
+            };
+            term.write_line("")?;
+            term.write_str(&summary(&state).render())?;
+            match Confirm::new(text("form.confirm", &[])).default(true).interact() {
+                Ok(true) => return Ok(state),
+                Ok(false) | Err(Error::Back) => term.write_line("")?,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! fzf-style picker for long candidate lists.
+
+use console::Key;
+
+use crate::output::accessible;
+use crate::output::theme::{self, Theme, Tone};
+use crate::output::width::{display_width, pad, truncate, Align};
+
+use super::answers;
+use super::{answered, escaped, question, Error, HiddenCursor, Result, Select, Terminal};
+
+/// A picker that narrows a long list as the user types.
+///
+/// Candidates match when they contain the typed characters in order, not
+/// necessarily next to each other, ignoring case, so `pdb` finds
+/// `prod-dashboard`. Matches are ranked so that consecutive runs and matches
+/// at the start of words come first, and the matched characters are
+/// highlighted. The list uses most of the terminal's height.
+///
+/// Lists no longer than the [`threshold`] are shown as a plain [`Select`]
+/// menu instead, since ranking adds nothing there, and so are all lists in
+/// the [accessible mode](crate::output::accessible). Without a terminal the
+/// default item is returned, or [`Error::NotInteractive`] when there is none.
+///
+/// [`threshold`]: FuzzySelect::threshold
+///
+/// # Examples
+///
+/// ```no_run
+/// use mycli::prompt::FuzzySelect;
+///
+/// let dashboards: Vec<String> = (0..5000).map(|i| format!("dashboard-{i}")).collect();
+/// let index = FuzzySelect::new("Open dashboard", &dashboards).interact()?;
+/// println!("opening {}", dashboards[index]);
+/// # Ok::<(), mycli::prompt::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct FuzzySelect {
+    prompt: String,
+    key: Option<String>,
+    items: Vec<String>,
+    details: Vec<String>,
+    query: String,
+    default: Option<usize>,
+    threshold: usize,
+    height: Option<usize>,
+    keep_answer: bool,
+}
+
+impl FuzzySelect {
+    /// Creates a picker asking `prompt` with the given candidates.
+    pub fn new<T: ToString>(prompt: impl Into<String>, items: &[T]) -> Self {
+        Self {
+            prompt: prompt.into(),
+            key: None,
+            items: items.iter().map(ToString::to_string).collect(),
+            details: Vec::new(),
+            query: String::new(),
+            default: None,
+            threshold: 20,
+            height: None,
+            keep_answer: true,
+        }
+    }
+
+    /// Sets the key of the pre-supplied [`Answers`] entry that answers this
+    /// prompt. Defaults to a key derived from the prompt.
+    ///
+    /// [`Answers`]: super::Answers
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Sets the index of the item returned when there is no terminal.
+    pub fn default(mut self, index: usize) -> Self {
+        self.default = Some(index);
+        self
+    }
+
+    /// Sets the list length up to which a plain [`Select`] menu is shown
+    /// instead. Defaults to 20; 0 always shows the picker.
+    pub fn threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets how many candidates are visible at once. Defaults to the
+    /// terminal's height minus the header lines.
+    pub fn height(mut self, height: usize) -> Self {
+        self.height = Some(height.max(1));
+        self
+    }
+
+    /// Sets text shown dimmed before each item, such as when it was last
+    /// used, in the same order as the items. Details are not searched, and
+    /// the plain [`Select`] menu leaves them out.
+    pub fn details<T: ToString>(mut self, details: &[T]) -> Self {
+        self.details = details.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// Sets the text the search starts from, which can be edited like
+    /// anything typed.
+    pub fn query(mut self, query: impl Into<String>) -> Self {
+        self.query = query.into();
+        self
+    }
+
+    /// Sets whether the chosen item is left on the screen after the
+    /// question. Defaults to `true`; pickers that put their answer somewhere
+    /// visible anyway, such as on the line being edited, can leave nothing.
+    pub fn keep_answer(mut self, keep: bool) -> Self {
+        self.keep_answer = keep;
+        self
+    }
+
+    /// Shows the picker and returns the index of the chosen item.
+    pub fn interact(&self) -> Result<usize> {
+        if self.items.is_empty() {
+            return Err(Error::NoItems);
+        }
+        if self.items.len() <= self.threshold || accessible::is_enabled() {
+            let mut select = Select::new(self.prompt.clone(), &self.items);
+            if let Some(key) = &self.key {
+                select = select.key(key.clone());
+            }
+            if let Some(default) = self.default {
+                select = select.default(default);
+            }
+            return select.interact();
+        }
+        let key = answers::key(&self.prompt, self.key.as_deref());
+        if let Some(answer) = answers::lookup(&key) {
+            return answers::find_item(&self.items, &answer)
+                .ok_or_else(|| answers::invalid(&key, format!("no choice named `{answer}`")));
+        }
+        let term = Terminal::stderr();
+        if !term.is_interactive() {
+            let default = self.default.filter(|&i| i < self.items.len());
+            return default.ok_or_else(|| answers::not_interactive(&self.prompt, &key));
+        }
+
+        let lowered: Vec<Vec<char>> = self.items.iter().map(|item| lower_chars(item)).collect();
+        let height = self.height.unwrap_or_else(|| term.height().saturating_sub(3).clamp(1, 30));
+        let _cursor = HiddenCursor::new(&term)?;
+        let mut query = self.query.clone();
+        let mut ranked: Vec<usize> = (0..self.items.len()).collect();
+        let mut searched = String::new();
+        let mut cursor = 0;
+        let mut drawn = 0;
+
+        loop {
+            if query != searched {
+                // Extending the query can only remove matches, so only the
+                // current ones need to be searched again.
+                let pool: Vec<usize> = if query.starts_with(searched.as_str()) {
+                    ranked
+                } else {
+                    (0..self.items.len()).collect()
+                };
+                ranked = rank(&lowered, &pool, &lower_chars(&query));
+                searched.clone_from(&query);
+                cursor = 0;
+            }
+
+            if drawn > 0 {
+                term.clear_last_lines(drawn)?;
+            }
+            let lines = self.render(&query, &ranked, &lowered, cursor, height, term.width());
+            for line in &lines {
+                term.write_line(line)?;
+            }
+            drawn = lines.len();
+
+            let len = ranked.len().max(1);
+            match term.read_key()? {
+                Key::ArrowUp => cursor = (cursor + len - 1) % len,
+                Key::ArrowDown | Key::Tab => cursor = (cursor + 1) % len,
+                Key::PageUp => cursor = cursor.saturating_sub(height),
+                Key::PageDown => cursor = (cursor + height).min(len - 1),
+                Key::Home => cursor = 0,
+                Key::End => cursor = len - 1,
+                Key::Enter if !ranked.is_empty() => {
+                    let index = ranked[cursor];
+                    term.clear_last_lines(drawn)?;
+                    if self.keep_answer {
+                        term.write_line(&answered(&self.prompt, &self.items[index]))?;
+                    }
+                    return Ok(index);
+                }
+                key @ (Key::Escape | Key::CtrlC) => {
+                    term.clear_last_lines(drawn)?;
+                    return Err(if key == Key::Escape { escaped() } else { Error::Interrupted });
+                }
+                Key::Backspace => {
+                    query.pop();
+                }
+                Key::Char(c) if !c.is_control() => query.push(c),
+                _ => {}
+            }
+        }
+    }
+
+    fn render(
+        &self,
+        query: &str,
+        ranked: &[usize],
+        lowered: &[Vec<char>],
+        cursor: usize,
+        height: usize,
+        width: usize,
+    ) -> Vec<String> {
+        let theme = theme::current();
+        let query_chars = lower_chars(query);
+        let mut lines = vec![format!("{} {} {query}", question(&self.prompt), theme.paint(Tone::Muted, "›"))];
+        lines.push(theme.paint(Tone::Muted, &format!("  {}/{}", ranked.len(), self.items.len())));
+
+        let page = height.min(ranked.len());
+        let start = cursor.saturating_sub(page / 2).min(ranked.len() - page);
+        let detail_width = self.details.iter().map(|detail| display_width(detail)).max().map_or(0, |width| width + 2);
+        for (pos, &index) in ranked.iter().enumerate().skip(start).take(page) {
+            let positions = score(&lowered[index], &query_chars).map_or_else(Vec::new, |(_, p)| p);
+            let mut item = highlight(&theme, &self.items[index], &positions, pos == cursor, width.saturating_sub(3 + detail_width));
+            if detail_width > 0 {
+                let detail = self.details.get(index).map_or("", String::as_str);
+                item = format!("{}{item}", theme.paint(Tone::Muted, &pad(detail, detail_width, Align::Left)));
+            }
+            if pos == cursor {
+                lines.push(format!("{} {item}", theme.paint(Tone::Accent, "❯")));
+            } else {
+                lines.push(format!("  {item}"));
+            }
+        }
+        lines
+    }
+}
+
+fn lower_chars(s: &str) -> Vec<char> {
+    s.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect()
+}
+
+/// Returns the candidates in `pool` that match `query`, best first. Ties keep
+/// shorter candidates first, then the original order.
+fn rank(lowered: &[Vec<char>], pool: &[usize], query: &[char]) -> Vec<usize> {
+    if query.is_empty() {
+        let mut all = pool.to_vec();
+        all.sort_unstable();
+        return all;
+    }
+    let mut scored: Vec<(i64, usize, usize)> = pool
+        .iter()
+        .filter_map(|&i| score(&lowered[i], query).map(|(s, _)| (s, lowered[i].len(), i)))
+        .collect();
+    scored.sort_unstable_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+    scored.into_iter().map(|(_, _, i)| i).collect()
+}
+
+/// Scores how well `candidate` matches `query`, returning the score and the
+/// matched character positions, or `None` if it does not match at all.
+///
+/// Each query character is matched at its earliest position after the
+/// previous one, except that a later position at the start of a word is
+/// preferred when it is still followed by the rest of the query.
+pub(crate) fn score(candidate: &[char], query: &[char]) -> Option<(i64, Vec<usize>)> {
+    let mut positions = Vec::with_capacity(query.len());
+    let mut from = 0;
+    for (qi, &q) in query.iter().enumerate() {
+        let first = (from..candidate.len()).find(|&i| candidate[i] == q)?;
+        let boundary = (first..candidate.len())
+            .filter(|&i| candidate[i] == q && is_boundary(candidate, i))
+            .find(|&i| is_subsequence(&candidate[i + 1..], &query[qi + 1..]));
+        // Staying consecutive with the previous match beats a boundary.
+        let consecutive = positions.last().is_some_and(|&p: &usize| p + 1 == first);
+        let pos = match boundary {
+            Some(b) if !consecutive => b,
+            _ => first,
+        };
+        positions.push(pos);
+        from = pos + 1;
+    }
+
+    let mut total = 0;
+    let mut previous: Option<usize> = None;
+    for &pos in &positions {
+        total += 1;
+        if is_boundary(candidate, pos) {
+            total += if pos == 0 { 12 } else { 8 };
+        }
+        match previous {
+            Some(p) if p + 1 == pos => total += 6,
+            Some(p) => total -= (pos - p - 1).min(10) as i64,
+            None => total -= pos.min(10) as i64 / 2,
+        }
+        previous = Some(pos);
+    }
+    Some((total, positions))
+}
+
+fn is_boundary(candidate: &[char], i: usize) -> bool {
+    i == 0 || matches!(candidate[i - 1], ' ' | '-' | '_' | '/' | '.' | ':')
+}
+
+fn is_subsequence(haystack: &[char], needle: &[char]) -> bool {
+    let mut rest = haystack.iter();
+    needle.iter().all(|c| rest.any(|h| h == c))
+}
+
+/// Paints `item` with the matched positions highlighted, cut to `width`.
+fn highlight(theme: &Theme, item: &str, positions: &[usize], current: bool, width: usize) -> String {
+    let shown = truncate(item, width);
+    let mut kept = shown.chars().count();
+    if shown.len() != item.len() {
+        // The marker at the end stands for cut-off text, not a match.
+        kept -= 1;
+    }
+
+    let base = if current { Tone::Accent } else { Tone::Plain };
+    let mut out = String::new();
+    let mut run = String::new();
+    let mut run_tone = base;
+    for (i, c) in shown.chars().enumerate() {
+        let tone = if i < kept && positions.binary_search(&i).is_ok() { Tone::Info } else { base };
+        if tone != run_tone && !run.is_empty() {
+            out.push_str(&theme.paint(run_tone, &run));
+            run.clear();
+        }
+        run_tone = tone;
+        run.push(c);
+    }
+    out.push_str(&theme.paint(run_tone, &run));
+    out
+}
+
+/// Shows a fuzzy picker with no default and returns the index of the chosen
+/// item.
+///
+/// This is a shorthand for `FuzzySelect::new(prompt, items).interact()`.
+pub fn fuzzy_select<T: ToString>(prompt: impl Into<String>, items: &[T]) -> Result<usize> {
+    FuzzySelect::new(prompt, items).interact()
+}
@@ -0,0 +1,247 @@
+//! Free-text input with validation and its own history.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+#[cfg(feature = "rustyline")]
+use rustyline::error::ReadlineError;
+#[cfg(feature = "rustyline")]
+use rustyline::config::{Behavior, Config};
+#[cfg(feature = "rustyline")]
+use rustyline::history::{DefaultHistory, History};
+#[cfg(feature = "rustyline")]
+use rustyline::{DefaultEditor, Editor};
+
+use crate::locale::text;
+use crate::output::theme::{self, Tone};
+
+use super::answers;
+#[cfg(feature = "rustyline")]
+use super::Error;
+use super::{error_line, question, Result, Terminal};
+
+type Validator = Box<dyn Fn(&str) -> std::result::Result<(), String>>;
+
+/// Answers previously given to each input prompt, keyed by history key.
+static HISTORY: Mutex<Option<HashMap<String, Vec<String>>>> = Mutex::new(None);
+
+/// A single line of text typed by the user.
+///
+/// The line is edited with the same key bindings as the REPL, but up and
+/// down recall earlier answers to this prompt rather than REPL commands.
+/// Without the `rustyline` feature the line is read as typed, with no
+/// editing.
+/// Answers rejected by the validator are reported inline and the user is
+/// asked again. Without a terminal the default is returned, or
+/// [`Error::NotInteractive`] when there is none.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mycli::prompt::Input;
+///
+/// let host = Input::new("Hostname")
+///     .default("localhost")
+///     .validate(|s| {
+///         if s.contains(' ') {
+///             Err("hostnames cannot contain spaces".into())
+///         } else {
+///             Ok(())
+///         }
+///     })
+///     .interact()?;
+/// # Ok::<(), mycli::prompt::Error>(())
+/// ```
+pub struct Input {
+    prompt: String,
+    key: Option<String>,
+    default: Option<String>,
+    allow_empty: bool,
+    history_key: Option<String>,
+    validator: Option<Validator>,
+}
+
+impl fmt::Debug for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Input")
+            .field("prompt", &self.prompt)
+            .field("default", &self.default)
+            .field("allow_empty", &self.allow_empty)
+            .field("history_key", &self.history_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Input {
+    /// Creates a prompt asking for `prompt`.
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            key: None,
+            default: None,
+            allow_empty: false,
+            history_key: None,
+            validator: None,
+        }
+    }
+
+    /// Sets the key of the pre-supplied [`Answers`] entry that answers this
+    /// prompt. Defaults to a key derived from the prompt.
+    ///
+    /// [`Answers`]: super::Answers
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Sets the value used when the user just presses Enter.
+    pub fn default(mut self, default: impl Into<String>) -> Self {
+        self.default = Some(default.into());
+        self
+    }
+
+    /// Accepts an empty answer when there is no default. By default the
+    /// user is asked again.
+    pub fn allow_empty(mut self, allow: bool) -> Self {
+        self.allow_empty = allow;
+        self
+    }
+
+    /// Sets the key under which answers are remembered. Prompts sharing a key
+    /// share their history. Defaults to the prompt text.
+    pub fn history_key(mut self, key: impl Into<String>) -> Self {
+        self.history_key = Some(key.into());
+        self
+    }
+
+    /// Checks each answer with `validator`, which returns an error message
+    /// for answers that are not acceptable.
+    pub fn validate(
+        mut self,
+        validator: impl Fn(&str) -> std::result::Result<(), String> + 'static,
+    ) -> Self {
+        self.validator = Some(Box::new(validator));
+        self
+    }
+
+    /// Asks for the value and returns it once it is valid.
+    pub fn interact(&self) -> Result<String> {
+        let key = answers::key(&self.prompt, self.key.as_deref());
+        if let Some(answer) = answers::lookup(&key) {
+            return self.accept_answer(&key, answer.trim());
+        }
+        let term = Terminal::stderr();
+        if !term.is_interactive() {
+            return match &self.default {
+                Some(default) if self.check(default).is_none() => Ok(default.clone()),
+                _ => Err(answers::not_interactive(&self.prompt, &key)),
+            };
+        }
+
+        let history_key = self.history_key.as_deref().unwrap_or(&self.prompt);
+        #[cfg(feature = "rustyline")]
+        let mut editor = editor(history_key).map_err(readline_error)?;
+        let mut line = question(&self.prompt);
+        if let Some(default) = &self.default {
+            line.push(' ');
+            line.push_str(&theme::current().paint(Tone::Muted, &format!("({default})")));
+        }
+        line.push(' ');
+
+        loop {
+            #[cfg(all(feature = "rustyline", feature = "testing"))]
+            let answer = match term.scripted() {
+                Some(_) => plain_line(&term, &line)?,
+                None => editor.readline(&line).map_err(readline_error)?,
+            };
+            #[cfg(all(feature = "rustyline", not(feature = "testing")))]
+            let answer = editor.readline(&line).map_err(readline_error)?;
+            #[cfg(not(feature = "rustyline"))]
+            let answer = plain_line(&term, &line)?;
+            let answer = answer.trim();
+            let answer = match &self.default {
+                Some(default) if answer.is_empty() => default.as_str(),
+                _ => answer,
+            };
+            if answer.is_empty() && !self.allow_empty {
+                term.write_line(&error_line(&text("prompt.required", &[])))?;
+                continue;
+            }
+            if let Some(message) = self.check(answer) {
+                term.write_line(&error_line(&message))?;
+                continue;
+            }
+            if !answer.is_empty() {
+                remember(history_key, answer);
+            }
+            return Ok(answer.to_string());
+        }
+    }
+
+    fn accept_answer(&self, key: &str, answer: &str) -> Result<String> {
+        let answer = match &self.default {
+            Some(default) if answer.is_empty() => default.as_str(),
+            _ => answer,
+        };
+        if answer.is_empty() && !self.allow_empty {
+            return Err(answers::invalid(key, text("prompt.required", &[])));
+        }
+        match self.check(answer) {
+            Some(message) => Err(answers::invalid(key, message)),
+            None => Ok(answer.to_string()),
+        }
+    }
+
+    fn check(&self, answer: &str) -> Option<String> {
+        self.validator.as_ref().and_then(|v| v(answer).err())
+    }
+}
+
+/// Shows `prompt` and reads a line as typed, with no editing.
+#[cfg(any(feature = "testing", not(feature = "rustyline")))]
+fn plain_line(term: &Terminal, prompt: &str) -> std::io::Result<String> {
+    term.write_str(prompt)?;
+    term.read_line()
+}
+
+/// Creates an editor preloaded with the history for `key`. It talks to the
+/// terminal directly so the prompt never ends up in piped stdout.
+#[cfg(feature = "rustyline")]
+fn editor(key: &str) -> rustyline::Result<Editor<(), DefaultHistory>> {
+    let config = Config::builder()
+        .behavior(Behavior::PreferTerm)
+        .auto_add_history(false)
+        .build();
+    let mut editor = DefaultEditor::with_config(config)?;
+    let history = HISTORY.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(entries) = history.as_ref().and_then(|h| h.get(key)) {
+        for entry in entries {
+            editor.history_mut().add(entry)?;
+        }
+    }
+    Ok(editor)
+}
+
+fn remember(key: &str, answer: &str) {
+    let mut history = HISTORY.lock().unwrap_or_else(|e| e.into_inner());
+    let entries = history.get_or_insert_with(HashMap::new).entry(key.to_string()).or_default();
+    entries.retain(|e| e != answer);
+    entries.push(answer.to_string());
+}
+
+#[cfg(feature = "rustyline")]
+fn readline_error(err: ReadlineError) -> Error {
+    match err {
+        ReadlineError::Interrupted | ReadlineError::Eof => Error::Interrupted,
+        ReadlineError::Io(err) => Error::Io(err),
+        err => Error::Io(std::io::Error::other(err)),
+    }
+}
+
+/// Asks for a non-empty line of text.
+///
+/// This is a shorthand for `Input::new(prompt).interact()`.
+pub fn input(prompt: impl Into<String>) -> Result<String> {
+    Input::new(prompt).interact()
+}
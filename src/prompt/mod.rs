@@ -0,0 +1,319 @@
+//! Interactive prompt widgets for use inside command handlers.
+//!
+//! The widgets draw on stderr and read from the terminal directly, so they
+//! can be called while a command is running without disturbing the REPL's
+//! own line editor, and without mixing prompts into piped stdout.
+//!
+//! Scripts can answer prompts ahead of time with [`Answers`], for example
+//! from flags, environment variables or an answers file.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use mycli::prompt;
+//!
+//! if prompt::confirm("Delete 14 records?")? {
+//!     println!("deleted");
+//! }
+//! # Ok::<(), prompt::Error>(())
+//! ```
+
+mod answers;
+mod confirm;
+#[cfg(feature = "datetime")]
+mod date;
+mod editor;
+mod form;
+mod input;
+mod number;
+mod password;
+#[cfg(feature = "testing")]
+pub(crate) mod scripted;
+#[cfg(feature = "menu")]
+mod fuzzy;
+#[cfg(feature = "menu")]
+mod multiselect;
+#[cfg(feature = "menu")]
+mod path;
+#[cfg(feature = "menu")]
+mod select;
+
+use std::fmt;
+use std::io::{self, IsTerminal};
+
+use console::{Key, Term};
+
+use crate::locale;
+use crate::output::accessible;
+use crate::output::theme::{self, Tone};
+
+pub(crate) use form::escaped;
+
+pub use answers::{answers, set_answers, Answers};
+pub use confirm::{confirm, parse_yes_no, Confirm};
+#[cfg(feature = "datetime")]
+pub use date::{parse_datetime, DateInput};
+pub use editor::{editor, Editor};
+pub use form::Form;
+pub use input::{input, Input};
+pub use number::{number, Bytes, Number, Numeric};
+pub use password::{password, Password};
+#[cfg(feature = "menu")]
+pub use fuzzy::{fuzzy_select, FuzzySelect};
+#[cfg(feature = "menu")]
+pub use multiselect::MultiSelect;
+#[cfg(feature = "menu")]
+pub use path::{PathKind, PathMode, PathSelect};
+#[cfg(feature = "menu")]
+pub use select::{select, Select};
+
+/// An error returned by a prompt widget.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading from or writing to the terminal failed.
+    Io(io::Error),
+    /// The user cancelled the prompt, for example with Escape or Ctrl+C.
+    Interrupted,
+    /// The user pressed Escape inside a [`Form`] to return to the previous
+    /// step. Forms handle this themselves; it only reaches callers that run
+    /// widgets inside a form step and inspect the error.
+    Back,
+    /// The prompt needs an answer but there is no terminal to ask on and no
+    /// pre-supplied answer.
+    NotInteractive {
+        /// The prompt that could not be asked.
+        prompt: String,
+        /// The key an [`Answers`] entry would need to answer it.
+        key: String,
+    },
+    /// A pre-supplied answer is not valid for its prompt.
+    InvalidAnswer {
+        /// The key of the answer.
+        key: String,
+        /// Why the answer was rejected.
+        message: String,
+    },
+    /// A choice was requested from an empty list.
+    NoItems,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => f.write_str(&locale::text("prompt.terminal_error", &[("error", err)])),
+            Error::Interrupted => f.write_str(&locale::text("prompt.cancelled", &[])),
+            Error::Back => f.write_str(&locale::text("prompt.back", &[])),
+            Error::NotInteractive { prompt, key } => f.write_str(&locale::text("prompt.not_interactive", &[("prompt", prompt), ("key", key)])),
+            Error::InvalidAnswer { key, message } => f.write_str(&locale::text("prompt.invalid_answer", &[("key", key), ("message", message)])),
+            Error::NoItems => f.write_str(&locale::text("prompt.no_items", &[])),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::Interrupted => Error::Interrupted,
+            _ => Error::Io(err),
+        }
+    }
+}
+
+/// A specialized `Result` type for prompt widgets.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The terminal the widgets talk to.
+pub(crate) struct Terminal {
+    term: Term,
+    #[cfg(feature = "testing")]
+    scripted: Option<std::rc::Rc<dyn scripted::ScriptedTerminal>>,
+}
+
+impl Terminal {
+    pub(crate) fn stderr() -> Self {
+        Self {
+            term: Term::stderr(),
+            #[cfg(feature = "testing")]
+            scripted: scripted::current(),
+        }
+    }
+
+    /// Returns the test script answering in place of the terminal, if any.
+    #[cfg(feature = "testing")]
+    pub(crate) fn scripted(&self) -> Option<&dyn scripted::ScriptedTerminal> {
+        self.scripted.as_deref()
+    }
+
+    /// Returns `true` if both the input and the prompt output are terminals.
+    pub(crate) fn is_interactive(&self) -> bool {
+        #[cfg(feature = "testing")]
+        if self.scripted.is_some() {
+            return true;
+        }
+        self.term.is_term() && io::stdin().is_terminal()
+    }
+
+    pub(crate) fn write_str(&self, s: &str) -> io::Result<()> {
+        #[cfg(feature = "testing")]
+        if let Some(scripted) = self.scripted() {
+            scripted.write(s);
+            return Ok(());
+        }
+        self.term.write_str(s)
+    }
+
+    pub(crate) fn write_line(&self, s: &str) -> io::Result<()> {
+        #[cfg(feature = "testing")]
+        if let Some(scripted) = self.scripted() {
+            scripted.write(&format!("{s}\n"));
+            return Ok(());
+        }
+        self.term.write_line(s)
+    }
+
+    pub(crate) fn clear_last_lines(&self, n: usize) -> io::Result<()> {
+        #[cfg(feature = "testing")]
+        if self.scripted.is_some() {
+            return Ok(());
+        }
+        self.term.clear_last_lines(n)
+    }
+
+    /// Clears the current line and moves the cursor to its start.
+    pub(crate) fn clear_line(&self) -> io::Result<()> {
+        #[cfg(feature = "testing")]
+        if self.scripted.is_some() {
+            return Ok(());
+        }
+        self.term.clear_line()
+    }
+
+    pub(crate) fn read_line(&self) -> io::Result<String> {
+        #[cfg(feature = "testing")]
+        if let Some(scripted) = self.scripted() {
+            return scripted::read_line(scripted, true);
+        }
+        self.term.read_line()
+    }
+
+    /// Reads a line without echoing it.
+    pub(crate) fn read_secure_line(&self) -> io::Result<String> {
+        #[cfg(feature = "testing")]
+        if let Some(scripted) = self.scripted() {
+            return scripted::read_line(scripted, false);
+        }
+        self.term.read_secure_line()
+    }
+
+    /// Reads a key press, reporting Ctrl+C as [`Key::CtrlC`] instead of
+    /// raising `SIGINT`.
+    pub(crate) fn read_key(&self) -> io::Result<Key> {
+        #[cfg(feature = "testing")]
+        if let Some(scripted) = self.scripted() {
+            return scripted.read_key();
+        }
+        self.term.read_key_raw()
+    }
+}
+
+#[cfg(feature = "menu")]
+impl Terminal {
+    pub(crate) fn hide_cursor(&self) -> io::Result<()> {
+        #[cfg(feature = "testing")]
+        if self.scripted.is_some() {
+            return Ok(());
+        }
+        self.term.hide_cursor()
+    }
+
+    pub(crate) fn show_cursor(&self) -> io::Result<()> {
+        #[cfg(feature = "testing")]
+        if self.scripted.is_some() {
+            return Ok(());
+        }
+        self.term.show_cursor()
+    }
+
+    pub(crate) fn width(&self) -> usize {
+        self.term.size_checked().map_or(crate::output::term::DEFAULT_WIDTH, |(_, w)| w as usize)
+    }
+
+    pub(crate) fn height(&self) -> usize {
+        self.term.size_checked().map_or(24, |(h, _)| h as usize)
+    }
+}
+
+/// Hides the cursor until dropped, so it is restored even when a widget
+/// returns early with an error.
+#[cfg(feature = "menu")]
+pub(crate) struct HiddenCursor<'a>(&'a Terminal);
+
+#[cfg(feature = "menu")]
+impl<'a> HiddenCursor<'a> {
+    pub(crate) fn new(term: &'a Terminal) -> io::Result<Self> {
+        term.hide_cursor()?;
+        Ok(Self(term))
+    }
+}
+
+#[cfg(feature = "menu")]
+impl Drop for HiddenCursor<'_> {
+    fn drop(&mut self) {
+        let _ = self.0.show_cursor();
+    }
+}
+
+/// Formats the question line shared by all widgets.
+pub(crate) fn question(prompt: &str) -> String {
+    let theme = theme::current();
+    format!("{} {}", theme.paint(Tone::Info, "?"), theme.paint(Tone::Accent, prompt))
+}
+
+/// Formats the question line together with the answer that was given,
+/// shown once a widget is done.
+pub(crate) fn answered(prompt: &str, answer: &str) -> String {
+    let theme = theme::current();
+    format!("{} {}", question(prompt), theme.paint(Tone::Success, answer))
+}
+
+/// Formats an inline validation error.
+pub(crate) fn error_line(message: &str) -> String {
+    if accessible::is_enabled() {
+        return format!("  {}", locale::text("error", &[("message", &message)]));
+    }
+    theme::current().paint(Tone::Error, &format!("  ✗ {message}"))
+}
+
+/// Lists `items` with numbers from 1, for menus in the
+/// [accessible mode](accessible), marking the `selected` ones.
+#[cfg(feature = "menu")]
+pub(crate) fn write_numbered(term: &Terminal, items: &[String], selected: &[bool]) -> io::Result<()> {
+    let mark = locale::text("menu.selected", &[]);
+    for (i, item) in items.iter().enumerate() {
+        match selected.get(i) {
+            Some(true) => term.write_line(&format!("  {}. {item} {mark}", i + 1))?,
+            _ => term.write_line(&format!("  {}. {item}", i + 1))?,
+        }
+    }
+    Ok(())
+}
+
+/// Returns the item a menu answer in the [accessible mode](accessible)
+/// names: a number from 1, or an item ignoring case.
+#[cfg(feature = "menu")]
+pub(crate) fn numbered_item(items: &[String], answer: &str) -> Option<usize> {
+    let answer = answer.trim();
+    match answer.parse::<usize>() {
+        Ok(n) => (1..=items.len()).contains(&n).then(|| n - 1),
+        Err(_) => items.iter().position(|item| item.eq_ignore_ascii_case(answer)),
+    }
+}
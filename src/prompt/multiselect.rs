@@ -0,0 +1,273 @@
+//! Checkbox list for choosing several items.
+
+use console::Key;
+
+use crate::locale::{self, text_count};
+use crate::output::accessible;
+use crate::output::theme::{self, Tone};
+use crate::output::width::truncate;
+
+use super::answers;
+use super::{answered, error_line, escaped, numbered_item, question, write_numbered, Error, HiddenCursor, Result, Terminal};
+
+/// A checkbox list navigated with the arrow keys.
+///
+/// Space toggles the highlighted item and `a` toggles all of them. Enter
+/// accepts the selection if it satisfies the [`min`] and [`max`] limits, and
+/// otherwise shows why it was rejected. Escape or Ctrl+C cancels with
+/// [`Error::Interrupted`]. Without a terminal the initially checked items are
+/// returned if [`checked`] was called and they satisfy the limits, and
+/// [`Error::NotInteractive`] otherwise.
+///
+/// In the [accessible mode](crate::output::accessible) the items are listed
+/// with numbers, and the selection is typed as numbers or names separated
+/// by commas; an empty line keeps the initially checked items.
+///
+/// [`min`]: MultiSelect::min
+/// [`max`]: MultiSelect::max
+/// [`checked`]: MultiSelect::checked
+///
+/// # Examples
+///
+/// ```no_run
+/// use mycli::prompt::MultiSelect;
+///
+/// let services = ["api", "worker", "scheduler", "web"];
+/// let chosen = MultiSelect::new("Services to restart", &services)
+///     .checked(&[0])
+///     .min(1)
+///     .interact()?;
+/// for index in chosen {
+///     println!("restarting {}", services[index]);
+/// }
+/// # Ok::<(), mycli::prompt::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct MultiSelect {
+    prompt: String,
+    key: Option<String>,
+    items: Vec<String>,
+    checked: Option<Vec<bool>>,
+    min: usize,
+    max: Option<usize>,
+    page_size: usize,
+}
+
+impl MultiSelect {
+    /// Creates a checkbox list asking `prompt` with the given items.
+    pub fn new<T: ToString>(prompt: impl Into<String>, items: &[T]) -> Self {
+        Self {
+            prompt: prompt.into(),
+            key: None,
+            items: items.iter().map(ToString::to_string).collect(),
+            checked: None,
+            min: 0,
+            max: None,
+            page_size: 7,
+        }
+    }
+
+    /// Sets the key of the pre-supplied [`Answers`] entry that answers this
+    /// prompt. Defaults to a key derived from the prompt.
+    ///
+    /// [`Answers`]: super::Answers
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Sets the indices of the items checked initially.
+    pub fn checked(mut self, indices: &[usize]) -> Self {
+        let mut checked = vec![false; self.items.len()];
+        for &i in indices {
+            if let Some(c) = checked.get_mut(i) {
+                *c = true;
+            }
+        }
+        self.checked = Some(checked);
+        self
+    }
+
+    /// Requires at least `min` items to be selected.
+    pub fn min(mut self, min: usize) -> Self {
+        self.min = min;
+        self
+    }
+
+    /// Allows at most `max` items to be selected.
+    pub fn max(mut self, max: usize) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Sets how many items are visible at once. Longer lists scroll.
+    pub fn page_size(mut self, size: usize) -> Self {
+        self.page_size = size.max(1);
+        self
+    }
+
+    /// Shows the list and returns the indices of the selected items in
+    /// ascending order.
+    pub fn interact(&self) -> Result<Vec<usize>> {
+        if self.items.is_empty() {
+            return Err(Error::NoItems);
+        }
+        let key = answers::key(&self.prompt, self.key.as_deref());
+        if let Some(answer) = answers::lookup(&key) {
+            return self.accept_answer(&key, &answer);
+        }
+        let term = Terminal::stderr();
+        let mut checked = self.checked.clone().unwrap_or_else(|| vec![false; self.items.len()]);
+        if !term.is_interactive() {
+            return match &self.checked {
+                Some(_) if self.validate(&checked).is_none() => Ok(selected(&checked)),
+                _ => Err(answers::not_interactive(&self.prompt, &key)),
+            };
+        }
+
+        if accessible::is_enabled() {
+            return self.interact_numbered(&term, checked);
+        }
+
+        let _cursor = HiddenCursor::new(&term)?;
+        let mut cursor = 0;
+        let mut drawn = 0;
+        let mut problem: Option<String> = None;
+
+        loop {
+            if drawn > 0 {
+                term.clear_last_lines(drawn)?;
+            }
+            let mut lines = self.render(&checked, cursor, term.width());
+            if let Some(problem) = &problem {
+                lines.push(error_line(problem));
+            }
+            for line in &lines {
+                term.write_line(line)?;
+            }
+            drawn = lines.len();
+
+            let len = self.items.len();
+            problem = None;
+            match term.read_key()? {
+                Key::ArrowUp => cursor = (cursor + len - 1) % len,
+                Key::ArrowDown | Key::Tab => cursor = (cursor + 1) % len,
+                Key::PageUp => cursor = cursor.saturating_sub(self.page_size),
+                Key::PageDown => cursor = (cursor + self.page_size).min(len - 1),
+                Key::Home => cursor = 0,
+                Key::End => cursor = len - 1,
+                Key::Char(' ') => checked[cursor] = !checked[cursor],
+                Key::Char('a') => {
+                    let all = checked.iter().all(|&c| c);
+                    checked.iter_mut().for_each(|c| *c = !all);
+                }
+                Key::Enter => match self.validate(&checked) {
+                    Some(message) => problem = Some(message),
+                    None => {
+                        let indices = selected(&checked);
+                        let names: Vec<&str> = indices.iter().map(|&i| self.items[i].as_str()).collect();
+                        term.clear_last_lines(drawn)?;
+                        term.write_line(&answered(&self.prompt, &names.join(", ")))?;
+                        return Ok(indices);
+                    }
+                },
+                key @ (Key::Escape | Key::CtrlC) => {
+                    term.clear_last_lines(drawn)?;
+                    return Err(if key == Key::Escape { escaped() } else { Error::Interrupted });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Asks for the items by number or name, for the accessible mode.
+    fn interact_numbered(&self, term: &Terminal, initial: Vec<bool>) -> Result<Vec<usize>> {
+        term.write_line(&question(&self.prompt))?;
+        write_numbered(term, &self.items, &initial)?;
+        let ask = locale::text("menu.choose_many", &[]);
+        loop {
+            term.write_str(&format!("{ask}: "))?;
+            let answer = term.read_line()?;
+            let mut checked = if answer.trim().is_empty() { initial.clone() } else { vec![false; self.items.len()] };
+            let mut problem = None;
+            for name in answer.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                match numbered_item(&self.items, name) {
+                    Some(index) => checked[index] = true,
+                    None => {
+                        problem = Some(locale::text("menu.no_choice", &[("name", &name)]));
+                        break;
+                    }
+                }
+            }
+            match problem.or_else(|| self.validate(&checked)) {
+                Some(problem) => term.write_line(&error_line(&problem))?,
+                None => {
+                    let indices = selected(&checked);
+                    let names: Vec<&str> = indices.iter().map(|&i| self.items[i].as_str()).collect();
+                    term.write_line(&answered(&self.prompt, &names.join(", ")))?;
+                    return Ok(indices);
+                }
+            }
+        }
+    }
+
+    /// Shows the list and returns the selected items themselves.
+    pub fn interact_values(&self) -> Result<Vec<String>> {
+        let indices = self.interact()?;
+        Ok(indices.into_iter().map(|i| self.items[i].clone()).collect())
+    }
+
+    /// Parses a comma-separated list of items given as a pre-supplied answer.
+    fn accept_answer(&self, key: &str, answer: &str) -> Result<Vec<usize>> {
+        let mut checked = vec![false; self.items.len()];
+        for name in answer.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            let index = answers::find_item(&self.items, name)
+                .ok_or_else(|| answers::invalid(key, format!("no choice named `{name}`")))?;
+            checked[index] = true;
+        }
+        match self.validate(&checked) {
+            Some(message) => Err(answers::invalid(key, message)),
+            None => Ok(selected(&checked)),
+        }
+    }
+
+    /// Returns why the selection is not acceptable, if it is not.
+    fn validate(&self, checked: &[bool]) -> Option<String> {
+        let count = checked.iter().filter(|&&c| c).count();
+        if count < self.min {
+            return Some(text_count("select.at_least", self.min, &[]));
+        }
+        match self.max {
+            Some(max) if count > max => Some(text_count("select.at_most", max, &[])),
+            _ => None,
+        }
+    }
+
+    fn render(&self, checked: &[bool], cursor: usize, width: usize) -> Vec<String> {
+        let theme = theme::current();
+        let hint = theme.paint(Tone::Muted, "(space to toggle, a for all)");
+        let mut lines = vec![format!("{} {hint}", question(&self.prompt))];
+
+        let len = self.items.len();
+        let page = self.page_size.min(len);
+        let start = cursor.saturating_sub(page / 2).min(len - page);
+        for (i, item) in self.items.iter().enumerate().skip(start).take(page) {
+            let item = truncate(item, width.saturating_sub(5));
+            let mark = if checked[i] {
+                theme.paint(Tone::Success, "◉")
+            } else {
+                "◯".to_string()
+            };
+            if i == cursor {
+                lines.push(format!("{} {mark} {}", theme.paint(Tone::Accent, "❯"), theme.paint(Tone::Accent, &item)));
+            } else {
+                lines.push(format!("  {mark} {item}"));
+            }
+        }
+        lines
+    }
+}
+
+fn selected(checked: &[bool]) -> Vec<usize> {
+    checked.iter().enumerate().filter(|&(_, &c)| c).map(|(i, _)| i).collect()
+}
@@ -0,0 +1,449 @@
+//! Numeric input with bounds, stepping and unit suffixes.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use console::Key;
+
+use crate::locale;
+use crate::output::accessible;
+use crate::output::theme::{self, Tone};
+use crate::output::width::display_width;
+
+use super::answers;
+use super::{answered, error_line, escaped, question, Error, Result, Terminal};
+
+/// A value that can be asked for with [`Number`].
+///
+/// It is implemented for the primitive integer and float types, for
+/// [`Duration`] (`1m30s`, `250ms`) and for [`Bytes`] (`2GiB`, `512K`).
+pub trait Numeric: Copy + PartialOrd {
+    /// The value stepping starts from when nothing has been typed.
+    const ZERO: Self;
+    /// The amount the arrow keys change the value by unless
+    /// [`Number::step`] is set.
+    const STEP: Self;
+    /// The [catalog](crate::locale) id of the text describing the accepted
+    /// syntax, shown when parsing fails. Text that is not an id in the
+    /// catalog is shown as it is.
+    const EXPECTED: &'static str;
+
+    /// Parses typed text, returning `None` if it is not a valid value.
+    fn parse(s: &str) -> Option<Self>;
+
+    /// Formats the value so that [`parse`](Numeric::parse) accepts it again.
+    fn format(&self) -> String;
+
+    /// Adds `step` to the value, or subtracts it when `up` is `false`,
+    /// saturating at the limits of the type.
+    fn step(self, step: Self, up: bool) -> Self;
+}
+
+macro_rules! numeric_int {
+    ($($t:ty),*) => {$(
+        impl Numeric for $t {
+            const ZERO: Self = 0;
+            const STEP: Self = 1;
+            const EXPECTED: &'static str = "number.expected_integer";
+
+            fn parse(s: &str) -> Option<Self> {
+                s.replace('_', "").parse().ok()
+            }
+
+            fn format(&self) -> String {
+                self.to_string()
+            }
+
+            fn step(self, step: Self, up: bool) -> Self {
+                if up { self.saturating_add(step) } else { self.saturating_sub(step) }
+            }
+        }
+    )*};
+}
+
+numeric_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! numeric_float {
+    ($($t:ty),*) => {$(
+        impl Numeric for $t {
+            const ZERO: Self = 0.0;
+            const STEP: Self = 1.0;
+            const EXPECTED: &'static str = "number.expected_float";
+
+            fn parse(s: &str) -> Option<Self> {
+                s.replace('_', "").parse().ok().filter(|v: &Self| v.is_finite())
+            }
+
+            fn format(&self) -> String {
+                self.to_string()
+            }
+
+            fn step(self, step: Self, up: bool) -> Self {
+                if up { self + step } else { self - step }
+            }
+        }
+    )*};
+}
+
+numeric_float!(f32, f64);
+
+/// Units accepted in durations, longest suffix first so `ms` wins over `m`.
+const DURATION_UNITS: [(&str, f64); 9] = [
+    ("ns", 1e-9),
+    ("us", 1e-6),
+    ("µs", 1e-6),
+    ("ms", 1e-3),
+    ("s", 1.0),
+    ("m", 60.0),
+    ("h", 3600.0),
+    ("d", 86400.0),
+    ("w", 604800.0),
+];
+
+impl Numeric for Duration {
+    const ZERO: Self = Duration::ZERO;
+    const STEP: Self = Duration::from_secs(1);
+    const EXPECTED: &'static str = "number.expected_duration";
+
+    /// Parses one or more `<number><unit>` parts, such as `1h30m` or `1.5s`.
+    /// A bare number is taken as seconds.
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if s.is_empty() {
+            return None;
+        }
+        if let Ok(secs) = s.parse::<f64>() {
+            return Duration::try_from_secs_f64(secs).ok();
+        }
+        let mut total = 0.0;
+        let mut rest = s;
+        while !rest.is_empty() {
+            let digits = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+            let value: f64 = rest[..digits].parse().ok()?;
+            rest = rest[digits..].trim_start();
+            let (unit, scale) = DURATION_UNITS.iter().find(|(unit, _)| {
+                rest.starts_with(unit) && !rest[unit.len()..].starts_with(|c: char| c.is_alphabetic())
+            })?;
+            total += value * scale;
+            rest = rest[unit.len()..].trim_start();
+        }
+        Duration::try_from_secs_f64(total).ok()
+    }
+
+    fn format(&self) -> String {
+        if self.subsec_nanos() != 0 {
+            if self.as_secs() == 0 && self.subsec_nanos().is_multiple_of(1_000_000) {
+                return format!("{}ms", self.subsec_millis());
+            }
+            return format!("{self:?}");
+        }
+        let secs = self.as_secs();
+        if secs == 0 {
+            return "0s".to_string();
+        }
+        let parts = [("d", secs / 86400), ("h", secs % 86400 / 3600), ("m", secs % 3600 / 60), ("s", secs % 60)];
+        parts
+            .iter()
+            .filter(|(_, n)| *n > 0)
+            .map(|(unit, n)| format!("{n}{unit}"))
+            .collect()
+    }
+
+    fn step(self, step: Self, up: bool) -> Self {
+        if up { self.saturating_add(step) } else { self.saturating_sub(step) }
+    }
+}
+
+/// A size in bytes, parsed from values like `2GiB`, `512K` or `1.5 MB`.
+///
+/// Suffixes ending in `iB`, and single-letter suffixes like `K` and `G`, are
+/// powers of 1024; `KB`, `MB` and so on are powers of 1000.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::prompt::Bytes;
+///
+/// assert_eq!("2GiB".parse::<Bytes>().unwrap(), Bytes(2 * 1024 * 1024 * 1024));
+/// assert_eq!("1.5 MB".parse::<Bytes>().unwrap(), Bytes(1_500_000));
+/// assert_eq!(Bytes(1536 * 1024).to_string(), "1.5MiB");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Bytes(pub u64);
+
+const BINARY_UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+
+impl FromStr for Bytes {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '_')).unwrap_or(s.len());
+        let value: f64 = s[..split]
+            .replace('_', "")
+            .parse()
+            .map_err(|_| locale::text("number.invalid_size", &[("size", &s)]))?;
+        let unit = s[split..].trim();
+        let scale: f64 = match unit.to_ascii_lowercase().as_str() {
+            "" | "b" => 1.0,
+            "k" | "kib" => 1024f64,
+            "m" | "mib" => 1024f64.powi(2),
+            "g" | "gib" => 1024f64.powi(3),
+            "t" | "tib" => 1024f64.powi(4),
+            "p" | "pib" => 1024f64.powi(5),
+            "kb" => 1e3,
+            "mb" => 1e6,
+            "gb" => 1e9,
+            "tb" => 1e12,
+            "pb" => 1e15,
+            _ => return Err(locale::text("number.unknown_unit", &[("unit", &unit)])),
+        };
+        let bytes = (value * scale).round();
+        if bytes > u64::MAX as f64 {
+            return Err(locale::text("number.size_too_large", &[("size", &s)]));
+        }
+        Ok(Bytes(bytes as u64))
+    }
+}
+
+impl fmt::Display for Bytes {
+    /// Uses the largest binary unit the value reaches, with up to two
+    /// decimals.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut unit = 0;
+        let mut value = self.0 as f64;
+        while value >= 1024.0 && unit < BINARY_UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        let number = format!("{value:.2}");
+        let number = number.trim_end_matches('0').trim_end_matches('.');
+        write!(f, "{number}{}", BINARY_UNITS[unit])
+    }
+}
+
+impl Numeric for Bytes {
+    const ZERO: Self = Bytes(0);
+    const STEP: Self = Bytes(1024 * 1024);
+    const EXPECTED: &'static str = "number.expected_size";
+
+    fn parse(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
+
+    fn format(&self) -> String {
+        self.to_string()
+    }
+
+    fn step(self, step: Self, up: bool) -> Self {
+        if up {
+            Bytes(self.0.saturating_add(step.0))
+        } else {
+            Bytes(self.0.saturating_sub(step.0))
+        }
+    }
+}
+
+/// A prompt for a number, duration or size.
+///
+/// The arrow keys step the value up and down by the [`step`], staying within
+/// the bounds. Enter accepts the value if it parses and is within bounds,
+/// and otherwise shows why next to the input. Escape or Ctrl+C cancels with
+/// [`Error::Interrupted`]. Without a terminal the default is returned, or
+/// [`Error::NotInteractive`] when there is none.
+///
+/// In the [accessible mode](crate::output::accessible) the value is read as
+/// a plain line, and a rejected value is explained on the next line before
+/// asking again.
+///
+/// [`step`]: Number::step
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use mycli::prompt::{Bytes, Number};
+///
+/// let port: u16 = Number::new("Port").min(1024).default(8080).interact()?;
+/// let timeout: Duration = Number::new("Timeout").default(Duration::from_secs(10)).interact()?;
+/// let quota: Bytes = Number::new("Quota").max(Bytes(1 << 40)).interact()?;
+/// # Ok::<(), mycli::prompt::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Number<T> {
+    prompt: String,
+    key: Option<String>,
+    default: Option<T>,
+    min: Option<T>,
+    max: Option<T>,
+    step: Option<T>,
+}
+
+impl<T: Numeric> Number<T> {
+    /// Creates a prompt asking for `prompt`.
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            key: None,
+            default: None,
+            min: None,
+            max: None,
+            step: None,
+        }
+    }
+
+    /// Sets the key of the pre-supplied [`Answers`] entry that answers this
+    /// prompt. Defaults to a key derived from the prompt.
+    ///
+    /// [`Answers`]: super::Answers
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Sets the value used when the user just presses Enter.
+    pub fn default(mut self, default: T) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Rejects values below `min`.
+    pub fn min(mut self, min: T) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// Rejects values above `max`.
+    pub fn max(mut self, max: T) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Sets the amount the arrow keys change the value by.
+    pub fn step(mut self, step: T) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    /// Asks for the value and returns it once it is valid.
+    pub fn interact(&self) -> Result<T> {
+        let key = answers::key(&self.prompt, self.key.as_deref());
+        if let Some(answer) = answers::lookup(&key) {
+            return self.accept(&answer).map_err(|message| answers::invalid(&key, message));
+        }
+        let term = Terminal::stderr();
+        if !term.is_interactive() {
+            return match self.default {
+                Some(default) if self.check(default).is_none() => Ok(default),
+                _ => Err(answers::not_interactive(&self.prompt, &key)),
+            };
+        }
+
+        if accessible::is_enabled() {
+            loop {
+                term.write_str(&format!("{} ", self.question()))?;
+                match self.accept(&term.read_line()?) {
+                    Ok(value) => return Ok(value),
+                    Err(message) => term.write_line(&error_line(&message))?,
+                }
+            }
+        }
+
+        let mut text = String::new();
+        let mut problem: Option<String> = None;
+        loop {
+            self.draw(&term, &text, problem.as_deref())?;
+            problem = None;
+            match term.read_key()? {
+                Key::Enter => match self.accept(&text) {
+                    Ok(value) => {
+                        term.clear_line()?;
+                        term.write_line(&answered(&self.prompt, &value.format()))?;
+                        return Ok(value);
+                    }
+                    Err(message) => problem = Some(message),
+                },
+                key @ (Key::Escape | Key::CtrlC) => {
+                    term.write_line("")?;
+                    return Err(if key == Key::Escape { escaped() } else { Error::Interrupted });
+                }
+                key @ (Key::ArrowUp | Key::ArrowDown) => {
+                    let current = T::parse(&text).or(self.default).or(self.min).unwrap_or(T::ZERO);
+                    let next = current.step(self.step.unwrap_or(T::STEP), key == Key::ArrowUp);
+                    text = self.clamp(next).format();
+                }
+                Key::Backspace => {
+                    text.pop();
+                }
+                Key::Char(c) if !c.is_control() => text.push(c),
+                _ => {}
+            }
+        }
+    }
+
+    fn accept(&self, text: &str) -> std::result::Result<T, String> {
+        let text = text.trim();
+        let value = match self.default {
+            Some(default) if text.is_empty() => default,
+            _ if text.is_empty() => return Err(locale::text("prompt.required", &[])),
+            _ => T::parse(text).ok_or_else(|| locale::text(T::EXPECTED, &[]))?,
+        };
+        match self.check(value) {
+            Some(message) => Err(message),
+            None => Ok(value),
+        }
+    }
+
+    /// Returns why `value` is out of bounds, if it is.
+    fn check(&self, value: T) -> Option<String> {
+        match (self.min, self.max) {
+            (Some(min), _) if value < min => Some(locale::text("number.at_least", &[("min", &min.format())])),
+            (_, Some(max)) if value > max => Some(locale::text("number.at_most", &[("max", &max.format())])),
+            _ => None,
+        }
+    }
+
+    fn clamp(&self, value: T) -> T {
+        match (self.min, self.max) {
+            (Some(min), _) if value < min => min,
+            (_, Some(max)) if value > max => max,
+            _ => value,
+        }
+    }
+
+    /// Returns the question with the default, if there is one.
+    fn question(&self) -> String {
+        let mut line = question(&self.prompt);
+        if let Some(default) = self.default {
+            line.push(' ');
+            line.push_str(&theme::current().paint(Tone::Muted, &format!("({})", default.format())));
+        }
+        line
+    }
+
+    /// Redraws the input line, with `problem` after the typed text and the
+    /// cursor left at the end of the text.
+    fn draw(&self, term: &Terminal, text: &str, problem: Option<&str>) -> Result<()> {
+        let theme = theme::current();
+        let mut line = self.question();
+        line.push(' ');
+        line.push_str(text);
+        term.clear_line()?;
+        term.write_str(&line)?;
+        if let Some(problem) = problem {
+            let message = format!("  ✗ {problem}");
+            term.write_str(&theme.paint(Tone::Error, &message))?;
+            term.write_str(&format!("\x1b[{}D", display_width(&message)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Asks for a number with no default or bounds.
+///
+/// This is a shorthand for `Number::new(prompt).interact()`.
+pub fn number<T: Numeric>(prompt: impl Into<String>) -> Result<T> {
+    Number::new(prompt).interact()
+}
@@ -0,0 +1,241 @@
+//! Hidden or masked secret input.
+
+use std::io::{self, BufRead};
+#[cfg(unix)]
+use std::os::fd::OwnedFd;
+#[cfg(unix)]
+use std::sync::{Arc, Mutex};
+
+use console::Key;
+
+use crate::locale::text;
+use super::answers;
+use super::{error_line, escaped, question, Error, Result, Terminal};
+
+/// Where a secret is read from when there is no terminal to ask on.
+#[derive(Debug, Clone)]
+enum Source {
+    Env(String),
+    /// A descriptor shared by the clones of a prompt, taken by the first
+    /// of them to read it, so it is read and closed once.
+    #[cfg(unix)]
+    Fd(Arc<Mutex<Option<OwnedFd>>>),
+    Stdin,
+}
+
+/// A prompt for a password or token whose input is not echoed.
+///
+/// By default nothing is shown while typing; with [`mask`] each character
+/// is shown as the mask character instead. Pasting works in both modes.
+///
+/// When the process is not attached to a terminal, the secret is taken from
+/// the configured fallback sources in the order they were added, and
+/// [`Error::NotInteractive`] is returned when none of them provides one.
+///
+/// [`mask`]: Password::mask
+///
+/// # Examples
+///
+/// ```no_run
+/// use mycli::prompt::Password;
+///
+/// let token = Password::new("API token")
+///     .mask('*')
+///     .from_env("MYAPP_TOKEN")
+///     .interact()?;
+/// # Ok::<(), mycli::prompt::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Password {
+    prompt: String,
+    key: Option<String>,
+    mask: Option<char>,
+    allow_empty: bool,
+    sources: Vec<Source>,
+    #[cfg(feature = "secrets")]
+    keyring: Option<(crate::secrets::Keyring, String)>,
+}
+
+impl Password {
+    /// Creates a prompt asking for `prompt`.
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            key: None,
+            mask: None,
+            allow_empty: false,
+            sources: Vec::new(),
+            #[cfg(feature = "secrets")]
+            keyring: None,
+        }
+    }
+
+    /// Sets the key of the pre-supplied [`Answers`] entry that answers this
+    /// prompt. Defaults to a key derived from the prompt.
+    ///
+    /// [`Answers`]: super::Answers
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Shows `mask` for each typed character instead of hiding the input.
+    pub fn mask(mut self, mask: char) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Accepts an empty answer. By default the user is asked again.
+    pub fn allow_empty(mut self, allow: bool) -> Self {
+        self.allow_empty = allow;
+        self
+    }
+
+    /// Reads the secret from the environment variable `name` when there is
+    /// no terminal.
+    pub fn from_env(mut self, name: impl Into<String>) -> Self {
+        self.sources.push(Source::Env(name.into()));
+        self
+    }
+
+    /// Reads the first line of the file descriptor `fd` when there is no
+    /// terminal, like `--password-fd` options of other tools.
+    ///
+    /// The prompt takes ownership of the descriptor and closes it after
+    /// reading. It is read only once, even if the prompt is cloned or asked
+    /// again; later attempts go on to the next source.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::os::fd::{FromRawFd, OwnedFd};
+    /// use mycli::prompt::Password;
+    ///
+    /// // SAFETY: descriptor 3 was opened for us by the caller, as with
+    /// // `myapp --password-fd 3 3<secret.txt`, and nothing else uses it.
+    /// let fd = unsafe { OwnedFd::from_raw_fd(3) };
+    /// let password = Password::new("Password").from_fd(fd).interact()?;
+    /// # Ok::<(), mycli::prompt::Error>(())
+    /// ```
+    #[cfg(unix)]
+    pub fn from_fd(mut self, fd: impl Into<OwnedFd>) -> Self {
+        self.sources.push(Source::Fd(Arc::new(Mutex::new(Some(fd.into())))));
+        self
+    }
+
+    /// Reads the first line of stdin when there is no terminal.
+    pub fn from_stdin(mut self) -> Self {
+        self.sources.push(Source::Stdin);
+        self
+    }
+
+    /// Remembers the secret in the system keyring under `account`.
+    ///
+    /// A secret already stored there is returned without asking, and a
+    /// newly entered one is stored for next time. Keyring failures are
+    /// ignored, so the secret is asked for as if no keyring were set.
+    #[cfg(feature = "secrets")]
+    pub fn keyring(mut self, keyring: &crate::secrets::Keyring, account: impl Into<String>) -> Self {
+        self.keyring = Some((keyring.clone(), account.into()));
+        self
+    }
+
+    /// Asks for the secret and returns it.
+    pub fn interact(&self) -> Result<String> {
+        let key = answers::key(&self.prompt, self.key.as_deref());
+        if let Some(answer) = answers::lookup(&key) {
+            if answer.is_empty() && !self.allow_empty {
+                return Err(answers::invalid(&key, text("prompt.required", &[])));
+            }
+            return Ok(answer);
+        }
+        #[cfg(feature = "secrets")]
+        if let Some((keyring, account)) = &self.keyring {
+            if let Ok(Some(secret)) = keyring.get(account) {
+                return Ok(secret);
+            }
+            let secret = self.ask(&key)?;
+            let _ = keyring.set(account, &secret);
+            return Ok(secret);
+        }
+        self.ask(&key)
+    }
+
+    fn ask(&self, key: &str) -> Result<String> {
+        let term = Terminal::stderr();
+        if !term.is_interactive() {
+            return self.read_fallback(key);
+        }
+
+        loop {
+            term.write_str(&format!("{} ", question(&self.prompt)))?;
+            let secret = match self.mask {
+                None => term.read_secure_line()?,
+                Some(mask) => read_masked(&term, mask)?,
+            };
+            if !secret.is_empty() || self.allow_empty {
+                return Ok(secret);
+            }
+            term.write_line(&error_line(&text("prompt.required", &[])))?;
+        }
+    }
+
+    fn read_fallback(&self, key: &str) -> Result<String> {
+        for source in &self.sources {
+            let secret = match source {
+                Source::Env(name) => std::env::var(name).ok(),
+                #[cfg(unix)]
+                Source::Fd(fd) => match fd.lock().unwrap_or_else(|e| e.into_inner()).take() {
+                    Some(fd) => read_first_line(io::BufReader::new(std::fs::File::from(fd)))?,
+                    None => None,
+                },
+                Source::Stdin => read_first_line(io::stdin().lock())?,
+            };
+            if let Some(secret) = secret.filter(|s| !s.is_empty() || self.allow_empty) {
+                return Ok(secret);
+            }
+        }
+        Err(answers::not_interactive(&self.prompt, key))
+    }
+}
+
+fn read_first_line(mut reader: impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim_end_matches(['\n', '\r']).to_string()))
+}
+
+/// Reads keys until Enter, echoing `mask` for each character.
+fn read_masked(term: &Terminal, mask: char) -> Result<String> {
+    let mut secret = String::new();
+    loop {
+        match term.read_key()? {
+            Key::Enter => {
+                term.write_line("")?;
+                return Ok(secret);
+            }
+            key @ (Key::CtrlC | Key::Escape) => {
+                term.write_line("")?;
+                return Err(if key == Key::Escape { escaped() } else { Error::Interrupted });
+            }
+            Key::Backspace if secret.pop().is_some() => {
+                term.write_str("\x08 \x08")?;
+            }
+            Key::Char(c) if !c.is_control() => {
+                secret.push(c);
+                term.write_str(&mask.to_string())?;
+            }
+            // Bracketed-paste markers and other escapes are ignored.
+            _ => {}
+        }
+    }
+}
+
+/// Asks for a secret without echoing it.
+///
+/// This is a shorthand for `Password::new(prompt).interact()`.
+pub fn password(prompt: impl Into<String>) -> Result<String> {
+    Password::new(prompt).interact()
+}
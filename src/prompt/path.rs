@@ -0,0 +1,415 @@
+//! Filesystem path picker.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use console::Key;
+
+use crate::output::accessible;
+use crate::output::theme::{self, Tone};
+use crate::output::width::truncate;
+
+use super::answers;
+use super::{answered, error_line, escaped, question, Error, HiddenCursor, Result, Terminal};
+
+/// Whether the chosen path must already exist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathMode {
+    /// Any path is accepted.
+    #[default]
+    Any,
+    /// The path must exist, for opening or reading.
+    MustExist,
+    /// The path must not exist yet, for creating.
+    MustNotExist,
+}
+
+/// The kind of entry a path may name when it exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathKind {
+    /// Files and directories.
+    #[default]
+    Any,
+    /// Files only; directories can still be browsed into.
+    File,
+    /// Directories only; files are not listed.
+    Dir,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    name: String,
+    is_dir: bool,
+}
+
+/// A path prompt that lists the directory being typed in.
+///
+/// The entries of the directory named by the typed text are listed below
+/// it, filtered by the part after the last `/`. Up and down move through
+/// the list; Tab completes the typed name as far as it is unambiguous; Right
+/// enters the highlighted directory and Left goes to the parent. Enter picks
+/// the highlighted entry, entering it instead when it is a directory and
+/// only files are wanted, or takes the typed text as is when nothing
+/// matches. A leading `~` stands for the home directory.
+///
+/// The chosen path is checked against the [`mode`] and [`kind`], and the
+/// reason is shown when it does not fit. Without a terminal the default is
+/// returned if it fits, and [`Error::NotInteractive`] otherwise.
+///
+/// In the [accessible mode](crate::output::accessible) nothing is listed:
+/// the path is read as a plain line, relative to the [`start`] directory,
+/// and a rejected path is explained before asking again.
+///
+/// [`start`]: PathSelect::start
+/// [`mode`]: PathSelect::mode
+/// [`kind`]: PathSelect::kind
+///
+/// # Examples
+///
+/// ```no_run
+/// use mycli::prompt::{PathKind, PathMode, PathSelect};
+///
+/// let config = PathSelect::new("Config file")
+///     .mode(PathMode::MustExist)
+///     .kind(PathKind::File)
+///     .interact()?;
+/// println!("loading {}", config.display());
+/// # Ok::<(), mycli::prompt::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct PathSelect {
+    prompt: String,
+    key: Option<String>,
+    start: Option<PathBuf>,
+    default: Option<PathBuf>,
+    mode: PathMode,
+    kind: PathKind,
+    show_hidden: bool,
+    page_size: usize,
+}
+
+impl PathSelect {
+    /// Creates a path prompt asking `prompt`.
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            key: None,
+            start: None,
+            default: None,
+            mode: PathMode::Any,
+            kind: PathKind::Any,
+            show_hidden: false,
+            page_size: 10,
+        }
+    }
+
+    /// Sets the key of the pre-supplied [`Answers`] entry that answers this
+    /// prompt. Defaults to a key derived from the prompt.
+    ///
+    /// [`Answers`]: super::Answers
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Sets the directory browsing starts in. Defaults to the current
+    /// directory.
+    pub fn start(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.start = Some(dir.into());
+        self
+    }
+
+    /// Sets the path returned when there is no terminal.
+    pub fn default(mut self, path: impl Into<PathBuf>) -> Self {
+        self.default = Some(path.into());
+        self
+    }
+
+    /// Sets whether the path must exist or must not exist.
+    pub fn mode(mut self, mode: PathMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Restricts which kind of entry the path may name.
+    pub fn kind(mut self, kind: PathKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Lists entries starting with `.`. They are otherwise listed only once
+    /// a `.` has been typed.
+    pub fn show_hidden(mut self, show: bool) -> Self {
+        self.show_hidden = show;
+        self
+    }
+
+    /// Sets how many entries are visible at once. Longer listings scroll.
+    pub fn page_size(mut self, size: usize) -> Self {
+        self.page_size = size.max(1);
+        self
+    }
+
+    /// Shows the picker and returns the chosen path.
+    pub fn interact(&self) -> Result<PathBuf> {
+        let key = answers::key(&self.prompt, self.key.as_deref());
+        if let Some(answer) = answers::lookup(&key) {
+            let path = expand(answer.trim());
+            return match self.check(&path) {
+                Some(message) => Err(answers::invalid(&key, message)),
+                None => Ok(path),
+            };
+        }
+        let term = Terminal::stderr();
+        if !term.is_interactive() {
+            return match &self.default {
+                Some(default) if self.check(default).is_none() => Ok(default.clone()),
+                _ => Err(answers::not_interactive(&self.prompt, &key)),
+            };
+        }
+
+        if accessible::is_enabled() {
+            return self.interact_plain(&term);
+        }
+
+        let _cursor = HiddenCursor::new(&term)?;
+        let mut text = match &self.start {
+            Some(dir) => with_slash(&dir.display().to_string()),
+            None => String::new(),
+        };
+        let mut cursor = 0;
+        // Whether the highlight was moved since the text last changed, which
+        // makes Enter pick the highlighted entry even in a listed directory.
+        let mut moved = false;
+        let mut drawn = 0;
+        let mut problem: Option<String> = None;
+
+        loop {
+            let (dir, filter) = split(&text);
+            let entries = self.list(&dir, filter);
+            cursor = cursor.min(entries.len().saturating_sub(1));
+
+            if drawn > 0 {
+                term.clear_last_lines(drawn)?;
+            }
+            let mut lines = self.render(&text, &entries, cursor, term.width());
+            if let Some(problem) = &problem {
+                lines.push(error_line(problem));
+            }
+            for line in &lines {
+                term.write_line(line)?;
+            }
+            drawn = lines.len();
+            problem = None;
+
+            let len = entries.len().max(1);
+            let pressed = term.read_key()?;
+            moved = matches!(pressed, Key::ArrowUp | Key::ArrowDown | Key::PageUp | Key::PageDown)
+                || (moved && pressed == Key::Enter);
+            match pressed {
+                Key::ArrowUp => cursor = (cursor + len - 1) % len,
+                Key::ArrowDown => cursor = (cursor + 1) % len,
+                Key::PageUp => cursor = cursor.saturating_sub(self.page_size),
+                Key::PageDown => cursor = (cursor + self.page_size).min(len - 1),
+                Key::ArrowRight => {
+                    if let Some(entry) = entries.get(cursor).filter(|e| e.is_dir) {
+                        text = format!("{}{}/", prefix(&text), entry.name);
+                        cursor = 0;
+                    }
+                }
+                Key::ArrowLeft => {
+                    text = parent(&text);
+                    cursor = 0;
+                }
+                Key::Tab => {
+                    text = complete(&text, &entries);
+                    cursor = 0;
+                }
+                Key::Enter => {
+                    // With nothing typed after the `/`, Enter takes the listed
+                    // directory itself unless an entry was highlighted.
+                    let listed = filter.is_empty() && !moved && self.kind != PathKind::File;
+                    let picked = if listed { None } else { entries.get(cursor) };
+                    match picked {
+                        Some(entry) if entry.is_dir && self.kind == PathKind::File => {
+                            text = format!("{}{}/", prefix(&text), entry.name);
+                            cursor = 0;
+                            moved = false;
+                            continue;
+                        }
+                        Some(entry) => text = format!("{}{}", prefix(&text), entry.name),
+                        None => {}
+                    }
+                    let path = expand(if text.is_empty() { "." } else { &text });
+                    match self.check(&path) {
+                        Some(message) => problem = Some(message),
+                        None => {
+                            term.clear_last_lines(drawn)?;
+                            term.write_line(&answered(&self.prompt, &path.display().to_string()))?;
+                            return Ok(path);
+                        }
+                    }
+                }
+                key @ (Key::Escape | Key::CtrlC) => {
+                    term.clear_last_lines(drawn)?;
+                    return Err(if key == Key::Escape { escaped() } else { Error::Interrupted });
+                }
+                Key::Backspace => {
+                    text.pop();
+                    cursor = 0;
+                }
+                Key::Char(c) if !c.is_control() => {
+                    text.push(c);
+                    cursor = 0;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Asks for the path as a line of text, for the accessible mode.
+    fn interact_plain(&self, term: &Terminal) -> Result<PathBuf> {
+        loop {
+            term.write_str(&format!("{} ", question(&self.prompt)))?;
+            let answer = term.read_line()?;
+            let answer = expand(if answer.trim().is_empty() { "." } else { answer.trim() });
+            let path = match &self.start {
+                Some(start) if answer.is_relative() => start.join(answer),
+                _ => answer,
+            };
+            match self.check(&path) {
+                Some(message) => term.write_line(&error_line(&message))?,
+                None => return Ok(path),
+            }
+        }
+    }
+
+    /// Returns why `path` is not acceptable, if it is not.
+    fn check(&self, path: &Path) -> Option<String> {
+        let meta = fs::metadata(path).ok();
+        match (self.mode, &meta) {
+            (PathMode::MustExist, None) => return Some(format!("{} does not exist", path.display())),
+            (PathMode::MustNotExist, Some(_)) => return Some(format!("{} already exists", path.display())),
+            _ => {}
+        }
+        match (self.kind, meta.map(|m| m.is_dir())) {
+            (PathKind::File, Some(true)) => Some(format!("{} is a directory", path.display())),
+            (PathKind::Dir, Some(false)) => Some(format!("{} is not a directory", path.display())),
+            _ => None,
+        }
+    }
+
+    /// Lists the entries of `dir` whose names start with `filter`,
+    /// directories first.
+    fn list(&self, dir: &Path, filter: &str) -> Vec<Entry> {
+        let Ok(read) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let hidden = self.show_hidden || filter.starts_with('.');
+        let mut entries: Vec<Entry> = read
+            .filter_map(|entry| entry.ok())
+            .map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                // Follow symlinks so links to directories can be entered.
+                let is_dir = fs::metadata(entry.path()).is_ok_and(|m| m.is_dir());
+                Entry { name, is_dir }
+            })
+            .filter(|e| hidden || !e.name.starts_with('.'))
+            .filter(|e| e.name.starts_with(filter))
+            .filter(|e| e.is_dir || self.kind != PathKind::Dir)
+            .collect();
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+        entries
+    }
+
+    fn render(&self, text: &str, entries: &[Entry], cursor: usize, width: usize) -> Vec<String> {
+        let theme = theme::current();
+        let mut lines = vec![format!("{} {} {text}", question(&self.prompt), theme.paint(Tone::Muted, "›"))];
+        if entries.is_empty() {
+            lines.push(theme.paint(Tone::Muted, "  no matching entries"));
+            return lines;
+        }
+
+        let page = self.page_size.min(entries.len());
+        let start = cursor.saturating_sub(page / 2).min(entries.len() - page);
+        for (i, entry) in entries.iter().enumerate().skip(start).take(page) {
+            let name = if entry.is_dir { format!("{}/", entry.name) } else { entry.name.clone() };
+            let name = truncate(&name, width.saturating_sub(3));
+            if i == cursor {
+                lines.push(theme.paint(Tone::Accent, &format!("❯ {name}")));
+            } else if entry.is_dir {
+                lines.push(format!("  {}", theme.paint(Tone::Info, &name)));
+            } else {
+                lines.push(format!("  {name}"));
+            }
+        }
+        lines
+    }
+}
+
+/// Replaces a leading `~` with the home directory.
+fn expand(text: &str) -> PathBuf {
+    match (text.strip_prefix('~'), crate::dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(text),
+    }
+}
+
+fn with_slash(text: &str) -> String {
+    if text.ends_with('/') { text.to_string() } else { format!("{text}/") }
+}
+
+/// Returns the typed text up to and including the last `/`.
+fn prefix(text: &str) -> &str {
+    text.rfind('/').map_or("", |i| &text[..=i])
+}
+
+/// Splits the typed text into the directory to list and the name filter.
+fn split(text: &str) -> (PathBuf, &str) {
+    let dir = prefix(text);
+    let filter = &text[dir.len()..];
+    let dir = if dir.is_empty() { PathBuf::from(".") } else { expand(dir) };
+    (dir, filter)
+}
+
+/// Returns the text for the parent of the directory being listed.
+fn parent(text: &str) -> String {
+    let dir = prefix(text);
+    if dir == "/" {
+        return dir.to_string();
+    }
+    let dir = dir.trim_end_matches('/');
+    let last = dir.rsplit('/').next().unwrap_or(dir);
+    if last.is_empty() || last == "." {
+        return "../".to_string();
+    }
+    if last == ".." {
+        return format!("{dir}/../");
+    }
+    dir.rfind('/').map_or_else(String::new, |i| dir[..=i].to_string())
+}
+
+/// Extends the typed name to the longest prefix shared by all matching
+/// entries, adding a `/` when a single directory matches.
+fn complete(text: &str, entries: &[Entry]) -> String {
+    let Some(first) = entries.first() else {
+        return text.to_string();
+    };
+    let mut common = first.name.as_str();
+    for entry in &entries[1..] {
+        let len = common
+            .char_indices()
+            .zip(entry.name.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8());
+        common = &common[..len];
+    }
+    let mut completed = format!("{}{common}", prefix(text));
+    if entries.len() == 1 && first.is_dir {
+        completed.push('/');
+    }
+    completed
+}
@@ -0,0 +1,57 @@
+//! Answering widgets from a test script instead of the terminal.
+
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+use console::Key;
+
+/// Stands in for the terminal while a test drives the widgets, as
+/// [`Expect`](crate::testing::Expect) does.
+pub(crate) trait ScriptedTerminal {
+    /// Shows `s` where the widget would draw it.
+    fn write(&self, s: &str);
+
+    /// Waits for the next key the test sends.
+    fn read_key(&self) -> io::Result<Key>;
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Rc<dyn ScriptedTerminal>>> = const { RefCell::new(None) };
+}
+
+/// Makes widgets on this thread talk to `terminal`, or to the real terminal
+/// again with `None`.
+pub(crate) fn install(terminal: Option<Rc<dyn ScriptedTerminal>>) {
+    CURRENT.with(|current| *current.borrow_mut() = terminal);
+}
+
+/// Returns the terminal installed on this thread, if any.
+pub(super) fn current() -> Option<Rc<dyn ScriptedTerminal>> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Reads keys up to Enter, echoing them as the terminal would. Ctrl+C is
+/// reported as an [`io::ErrorKind::Interrupted`] error.
+pub(super) fn read_line(terminal: &dyn ScriptedTerminal, echo: bool) -> io::Result<String> {
+    let mut line = String::new();
+    loop {
+        match terminal.read_key()? {
+            Key::Enter => break,
+            Key::Char(c) => line.push(c),
+            Key::Backspace => {
+                line.pop();
+            }
+            Key::CtrlC => {
+                terminal.write("^C\n");
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            _ => {}
+        }
+    }
+    if echo {
+        terminal.write(&line);
+    }
+    terminal.write("\n");
+    Ok(line)
+}
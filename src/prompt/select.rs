@@ -0,0 +1,226 @@
+//! Arrow-key driven single-choice menu.
+
+use console::Key;
+
+use crate::locale;
+use crate::output::accessible;
+use crate::output::theme::{self, Tone};
+use crate::output::width::truncate;
+
+use super::answers;
+use super::{answered, error_line, escaped, numbered_item, question, write_numbered, Error, HiddenCursor, Result, Terminal};
+
+/// A list of choices navigated with the arrow keys.
+///
+/// Typing filters the list to the items containing the typed text, ignoring
+/// case; Backspace edits the filter. Enter picks the highlighted item and
+/// Escape or Ctrl+C cancels with [`Error::Interrupted`]. Without a terminal
+/// the default item is returned, or [`Error::NotInteractive`] when there is
+/// none.
+///
+/// In the [accessible mode](crate::output::accessible) the items are listed
+/// with numbers, and the item is chosen by typing its number or name.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mycli::prompt::Select;
+///
+/// let envs = ["dev", "staging", "prod"];
+/// let index = Select::new("Environment", &envs).default(1).interact()?;
+/// println!("deploying to {}", envs[index]);
+/// # Ok::<(), mycli::prompt::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Select {
+    prompt: String,
+    key: Option<String>,
+    items: Vec<String>,
+    default: Option<usize>,
+    page_size: usize,
+    filterable: bool,
+}
+
+impl Select {
+    /// Creates a menu asking `prompt` with the given items.
+    pub fn new<T: ToString>(prompt: impl Into<String>, items: &[T]) -> Self {
+        Self {
+            prompt: prompt.into(),
+            key: None,
+            items: items.iter().map(ToString::to_string).collect(),
+            default: None,
+            page_size: 7,
+            filterable: true,
+        }
+    }
+
+    /// Sets the key of the pre-supplied [`Answers`] entry that answers this
+    /// prompt. Defaults to a key derived from the prompt.
+    ///
+    /// [`Answers`]: super::Answers
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Sets the index of the item highlighted initially and returned when
+    /// there is no terminal.
+    pub fn default(mut self, index: usize) -> Self {
+        self.default = Some(index);
+        self
+    }
+
+    /// Sets how many items are visible at once. Longer lists scroll.
+    pub fn page_size(mut self, size: usize) -> Self {
+        self.page_size = size.max(1);
+        self
+    }
+
+    /// Enables or disables filtering by typing. Enabled by default.
+    pub fn filterable(mut self, filterable: bool) -> Self {
+        self.filterable = filterable;
+        self
+    }
+
+    /// Shows the menu and returns the index of the chosen item.
+    pub fn interact(&self) -> Result<usize> {
+        if self.items.is_empty() {
+            return Err(Error::NoItems);
+        }
+        let key = answers::key(&self.prompt, self.key.as_deref());
+        if let Some(answer) = answers::lookup(&key) {
+            return answers::find_item(&self.items, &answer)
+                .ok_or_else(|| answers::invalid(&key, format!("no choice named `{answer}`")));
+        }
+        let default = self.default.filter(|&i| i < self.items.len());
+        let term = Terminal::stderr();
+        if !term.is_interactive() {
+            return default.ok_or_else(|| answers::not_interactive(&self.prompt, &key));
+        }
+        if accessible::is_enabled() {
+            return self.interact_numbered(&term, default);
+        }
+
+        let _cursor = HiddenCursor::new(&term)?;
+        let mut query = String::new();
+        let mut cursor = default.unwrap_or(0);
+        let mut drawn = 0;
+
+        loop {
+            let matches = self.matches(&query);
+            cursor = cursor.min(matches.len().saturating_sub(1));
+
+            if drawn > 0 {
+                term.clear_last_lines(drawn)?;
+            }
+            let lines = self.render(&query, &matches, cursor, term.width());
+            for line in &lines {
+                term.write_line(line)?;
+            }
+            drawn = lines.len();
+
+            let len = matches.len().max(1);
+            match term.read_key()? {
+                Key::ArrowUp => cursor = (cursor + len - 1) % len,
+                Key::ArrowDown | Key::Tab => cursor = (cursor + 1) % len,
+                Key::PageUp => cursor = cursor.saturating_sub(self.page_size),
+                Key::PageDown => cursor = (cursor + self.page_size).min(len - 1),
+                Key::Home => cursor = 0,
+                Key::End => cursor = len - 1,
+                Key::Enter if !matches.is_empty() => {
+                    let index = matches[cursor];
+                    term.clear_last_lines(drawn)?;
+                    term.write_line(&answered(&self.prompt, &self.items[index]))?;
+                    return Ok(index);
+                }
+                key @ (Key::Escape | Key::CtrlC) => {
+                    term.clear_last_lines(drawn)?;
+                    return Err(if key == Key::Escape { escaped() } else { Error::Interrupted });
+                }
+                Key::Backspace if self.filterable => {
+                    query.pop();
+                }
+                Key::Char(c) if self.filterable && !c.is_control() => {
+                    query.push(c);
+                    cursor = 0;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Asks for the item by number or name, for the accessible mode.
+    fn interact_numbered(&self, term: &Terminal, default: Option<usize>) -> Result<usize> {
+        term.write_line(&question(&self.prompt))?;
+        write_numbered(term, &self.items, &[])?;
+        let mut ask = locale::text("menu.choose", &[]);
+        if let Some(default) = default {
+            ask.push_str(&format!(" [{}]", default + 1));
+        }
+        loop {
+            term.write_str(&format!("{ask}: "))?;
+            let answer = term.read_line()?;
+            let index = match default {
+                Some(default) if answer.trim().is_empty() => Some(default),
+                _ => numbered_item(&self.items, &answer),
+            };
+            match index {
+                Some(index) => {
+                    term.write_line(&answered(&self.prompt, &self.items[index]))?;
+                    return Ok(index);
+                }
+                None => term.write_line(&error_line(&locale::text("menu.no_choice", &[("name", &answer.trim())])))?,
+            }
+        }
+    }
+
+    /// Returns the indices of the items matching `query`.
+    fn matches(&self, query: &str) -> Vec<usize> {
+        let query = query.to_lowercase();
+        self.items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.to_lowercase().contains(&query))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn render(&self, query: &str, matches: &[usize], cursor: usize, width: usize) -> Vec<String> {
+        let theme = theme::current();
+        let mut lines = Vec::new();
+
+        let mut header = question(&self.prompt);
+        if !query.is_empty() {
+            header.push(' ');
+            header.push_str(query);
+        } else if self.filterable {
+            header.push(' ');
+            header.push_str(&theme.paint(Tone::Muted, &locale::text("select.filter_hint", &[])));
+        }
+        lines.push(header);
+
+        if matches.is_empty() {
+            lines.push(theme.paint(Tone::Muted, &format!("  {}", locale::text("select.no_matches", &[]))));
+            return lines;
+        }
+
+        let page = self.page_size.min(matches.len());
+        let start = cursor.saturating_sub(page / 2).min(matches.len() - page);
+        for (pos, &index) in matches.iter().enumerate().skip(start).take(page) {
+            let item = truncate(&self.items[index], width.saturating_sub(3));
+            if pos == cursor {
+                lines.push(theme.paint(Tone::Accent, &format!("❯ {item}")));
+            } else {
+                lines.push(format!("  {item}"));
+            }
+        }
+        lines
+    }
+}
+
+/// Shows a menu with no default and returns the index of the chosen item.
+///
+/// This is a shorthand for `Select::new(prompt, items).interact()`.
+pub fn select<T: ToString>(prompt: impl Into<String>, items: &[T]) -> Result<usize> {
+    Select::new(prompt, items).interact()
+}
@@ -0,0 +1,189 @@
+//! Keeping secrets typed into commands out of everything that is saved.
+//!
+//! A process-wide [`Redactor`] masks secrets wherever the framework keeps
+//! a copy of a command or its output: history entries, including those
+//! saved to the history file, [audit log](crate::audit) entries, the
+//! arguments of `tracing` spans and everything written to a
+//! [`FileSink`](crate::output::FileSink), such as a transcript. What is
+//! shown on the terminal and what the command handler receives are left
+//! alone.
+//!
+//! The default redactor masks the values of arguments whose names mention
+//! a password, token, key and the like, given as `name=value`,
+//! `--name=value` or `--name value`, quotes and all when the value is
+//! quoted. With the `redact` feature, [`Redactor::pattern`] also masks
+//! everything matching a regular expression, for secrets pasted without a
+//! name.
+//!
+//! # Examples
+//!
+//! ```
+//! use mycli::redact::{self, Redactor};
+//!
+//! let redactor = Redactor::default();
+//! assert_eq!(redactor.redact("login --password hunter2 --user bob"), "login --password *** --user bob");
+//! assert_eq!(redactor.redact("call api_token=abc123"), "call api_token=***");
+//! assert_eq!(redactor.redact(r#"login --password "correct horse" -v"#), "login --password *** -v");
+//! assert_eq!(redactor.redact("set token='a b c'"), "set token=***");
+//!
+//! redact::set_redactor(Redactor::default().argument("pin"));
+//! assert_eq!(redact::current().redact("unlock pin=1234"), "unlock pin=***");
+//! ```
+
+use std::borrow::Cow;
+use std::ops::Range;
+use std::sync::{Arc, RwLock};
+
+// Words whose arguments are masked by default.
+const SENSITIVE: [&str; 7] = ["password", "passwd", "secret", "token", "key", "credential", "auth"];
+
+/// Rules for masking secrets in a line of text.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    words: Vec<String>,
+    #[cfg(feature = "redact")]
+    patterns: Vec<regex::Regex>,
+    mask: String,
+}
+
+impl Default for Redactor {
+    /// Masks arguments whose names contain `password`, `passwd`, `secret`,
+    /// `token`, `key`, `credential` or `auth`.
+    fn default() -> Self {
+        SENSITIVE.into_iter().fold(Self::none(), Self::argument)
+    }
+}
+
+impl Redactor {
+    /// Creates a redactor that masks nothing.
+    pub fn none() -> Self {
+        Self {
+            words: Vec::new(),
+            #[cfg(feature = "redact")]
+            patterns: Vec::new(),
+            mask: "***".to_string(),
+        }
+    }
+
+    /// Also masks the values of arguments whose names contain `word`,
+    /// ignoring case.
+    pub fn argument(mut self, word: &str) -> Self {
+        self.words.push(word.to_ascii_lowercase());
+        self
+    }
+
+    /// Also masks everything matching the regular expression `pattern`,
+    /// such as `ghp_[A-Za-z0-9]{36}` for GitHub tokens.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::redact::Redactor;
+    ///
+    /// let redactor = Redactor::default().pattern(r"sk-[A-Za-z0-9]{8,}").unwrap();
+    /// assert_eq!(redactor.redact("ask sk-abcdef123456 hello"), "ask *** hello");
+    /// assert!(Redactor::none().pattern("(").is_err());
+    /// ```
+    #[cfg(feature = "redact")]
+    pub fn pattern(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.patterns.push(regex::Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Replaces secrets with `mask` instead of `***`.
+    pub fn mask(mut self, mask: impl Into<String>) -> Self {
+        self.mask = mask.into();
+        self
+    }
+
+    /// Returns `text` with its secrets masked. Whitespace between words is
+    /// kept as it was.
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut masked = self.arguments(text);
+        #[cfg(feature = "redact")]
+        for pattern in &self.patterns {
+            masked.extend(pattern.find_iter(text).map(|found| found.range()));
+        }
+        if masked.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        masked.sort_by_key(|range| range.start);
+        let mut redacted = String::with_capacity(text.len());
+        let mut end = 0;
+        for range in masked {
+            if range.start >= end {
+                redacted.push_str(&text[end..range.start]);
+                redacted.push_str(&self.mask);
+            }
+            end = end.max(range.end);
+        }
+        redacted.push_str(&text[end..]);
+        Cow::Owned(redacted)
+    }
+
+    /// Returns the bytes of `text` holding values of sensitive arguments.
+    fn arguments(&self, text: &str) -> Vec<Range<usize>> {
+        let sensitive = |name: &str| {
+            let name = name.trim_start_matches('-').to_ascii_lowercase();
+            self.words.iter().any(|word| name.contains(word.as_str()))
+        };
+        let mut masked = Vec::new();
+        let mut mask_next = false;
+        for Range { start, end } in words(text) {
+            let word = &text[start..end];
+            if std::mem::take(&mut mask_next) {
+                masked.push(start..end);
+            } else if let Some((name, value)) = word.split_once('=')
+                && sensitive(name)
+            {
+                if !value.is_empty() {
+                    masked.push(end - value.len()..end);
+                }
+            } else {
+                mask_next = word.starts_with('-') && sensitive(word);
+            }
+        }
+        masked
+    }
+}
+
+/// Returns where the words of `text` are, a word ending at whitespace
+/// outside quotes, so `"correct horse"` and `token='a b'` are one word
+/// each, quotes included. A quote left open runs to the end of the text.
+fn words(text: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let rest = &text[pos..];
+        let start = pos + (rest.len() - rest.trim_start().len());
+        let mut quote = None;
+        let mut end = text.len();
+        for (i, c) in text[start..].char_indices() {
+            match quote {
+                Some(open) if c == open => quote = None,
+                Some(_) => {}
+                None if c == '"' || c == '\'' => quote = Some(c),
+                None if c.is_whitespace() => {
+                    end = start + i;
+                    break;
+                }
+                None => {}
+            }
+        }
+        pos = end;
+        (start < end).then_some(start..end)
+    })
+}
+
+static CURRENT: RwLock<Option<Arc<Redactor>>> = RwLock::new(None);
+
+/// Returns the redactor the framework currently uses.
+pub fn current() -> Arc<Redactor> {
+    let current = CURRENT.read().unwrap_or_else(|e| e.into_inner());
+    current.clone().unwrap_or_default()
+}
+
+/// Replaces the redactor the framework uses. Pass [`Redactor::none`] to
+/// keep everything as typed.
+pub fn set_redactor(redactor: Redactor) {
+    *CURRENT.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(redactor));
+}
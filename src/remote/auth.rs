@@ -0,0 +1,235 @@
+//! Checking who remote clients are before their session starts.
+
+use std::fmt;
+use std::io::{self, BufRead, Read};
+use std::sync::Arc;
+
+use crate::output::Output;
+
+// Credentials and command lines longer than this end the connection
+// instead of being read into memory.
+pub(super) const MAX_LINE: usize = 4096;
+
+/// What a client offered to prove who it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Credentials<'a> {
+    /// A token, sent by the client as its first line.
+    Token(&'a str),
+    /// A user name and password, sent by the client as its first two
+    /// lines, or given by an SSH client logging in.
+    Password {
+        /// The name the client gave.
+        user: &'a str,
+        /// The password the client gave.
+        password: &'a str,
+    },
+    /// The DER-encoded certificate a TLS client presented, already
+    /// verified by the client certificate verifier of the server's TLS
+    /// configuration.
+    Certificate(&'a [u8]),
+    /// The user ID of the process connected to a
+    /// [`ControlServer`](super::ControlServer), as reported by the
+    /// operating system.
+    PeerUid(u32),
+}
+
+/// Decides whether clients are who they claim to be.
+///
+/// Closures taking [`Credentials`] implement it, so any scheme can be
+/// plugged into an [`Auth`] with [`Auth::new`].
+pub trait Authenticator: Send + Sync {
+    /// Returns the identity `credentials` prove, such as a user name, or
+    /// `None` to turn the client away.
+    fn authenticate(&self, credentials: &Credentials<'_>) -> Option<String>;
+}
+
+impl<F> Authenticator for F
+where
+    F: Fn(&Credentials<'_>) -> Option<String> + Send + Sync,
+{
+    fn authenticate(&self, credentials: &Credentials<'_>) -> Option<String> {
+        self(credentials)
+    }
+}
+
+/// Which credentials a server asks its clients for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// A token, read from the first line.
+    Token,
+    /// A user name and password, read from the first two lines.
+    Password,
+    /// A TLS client certificate. Clients connecting without TLS, or
+    /// without a certificate, are turned away.
+    Certificate,
+    /// The user ID of the client process, which only a
+    /// [`ControlServer`](super::ControlServer) knows. Clients of other
+    /// servers are turned away.
+    PeerUid,
+}
+
+/// How a server authenticates its clients, given to
+/// [`RemoteServer::with_auth`](super::RemoteServer::with_auth) and the
+/// like.
+///
+/// Clients that are turned away get `Error: authentication failed`, after a
+/// second's delay to slow down guessing, and are disconnected. The identity
+/// of those let in is [`Connection::user`](super::Connection::user), which
+/// the factory can use to decide what they may run, and is the user their
+/// commands are recorded for in an audit log.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::remote::{Auth, Credentials, Method};
+///
+/// let auth = Auth::token(|token| (token == "s3cret").then(|| "deployer".to_string()));
+/// assert_eq!(auth.method(), Method::Token);
+/// assert_eq!(auth.authenticate(&Credentials::Token("s3cret")).as_deref(), Some("deployer"));
+/// assert_eq!(auth.authenticate(&Credentials::Token("guess")), None);
+///
+/// let auth = Auth::password(|user, password| user == "alice" && password == "hunter2");
+/// let credentials = Credentials::Password { user: "alice", password: "hunter2" };
+/// assert_eq!(auth.authenticate(&credentials).as_deref(), Some("alice"));
+/// ```
+#[derive(Clone)]
+pub struct Auth {
+    method: Method,
+    authenticator: Arc<dyn Authenticator>,
+}
+
+impl Auth {
+    /// Asks clients for the credentials of `method` and checks them with
+    /// `authenticator`.
+    pub fn new(method: Method, authenticator: impl Authenticator + 'static) -> Self {
+        Self { method, authenticator: Arc::new(authenticator) }
+    }
+
+    /// Asks clients for a token, and lets them in as the identity
+    /// `check(token)` returns.
+    pub fn token(check: impl Fn(&str) -> Option<String> + Send + Sync + 'static) -> Self {
+        Self::new(Method::Token, move |credentials: &Credentials<'_>| match credentials {
+            Credentials::Token(token) => check(token),
+            _ => None,
+        })
+    }
+
+    /// Asks clients for a user name and password, and lets them in under
+    /// that name when `check(user, password)` returns `true`.
+    pub fn password(check: impl Fn(&str, &str) -> bool + Send + Sync + 'static) -> Self {
+        Self::new(Method::Password, move |credentials: &Credentials<'_>| match credentials {
+            Credentials::Password { user, password } if check(user, password) => Some(user.to_string()),
+            _ => None,
+        })
+    }
+
+    /// Checks the certificate of TLS clients, and lets them in as the
+    /// identity `check(certificate)` returns, for instance a name looked up
+    /// from the certificate's fingerprint. Configure the server's TLS with
+    /// a client certificate verifier so that clients present one.
+    pub fn certificate(check: impl Fn(&[u8]) -> Option<String> + Send + Sync + 'static) -> Self {
+        Self::new(Method::Certificate, move |credentials: &Credentials<'_>| match credentials {
+            Credentials::Certificate(certificate) => check(certificate),
+            _ => None,
+        })
+    }
+
+    /// Lets clients of a [`ControlServer`](super::ControlServer) in as the
+    /// identity `check(uid)` returns for the user ID of their process.
+    #[cfg(unix)]
+    pub fn peer_uid(check: impl Fn(u32) -> Option<String> + Send + Sync + 'static) -> Self {
+        Self::new(Method::PeerUid, move |credentials: &Credentials<'_>| match credentials {
+            Credentials::PeerUid(uid) => check(*uid),
+            _ => None,
+        })
+    }
+
+    /// Asks clients for a user name and password, and checks them with the
+    /// system's PAM stack for `service`, a file in `/etc/pam.d`. Clients are
+    /// let in under their user name when both authentication and the
+    /// account checks succeed.
+    ///
+    /// PAM is loaded when the first client logs in; if it cannot be, every
+    /// client is turned away. Checking other users' passwords usually
+    /// requires running as root.
+    #[cfg(feature = "pam")]
+    pub fn pam(service: impl Into<String>) -> Self {
+        let service = service.into();
+        Self::new(Method::Password, move |credentials: &Credentials<'_>| match credentials {
+            Credentials::Password { user, password } if super::pam::authenticate(&service, user, password) => {
+                Some(user.to_string())
+            }
+            _ => None,
+        })
+    }
+
+    /// Returns which credentials clients are asked for.
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    /// Returns the identity `credentials` prove, or `None` if they do not.
+    pub fn authenticate(&self, credentials: &Credentials<'_>) -> Option<String> {
+        self.authenticator.authenticate(credentials)
+    }
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Auth").field("method", &self.method).finish_non_exhaustive()
+    }
+}
+
+/// What the server knows about a client before it sends anything.
+#[derive(Debug, Default)]
+pub(super) struct Origin {
+    pub(super) peer: String,
+    /// Who the client logged in as, when the transport authenticated it.
+    pub(super) user: Option<String>,
+    pub(super) certificate: Option<Vec<u8>>,
+    pub(super) uid: Option<u32>,
+}
+
+impl Origin {
+    pub(super) fn new(peer: impl Into<String>) -> Self {
+        Self { peer: peer.into(), ..Self::default() }
+    }
+}
+
+/// Asks the client for the credentials `auth` wants, reading them from
+/// `reader` and writing prompts to `output` if `prompt` is set, and
+/// returns the identity they prove. Fails if the client sends a line
+/// longer than `MAX_LINE` bytes.
+pub(super) fn authenticate(
+    auth: &Auth,
+    origin: &Origin,
+    reader: &mut impl BufRead,
+    output: &Output,
+    prompt: bool,
+) -> io::Result<Option<String>> {
+    let mut ask = |label: &str| -> io::Result<String> {
+        if prompt {
+            output.print(label)?;
+            output.flush()?;
+        }
+        let mut line = String::new();
+        reader.by_ref().take(MAX_LINE as u64 + 1).read_line(&mut line)?;
+        if line.len() > MAX_LINE {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "the credentials are too long"));
+        }
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    };
+    Ok(match auth.method() {
+        Method::Token => auth.authenticate(&Credentials::Token(&ask("Token: ")?)),
+        Method::Password => {
+            let user = ask("User: ")?;
+            let password = ask("Password: ")?;
+            auth.authenticate(&Credentials::Password { user: &user, password: &password })
+        }
+        Method::Certificate => {
+            origin.certificate.as_deref().and_then(|certificate| auth.authenticate(&Credentials::Certificate(certificate)))
+        }
+        Method::PeerUid => origin.uid.and_then(|uid| auth.authenticate(&Credentials::PeerUid(uid))),
+    })
+}
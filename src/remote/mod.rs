@@ -0,0 +1,604 @@
+//! Serving a REPL over the network, so a long-running program can be
+//! attached to and driven from another terminal.
+//!
+//! Every connection gets its own [`Repl`] and command handler, created by
+//! the factory given to the server, so each session has its own aliases,
+//! settings and state. The protocol is plain lines of text: the server
+//! writes the prompt, the client sends a command terminated by a newline,
+//! and the command's output comes back, followed by the next prompt. Tools
+//! such as `nc` or `telnet` work as clients. The session ends when the
+//! client disconnects or the handler returns `false`.
+//!
+//! Unless given an [`Auth`] with [`RemoteServer::with_auth`], a
+//! [`RemoteServer`] does not authenticate clients: anyone who can connect
+//! can run commands. Clients can be asked for a token or a user name and
+//! password, checked by the program or, with the `pam` feature, by the
+//! system's PAM stack, or identified by their TLS client certificate. The
+//! identity they prove is available from [`Connection::user`], so the
+//! factory can decide what each client may run, and is the user their
+//! commands are recorded for in an audit log. Limit what all remote
+//! clients may run with [`RemoteServer::allow`].
+//!
+//! With the `ssh` feature, an `SshServer` serves the same sessions to
+//! SSH clients, such as `ssh -p 2222 alice@host`, after checking their
+//! password or public key. The name they logged in with is available from
+//! [`Connection::user`] too.
+//!
+//! On Unix, a [`ControlServer`] offers the same sessions on a socket file
+//! for scripts: [`send`] connects, runs commands and returns their output,
+//! which makes `myapp ctl status` style commands easy to write. It can
+//! also identify clients by the user running them, with
+//! [`Auth::peer_uid`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use mycli::output::Output;
+//! use mycli::remote::RemoteServer;
+//! use mycli::repl::{CommandHandler, Context, Repl};
+//!
+//! struct Daemon {
+//!     out: Output,
+//! }
+//!
+//! impl CommandHandler for Daemon {
+//!     fn handle(&mut self, command: &str, _: &Context) -> bool {
+//!         match command {
+//!             "quit" => false,
+//!             "status" => {
+//!                 let _ = self.out.println("all workers running");
+//!                 true
+//!             }
+//!             _ => true,
+//!         }
+//!     }
+//! }
+//!
+//! let server = RemoteServer::bind("127.0.0.1:7000", |connection| {
+//!     Repl::new("daemon> ", Daemon { out: connection.output() })
+//! })?;
+//! std::thread::spawn(move || server.serve());
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::collections::HashSet;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::sync::Arc;
+use std::thread;
+
+use crate::locale::text;
+use crate::output::{Output, WriterSink};
+use crate::repl::{CommandHandler, Repl};
+
+mod auth;
+pub use auth::{Auth, Authenticator, Credentials, Method};
+use auth::Origin;
+
+#[cfg(feature = "pam")]
+mod pam;
+#[cfg(feature = "ssh")]
+mod ssh;
+#[cfg(feature = "ssh")]
+pub use ssh::SshServer;
+
+/// One client session of a [`RemoteServer`].
+#[derive(Debug, Clone)]
+pub struct Connection {
+    peer: String,
+    user: Option<String>,
+    output: Output,
+}
+
+impl Connection {
+    /// Returns who is connected, such as `127.0.0.1:53422`.
+    pub fn peer(&self) -> &str {
+        &self.peer
+    }
+
+    /// Returns the identity the client proved: the name it logged in with
+    /// on an `SshServer`, or the one returned by the [`Auth`] of other
+    /// servers. Without an [`Auth`], clients are not authenticated and this
+    /// is `None`.
+    pub fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    /// Returns the output that is sent to the client. Command handlers
+    /// should write to it instead of stdout.
+    pub fn output(&self) -> Output {
+        self.output.clone()
+    }
+}
+
+/// Serves REPL sessions to clients connecting over TCP.
+///
+/// See the [module documentation](self) for the protocol.
+pub struct RemoteServer<F> {
+    listener: TcpListener,
+    factory: Arc<F>,
+    rules: Arc<Rules>,
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<rustls::ServerConfig>>,
+}
+
+impl<F, H> RemoteServer<F>
+where
+    F: Fn(&Connection) -> crate::repl::Result<Repl<H>> + Send + Sync + 'static,
+    H: CommandHandler,
+{
+    /// Listens on `addr`. For each client, `factory` creates the REPL that
+    /// serves it; its output is sent to the client.
+    pub fn bind(addr: impl ToSocketAddrs, factory: F) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            factory: Arc::new(factory),
+            rules: Arc::new(Rules::new(true)),
+            #[cfg(feature = "tls")]
+            tls: None,
+        })
+    }
+
+    /// Returns the address the server listens on, for instance to find the
+    /// port chosen when binding to port 0.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Lets clients run only `commands`, matched on the first word of each
+    /// line as it was sent. Other lines are answered with an error.
+    pub fn allow<I>(mut self, commands: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Arc::make_mut(&mut self.rules).allowed = Some(commands.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Authenticates clients with `auth` before their session starts.
+    ///
+    /// # Arguments
+    ///
+    /// * `auth` - Which credentials to ask for and how to check them
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mycli::remote::{Auth, RemoteServer};
+    /// use mycli::repl::{CommandHandler, Repl};
+    /// # struct App(mycli::output::Output);
+    /// # impl CommandHandler for App {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    ///
+    /// let server = RemoteServer::bind("0.0.0.0:7000", |connection| {
+    ///     let repl = Repl::new("> ", App(connection.output()))?;
+    ///     Ok(match connection.user() {
+    ///         Some("admin") => repl,
+    ///         _ => repl.with_allowed_commands(["status"]),
+    ///     })
+    /// })?
+    /// .with_auth(Auth::password(|user, password| {
+    ///     matches!((user, password), ("admin", "hunter2") | ("viewer", "letmein"))
+    /// }));
+    /// std::thread::spawn(move || server.serve());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        Arc::make_mut(&mut self.rules).auth = Some(auth);
+        self
+    }
+
+    /// Counts connected clients in `metrics`, and records the commands
+    /// they run in it.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: crate::metrics::Metrics) -> Self {
+        Arc::make_mut(&mut self.rules).metrics = Some(metrics);
+        self
+    }
+
+    /// Encrypts connections with TLS. Clients must then connect with a TLS
+    /// client such as `openssl s_client`.
+    #[cfg(feature = "tls")]
+    pub fn with_tls(mut self, config: Arc<rustls::ServerConfig>) -> Self {
+        self.tls = Some(config);
+        self
+    }
+
+    /// Accepts clients until accepting fails, serving each one on its own
+    /// thread.
+    pub fn serve(self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) if is_transient(&err) => continue,
+                Err(err) => return Err(err),
+            };
+            let factory = self.factory.clone();
+            let rules = self.rules.clone();
+            #[cfg(feature = "tls")]
+            let tls = self.tls.clone();
+            thread::spawn(move || {
+                let peer = stream.peer_addr().map_or_else(|_| "unknown".to_string(), |addr| addr.to_string());
+                #[cfg(feature = "tls")]
+                if let Some(tls) = tls {
+                    return serve_tls(&*factory, &rules, stream, tls, peer);
+                }
+                let reader = BufReader::new(stream.try_clone()?);
+                serve_stream(&*factory, &rules, reader, stream, Origin::new(peer))
+            });
+        }
+        Ok(())
+    }
+}
+
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset | io::ErrorKind::Interrupted
+    )
+}
+
+/// What clients of a server may do.
+#[derive(Clone)]
+struct Rules {
+    /// Whether to write the prompt before each command.
+    prompt: bool,
+    allowed: Option<HashSet<String>>,
+    auth: Option<Auth>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<crate::metrics::Metrics>,
+}
+
+impl Rules {
+    fn new(prompt: bool) -> Self {
+        Self {
+            prompt,
+            allowed: None,
+            auth: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+
+    fn allows(&self, line: &str) -> bool {
+        let word = line.split_whitespace().next().unwrap_or(line);
+        self.allowed.as_ref().is_none_or(|allowed| allowed.contains(word))
+    }
+}
+
+/// How long to wait before turning away a client that failed to
+/// authenticate.
+const REJECTION_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// How long a TLS client may take to finish its handshake before it is
+/// disconnected.
+#[cfg(feature = "tls")]
+const HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Runs one session reading commands from `reader` and writing output to
+/// `writer`, once the client is authenticated. A command line longer than
+/// `auth::MAX_LINE` bytes ends the session.
+fn serve_stream<F, H>(
+    factory: &F,
+    rules: &Rules,
+    mut reader: impl BufRead,
+    writer: impl Write + Send + 'static,
+    origin: Origin,
+) -> io::Result<()>
+where
+    F: Fn(&Connection) -> crate::repl::Result<Repl<H>>,
+    H: CommandHandler,
+{
+    let output = Output::new(WriterSink::new(writer));
+    let user = match &rules.auth {
+        Some(auth) => match auth::authenticate(auth, &origin, &mut reader, &output, rules.prompt)? {
+            Some(user) => Some(user),
+            None => {
+                thread::sleep(REJECTION_DELAY);
+                return output.eprintln(&text("error", &[("message", &text("remote.auth_failed", &[]))]));
+            }
+        },
+        None => origin.user,
+    };
+    let connection = Connection { peer: origin.peer, user, output: output.clone() };
+    let mut repl = match factory(&connection) {
+        Ok(repl) => repl.with_output(output.clone()),
+        Err(err) => return output.eprintln(&text("error", &[("message", &err)])),
+    };
+    // Job output goes to the client, not to the server's terminal.
+    repl.print_through_output();
+    if let Some(user) = connection.user() {
+        repl.authenticated_as(user);
+    }
+    #[cfg(feature = "metrics")]
+    let _session = match &rules.metrics {
+        Some(metrics) => {
+            repl = repl.with_metrics(metrics.clone());
+            Some(metrics.session())
+        }
+        None => None,
+    };
+
+    let mut line = String::new();
+    let result = loop {
+        if rules.prompt {
+            output.print(&repl.prompt_text())?;
+            output.flush()?;
+        }
+        line.clear();
+        match reader.by_ref().take(auth::MAX_LINE as u64 + 1).read_line(&mut line) {
+            Ok(0) => break Ok(()),
+            Ok(_) if line.len() > auth::MAX_LINE => {
+                break output.eprintln(&text("error", &[("message", &text("remote.line_too_long", &[("limit", &auth::MAX_LINE)]))]));
+            }
+            Ok(_) => {}
+            Err(err) => break Err(err),
+        }
+        let cmd = line.trim();
+        if cmd.is_empty() {
+            continue;
+        }
+        if !rules.allows(cmd) {
+            let word = cmd.split_whitespace().next().unwrap_or(cmd);
+            output.eprintln(&text("error", &[("message", &text("remote.not_allowed", &[("command", &word)]))]))?;
+            continue;
+        }
+        if !repl.execute(cmd) {
+            break Ok(());
+        }
+    };
+    repl.cancel_jobs();
+    let _ = output.flush();
+    result
+}
+
+#[cfg(feature = "tls")]
+fn serve_tls<F, H>(
+    factory: &F,
+    rules: &Rules,
+    stream: std::net::TcpStream,
+    config: Arc<rustls::ServerConfig>,
+    peer: String,
+) -> io::Result<()>
+where
+    F: Fn(&Connection) -> crate::repl::Result<Repl<H>>,
+    H: CommandHandler,
+{
+    let mut stream = stream;
+    let mut session = rustls::ServerConnection::new(config).map_err(io::Error::other)?;
+    // A client that stops sending during the handshake is dropped instead
+    // of holding the connection open.
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    while session.is_handshaking() {
+        session.complete_io(&mut stream)?;
+    }
+    stream.set_read_timeout(None)?;
+    let origin = Origin {
+        certificate: session.peer_certificates().and_then(|chain| chain.first()).map(|cert| cert.to_vec()),
+        ..Origin::new(peer)
+    };
+    // Reads give up the lock now and then, so that background jobs can
+    // write to the client while the session waits for a command.
+    stream.set_read_timeout(Some(std::time::Duration::from_millis(100)))?;
+    let shared = tls::Shared::new(rustls::StreamOwned::new(session, stream));
+    serve_stream(factory, rules, BufReader::new(shared.clone()), shared, origin)
+}
+
+/// Serves REPL sessions on a Unix socket, for controlling a running
+/// program from scripts.
+///
+/// Sessions are the same as those of a [`RemoteServer`], except that no
+/// prompt is written: a client sends its commands, closes its side of the
+/// connection and reads the output until the server closes the other. The
+/// socket file is only accessible to the user running the server, and is
+/// removed when the server is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mycli::remote::{self, ControlServer};
+/// use mycli::repl::{CommandHandler, Repl};
+/// # struct App(mycli::output::Output);
+/// # impl CommandHandler for App {
+/// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+/// # }
+///
+/// let socket = std::env::temp_dir().join("myapp.sock");
+/// let args: Vec<String> = std::env::args().skip(1).collect();
+/// if args.first().is_some_and(|arg| arg == "ctl") {
+///     // `myapp ctl status` runs `status` in the running instance.
+///     print!("{}", remote::send(&socket, &args[1..].join(" "))?);
+///     return Ok(());
+/// }
+///
+/// let server = ControlServer::bind(&socket, |connection| Repl::new("", App(connection.output())))?
+///     .allow(["status", "reload"]);
+/// std::thread::spawn(move || server.serve());
+/// // ... run the program ...
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(unix)]
+pub struct ControlServer<F> {
+    listener: std::os::unix::net::UnixListener,
+    path: std::path::PathBuf,
+    factory: Arc<F>,
+    rules: Arc<Rules>,
+}
+
+#[cfg(unix)]
+impl<F, H> ControlServer<F>
+where
+    F: Fn(&Connection) -> crate::repl::Result<Repl<H>> + Send + Sync + 'static,
+    H: CommandHandler,
+{
+    /// Listens on the socket file `path`. A socket file left behind by a
+    /// server that is no longer running is replaced.
+    pub fn bind(path: impl Into<std::path::PathBuf>, factory: F) -> io::Result<Self> {
+        use std::os::unix::fs::PermissionsExt;
+        use std::os::unix::net::{UnixListener, UnixStream};
+
+        let path = path.into();
+        if path.exists() && UnixStream::connect(&path).is_err() {
+            std::fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path)?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        Ok(Self {
+            listener,
+            path,
+            factory: Arc::new(factory),
+            rules: Arc::new(Rules::new(false)),
+        })
+    }
+
+    /// Lets clients run only `commands`, as [`RemoteServer::allow`] does.
+    pub fn allow<I>(mut self, commands: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Arc::make_mut(&mut self.rules).allowed = Some(commands.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Authenticates clients with `auth`, as [`RemoteServer::with_auth`]
+    /// does. No prompts are written, so clients asked for a token or a
+    /// password send it as the first lines, before their commands;
+    /// [`Auth::peer_uid`] needs nothing from them.
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        Arc::make_mut(&mut self.rules).auth = Some(auth);
+        self
+    }
+
+    /// Counts connected clients in `metrics`, as
+    /// [`RemoteServer::with_metrics`] does.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: crate::metrics::Metrics) -> Self {
+        Arc::make_mut(&mut self.rules).metrics = Some(metrics);
+        self
+    }
+
+    /// Accepts clients until accepting fails, serving each one on its own
+    /// thread.
+    pub fn serve(&self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) if is_transient(&err) => continue,
+                Err(err) => return Err(err),
+            };
+            let factory = self.factory.clone();
+            let rules = self.rules.clone();
+            thread::spawn(move || {
+                let origin = Origin { uid: peer_uid(&stream), ..Origin::new("unix socket") };
+                let reader = BufReader::new(stream.try_clone()?);
+                serve_stream(&*factory, &rules, reader, stream, origin)
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Returns the user ID of the process at the other end of `stream`.
+#[cfg(unix)]
+fn peer_uid(stream: &std::os::unix::net::UnixStream) -> Option<u32> {
+    use std::os::fd::AsRawFd;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        // SAFETY: `ucred` is plain data and `getsockopt` writes at most
+        // `len` bytes into it.
+        let mut cred: libc::ucred = unsafe { std::mem::zeroed() };
+        let mut len = size_of::<libc::ucred>() as libc::socklen_t;
+        let ret = unsafe {
+            libc::getsockopt(
+                stream.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_PEERCRED,
+                (&raw mut cred).cast(),
+                &mut len,
+            )
+        };
+        (ret == 0).then_some(cred.uid)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    {
+        let (mut uid, mut gid) = (0, 0);
+        // SAFETY: `getpeereid` only writes the two IDs.
+        let ret = unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) };
+        (ret == 0).then_some(uid)
+    }
+}
+
+#[cfg(unix)]
+impl<F> Drop for ControlServer<F> {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Runs `commands`, one per line, on the [`ControlServer`] listening on
+/// `path`, and returns their output.
+#[cfg(unix)]
+pub fn send(path: impl AsRef<std::path::Path>, commands: &str) -> io::Result<String> {
+    use std::io::Read;
+
+    let mut stream = std::os::unix::net::UnixStream::connect(path)?;
+    stream.write_all(commands.as_bytes())?;
+    if !commands.ends_with('\n') {
+        stream.write_all(b"\n")?;
+    }
+    stream.shutdown(std::net::Shutdown::Write)?;
+    let mut output = String::new();
+    stream.read_to_string(&mut output)?;
+    Ok(output)
+}
+
+#[cfg(feature = "tls")]
+mod tls {
+    use std::io::{self, Read, Write};
+    use std::sync::{Arc, Mutex, MutexGuard};
+
+    /// A TLS stream shared between the session reading from it and the
+    /// output writing to it.
+    pub(super) struct Shared<S>(Arc<Mutex<S>>);
+
+    impl<S> Shared<S> {
+        pub(super) fn new(stream: S) -> Self {
+            Self(Arc::new(Mutex::new(stream)))
+        }
+
+        fn lock(&self) -> MutexGuard<'_, S> {
+            self.0.lock().unwrap_or_else(|e| e.into_inner())
+        }
+    }
+
+    impl<S> Clone for Shared<S> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+
+    impl<S: Read> Read for Shared<S> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            loop {
+                match self.lock().read(buf) {
+                    Err(err) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+                    result => return result,
+                }
+            }
+        }
+    }
+
+    impl<S: Write> Write for Shared<S> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.lock().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.lock().flush()
+        }
+    }
+}
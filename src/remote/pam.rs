@@ -0,0 +1,141 @@
+//! Checking passwords with PAM, loaded from the system's `libpam` when
+//! first needed.
+
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::sync::OnceLock;
+
+use libloading::Library;
+
+const PAM_SUCCESS: c_int = 0;
+const PAM_BUF_ERR: c_int = 5;
+const PAM_PROMPT_ECHO_OFF: c_int = 1;
+const PAM_PROMPT_ECHO_ON: c_int = 2;
+
+#[cfg(target_os = "macos")]
+const LIBRARIES: [&str; 2] = ["libpam.2.dylib", "libpam.dylib"];
+#[cfg(not(target_os = "macos"))]
+const LIBRARIES: [&str; 2] = ["libpam.so.0", "libpam.so"];
+
+#[repr(C)]
+struct Message {
+    style: c_int,
+    _text: *const c_char,
+}
+
+#[repr(C)]
+struct Response {
+    text: *mut c_char,
+    _code: c_int,
+}
+
+type Converse = extern "C" fn(c_int, *const *const Message, *mut *mut Response, *mut c_void) -> c_int;
+
+#[repr(C)]
+struct Conversation {
+    converse: Converse,
+    data: *mut c_void,
+}
+
+type Start = unsafe extern "C" fn(*const c_char, *const c_char, *const Conversation, *mut *mut c_void) -> c_int;
+type Step = unsafe extern "C" fn(*mut c_void, c_int) -> c_int;
+
+/// The functions of `libpam` used to check a password.
+struct Pam {
+    start: Start,
+    authenticate: Step,
+    account: Step,
+    end: Step,
+    _library: Library,
+}
+
+fn library() -> Option<&'static Pam> {
+    static PAM: OnceLock<Option<Pam>> = OnceLock::new();
+    PAM.get_or_init(|| {
+        // SAFETY: libpam is a system library whose initializers are sound,
+        // and the symbols are declared with their C signatures.
+        unsafe {
+            let library = LIBRARIES.iter().find_map(|name| Library::new(name).ok())?;
+            Some(Pam {
+                start: *library.get::<Start>(b"pam_start\0").ok()?,
+                authenticate: *library.get::<Step>(b"pam_authenticate\0").ok()?,
+                account: *library.get::<Step>(b"pam_acct_mgmt\0").ok()?,
+                end: *library.get::<Step>(b"pam_end\0").ok()?,
+                _library: library,
+            })
+        }
+    })
+    .as_ref()
+}
+
+/// The answers given to the PAM modules' prompts.
+struct Answers {
+    user: CString,
+    password: CString,
+}
+
+/// Returns `true` if PAM's `service` accepts `password` for `user` and
+/// lets the account in.
+pub(super) fn authenticate(service: &str, user: &str, password: &str) -> bool {
+    let (Some(pam), Ok(service), Ok(user), Ok(password)) =
+        (library(), CString::new(service), CString::new(user), CString::new(password))
+    else {
+        return false;
+    };
+    let mut answers = Answers { user, password };
+    let conversation = Conversation { converse, data: (&raw mut answers).cast() };
+    let mut handle = std::ptr::null_mut();
+    // SAFETY: the strings and the conversation outlive the PAM handle,
+    // which is ended before returning.
+    unsafe {
+        if (pam.start)(service.as_ptr(), answers.user.as_ptr(), &conversation, &mut handle) != PAM_SUCCESS {
+            return false;
+        }
+        let mut status = (pam.authenticate)(handle, 0);
+        if status == PAM_SUCCESS {
+            status = (pam.account)(handle, 0);
+        }
+        (pam.end)(handle, status);
+        status == PAM_SUCCESS
+    }
+}
+
+/// Answers the prompts of PAM modules: those that echo get the user name,
+/// those that do not get the password, and messages get no answer.
+extern "C" fn converse(
+    count: c_int,
+    messages: *const *const Message,
+    responses: *mut *mut Response,
+    data: *mut c_void,
+) -> c_int {
+    let Ok(count) = usize::try_from(count) else {
+        return PAM_BUF_ERR;
+    };
+    // SAFETY: PAM passes `count` messages and the `Answers` given to
+    // `pam_start`. The responses are allocated with `calloc` and the
+    // answers with `strdup`, because PAM frees them with `free`.
+    unsafe {
+        let answers = &*data.cast::<Answers>();
+        let replies = libc::calloc(count.max(1), size_of::<Response>()).cast::<Response>();
+        if replies.is_null() {
+            return PAM_BUF_ERR;
+        }
+        for i in 0..count {
+            // Linux-PAM passes an array of pointers, OpenPAM a pointer to
+            // an array.
+            #[cfg(target_os = "linux")]
+            let message = &**messages.add(i);
+            #[cfg(not(target_os = "linux"))]
+            let message = &*(*messages).add(i);
+            let answer: Option<&CStr> = match message.style {
+                PAM_PROMPT_ECHO_ON => Some(&answers.user),
+                PAM_PROMPT_ECHO_OFF => Some(&answers.password),
+                _ => None,
+            };
+            if let Some(answer) = answer {
+                (*replies.add(i)).text = libc::strdup(answer.as_ptr());
+            }
+        }
+        *responses = replies;
+    }
+    PAM_SUCCESS
+}
@@ -0,0 +1,495 @@
+//! Serving REPL sessions over SSH, with the `ssh` feature. Clients log in
+//! through SSH itself rather than the line-based authentication of plain
+//! connections; each session channel then runs the same command loop as a
+//! [`RemoteServer`](super::RemoteServer) session, on its own thread, with
+//! a small line editor in front of it when the client asks for a pty.
+
+use std::collections::HashMap;
+use std::io::{self, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::path::Path;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use russh::keys::{Algorithm, PrivateKey, PublicKey};
+use russh::server::{Auth, Config, Handle, Handler, Msg, Server, Session};
+use russh::{Channel, ChannelId, CryptoVec, MethodKind, MethodSet, Pty};
+
+use super::{serve_stream, Connection, Credentials, Method, Origin, Rules};
+use crate::repl::{CommandHandler, Repl};
+
+type PublicKeyCheck = dyn Fn(&str, &str) -> bool + Send + Sync;
+
+/// Serves REPL sessions to SSH clients.
+///
+/// Sessions are the same as those of a [`RemoteServer`](super::RemoteServer),
+/// except that clients log in first, with a password or a public key
+/// accepted by the checks given to [`with_password`](Self::with_password),
+/// [`with_auth`](Self::with_auth) and
+/// [`with_public_key`](Self::with_public_key); without any, nobody can log
+/// in. Interactive clients get a minimal line editor, and
+/// `ssh host command` runs a single command without writing a prompt.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mycli::remote::SshServer;
+/// use mycli::repl::{CommandHandler, Repl};
+/// # struct App(mycli::output::Output);
+/// # impl CommandHandler for App {
+/// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+/// # }
+///
+/// // `ssh -p 2222 operator@host` opens a session.
+/// let server = SshServer::bind("0.0.0.0:2222", |connection| {
+///     let prompt = format!("{}> ", connection.user().unwrap_or("tool"));
+///     Repl::new(prompt, App(connection.output()))
+/// })?
+/// .with_host_key_file("host_key")?
+/// .with_password(|user, password| user == "operator" && password == "secret");
+/// std::thread::spawn(move || server.serve());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct SshServer<F> {
+    listener: TcpListener,
+    factory: Arc<F>,
+    rules: Arc<Rules>,
+    key: PrivateKey,
+    password: Option<super::Auth>,
+    public_key: Option<Arc<PublicKeyCheck>>,
+}
+
+impl<F, H> SshServer<F>
+where
+    F: Fn(&Connection) -> crate::repl::Result<Repl<H>> + Send + Sync + 'static,
+    H: CommandHandler,
+{
+    /// Listens on `addr`. For each client, `factory` creates the REPL that
+    /// serves it; its output is sent to the client.
+    ///
+    /// The server identifies itself with a host key generated on the spot,
+    /// so clients are warned that it changed every time the program starts;
+    /// keep one with [`with_host_key_file`](Self::with_host_key_file).
+    pub fn bind(addr: impl ToSocketAddrs, factory: F) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            factory: Arc::new(factory),
+            rules: Arc::new(Rules::new(true)),
+            key: generate_key()?,
+            password: None,
+            public_key: None,
+        })
+    }
+
+    /// Returns the address the server listens on, for instance to find the
+    /// port chosen when binding to port 0.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Lets clients run only `commands`, as
+    /// [`RemoteServer::allow`](super::RemoteServer::allow) does.
+    pub fn allow<I>(mut self, commands: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Arc::make_mut(&mut self.rules).allowed = Some(commands.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Counts connected clients in `metrics`, as
+    /// [`RemoteServer::with_metrics`](super::RemoteServer::with_metrics)
+    /// does.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: crate::metrics::Metrics) -> Self {
+        Arc::make_mut(&mut self.rules).metrics = Some(metrics);
+        self
+    }
+
+    /// Identifies the server with the OpenSSH private key in the file at
+    /// `path`. If there is no such file, a new ed25519 key is generated and
+    /// saved there, only accessible to the user running the program.
+    pub fn with_host_key_file(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        self.key = match std::fs::read_to_string(path) {
+            Ok(text) => PrivateKey::from_openssh(text).map_err(invalid_key)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                let key = generate_key()?;
+                let text = key.to_openssh(russh::keys::ssh_key::LineEnding::LF).map_err(invalid_key)?;
+                let mut options = std::fs::OpenOptions::new();
+                options.write(true).create_new(true);
+                #[cfg(unix)]
+                std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+                options.open(path)?.write_all(text.as_bytes())?;
+                key
+            }
+            Err(err) => return Err(err),
+        };
+        Ok(self)
+    }
+
+    /// Lets clients log in with a password, when `check(user, password)`
+    /// returns `true`.
+    pub fn with_password(self, check: impl Fn(&str, &str) -> bool + Send + Sync + 'static) -> Self {
+        self.with_auth(super::Auth::password(check))
+    }
+
+    /// Lets clients log in with a password checked by `auth`, such as
+    /// [`Auth::pam`](super::Auth::pam), under the identity it returns.
+    /// Only an `auth` asking for a [password](Method::Password) applies to
+    /// SSH; with others, nobody can log in with a password.
+    pub fn with_auth(mut self, auth: super::Auth) -> Self {
+        self.password = (auth.method() == Method::Password).then_some(auth);
+        self
+    }
+
+    /// Lets clients log in with a public key, when `check(user, key)`
+    /// returns `true`. The key is given in the OpenSSH format of
+    /// `authorized_keys` files, without a comment, such as
+    /// `ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAA...`.
+    pub fn with_public_key(mut self, check: impl Fn(&str, &str) -> bool + Send + Sync + 'static) -> Self {
+        self.public_key = Some(Arc::new(check));
+        self
+    }
+
+    /// Accepts clients until accepting fails, serving each session on its
+    /// own thread.
+    pub fn serve(self) -> io::Result<()> {
+        let mut methods = MethodSet::empty();
+        if self.public_key.is_some() {
+            methods.push(MethodKind::PublicKey);
+        }
+        if self.password.is_some() {
+            methods.push(MethodKind::Password);
+        }
+        let config = Arc::new(Config {
+            methods,
+            keys: vec![self.key],
+            auth_rejection_time: Duration::from_secs(1),
+            auth_rejection_time_initial: Some(Duration::ZERO),
+            ..Config::default()
+        });
+        let mut acceptor = Acceptor {
+            factory: self.factory,
+            rules: self.rules,
+            password: self.password,
+            public_key: self.public_key,
+        };
+        let listener = self.listener;
+        listener.set_nonblocking(true)?;
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+        runtime.block_on(async {
+            let listener = tokio::net::TcpListener::from_std(listener)?;
+            acceptor.run_on_socket(config, &listener).await
+        })
+    }
+}
+
+fn generate_key() -> io::Result<PrivateKey> {
+    PrivateKey::random(&mut rand_core::OsRng, Algorithm::Ed25519).map_err(invalid_key)
+}
+
+fn invalid_key(err: russh::keys::ssh_key::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid host key: {err}"))
+}
+
+/// Creates a [`Client`] for each connection.
+struct Acceptor<F> {
+    factory: Arc<F>,
+    rules: Arc<Rules>,
+    password: Option<super::Auth>,
+    public_key: Option<Arc<PublicKeyCheck>>,
+}
+
+impl<F, H> Server for Acceptor<F>
+where
+    F: Fn(&Connection) -> crate::repl::Result<Repl<H>> + Send + Sync + 'static,
+    H: CommandHandler,
+{
+    type Handler = Client<F>;
+
+    fn new_client(&mut self, peer: Option<SocketAddr>) -> Client<F> {
+        Client {
+            factory: self.factory.clone(),
+            rules: self.rules.clone(),
+            password: self.password.clone(),
+            public_key: self.public_key.clone(),
+            peer: peer.map_or_else(|| "unknown".to_string(), |addr| addr.to_string()),
+            user: None,
+            channels: HashMap::new(),
+        }
+    }
+}
+
+/// One SSH connection, which may open several sessions.
+struct Client<F> {
+    factory: Arc<F>,
+    rules: Arc<Rules>,
+    password: Option<super::Auth>,
+    public_key: Option<Arc<PublicKeyCheck>>,
+    peer: String,
+    user: Option<String>,
+    channels: HashMap<ChannelId, Terminal>,
+}
+
+/// The input side of a session: what the client typed so far, and where
+/// complete lines go.
+#[derive(Default)]
+struct Terminal {
+    pty: bool,
+    line: Vec<u8>,
+    escape: bool,
+    input: Option<mpsc::Sender<Vec<u8>>>,
+}
+
+impl<F, H> Client<F>
+where
+    F: Fn(&Connection) -> crate::repl::Result<Repl<H>> + Send + Sync + 'static,
+    H: CommandHandler,
+{
+    /// Runs a session on its own thread, reading from `reader`.
+    fn start(&self, channel: ChannelId, rules: Rules, reader: impl Read + Send + 'static, session: &Session) {
+        let pty = self.channels.get(&channel).is_some_and(|terminal| terminal.pty);
+        let handle = session.handle();
+        let runtime = tokio::runtime::Handle::current();
+        let writer = ChannelWriter { handle: handle.clone(), runtime: runtime.clone(), channel, pty };
+        let factory = self.factory.clone();
+        let peer = self.peer.clone();
+        let user = self.user.clone();
+        thread::spawn(move || {
+            let origin = Origin { user, ..Origin::new(peer) };
+            let result = serve_stream(&*factory, &rules, BufReader::new(reader), writer, origin);
+            runtime.block_on(async {
+                let _ = handle.exit_status_request(channel, u32::from(result.is_err())).await;
+                let _ = handle.eof(channel).await;
+                let _ = handle.close(channel).await;
+            });
+        });
+    }
+}
+
+impl<F, H> Handler for Client<F>
+where
+    F: Fn(&Connection) -> crate::repl::Result<Repl<H>> + Send + Sync + 'static,
+    H: CommandHandler,
+{
+    type Error = russh::Error;
+
+    async fn auth_password(&mut self, user: &str, password: &str) -> Result<Auth, Self::Error> {
+        let identity = self.password.as_ref().and_then(|auth| auth.authenticate(&Credentials::Password { user, password }));
+        Ok(self.accept(identity))
+    }
+
+    async fn auth_publickey(&mut self, user: &str, key: &PublicKey) -> Result<Auth, Self::Error> {
+        let accepted = match (&self.public_key, key.to_openssh()) {
+            (Some(check), Ok(key)) => check(user, &key),
+            _ => false,
+        };
+        Ok(self.accept(accepted.then(|| user.to_string())))
+    }
+
+    async fn channel_open_session(&mut self, channel: Channel<Msg>, _: &mut Session) -> Result<bool, Self::Error> {
+        self.channels.insert(channel.id(), Terminal::default());
+        Ok(true)
+    }
+
+    async fn pty_request(
+        &mut self,
+        channel: ChannelId,
+        _: &str,
+        _: u32,
+        _: u32,
+        _: u32,
+        _: u32,
+        _: &[(Pty, u32)],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.channels.entry(channel).or_default().pty = true;
+        session.channel_success(channel)
+    }
+
+    async fn shell_request(&mut self, channel: ChannelId, session: &mut Session) -> Result<(), Self::Error> {
+        let (input, reader) = mpsc::channel();
+        self.channels.entry(channel).or_default().input = Some(input);
+        session.channel_success(channel)?;
+        let rules = Rules::clone(&self.rules);
+        self.start(channel, rules, ChannelReader { input: reader, data: Vec::new(), at: 0 }, session);
+        Ok(())
+    }
+
+    async fn exec_request(&mut self, channel: ChannelId, data: &[u8], session: &mut Session) -> Result<(), Self::Error> {
+        session.channel_success(channel)?;
+        let mut command = data.to_vec();
+        command.push(b'\n');
+        let rules = Rules { prompt: false, ..Rules::clone(&self.rules) };
+        self.start(channel, rules, io::Cursor::new(command), session);
+        Ok(())
+    }
+
+    async fn data(&mut self, channel: ChannelId, data: &[u8], session: &mut Session) -> Result<(), Self::Error> {
+        let Some(terminal) = self.channels.get_mut(&channel) else {
+            return Ok(());
+        };
+        if !terminal.pty {
+            if let Some(input) = &terminal.input {
+                let _ = input.send(data.to_vec());
+            }
+            return Ok(());
+        }
+        let echo = terminal.edit(data);
+        if !echo.is_empty() {
+            session.data(channel, CryptoVec::from(echo))?;
+        }
+        Ok(())
+    }
+
+    async fn channel_eof(&mut self, channel: ChannelId, _: &mut Session) -> Result<(), Self::Error> {
+        if let Some(terminal) = self.channels.get_mut(&channel) {
+            terminal.input = None;
+        }
+        Ok(())
+    }
+
+    async fn channel_close(&mut self, channel: ChannelId, _: &mut Session) -> Result<(), Self::Error> {
+        self.channels.remove(&channel);
+        Ok(())
+    }
+}
+
+impl<F> Client<F> {
+    fn accept(&mut self, identity: Option<String>) -> Auth {
+        match identity {
+            Some(user) => {
+                self.user = Some(user);
+                Auth::Accept
+            }
+            None => Auth::reject(),
+        }
+    }
+}
+
+impl Terminal {
+    /// Applies what the client typed to the line being edited, sending it
+    /// to the session on Enter, and returns what to echo back.
+    fn edit(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut echo = Vec::new();
+        for &byte in data {
+            if self.escape {
+                // Skip escape sequences such as arrow keys, up to their
+                // final byte.
+                self.escape = !(0x40..=0x7e).contains(&byte) || byte == b'[' || byte == b'O';
+                continue;
+            }
+            match byte {
+                b'\r' => {
+                    echo.extend_from_slice(b"\r\n");
+                    let mut line = std::mem::take(&mut self.line);
+                    line.push(b'\n');
+                    self.send(line);
+                }
+                0x7f | 0x08 => {
+                    // Remove a whole character, not just its last byte.
+                    while let Some(byte) = self.line.pop() {
+                        if byte & 0xc0 != 0x80 {
+                            echo.extend_from_slice(b"\x08 \x08");
+                            break;
+                        }
+                    }
+                }
+                // Ctrl-C abandons the line.
+                0x03 => {
+                    echo.extend_from_slice(b"^C\r\n");
+                    self.line.clear();
+                    self.send(b"\n".to_vec());
+                }
+                // Ctrl-D on an empty line ends the session.
+                0x04 if self.line.is_empty() => self.input = None,
+                // Ctrl-U erases the line.
+                0x15 => {
+                    for _ in String::from_utf8_lossy(&self.line).chars() {
+                        echo.extend_from_slice(b"\x08 \x08");
+                    }
+                    self.line.clear();
+                }
+                0x1b => self.escape = true,
+                byte if byte < 0x20 => {}
+                // A full line rings the bell, leaving room for the newline
+                // within the session's line limit.
+                _ if self.line.len() + 1 >= super::auth::MAX_LINE => echo.push(0x07),
+                byte => {
+                    self.line.push(byte);
+                    echo.push(byte);
+                }
+            }
+        }
+        echo
+    }
+
+    fn send(&self, line: Vec<u8>) {
+        if let Some(input) = &self.input {
+            let _ = input.send(line);
+        }
+    }
+}
+
+/// Reads what the client sends to a session, until it sends EOF.
+struct ChannelReader {
+    input: mpsc::Receiver<Vec<u8>>,
+    data: Vec<u8>,
+    at: usize,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.at == self.data.len() {
+            match self.input.recv() {
+                Ok(data) => {
+                    self.data = data;
+                    self.at = 0;
+                }
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.data.len() - self.at);
+        buf[..n].copy_from_slice(&self.data[self.at..self.at + n]);
+        self.at += n;
+        Ok(n)
+    }
+}
+
+/// Sends the output of a session to the client.
+struct ChannelWriter {
+    handle: Handle,
+    runtime: tokio::runtime::Handle,
+    channel: ChannelId,
+    pty: bool,
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let data = if self.pty {
+            // A terminal needs a carriage return to go back to the start of
+            // the line.
+            let mut data = Vec::with_capacity(buf.len());
+            for &byte in buf {
+                if byte == b'\n' {
+                    data.push(b'\r');
+                }
+                data.push(byte);
+            }
+            CryptoVec::from(data)
+        } else {
+            CryptoVec::from_slice(buf)
+        };
+        self.runtime
+            .block_on(self.handle.data(self.channel, data))
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
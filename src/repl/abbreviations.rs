@@ -0,0 +1,44 @@
+//! Abbreviations expanded in place as they are typed.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use rustyline::{Cmd, ConditionalEventHandler, Event, EventContext, InputMode, RepeatCount};
+
+use super::reprompt::{self, Edited};
+
+/// The abbreviations, shared with [`SpaceKey`] so they can change while
+/// the REPL runs.
+pub(super) type Abbreviations = Arc<Mutex<HashMap<String, String>>>;
+
+/// Expands the abbreviation starting the line when space is typed right
+/// after it, leaving the cursor after the expansion and a space.
+pub(super) struct SpaceKey {
+    pub(super) abbreviations: Abbreviations,
+    pub(super) edited: Edited,
+}
+
+impl ConditionalEventHandler for SpaceKey {
+    fn handle(&self, _: &Event, _: RepeatCount, _: bool, ctx: &EventContext) -> Option<Cmd> {
+        if ctx.input_mode() != InputMode::Insert {
+            return None;
+        }
+        let (before, after) = ctx.line().split_at(ctx.pos());
+        let word = before.trim_start();
+        if word.is_empty() || word.contains(char::is_whitespace) || after.starts_with(|c: char| !c.is_whitespace()) {
+            return None;
+        }
+        let abbreviations = self.abbreviations.lock().unwrap_or_else(|e| e.into_inner());
+        let expansion = abbreviations.get(word)?;
+        let start = before.len() - word.len();
+        let line = format!("{}{expansion} {after}", &before[..start]);
+        let pos = start + expansion.len() + 1;
+        Some(reprompt::edit(&self.edited, ctx, line, pos))
+    }
+}
+
+/// Returns the expansion of `line` if it is nothing but an abbreviation,
+/// for lines entered right after typing one.
+pub(super) fn expand(abbreviations: &Abbreviations, line: &str) -> Option<String> {
+    abbreviations.lock().unwrap_or_else(|e| e.into_inner()).get(line.trim()).cloned()
+}
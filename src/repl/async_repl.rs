@@ -0,0 +1,447 @@
+//! A REPL for applications running on a tokio runtime.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::future::Future;
+#[cfg(feature = "rustyline")]
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+#[cfg(feature = "rustyline")]
+use super::history::{self, LineEditor};
+use super::reader;
+use super::{
+    expand_alias, format_elapsed, trace, CancelToken, Context, DefaultErrorRenderer, ErrorRenderer, LineReader,
+    ReadlineError, ReplPrinter, Result, StreamReader, WorkingDir,
+};
+use crate::locale::text;
+use crate::output::{CodedError, Output};
+
+/// Trait for handling commands with `async` code in an [`AsyncRepl`].
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::{AsyncCommandHandler, Context};
+///
+/// struct Fetcher;
+///
+/// impl AsyncCommandHandler for Fetcher {
+///     async fn handle(&mut self, command: &str, ctx: &Context<'_>) -> bool {
+///         if command == "quit" {
+///             return false;
+///         }
+///         // Await network calls, timers or channels here.
+///         let _ = ctx.output().println(&format!("fetched {command}"));
+///         true
+///     }
+/// }
+/// ```
+pub trait AsyncCommandHandler: Send {
+    /// Handles a command entered by the user.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command string to process
+    /// * `ctx` - What the command can use while it runs, as for
+    ///   [`CommandHandler::handle`](super::CommandHandler::handle). Its
+    ///   token is cancelled when the command times out or is interrupted,
+    ///   for tasks the command spawned
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` to continue the REPL, `false` to exit
+    fn handle(&mut self, command: &str, ctx: &Context<'_>) -> impl Future<Output = bool> + Send;
+
+    /// Returns how long `command` may run before it is cancelled, in place
+    /// of the REPL's [default](AsyncRepl::with_timeout). Returns `None` by
+    /// default, meaning the REPL's default applies.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command string about to run
+    fn timeout(&self, command: &str) -> Option<Duration> {
+        let _ = command;
+        None
+    }
+}
+
+/// A handle that stops a running [`AsyncRepl`] from another task.
+///
+/// Clones share the same REPL.
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl ShutdownHandle {
+    /// Stops the REPL: a command in progress is cancelled by dropping its
+    /// future, and [`AsyncRepl::run`] returns as soon as it notices.
+    pub fn shutdown(&self) {
+        self.sender.send_replace(true);
+    }
+}
+
+/// Where an [`AsyncRepl`] reads lines from.
+enum Input {
+    /// The line editor.
+    #[cfg(feature = "rustyline")]
+    Editor(Box<LineEditor>),
+    /// Stdin, read without editing on a dumb terminal and without the
+    /// `rustyline` feature.
+    Stdin(StreamReader<std::io::Stdin>),
+}
+
+impl LineReader for Input {
+    fn readline(&mut self, prompt: &str) -> Result<String> {
+        match self {
+            #[cfg(feature = "rustyline")]
+            Input::Editor(editor) => LineReader::readline(editor.as_mut(), prompt),
+            Input::Stdin(stdin) => stdin.readline(prompt),
+        }
+    }
+}
+
+/// A Read-Eval-Print Loop whose command handler is `async`.
+///
+/// Line editing blocks, so each line is read on tokio's blocking thread
+/// pool while the runtime keeps running other tasks. Commands run on the
+/// task that called [`run`](AsyncRepl::run), so handlers can await
+/// anything without calling `block_on`.
+///
+/// Pressing Ctrl+C while a command runs cancels it and returns to the
+/// prompt. A [`ShutdownHandle`] ends the session from elsewhere, such as a
+/// task watching for a termination signal; when that happens while a line
+/// is being read, the read is abandoned and its thread finishes once the
+/// terminal delivers the line; the history file is not saved in that case,
+/// because the line editor is still reading.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mycli::repl::{AsyncCommandHandler, AsyncRepl, Context};
+///
+/// struct App;
+///
+/// impl AsyncCommandHandler for App {
+///     async fn handle(&mut self, command: &str, _: &Context<'_>) -> bool {
+///         tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+///         command != "quit"
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> mycli::repl::Result<()> {
+///     let mut repl = AsyncRepl::new("app> ", App)?;
+///     let shutdown = repl.shutdown_handle();
+///     tokio::spawn(async move {
+///         tokio::time::sleep(std::time::Duration::from_secs(3600)).await;
+///         shutdown.shutdown();
+///     });
+///     repl.run().await
+/// }
+/// ```
+pub struct AsyncRepl<H>
+where H: AsyncCommandHandler {
+    prompt: String,
+    handler: H,
+    // Taken while a line is read.
+    input: Option<Input>,
+    output: Output,
+    printer: Option<ReplPrinter>,
+    errors: Box<dyn ErrorRenderer>,
+    aliases: HashMap<String, String>,
+    #[cfg(feature = "rustyline")]
+    history_file: Option<PathBuf>,
+    timeout: Option<Duration>,
+    shutdown: Arc<watch::Sender<bool>>,
+    working_dir: WorkingDir,
+    #[cfg(feature = "config")]
+    settings: crate::config::Config,
+    #[cfg(feature = "metrics")]
+    metrics: Option<crate::metrics::Metrics>,
+    #[cfg(feature = "audit")]
+    audit: Option<crate::audit::AuditLog>,
+}
+
+impl<H: AsyncCommandHandler> AsyncRepl<H> {
+    /// Creates a new REPL with the specified prompt and command handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - The prompt string to display
+    /// * `handler` - The command handler
+    ///
+    /// # Returns
+    ///
+    /// Returns the REPL, or an error if the line editor cannot be set up.
+    pub fn new(prompt: impl Into<String>, handler: H) -> Result<Self> {
+        Ok(Self {
+            prompt: prompt.into(),
+            handler,
+            #[cfg(feature = "rustyline")]
+            input: Some(Input::Editor(Box::new(history::editor(rustyline::Config::default())?))),
+            #[cfg(not(feature = "rustyline"))]
+            input: None,
+            output: Output::stdout(),
+            printer: None,
+            errors: Box::new(DefaultErrorRenderer),
+            aliases: HashMap::new(),
+            #[cfg(feature = "rustyline")]
+            history_file: None,
+            timeout: None,
+            shutdown: Arc::new(watch::Sender::new(false)),
+            working_dir: WorkingDir::process(),
+            #[cfg(feature = "config")]
+            settings: crate::config::Config::default(),
+            #[cfg(feature = "metrics")]
+            metrics: None,
+            #[cfg(feature = "audit")]
+            audit: None,
+        })
+    }
+
+    /// Sets where framework output, such as error messages, is written.
+    pub fn with_output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Formats the REPL's own errors with `renderer`, as
+    /// [`Repl::with_error_renderer`](super::Repl::with_error_renderer)
+    /// does.
+    pub fn with_error_renderer(mut self, renderer: impl ErrorRenderer + 'static) -> Self {
+        self.errors = Box::new(renderer);
+        self
+    }
+
+    /// Cancels commands that run longer than `timeout` by dropping their
+    /// futures, reporting an error such as
+    /// ``Error: `report` timed out after 30.0s``.
+    /// [`AsyncCommandHandler::timeout`] can set another limit for each
+    /// command.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Records every command in `metrics`, as
+    /// [`Repl::with_metrics`](super::Repl::with_metrics) does.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: crate::metrics::Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Records every command in an audit log, as
+    /// [`Repl::with_audit_log`](super::Repl::with_audit_log) does.
+    #[cfg(feature = "audit")]
+    pub fn with_audit_log(mut self, log: crate::audit::AuditLog) -> Self {
+        self.audit = Some(log);
+        self
+    }
+
+    /// Keeps command history in a file: history is loaded from it now and
+    /// saved to it when [`run`](AsyncRepl::run) returns.
+    #[cfg(feature = "rustyline")]
+    pub fn with_history_file(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        if let Some(Input::Editor(editor)) = &mut self.input {
+            let _ = editor.load_history(&path);
+        }
+        self.history_file = Some(path);
+        self
+    }
+
+    /// Applies the prompt, command timeout, colors, aliases and history
+    /// file of a
+    /// configuration, as [`Repl::with_config`](super::Repl::with_config)
+    /// does.
+    #[cfg(feature = "config")]
+    pub fn with_config(mut self, config: &crate::config::Config) -> Self {
+        use crate::output::theme;
+
+        self.settings = config.clone();
+        if let Some(prompt) = &config.prompt {
+            self.prompt = prompt.clone();
+        }
+        if let Some(secs) = config.command_timeout {
+            self.timeout = Some(Duration::from_secs(secs)).filter(|limit| !limit.is_zero());
+        }
+        if (config.theme.is_some() || !config.colors.is_empty())
+            && let Ok(theme) = config.theme((*theme::current()).clone())
+        {
+            theme::set_theme(theme);
+        }
+        self.aliases.extend(config.aliases.iter().map(|(k, v)| (k.clone(), v.clone())));
+        #[cfg(feature = "rustyline")]
+        if let Some(file) = config.history_file() {
+            self = self.with_history_file(file);
+        }
+        self
+    }
+
+    /// Returns a handle for printing from other tasks while the prompt is
+    /// shown, as [`Repl::printer`](super::Repl::printer) does.
+    ///
+    /// Call it before [`run`](AsyncRepl::run): while a line is being read,
+    /// the handle printing to the terminal cannot be created.
+    pub fn printer(&mut self) -> ReplPrinter {
+        #[cfg(feature = "rustyline")]
+        if self.printer.is_none()
+            && let Some(Input::Editor(editor)) = &mut self.input
+        {
+            self.printer = Some(ReplPrinter::new(editor, self.output.clone()));
+        }
+        match &self.printer {
+            Some(printer) => printer.clone(),
+            None => ReplPrinter::fallback(self.output.clone()),
+        }
+    }
+
+    /// Returns a handle that stops the REPL from another task.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle { sender: self.shutdown.clone() }
+    }
+
+    /// Runs the REPL until the handler returns `false`, the user presses
+    /// Ctrl+D, or the REPL is shut down.
+    ///
+    /// On a dumb terminal and without the `rustyline` feature, lines are
+    /// read from stdin without editing or history, as
+    /// [`Repl::run`](super::Repl::run) does.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` when the REPL exits normally, or an error if a
+    /// critical readline error occurs.
+    pub async fn run(&mut self) -> Result<()> {
+        let mut shutdown = self.shutdown.subscribe();
+        if !cfg!(feature = "rustyline") || reader::is_dumb_terminal() {
+            self.input = Some(Input::Stdin(StreamReader::new(std::io::stdin(), self.output.clone())));
+        }
+        loop {
+            if *shutdown.borrow_and_update() {
+                break;
+            }
+            let Some(mut input) = self.input.take() else {
+                break;
+            };
+            let prompt = self.prompt.clone();
+            let read = tokio::task::spawn_blocking(move || {
+                let line = input.readline(&prompt);
+                (input, line)
+            });
+
+            let readline = tokio::select! {
+                read = read => match read {
+                    Ok((input, line)) => {
+                        self.input = Some(input);
+                        line
+                    }
+                    Err(err) => Err(ReadlineError::Io(std::io::Error::other(err))),
+                },
+                _ = shutdown.wait_for(|stop| *stop) => break,
+            };
+
+            match readline {
+                Ok(line) => {
+                    let cmd = line.trim();
+                    if cmd.is_empty() {
+                        continue;
+                    }
+                    #[cfg(feature = "rustyline")]
+                    if let Some(Input::Editor(editor)) = &mut self.input {
+                        let _ = editor.add_history_entry(crate::redact::current().redact(line.trim_end()));
+                    }
+
+                    let cmd = expand_alias(&self.aliases, cmd).into_owned();
+                    let span = trace::CommandSpan::new(&cmd);
+                    #[cfg(feature = "metrics")]
+                    let started = std::time::Instant::now();
+                    #[cfg(any(feature = "metrics", feature = "audit"))]
+                    let errors = self.output.errors_written();
+                    let limit = self.handler.timeout(&cmd).or(self.timeout);
+                    let cancel = CancelToken::new();
+                    let ctx = Context {
+                        output: Cow::Borrowed(&self.output),
+                        working_dir: Cow::Borrowed(&self.working_dir),
+                        cancel: Cow::Borrowed(&cancel),
+                        dry_run: false,
+                        allowed: None,
+                        user: None,
+                        #[cfg(feature = "config")]
+                        config: Cow::Borrowed(&self.settings),
+                    };
+                    let handler = &mut self.handler;
+                    let handled = async {
+                        match limit {
+                            Some(limit) => tokio::time::timeout(limit, handler.handle(&cmd, &ctx)).await.ok(),
+                            None => Some(handler.handle(&cmd, &ctx).await),
+                        }
+                    };
+                    let (keep_going, outcome) = tokio::select! {
+                        handled = span.instrument(handled) => match (handled, limit) {
+                            (Some(keep_going), _) => (keep_going, if keep_going { "ok" } else { "exit" }),
+                            (None, limit) => {
+                                let name = cmd.split_whitespace().next().unwrap_or(&cmd);
+                                let timeout = format_elapsed(limit.unwrap_or_default());
+                                let message = text("command.timed_out", &[("command", &name), ("timeout", &timeout)]);
+                                let error = CodedError::new("command.timed_out", message).detail("command", name);
+                                let _ = self.output.report_as(&error, &self.errors.render_error(&error));
+                                (true, "timeout")
+                            }
+                        },
+                        _ = tokio::signal::ctrl_c() => {
+                            let _ = self.output.eprintln(&text("command.interrupted", &[]));
+                            (true, "interrupted")
+                        }
+                        _ = shutdown.wait_for(|stop| *stop) => (false, "shutdown"),
+                    };
+                    if outcome != "ok" && outcome != "exit" {
+                        cancel.cancel();
+                    }
+                    #[cfg(any(feature = "metrics", feature = "audit"))]
+                    let failed = self.output.errors_written() > errors;
+                    #[cfg(feature = "metrics")]
+                    if let Some(metrics) = &self.metrics {
+                        metrics.observe(&cmd, started.elapsed(), failed);
+                    }
+                    #[cfg(feature = "audit")]
+                    if let Some(log) = &self.audit
+                        && let Err(err) = log.record(&cmd, i32::from(failed))
+                    {
+                        let error = CodedError::new("audit.write_failed", text("audit.write_failed", &[("error", &err)]));
+                        let _ = self.output.report_as(&error, &self.errors.render_error(&error));
+                    }
+                    span.finish(outcome);
+                    let _ = self.output.flush();
+                    if !keep_going {
+                        break;
+                    }
+                }
+                Err(ReadlineError::Interrupted) => {
+                    continue;
+                }
+                Err(ReadlineError::Eof) => {
+                    break;
+                }
+                Err(err) => {
+                    let _ = self.output.eprintln(&self.errors.render_readline(&err));
+                    if self.errors.is_fatal(&err) {
+                        break;
+                    }
+                }
+            }
+        }
+
+        #[cfg(feature = "rustyline")]
+        if let (Some(Input::Editor(editor)), Some(file)) = (&mut self.input, &self.history_file) {
+            let _ = editor.save_history(file);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+//! Measuring how long commands take to go through a REPL.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use super::{CommandHandler, Repl};
+
+/// Runs commands through a [`Repl`] without a terminal and measures how
+/// long each one takes.
+///
+/// Each command goes through the same steps as a typed line: alias
+/// expansion, built-ins and the command handler, with output written to
+/// the REPL's [`Output`](crate::output::Output). Give the REPL a
+/// [`BufferSink`](crate::output::BufferSink) to keep the terminal out of the
+/// measurement.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::{BufferSink, Output};
+/// use mycli::repl::{CommandHandler, Context, Repl, ReplBench};
+///
+/// struct Echo(Output);
+///
+/// impl CommandHandler for Echo {
+///     fn handle(&mut self, command: &str, _: &Context) -> bool {
+///         let _ = self.0.println(command);
+///         true
+///     }
+/// }
+///
+/// let out = Output::new(BufferSink::new());
+/// let repl = Repl::new("> ", Echo(out.clone())).unwrap().with_output(out);
+/// let report = ReplBench::new(repl).iterations(50).run(&["status", "list users"]);
+///
+/// let status = report.command("status").unwrap();
+/// assert_eq!(status.runs, 50);
+/// assert!(status.min <= status.median && status.median <= status.max);
+/// println!("{report}");
+/// ```
+pub struct ReplBench<H: CommandHandler> {
+    repl: Repl<H>,
+    warmup: usize,
+    iterations: usize,
+}
+
+impl<H: CommandHandler> ReplBench<H> {
+    /// Creates a harness running commands through `repl`.
+    pub fn new(repl: Repl<H>) -> Self {
+        Self { repl, warmup: 10, iterations: 100 }
+    }
+
+    /// Sets how many times each command runs before measuring starts.
+    /// Defaults to 10.
+    pub fn warmup(mut self, runs: usize) -> Self {
+        self.warmup = runs;
+        self
+    }
+
+    /// Sets how many measured runs each command gets. Defaults to 100.
+    pub fn iterations(mut self, runs: usize) -> Self {
+        self.iterations = runs.max(1);
+        self
+    }
+
+    /// Runs each of `commands` the configured number of times, in turn,
+    /// and returns how long they took.
+    pub fn run(&mut self, commands: &[&str]) -> BenchReport {
+        for _ in 0..self.warmup {
+            for command in commands {
+                self.repl.execute(command);
+            }
+        }
+        let mut samples = vec![Vec::with_capacity(self.iterations); commands.len()];
+        for _ in 0..self.iterations {
+            for (command, samples) in commands.iter().zip(&mut samples) {
+                let started = Instant::now();
+                self.repl.execute(command);
+                samples.push(started.elapsed());
+            }
+        }
+        let commands = commands.iter().zip(samples).map(|(command, samples)| CommandStats::new(command, samples));
+        BenchReport { commands: commands.collect() }
+    }
+
+    /// Returns the REPL, for instance to inspect its handler afterwards.
+    pub fn into_inner(self) -> Repl<H> {
+        self.repl
+    }
+}
+
+/// How long one command took over the runs of a [`ReplBench`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandStats {
+    /// The command as given to [`ReplBench::run`].
+    pub command: String,
+    /// The number of measured runs.
+    pub runs: usize,
+    /// The fastest run.
+    pub min: Duration,
+    /// The middle run.
+    pub median: Duration,
+    /// The run that 99% of runs were at least as fast as.
+    pub p99: Duration,
+    /// The slowest run.
+    pub max: Duration,
+    /// The average run.
+    pub mean: Duration,
+}
+
+impl CommandStats {
+    fn new(command: &str, mut samples: Vec<Duration>) -> Self {
+        samples.sort_unstable();
+        let runs = samples.len();
+        let at = |fraction: f64| samples[((runs - 1) as f64 * fraction).round() as usize];
+        Self {
+            command: command.to_string(),
+            runs,
+            min: samples[0],
+            median: at(0.5),
+            p99: at(0.99),
+            max: samples[runs - 1],
+            mean: samples.iter().sum::<Duration>() / runs as u32,
+        }
+    }
+}
+
+/// The results of [`ReplBench::run`], one entry per command.
+///
+/// Displays as a table with a row per command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchReport {
+    /// The statistics of each command, in the order they were given.
+    pub commands: Vec<CommandStats>,
+}
+
+impl BenchReport {
+    /// Returns the statistics of `command`.
+    pub fn command(&self, command: &str) -> Option<&CommandStats> {
+        self.commands.iter().find(|stats| stats.command == command)
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.commands.iter().map(|stats| stats.command.chars().count()).max().unwrap_or(0).max(7);
+        writeln!(f, "{:<width$}  {:>10}  {:>10}  {:>10}  {:>10}", "command", "min", "median", "p99", "max")?;
+        for stats in &self.commands {
+            writeln!(
+                f,
+                "{:<width$}  {:>10}  {:>10}  {:>10}  {:>10}",
+                stats.command,
+                format!("{:.1?}", stats.min),
+                format!("{:.1?}", stats.median),
+                format!("{:.1?}", stats.p99),
+                format!("{:.1?}", stats.max),
+            )?;
+        }
+        Ok(())
+    }
+}
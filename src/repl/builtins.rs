@@ -0,0 +1,613 @@
+//! Commands the REPL handles itself.
+
+use super::{expand_alias, CommandHandler, Repl};
+use crate::locale::text;
+use crate::output::CodedError;
+
+/// A group of built-in commands that [`Repl::with_builtin`] can enable.
+///
+/// Built-ins are matched on the first word of a line, after alias
+/// expansion, and lines they handle never reach the command handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Builtin {
+    /// A command ending in `&` runs in the background, using the [`Job`]
+    /// returned by [`CommandHandler::background`]. `jobs` lists the jobs
+    /// still running, `fg [id]` waits for one and prints its output, and
+    /// `kill <id>` asks one to stop. Jobs announce themselves above the
+    /// prompt when they finish.
+    ///
+    /// [`Job`]: super::Job
+    Jobs,
+    /// `par cmd1 | cmd2 | cmd3`, also written `parallel { cmd1 | cmd2 }`,
+    /// runs the commands at the same time using the jobs returned by
+    /// [`CommandHandler::background`], and waits for all of them. Output
+    /// is printed as it arrives, each line labeled with its command. At
+    /// most [`Repl::with_parallelism`] commands run at once.
+    Parallel,
+    /// `set [--save] <key> <value>` changes a framework setting, such as
+    /// `set timing on` or `set colors.accent "bold cyan"`, and `show [key]`
+    /// lists the current ones. With `--save` the change is also written to
+    /// the file given to [`Repl::watch_config`]. `set dry_run on` turns on
+    /// [dry-run mode](super::DryRun) for the rest of the session.
+    #[cfg(feature = "config")]
+    Settings,
+    /// `profile` lists the configuration profiles, marking the one in use,
+    /// and `profile use <name>` switches to another with
+    /// [`Repl::use_profile`].
+    #[cfg(feature = "config")]
+    Profiles,
+    /// `source <file> [args...]` runs the Rhai script in `file`, relative
+    /// to the [working directory](Repl::with_working_dir), with
+    /// [`Repl::run_script`], giving it the remaining words as `args`. With
+    /// [`Repl::with_script_keys`], only signed scripts run.
+    #[cfg(feature = "script")]
+    Scripts,
+    /// `copy` puts the output of the previous command on the system
+    /// clipboard, without colors, and Alt-W copies the line being edited.
+    /// Copying goes through the terminal with the OSC 52 escape sequence,
+    /// which most terminal emulators support, so it also works over SSH.
+    #[cfg(feature = "clipboard")]
+    Clipboard,
+    /// `cd [dir]` changes the working directory, to the home directory
+    /// without an argument and back to the previous one with `cd -`, and
+    /// `pwd` prints it. The directory is the one given to
+    /// [`Repl::with_working_dir`], the process's current directory by
+    /// default, and a prompt containing `{cwd}` shows it.
+    WorkingDir,
+    /// `clear` clears the screen, as Ctrl+L does while a line is edited,
+    /// and draws the [status bar](super::StatusBar) again at the top or
+    /// bottom. Nothing is written when the output is not a terminal.
+    Clear,
+    /// `snippet <name>` puts the snippet added with [`Repl::with_snippet`]
+    /// or configured in `[snippets]` on the line to edit at the next
+    /// prompt, and `snippet` lists them.
+    #[cfg(feature = "rustyline")]
+    Snippets,
+    /// `at <delay> <command>` runs a command once after a delay such as
+    /// `30s`, `5m` or `1h`, and `every <interval> <command>` runs it
+    /// repeatedly until cancelled, printing each run's output above the
+    /// prompt. `schedule` lists the scheduled commands and
+    /// `schedule cancel <id>` stops one.
+    ///
+    /// Scheduled commands run in the background, using the [`Job`]
+    /// returned by [`CommandHandler::background`], and are listed by `jobs`
+    /// as well. Commands run with `every` need a job created with
+    /// [`Job::repeatable`].
+    ///
+    /// [`Job`]: super::Job
+    /// [`Job::repeatable`]: super::Job::repeatable
+    Schedule,
+    /// `repeat <count> <command>` runs a command several times in a row,
+    /// stopping early when the command is cancelled through
+    /// [`Repl::cancel_token`] or asks to exit. `--delay <interval>` waits
+    /// between runs, such as `--delay 500ms`, and `--index <name>` replaces
+    /// `{name}` in the command with the number of the run, from 1:
+    /// `repeat 3 --index n ping host-{n}`. The command may be an alias.
+    Repeat,
+    /// `summary` reports what the session has run: how many commands, how
+    /// many failed, the time spent in them and the slowest few, ready to
+    /// paste into notes. Commands are shown with secrets masked by the
+    /// [redactor](crate::redact).
+    Summary,
+    /// `help` lists the commands described with [`Repl::with_commands`],
+    /// `help <command>` shows one with its usage and examples, and
+    /// `help <command> --run-example <n>` runs its `n`th example in
+    /// [dry-run mode](super::DryRun).
+    Help,
+    /// `update` installs the newer release found by the updater given to
+    /// [`Repl::with_updater`], after checking its signature, and
+    /// `update check` only reports it. The new version runs from the next
+    /// start.
+    #[cfg(feature = "update")]
+    Update,
+}
+
+impl<H: CommandHandler> Repl<H> {
+    /// Runs `line` if it starts with an enabled built-in. Returns whether it
+    /// did.
+    pub(super) fn run_builtin(&mut self, line: &str) -> bool {
+        let (word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        if self.builtins.contains(&Builtin::Jobs) {
+            if let Some(command) = line.strip_suffix('&') {
+                self.background_command(command.trim_end());
+                return true;
+            }
+            match word {
+                "jobs" => {
+                    self.jobs_command();
+                    return true;
+                }
+                "fg" => {
+                    self.fg_command(rest);
+                    return true;
+                }
+                "kill" => {
+                    self.kill_command(rest);
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        if self.builtins.contains(&Builtin::WorkingDir) {
+            match word {
+                "cd" => {
+                    self.cd_command(rest);
+                    return true;
+                }
+                "pwd" => {
+                    let _ = self.output.println(&self.working_dir.get().display().to_string());
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        if self.builtins.contains(&Builtin::Clear) && word == "clear" {
+            if rest.is_empty() {
+                self.clear_screen();
+            } else {
+                let _ = self.output.eprintln(&text("usage", &[("usage", &"clear")]));
+            }
+            return true;
+        }
+        #[cfg(feature = "rustyline")]
+        if self.builtins.contains(&Builtin::Snippets) && word == "snippet" {
+            self.snippet_command(rest);
+            return true;
+        }
+        if self.builtins.contains(&Builtin::Schedule) {
+            match word {
+                "at" | "every" => {
+                    self.schedule_at(word, rest);
+                    return true;
+                }
+                "schedule" => {
+                    self.schedule_command(rest);
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        if self.builtins.contains(&Builtin::Repeat) && word == "repeat" {
+            self.repeat_command(rest);
+            return true;
+        }
+        if self.builtins.contains(&Builtin::Summary) && word == "summary" {
+            self.summary_command(rest);
+            return true;
+        }
+        if self.builtins.contains(&Builtin::Help) && word == "help" {
+            self.help_command(rest);
+            return true;
+        }
+        if self.builtins.contains(&Builtin::Parallel) && matches!(word, "par" | "parallel") {
+            self.parallel_command(rest);
+            return true;
+        }
+        #[cfg(feature = "script")]
+        if self.builtins.contains(&Builtin::Scripts) && word == "source" {
+            self.source_command(line);
+            return true;
+        }
+        #[cfg(feature = "update")]
+        if self.builtins.contains(&Builtin::Update) && self.updater.is_some() && word == "update" {
+            self.update_command(rest);
+            return true;
+        }
+        #[cfg(feature = "clipboard")]
+        if self.builtins.contains(&Builtin::Clipboard) && word == "copy" {
+            self.copy_command(rest);
+            return true;
+        }
+        #[cfg(feature = "config")]
+        {
+            if self.builtins.contains(&Builtin::Settings) {
+                match word {
+                    "set" => {
+                        self.set_command(rest);
+                        return true;
+                    }
+                    "show" => {
+                        self.show_command(rest);
+                        return true;
+                    }
+                    _ => {}
+                }
+            }
+            if self.builtins.contains(&Builtin::Profiles) && word == "profile" {
+                self.profile_command(rest);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn background_command(&mut self, command: &str) {
+        if command.is_empty() {
+            let _ = self.output.eprintln(&text("usage", &[("usage", &"<command> &")]));
+            return;
+        }
+        let Some(job) = self.handler.background(command) else {
+            self.print_error(CodedError::new("job.cannot_background", text("job.cannot_background", &[("command", &command)])).detail("command", command));
+            return;
+        };
+        let printer = self.printer();
+        let id = self.jobs.spawn(command, job, printer, self.job_env());
+        let _ = self.output.println(&format!("[{id}] {command}"));
+    }
+
+    fn parallel_command(&mut self, args: &str) {
+        let args = args.strip_prefix('{').and_then(|a| a.strip_suffix('}')).unwrap_or(args);
+        let commands: Vec<&str> = args.split('|').map(str::trim).filter(|c| !c.is_empty()).collect();
+        if commands.is_empty() {
+            let _ = self.output.eprintln(&text("usage", &[("usage", &"par <command> | <command> ...")]));
+            return;
+        }
+        let mut jobs = Vec::with_capacity(commands.len());
+        for command in &commands {
+            let command = expand_alias(&self.aliases, command).into_owned();
+            if !self.allows(&command) {
+                return;
+            }
+            let Some(job) = self.handler.background(&command) else {
+                self.print_error(CodedError::new("job.cannot_parallel", text("job.cannot_parallel", &[("command", &command)])).detail("command", &command));
+                return;
+            };
+            jobs.push((command, job));
+        }
+        let printer = self.printer();
+        let failed = super::jobs::run_parallel(jobs, self.parallelism, &printer, &self.job_env());
+        if failed > 0 {
+            let _ = self.output.eprintln(&text("job.parallel_failed", &[("failed", &failed), ("total", &commands.len())]));
+        }
+    }
+
+    #[cfg(feature = "script")]
+    fn source_command(&mut self, line: &str) {
+        let args: Result<Vec<_>, _> = super::tokenize(line).skip(1).map(|token| token.map(|token| token.text)).collect();
+        let args = match args {
+            Ok(args) => args,
+            Err(err) => {
+                let error = CodedError::new("input.unterminated_quote", text("input.unterminated_quote", &[("quote", &err.quote)]))
+                    .detail("quote", err.quote)
+                    .detail("offset", err.start);
+                let _ = self.output.report_as(&error, &err.diagnostic(line).render());
+                return;
+            }
+        };
+        let Some((path, args)) = args.split_first() else {
+            let _ = self.output.eprintln(&text("usage", &[("usage", &"source <file> [args...]")]));
+            return;
+        };
+        let file = self.working_dir.resolve(path);
+        let source = match std::fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(err) => {
+                self.print_error(CodedError::new("script.unreadable", text("script.unreadable", &[("path", path), ("error", &err)])).detail("path", path));
+                return;
+            }
+        };
+        #[cfg(feature = "signing")]
+        if let Some(keys) = &self.script_keys {
+            let mut signature = file.into_os_string();
+            signature.push(".minisig");
+            let checked = match std::fs::read_to_string(&signature) {
+                Ok(signature) => super::signing::verify(keys, source.as_bytes(), &signature).map(|_| ()),
+                Err(err) => {
+                    self.print_error(CodedError::new("script.unsigned", text("script.unsigned", &[("path", path), ("error", &err)])).detail("path", path));
+                    return;
+                }
+            };
+            if let Err(err) = checked {
+                self.print_error(CodedError::new("script.bad_signature", text("script.bad_signature", &[("path", path), ("error", &err)])).detail("path", path));
+                return;
+            }
+        }
+        if let Err(err) = self.run_script(&source, args) {
+            self.print_error(CodedError::new("script.failed", err.to_string()).detail("path", path));
+        }
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn copy_command(&mut self, args: &str) {
+        if !args.is_empty() {
+            let _ = self.output.eprintln(&text("usage", &[("usage", &"copy")]));
+            return;
+        }
+        if self.last_output.is_empty() {
+            self.print_error(CodedError::new("clipboard.empty", text("clipboard.empty", &[])));
+            return;
+        }
+        if !self.output.is_terminal() {
+            self.print_error(CodedError::new("clipboard.no_terminal", text("clipboard.no_terminal", &[])));
+            return;
+        }
+        let lines = self.last_output.lines().count();
+        if self.dry_run.is_on() {
+            let _ = self.output.println(&crate::locale::text_count("clipboard.would_copy", lines, &[]));
+            return;
+        }
+        match super::clipboard::copy(&self.last_output) {
+            Ok(()) => {
+                let copied = crate::locale::text_count("clipboard.copied", lines, &[]);
+                let _ = self.output.println(&super::theme::current().paint(super::Tone::Muted, &copied));
+            }
+            Err(err) => {
+                self.print_error(CodedError::new("clipboard.failed", text("clipboard.failed", &[("error", &err)])));
+            }
+        }
+    }
+
+    fn cd_command(&mut self, args: &str) {
+        let result = match unquote(args) {
+            "" => match crate::dirs::home_dir() {
+                Some(home) => self.working_dir.change(home),
+                None => {
+                    self.print_error(CodedError::new("dir.no_home", text("dir.no_home", &[])));
+                    return;
+                }
+            },
+            "-" => match self.working_dir.back() {
+                Some(Ok(dir)) => {
+                    let _ = self.output.println(&dir.display().to_string());
+                    return;
+                }
+                Some(Err(err)) => Err(err),
+                None => {
+                    self.print_error(CodedError::new("dir.no_previous", text("dir.no_previous", &[])));
+                    return;
+                }
+            },
+            path => self.working_dir.change(path),
+        };
+        if let Err(err) = result {
+            let path = unquote(args);
+            self.print_error(
+                CodedError::new("dir.change_failed", text("dir.change_failed", &[("path", &path), ("error", &err)]))
+                    .detail("path", path),
+            );
+        }
+    }
+
+    fn jobs_command(&mut self) {
+        let running = self.jobs.list();
+        if running.is_empty() {
+            let _ = self.output.println(&text("job.none", &[]));
+        }
+        for (id, command) in running {
+            let _ = self.output.println(&text("job.running", &[("id", &id), ("command", &command)]));
+        }
+    }
+
+    fn fg_command(&mut self, args: &str) {
+        let id = match args {
+            "" => self.jobs.list().last().map(|(id, _)| *id),
+            id => parse_job_id(id),
+        };
+        let Some(id) = id else {
+            if args.is_empty() {
+                self.print_error(CodedError::new("job.none_running", text("job.none_running", &[])));
+            } else {
+                let _ = self.output.eprintln(&text("usage", &[("usage", &"fg [id]")]));
+            }
+            return;
+        };
+        match self.jobs.wait(id) {
+            Some(lines) => {
+                for line in lines {
+                    let _ = self.output.println(&line);
+                }
+            }
+            None => {
+                self.print_error(CodedError::new("job.not_found", text("job.not_found", &[("id", &id)])).detail("id", id));
+            }
+        }
+    }
+
+    fn kill_command(&mut self, args: &str) {
+        let Some(id) = parse_job_id(args) else {
+            let _ = self.output.eprintln(&text("usage", &[("usage", &"kill <id>")]));
+            return;
+        };
+        if self.dry_run.is_on() {
+            match self.jobs.list().into_iter().find(|(running, _)| *running == id) {
+                Some((_, command)) => {
+                    let _ = self.output.println(&text("job.would_stop", &[("id", &id), ("command", &command)]));
+                }
+                None => {
+                    self.print_error(CodedError::new("job.not_found", text("job.not_found", &[("id", &id)])).detail("id", id));
+                }
+            }
+            return;
+        }
+        match self.jobs.kill(id) {
+            Some(command) => {
+                let _ = self.output.println(&text("job.stopping", &[("id", &id), ("command", &command)]));
+            }
+            None => {
+                self.print_error(CodedError::new("job.not_found", text("job.not_found", &[("id", &id)])).detail("id", id));
+            }
+        }
+    }
+
+    #[cfg(feature = "config")]
+    fn set_command(&mut self, args: &str) {
+        let (save, args) = match args.strip_prefix("--save") {
+            Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => (true, rest.trim_start()),
+            _ => (false, args),
+        };
+        let (key, value) = match args.split_once(|c: char| c.is_whitespace() || c == '=') {
+            Some((key, value)) => (key, unquote(value.trim_start_matches('=').trim())),
+            None => (args, ""),
+        };
+        if key.is_empty() || value.is_empty() {
+            let _ = self.output.eprintln(&text("usage", &[("usage", &"set [--save] <key> <value>")]));
+            return;
+        }
+
+        if let Err(err) = self.apply_setting(key, value) {
+            self.print_error(CodedError::new("config.invalid_setting", err.to_string()).detail("key", key));
+            return;
+        }
+        if save {
+            let path = self.live.as_ref().and_then(|live| live.path.clone());
+            let Some(path) = path else {
+                self.print_error(CodedError::new("config.no_file", text("config.no_file", &[])));
+                return;
+            };
+            if self.dry_run.is_on() {
+                let _ = self.output.println(&text("config.would_save", &[("key", &key), ("value", &value), ("path", &path.display())]));
+                self.show_command(key);
+                return;
+            }
+            if let Err(err) = crate::config::Config::save_setting(&path, key, value) {
+                self.print_error(CodedError::new("config.save_failed", err.to_string()).detail("path", path.display()));
+                return;
+            }
+            if let Some(live) = &mut self.live {
+                live.saved(key, value);
+            }
+        }
+        self.show_command(key);
+    }
+
+    #[cfg(feature = "config")]
+    fn apply_setting(&mut self, key: &str, value: &str) -> std::result::Result<(), crate::config::ConfigError> {
+        use crate::config::Config;
+        use crate::output::theme;
+
+        let mut single = Config::default();
+        single.set(key, value)?;
+        self.settings.merge(single.clone());
+
+        if let Some(prompt) = &single.prompt {
+            self.prompt = prompt.clone();
+        }
+        if let Some(timing) = single.timing {
+            self.timing = timing;
+        }
+        if let Some(secs) = single.report_time {
+            self.report_time = Some(std::time::Duration::from_secs(secs)).filter(|threshold| !threshold.is_zero());
+        }
+        if let Some(dry_run) = single.dry_run {
+            self.dry_run.set(dry_run);
+        }
+        if let Some(secs) = single.command_timeout {
+            self.timeout = Some(std::time::Duration::from_secs(secs)).filter(|limit| !limit.is_zero());
+        }
+        if let Some(title) = &single.title {
+            self.set_title(title.clone());
+        }
+        if single.theme.is_some() {
+            // Colors set earlier still apply on top of the new theme.
+            theme::set_theme(self.settings.theme((*theme::current()).clone())?);
+        } else if !single.colors.is_empty() {
+            theme::set_theme(single.theme((*theme::current()).clone())?);
+        }
+        #[cfg(feature = "rustyline")]
+        self.apply_editor_setting(&single);
+        #[cfg(feature = "rustyline")]
+        {
+            self.add_abbreviations(std::mem::take(&mut single.abbreviations));
+            self.add_snippets(std::mem::take(&mut single.snippets));
+        }
+        self.aliases.extend(single.aliases);
+        Ok(())
+    }
+
+    /// Applies a history setting or key binding changed with `set` to the
+    /// line editor.
+    #[cfg(all(feature = "config", feature = "rustyline"))]
+    fn apply_editor_setting(&mut self, single: &crate::config::Config) {
+        use rustyline::config::Configurer;
+        use rustyline::EventHandler;
+
+        if single.history.file.is_some() {
+            self.history_file = single.history_file();
+        }
+        if let Some(size) = single.history.max_size {
+            let _ = self.editor.set_max_history_size(size);
+        }
+        if let Some(ignore) = single.history.ignore_duplicates {
+            let _ = self.editor.set_history_ignore_dups(ignore);
+        }
+        if let Some(ignore) = single.history.ignore_space {
+            self.editor.set_history_ignore_space(ignore);
+        }
+        for (key, action) in &single.keybindings {
+            if let (Ok(key), Ok(cmd)) = (super::keys::parse_key(key), super::keys::parse_action(action)) {
+                self.editor.bind_sequence(key, EventHandler::Simple(cmd));
+            }
+        }
+    }
+
+    #[cfg(feature = "config")]
+    fn show_command(&self, filter: &str) {
+        let mut shown = self.settings.clone();
+        shown.prompt = Some(self.prompt.clone());
+        shown.timing = Some(self.timing);
+        shown.report_time = Some(self.report_time.map_or(0, |threshold| threshold.as_secs()));
+        shown.dry_run = Some(self.dry_run.is_on());
+        shown.title = Some(self.title.clone().unwrap_or_default());
+        shown.aliases = self.aliases.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+
+        let section = format!("{filter}.");
+        let entries: Vec<_> = shown
+            .entries()
+            .into_iter()
+            .filter(|(key, _)| filter.is_empty() || key == filter || key.starts_with(&section))
+            .collect();
+        if entries.is_empty() {
+            let _ = self.output.eprintln(&text("config.not_set", &[("key", &filter)]));
+            return;
+        }
+        for (key, value) in entries {
+            let _ = self.output.println(&format!("{key} = {value}"));
+        }
+    }
+
+    #[cfg(feature = "config")]
+    fn profile_command(&mut self, args: &str) {
+        let (sub, name) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        match (sub, name.trim()) {
+            ("" | "list", "") => {
+                if self.settings.profiles.is_empty() {
+                    let _ = self.output.println(&text("config.no_profiles", &[]));
+                    return;
+                }
+                let current = self.profile().map(str::to_string);
+                for name in self.settings.profiles.keys() {
+                    let marker = if current.as_deref() == Some(name) { '*' } else { ' ' };
+                    let _ = self.output.println(&format!("{marker} {name}"));
+                }
+            }
+            ("use", name) if !name.is_empty() => match self.use_profile(name) {
+                Ok(()) => {
+                    let _ = self.output.println(&text("config.using_profile", &[("name", &name)]));
+                }
+                Err(err) => {
+                    self.print_error(CodedError::new("config.profile_failed", err.to_string()).detail("profile", name));
+                }
+            },
+            _ => {
+                let _ = self.output.eprintln(&text("usage", &[("usage", &"profile [list | use <name>]")]));
+            }
+        }
+    }
+}
+
+/// Parses a job number, written as `1` or `%1`.
+pub(super) fn parse_job_id(text: &str) -> Option<usize> {
+    text.strip_prefix('%').unwrap_or(text).parse().ok()
+}
+
+/// Removes one pair of matching quotes around `value`.
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(quote).and_then(|v| v.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    value
+}
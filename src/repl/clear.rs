@@ -0,0 +1,46 @@
+//! Clearing the screen, with the `clear` built-in and Ctrl+L.
+
+#[cfg(feature = "rustyline")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "rustyline")]
+use rustyline::{Cmd, ConditionalEventHandler, Event, EventContext, RepeatCount};
+
+use super::{osc, CommandHandler, Repl};
+
+/// The line being edited when Ctrl+L was pressed, with the cursor
+/// position in it, to be edited again once the screen is cleared. The
+/// `snippet` built-in also leaves its line here for the next prompt.
+#[cfg(feature = "rustyline")]
+pub(super) type Cleared = Arc<Mutex<Option<(String, usize)>>>;
+
+/// Clears the screen, bound to Ctrl+L while a status bar is shown.
+///
+/// Reading the line is interrupted, after keeping it in the shared slot,
+/// so the REPL can clear the screen and draw the bar before prompting
+/// again with the same line.
+#[cfg(feature = "rustyline")]
+pub(super) struct ClearKey(pub(super) Cleared);
+
+#[cfg(feature = "rustyline")]
+impl ConditionalEventHandler for ClearKey {
+    fn handle(&self, _: &Event, _: RepeatCount, _: bool, ctx: &EventContext) -> Option<Cmd> {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some((ctx.line().to_string(), ctx.pos()));
+        Some(Cmd::Interrupt)
+    }
+}
+
+impl<H: CommandHandler> Repl<H> {
+    /// Clears the screen when the output is the terminal, and has the
+    /// status bar drawn in full before the next prompt.
+    pub(super) fn clear_screen(&mut self) {
+        if !self.output.is_terminal() {
+            return;
+        }
+        let _ = self.output.flush();
+        let _ = osc::clear_screen();
+        if let Some(status) = &mut self.status {
+            status.forget();
+        }
+    }
+}
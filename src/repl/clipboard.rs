@@ -0,0 +1,57 @@
+//! Copying to the system clipboard through the terminal.
+//!
+//! The text is sent in an OSC 52 escape sequence, which most terminal
+//! emulators turn into a clipboard update, so copying also works from a
+//! program running over SSH.
+
+use std::io;
+
+#[cfg(feature = "rustyline")]
+use rustyline::{Cmd, ConditionalEventHandler, Event, EventContext, RepeatCount};
+
+use super::{osc, CommandHandler, Repl};
+
+/// Puts `text` on the clipboard of the terminal on stdout.
+pub(super) fn copy(text: &str) -> io::Result<()> {
+    osc::send(&format!("52;c;{}", base64(text.as_bytes())))
+}
+
+/// Copies the line being edited, bound to Alt-W.
+#[cfg(feature = "rustyline")]
+pub(super) struct CopyLine;
+
+#[cfg(feature = "rustyline")]
+impl ConditionalEventHandler for CopyLine {
+    fn handle(&self, _: &Event, _: RepeatCount, _: bool, ctx: &EventContext) -> Option<Cmd> {
+        let _ = copy(ctx.line());
+        Some(Cmd::Noop)
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let n = u32::from(b[0]) << 16 | u32::from(b[1]) << 8 | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+impl<H: CommandHandler> Repl<H> {
+    /// Runs `cmd` as [`run_command`](Repl::run_command) does, keeping its
+    /// output for `copy`.
+    pub(super) fn run_keeping_output(&mut self, cmd: &str) -> (bool, &'static str) {
+        let output = self.output.clone();
+        let (result, out, _) = output.record(true, || self.run_command(cmd));
+        self.last_output = out;
+        result
+    }
+}
@@ -0,0 +1,86 @@
+//! The time as commands see it.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Where the REPL and its commands read the time.
+///
+/// The system clock is used unless the REPL is given another with
+/// [`Repl::with_clock`]. A [manual](Self::manual) clock stands still until
+/// it is advanced, so commands that print timestamps produce the same
+/// output on every run, and a recorded session replays byte for byte.
+/// Clones share the same time. Background jobs get the REPL's clock
+/// through [`JobContext::clock`].
+///
+/// [`Repl::with_clock`]: super::Repl::with_clock
+/// [`JobContext::clock`]: super::JobContext::clock
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, SystemTime};
+/// use mycli::repl::Clock;
+///
+/// let clock = Clock::manual(SystemTime::UNIX_EPOCH);
+/// let started = clock.instant();
+/// clock.advance(Duration::from_secs(90));
+///
+/// assert_eq!(clock.now(), SystemTime::UNIX_EPOCH + Duration::from_secs(90));
+/// assert_eq!(clock.instant() - started, Duration::from_secs(90));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Clock(Option<Arc<Manual>>);
+
+#[derive(Debug)]
+struct Manual {
+    wall: SystemTime,
+    instant: Instant,
+    elapsed: Mutex<Duration>,
+}
+
+impl Clock {
+    /// Returns the system clock.
+    pub fn system() -> Self {
+        Self(None)
+    }
+
+    /// Creates a clock reading `start` until it is advanced.
+    pub fn manual(start: SystemTime) -> Self {
+        Self(Some(Arc::new(Manual { wall: start, instant: Instant::now(), elapsed: Mutex::default() })))
+    }
+
+    /// Returns `true` for a clock created with [`manual`](Self::manual).
+    pub fn is_manual(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Returns the current time, for timestamps.
+    pub fn now(&self) -> SystemTime {
+        match &self.0 {
+            Some(manual) => manual.wall + manual.elapsed(),
+            None => SystemTime::now(),
+        }
+    }
+
+    /// Returns the current instant, for measuring how long something took.
+    pub fn instant(&self) -> Instant {
+        match &self.0 {
+            Some(manual) => manual.instant + manual.elapsed(),
+            None => Instant::now(),
+        }
+    }
+
+    /// Moves a manual clock forward by `by`. Does nothing to the system
+    /// clock.
+    pub fn advance(&self, by: Duration) {
+        if let Some(manual) = &self.0 {
+            *manual.elapsed.lock().unwrap_or_else(|e| e.into_inner()) += by;
+        }
+    }
+}
+
+impl Manual {
+    fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
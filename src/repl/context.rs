@@ -0,0 +1,176 @@
+//! What a command can use while it runs.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::WorkingDir;
+use crate::output::Output;
+
+/// What the REPL gives a command handler along with each command.
+///
+/// New capabilities are added here rather than as parameters of
+/// [`CommandHandler::handle`], so handlers keep compiling as the framework
+/// grows.
+///
+/// [`CommandHandler::handle`]: super::CommandHandler::handle
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::{BufferSink, Output};
+/// use mycli::repl::{CommandHandler, Context};
+///
+/// struct Files;
+///
+/// impl CommandHandler for Files {
+///     fn handle(&mut self, command: &str, ctx: &Context) -> bool {
+///         if let Some(name) = command.strip_prefix("open ") {
+///             let path = ctx.working_dir().resolve(name);
+///             let _ = ctx.output().println(&format!("opening {}", path.display()));
+///         }
+///         true
+///     }
+/// }
+///
+/// // Handlers can be called directly, for instance in tests.
+/// let buffer = BufferSink::new();
+/// let ctx = Context::new(Output::new(buffer.clone()));
+/// Files.handle("open /etc/hosts", &ctx);
+/// assert_eq!(buffer.contents(), "opening /etc/hosts\n");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Context<'a> {
+    pub(super) output: Cow<'a, Output>,
+    pub(super) working_dir: Cow<'a, WorkingDir>,
+    pub(super) cancel: Cow<'a, CancelToken>,
+    pub(super) dry_run: bool,
+    pub(super) allowed: Option<&'a HashSet<String>>,
+    pub(super) user: Option<&'a str>,
+    #[cfg(feature = "config")]
+    pub(super) config: Cow<'a, crate::config::Config>,
+}
+
+impl Context<'static> {
+    /// Creates a context writing to `output`, with the process's working
+    /// directory, dry-run mode off, every command allowed and the default
+    /// configuration, for calling a handler outside a REPL.
+    pub fn new(output: Output) -> Self {
+        Self {
+            output: Cow::Owned(output),
+            working_dir: Cow::Owned(WorkingDir::process()),
+            cancel: Cow::Owned(CancelToken::new()),
+            dry_run: false,
+            allowed: None,
+            user: None,
+            #[cfg(feature = "config")]
+            config: Cow::Owned(crate::config::Config::default()),
+        }
+    }
+}
+
+impl Context<'_> {
+    /// Returns the output commands should write to instead of stdout.
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    /// Returns the REPL's [working directory](WorkingDir), to resolve the
+    /// paths the command was given.
+    pub fn working_dir(&self) -> &WorkingDir {
+        &self.working_dir
+    }
+
+    /// Returns `true` once the command was asked to stop. Long-running
+    /// commands should check it regularly and return early.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Returns the token that tells the command to stop, for checking it
+    /// from threads or tasks the command starts.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone().into_owned()
+    }
+
+    /// Returns `true` if the command should only describe what it would do.
+    /// See [`DryRun`](super::DryRun).
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Returns `true` if `command` may run here, matching its first word
+    /// against the commands given to
+    /// [`Repl::with_allowed_commands`](super::Repl::with_allowed_commands),
+    /// for instance to leave commands the user may not run out of a menu.
+    pub fn is_allowed(&self, command: &str) -> bool {
+        let name = command.split_whitespace().next().unwrap_or(command);
+        self.allowed.is_none_or(|allowed| allowed.contains(name))
+    }
+
+    /// Returns the identity the user authenticated as, in sessions of an
+    /// authenticating server from [`remote`](crate::remote); `None`
+    /// elsewhere.
+    pub fn user(&self) -> Option<&str> {
+        self.user
+    }
+
+    /// Returns the settings in effect, including changes made with `set`
+    /// and the profile in use.
+    #[cfg(feature = "config")]
+    pub fn config(&self) -> &crate::config::Config {
+        &self.config
+    }
+
+    /// Returns the value of the configured variable `name`, from the
+    /// `[variables]` table of the configuration.
+    #[cfg(feature = "config")]
+    pub fn variable(&self, name: &str) -> Option<&str> {
+        self.config.variables.get(name).map(String::as_str)
+    }
+}
+
+/// Tells a running command to stop.
+///
+/// Clones share the same token. [`Repl::cancel_token`] returns the REPL's,
+/// which is cleared before each command, so cancelling it from another
+/// thread, for instance when Ctrl+C is pressed, stops the command running
+/// at the time. Commands check it with [`Context::is_cancelled`].
+///
+/// [`Repl::cancel_token`]: super::Repl::cancel_token
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::CancelToken;
+///
+/// let token = CancelToken::new();
+/// let seen = token.clone();
+/// token.cancel();
+/// assert!(seen.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Creates a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks the command to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` once [`cancel`](Self::cancel) was called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Clears the token before the next command runs.
+    pub(super) fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
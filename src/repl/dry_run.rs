@@ -0,0 +1,99 @@
+//! Running commands without their side effects.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// The flag that puts commands in dry-run mode, where they describe what
+/// they would do instead of doing it.
+///
+/// Clones share the flag. Give one to [`Repl::with_dry_run`] and keep
+/// another in the command handler to check with [`is_on`](Self::is_on);
+/// background jobs see it through [`JobContext::is_dry_run`]. Set it for
+/// the whole session, for example from a `--dry-run` command-line flag, or
+/// let users turn it on with `set dry_run on` or for one command by ending
+/// it with `--dry-run`.
+///
+/// [`Repl::with_dry_run`]: super::Repl::with_dry_run
+/// [`JobContext::is_dry_run`]: super::JobContext::is_dry_run
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::{BufferSink, Output};
+/// use mycli::repl::{CommandHandler, Context, DryRun, Repl};
+/// # use mycli::repl::LineReader;
+/// # struct Lines(std::vec::IntoIter<&'static str>);
+/// # impl LineReader for Lines {
+/// #     fn readline(&mut self, _: &str) -> mycli::repl::Result<String> {
+/// #         self.0.next().map(String::from).ok_or(mycli::repl::ReadlineError::Eof)
+/// #     }
+/// # }
+///
+/// struct Deployer {
+///     out: Output,
+///     dry_run: DryRun,
+/// }
+///
+/// impl CommandHandler for Deployer {
+///     fn handle(&mut self, command: &str, _: &Context) -> bool {
+///         if self.dry_run.is_on() {
+///             let _ = self.out.println(&format!("would run `{command}`"));
+///         } else {
+///             let _ = self.out.println(&format!("ran `{command}`"));
+///         }
+///         true
+///     }
+/// }
+///
+/// let buffer = BufferSink::new();
+/// let out = Output::new(buffer.clone());
+/// let dry_run = DryRun::new();
+/// let handler = Deployer { out: out.clone(), dry_run: dry_run.clone() };
+/// let mut repl = Repl::new("> ", handler).unwrap().with_output(out).with_dry_run(dry_run);
+///
+/// // `Lines` is a `LineReader` that replays the lines it is given.
+/// let mut typed = Lines(vec!["deploy prod --dry-run", "deploy prod"].into_iter());
+/// repl.run_with(&mut typed).unwrap();
+/// assert_eq!(buffer.contents(), "would run `deploy prod`\nran `deploy prod`\n");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DryRun(Arc<AtomicBool>);
+
+impl DryRun {
+    /// Creates the flag, turned off.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if commands should only describe what they would do.
+    pub fn is_on(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Turns dry-run mode on or off.
+    pub fn set(&self, on: bool) {
+        self.0.store(on, Ordering::Relaxed);
+    }
+
+    /// Turns dry-run mode on or off and returns whether it was on.
+    pub(super) fn replace(&self, on: bool) -> bool {
+        self.0.swap(on, Ordering::Relaxed)
+    }
+}
+
+/// The word that runs one command in dry-run mode.
+const FLAG: &str = "--dry-run";
+
+/// Removes a `--dry-run` word from the end of `cmd`, or from before a
+/// trailing `&`. Returns `None` if there is none, or nothing else.
+pub(super) fn strip_flag(cmd: &str) -> Option<String> {
+    let (body, background) = match cmd.strip_suffix('&') {
+        Some(body) => (body.trim_end(), " &"),
+        None => (cmd, ""),
+    };
+    let rest = body.strip_suffix(FLAG)?;
+    if !rest.ends_with(char::is_whitespace) || rest.trim().is_empty() {
+        return None;
+    }
+    Some(format!("{}{background}", rest.trim_end()))
+}
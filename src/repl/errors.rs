@@ -0,0 +1,75 @@
+//! How the REPL reports its own errors.
+
+use super::ReadlineError;
+
+use crate::locale::text;
+use crate::output::CodedError;
+
+/// Formats the errors the REPL reports itself, and decides which errors
+/// reading a line end the loop.
+///
+/// Errors from built-ins, such as `no job 3`, and from reading input go
+/// through the renderer given to [`Repl::with_error_renderer`], so an
+/// application can translate them, style them its own way or keep the REPL
+/// running after a failed read. Every method has a default matching
+/// [`DefaultErrorRenderer`].
+///
+/// [`Repl::with_error_renderer`]: super::Repl::with_error_renderer
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::{ErrorRenderer, ReadlineError};
+///
+/// struct German;
+///
+/// impl ErrorRenderer for German {
+///     fn render(&self, message: &str) -> String {
+///         format!("Fehler: {message}")
+///     }
+///
+///     fn is_fatal(&self, err: &ReadlineError) -> bool {
+///         // Keep prompting after a read interrupted by a signal.
+///         !matches!(err, ReadlineError::Io(e) if e.kind() == std::io::ErrorKind::Interrupted)
+///     }
+/// }
+///
+/// assert_eq!(German.render("no job 3"), "Fehler: no job 3");
+/// ```
+pub trait ErrorRenderer: Send {
+    /// Formats `message`, an error from the REPL such as `no job 3`, for
+    /// printing as error output. Returns `Error: ` followed by the message
+    /// by default.
+    fn render(&self, message: &str) -> String {
+        text("error", &[("message", &message)])
+    }
+
+    /// Formats `error`, an error from the REPL with a stable
+    /// [code](CodedError::code), for printing as error output. Returns
+    /// [`render`](Self::render) of its message by default.
+    fn render_error(&self, error: &CodedError) -> String {
+        self.render(error.message())
+    }
+
+    /// Formats an error from reading a line other than Ctrl+C and Ctrl+D,
+    /// which the REPL handles itself. Returns `Error: ` followed by the
+    /// error's debug form by default.
+    fn render_readline(&self, err: &ReadlineError) -> String {
+        text("error", &[("message", &format!("{err:?}"))])
+    }
+
+    /// Returns whether the REPL stops after failing to read a line with
+    /// `err`. Returns `true` by default; otherwise the REPL prints the
+    /// error and shows the prompt again.
+    fn is_fatal(&self, err: &ReadlineError) -> bool {
+        let _ = err;
+        true
+    }
+}
+
+/// The [`ErrorRenderer`] used unless the REPL is given another: errors are
+/// printed after `Error: `, and any failure to read a line ends the REPL.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultErrorRenderer;
+
+impl ErrorRenderer for DefaultErrorRenderer {}
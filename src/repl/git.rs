@@ -0,0 +1,102 @@
+//! The Git status of the working directory, for the `{git}` prompt
+//! placeholder.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// The current branch of a Git repository and whether it has uncommitted
+/// changes.
+///
+/// The `{git}` [placeholder](super::Repl::with_placeholder) shows it for
+/// the working directory, as `main`, or `main*` when files were changed,
+/// and nothing outside a repository. It is read by running `git`, once
+/// after each command and whenever the working directory changes, so
+/// prompts stay fast. Register a placeholder of your own calling
+/// [`GitStatus::read`] to show it differently.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mycli::repl::GitStatus;
+///
+/// if let Some(status) = GitStatus::read(std::path::Path::new(".")) {
+///     println!("on {} ({})", status.branch, if status.dirty { "changed" } else { "clean" });
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitStatus {
+    /// The branch checked out, or the abbreviated commit when none is.
+    pub branch: String,
+    /// Whether tracked files have changes that are not committed.
+    pub dirty: bool,
+}
+
+impl GitStatus {
+    /// Reads the status of the repository containing `dir`. Returns `None`
+    /// outside a repository, or if `git` cannot be run.
+    pub fn read(dir: &Path) -> Option<Self> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["status", "--porcelain=v2", "--branch", "--untracked-files=no"])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        let (mut head, mut oid) = (None, None);
+        let mut dirty = false;
+        for line in text.lines() {
+            if let Some(value) = line.strip_prefix("# branch.head ") {
+                head = Some(value);
+            } else if let Some(value) = line.strip_prefix("# branch.oid ") {
+                oid = Some(value);
+            } else if !line.starts_with('#') {
+                dirty = true;
+            }
+        }
+        let branch = match head? {
+            "(detached)" => oid?.chars().take(7).collect(),
+            name => name.to_string(),
+        };
+        Some(Self { branch, dirty })
+    }
+}
+
+impl fmt::Display for GitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.branch)?;
+        if self.dirty {
+            f.write_str("*")?;
+        }
+        Ok(())
+    }
+}
+
+/// The status last read, and the directory it was read in.
+#[derive(Debug, Default)]
+pub(super) struct Cache(RefCell<Option<(PathBuf, Option<GitStatus>)>>);
+
+impl Cache {
+    /// Returns the status of `dir`, reading it again if it was not read
+    /// there since the last command.
+    pub(super) fn get(&self, dir: PathBuf) -> Option<GitStatus> {
+        let mut cached = self.0.borrow_mut();
+        match &*cached {
+            Some((read_in, status)) if *read_in == dir => status.clone(),
+            _ => {
+                let status = GitStatus::read(&dir);
+                *cached = Some((dir, status.clone()));
+                status
+            }
+        }
+    }
+
+    /// Forgets the status, after a command that may have changed it.
+    pub(super) fn clear(&self) {
+        self.0.borrow_mut().take();
+    }
+}
@@ -0,0 +1,242 @@
+//! Descriptions of the application's commands, shown by the `help`
+//! built-in.
+
+use super::{CommandHandler, Repl};
+use crate::locale::text;
+use crate::output::theme::{self, Tone};
+use crate::output::width::{display_width, pad, Align};
+use crate::output::CodedError;
+use crate::suggest::closest;
+
+const USAGE: &str = "help [command] [--run-example <n>]";
+
+/// A description of one of the application's commands: what it does, how
+/// to call it and examples of calling it.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::CommandInfo;
+///
+/// let deploy = CommandInfo::new("deploy", "Deploys a service")
+///     .with_usage("deploy <service> [--canary]")
+///     .with_example("deploy web --canary", "Deploys web to the canary hosts only");
+/// assert_eq!(deploy.examples()[0].command(), "deploy web --canary");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandInfo {
+    name: String,
+    summary: String,
+    usage: Option<String>,
+    description: Option<String>,
+    examples: Vec<Example>,
+}
+
+impl CommandInfo {
+    /// Describes the command `name`, matched on the first word of a line,
+    /// with a one-line summary of what it does.
+    pub fn new(name: impl Into<String>, summary: impl Into<String>) -> Self {
+        Self { name: name.into(), summary: summary.into(), usage: None, description: None, examples: Vec::new() }
+    }
+
+    /// Sets how the command is called, such as
+    /// `deploy <service> [--canary]`.
+    pub fn with_usage(mut self, usage: impl Into<String>) -> Self {
+        self.usage = Some(usage.into());
+        self
+    }
+
+    /// Sets a longer description, shown after the usage. Blank lines
+    /// separate paragraphs.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Adds an example: a complete command line and what it does.
+    /// Examples are numbered from 1 in the order they are added.
+    pub fn with_example(mut self, command: impl Into<String>, description: impl Into<String>) -> Self {
+        self.examples.push(Example { command: command.into(), description: description.into() });
+        self
+    }
+
+    /// Returns the name of the command.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the one-line summary of the command.
+    pub fn summary(&self) -> &str {
+        &self.summary
+    }
+
+    /// Returns how the command is called, if set.
+    pub fn usage(&self) -> Option<&str> {
+        self.usage.as_deref()
+    }
+
+    /// Returns the longer description, if set.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Returns the examples, in order.
+    pub fn examples(&self) -> &[Example] {
+        &self.examples
+    }
+}
+
+/// An example of calling a command, added with [`CommandInfo::with_example`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Example {
+    command: String,
+    description: String,
+}
+
+impl Example {
+    /// Returns the command line of the example.
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// Returns what the example does.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+/// The descriptions of an application's commands, given to
+/// [`Repl::with_commands`](super::Repl::with_commands) for the `help`
+/// built-in.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::{CommandInfo, Registry};
+///
+/// let commands = Registry::new()
+///     .command(CommandInfo::new("deploy", "Deploys a service").with_usage("deploy <service>"))
+///     .command(CommandInfo::new("logs", "Shows the logs of a service"));
+/// assert_eq!(commands.get("logs").map(CommandInfo::summary), Some("Shows the logs of a service"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    commands: Vec<CommandInfo>,
+}
+
+impl Registry {
+    /// Creates a registry with no commands.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the description of a command, replacing an earlier one with
+    /// the same name.
+    pub fn command(mut self, info: CommandInfo) -> Self {
+        match self.commands.iter_mut().find(|c| c.name == info.name) {
+            Some(known) => *known = info,
+            None => self.commands.push(info),
+        }
+        self
+    }
+
+    /// Returns the description of the command `name`.
+    pub fn get(&self, name: &str) -> Option<&CommandInfo> {
+        self.commands.iter().find(|c| c.name == name)
+    }
+
+    /// Returns the descriptions of the commands, in the order they were
+    /// added.
+    pub fn iter(&self) -> impl Iterator<Item = &CommandInfo> {
+        self.commands.iter()
+    }
+
+    /// Returns `true` if no commands are described.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+impl<H: CommandHandler> Repl<H> {
+    /// Runs `help [command] [--run-example <n>]`.
+    pub(super) fn help_command(&mut self, args: &str) {
+        let (name, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        if name.is_empty() {
+            self.list_commands();
+            return;
+        }
+        let Some(info) = self.commands.get(name) else {
+            let known: Vec<&str> = self.commands.iter().map(CommandInfo::name).collect();
+            let message = match closest(name, &known) {
+                Some(guess) => text("help.unknown_command.guess", &[("name", &name), ("guess", &guess)]),
+                None => text("help.unknown_command", &[("name", &name)]),
+            };
+            self.print_error(CodedError::new("help.unknown_command", message).detail("name", name));
+            return;
+        };
+        match rest.trim().split_once(char::is_whitespace) {
+            None if rest.trim().is_empty() => {
+                let lines = describe(info);
+                for line in lines {
+                    let _ = self.output.println(&line);
+                }
+            }
+            Some(("--run-example", number)) => {
+                let number = number.trim();
+                let Some(example) = number.parse::<usize>().ok().and_then(|n| info.examples.get(n.checked_sub(1)?)) else {
+                    self.print_error(
+                        CodedError::new("help.no_example", text("help.no_example", &[("name", &name), ("number", &number)]))
+                            .detail("name", name)
+                            .detail("number", number),
+                    );
+                    return;
+                };
+                let command = example.command.clone();
+                let running = text("help.running_example", &[("command", &command)]);
+                let _ = self.output.println(&theme::current().paint(Tone::Muted, &running));
+                let was_on = self.dry_run.replace(true);
+                self.run_command(&command);
+                self.dry_run.set(was_on);
+            }
+            _ => {
+                let _ = self.output.eprintln(&text("usage", &[("usage", &USAGE)]));
+            }
+        }
+    }
+
+    /// Prints the described commands with their summaries.
+    fn list_commands(&self) {
+        if self.commands.is_empty() {
+            let _ = self.output.println(&text("help.none", &[]));
+            return;
+        }
+        let width = self.commands.iter().map(|c| display_width(&c.name)).max().unwrap_or(0);
+        for info in self.commands.iter() {
+            let _ = self.output.println(&format!("  {}  {}", pad(&info.name, width, Align::Left), info.summary));
+        }
+    }
+}
+
+/// Returns the lines `help <command>` prints for `info`.
+fn describe(info: &CommandInfo) -> Vec<String> {
+    let mut lines = vec![format!("{} - {}", info.name, info.summary)];
+    if let Some(usage) = &info.usage {
+        lines.push(text("usage", &[("usage", usage)]));
+    }
+    if let Some(description) = &info.description {
+        lines.push(String::new());
+        lines.extend(description.lines().map(str::to_string));
+    }
+    if !info.examples.is_empty() {
+        lines.push(String::new());
+        lines.push(text("help.examples", &[]));
+        for (n, example) in info.examples.iter().enumerate() {
+            lines.push(format!("  {}. {}", n + 1, example.command));
+            if !example.description.is_empty() {
+                lines.push(theme::current().paint(Tone::Muted, &format!("     {}", example.description)));
+            }
+        }
+        lines.push(text("help.run_example", &[("name", &info.name)]));
+    }
+    lines
+}
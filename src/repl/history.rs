@@ -0,0 +1,418 @@
+//! Command history that is decoded only when it is used.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use rustyline::history::{History, SearchDirection, SearchResult};
+use rustyline::{Config, Editor, Result};
+
+/// The line editor used by the REPLs.
+pub(crate) type LineEditor = Editor<(), LazyHistory>;
+
+/// Creates a line editor with `config` and an empty [`LazyHistory`].
+pub(crate) fn editor(config: rustyline::Config) -> Result<LineEditor> {
+    let history = LazyHistory::with_config(&config);
+    Editor::with_history(config, history)
+}
+
+// Files written by rustyline start with this line and escape newlines and
+// backslashes in entries.
+const FILE_VERSION_V2: &str = "#V2";
+
+enum Entry {
+    /// An entry read from a history file, still as it is written there.
+    File { start: usize, end: usize, escaped: bool },
+    /// An entry added during this session, with how its command ran once
+    /// it has, for the history picker.
+    Added {
+        line: String,
+        #[cfg(feature = "menu")]
+        ran: Option<Ran>,
+    },
+}
+
+/// When the command of a history entry was entered and how it ended, known
+/// for the entries added during the session.
+#[cfg(feature = "menu")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Ran {
+    pub(crate) at: std::time::SystemTime,
+    /// 1 if the command wrote error output, as `{status}` shows.
+    pub(crate) status: i32,
+}
+
+/// A history that keeps the text of its file in one buffer.
+///
+/// Loading reads the file in a single pass and only remembers where each
+/// entry is; entries are decoded when they are navigated to or searched,
+/// and most need no copy at all. This keeps startup fast and memory low
+/// with histories of hundreds of thousands of entries. The file format is
+/// the one rustyline uses, so existing history files keep working.
+///
+/// The REPLs use it already; it can also be given to a rustyline `Editor`
+/// with `Editor::with_history`.
+pub struct LazyHistory {
+    text: String,
+    entries: VecDeque<Entry>,
+    // Entries added since the history was last loaded or saved.
+    new_entries: usize,
+    max_len: usize,
+    ignore_space: bool,
+    ignore_dups: bool,
+}
+
+impl LazyHistory {
+    /// Creates an empty history with the size and filters of `config`.
+    pub fn with_config(config: &Config) -> Self {
+        Self {
+            text: String::new(),
+            entries: VecDeque::new(),
+            new_entries: 0,
+            max_len: config.max_history_size(),
+            ignore_space: config.history_ignore_space(),
+            ignore_dups: config.history_duplicates() == rustyline::config::HistoryDuplicates::IgnoreConsecutive,
+        }
+    }
+
+    /// Records how the command of the last entry ran, if it was added during
+    /// the session and nothing is recorded for it yet, so a command that was
+    /// not added leaves the entry before it alone.
+    #[cfg(feature = "menu")]
+    pub(crate) fn ran(&mut self, ran: Ran) {
+        if let Some(Entry::Added { ran: last @ None, .. }) = self.entries.back_mut() {
+            *last = Some(ran);
+        }
+    }
+
+    /// Returns the entries, newest first, with how their commands ran when
+    /// that is known.
+    #[cfg(feature = "menu")]
+    pub(crate) fn newest_first(&self) -> impl Iterator<Item = (Cow<'_, str>, Option<Ran>)> {
+        self.entries.iter().rev().map(|entry| {
+            let ran = match entry {
+                Entry::Added { ran, .. } => *ran,
+                Entry::File { .. } => None,
+            };
+            (self.entry(entry), ran)
+        })
+    }
+
+    fn entry<'a>(&'a self, entry: &'a Entry) -> Cow<'a, str> {
+        match entry {
+            Entry::File { start, end, escaped } => {
+                let raw = &self.text[*start..*end];
+                if *escaped { unescape(raw) } else { Cow::Borrowed(raw) }
+            }
+            Entry::Added { line, .. } => Cow::Borrowed(line),
+        }
+    }
+
+    fn ignore(&self, line: &str) -> bool {
+        if self.max_len == 0 || line.is_empty() {
+            return true;
+        }
+        if self.ignore_space && line.starts_with(char::is_whitespace) {
+            return true;
+        }
+        self.ignore_dups && self.entries.back().is_some_and(|last| self.entry(last) == line)
+    }
+
+    fn insert(&mut self, entry: Entry) {
+        if self.entries.len() >= self.max_len {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    fn added(&mut self, line: String) {
+        self.insert(Entry::Added {
+            line,
+            #[cfg(feature = "menu")]
+            ran: None,
+        });
+        self.new_entries = (self.new_entries + 1).min(self.entries.len());
+    }
+
+    /// Drops the part of the file text no remaining entry points into.
+    fn compact(&mut self) {
+        let first = self.entries.iter().find_map(|entry| match entry {
+            Entry::File { start, .. } => Some(*start),
+            Entry::Added { .. } => None,
+        });
+        let first = first.unwrap_or(self.text.len());
+        if first == 0 {
+            return;
+        }
+        self.text.drain(..first);
+        for entry in &mut self.entries {
+            if let Entry::File { start, end, .. } = entry {
+                *start -= first;
+                *end -= first;
+            }
+        }
+    }
+
+    fn search_with(
+        &self,
+        start: usize,
+        dir: SearchDirection,
+        test: impl Fn(&str) -> Option<usize>,
+    ) -> Option<SearchResult<'_>> {
+        if start >= self.entries.len() {
+            return None;
+        }
+        let check = |idx: usize| {
+            let entry = self.entry(&self.entries[idx]);
+            test(&entry).map(|pos| SearchResult { entry, idx, pos })
+        };
+        match dir {
+            SearchDirection::Reverse => (0..=start).rev().find_map(check),
+            SearchDirection::Forward => (start..self.entries.len()).find_map(check),
+        }
+    }
+
+    fn write(&self, file: File, from: usize, header: bool) -> Result<()> {
+        let mut out = BufWriter::new(file);
+        if header {
+            writeln!(out, "{FILE_VERSION_V2}")?;
+        }
+        for entry in self.entries.iter().skip(from) {
+            match entry {
+                Entry::File { start, end, escaped: true } => out.write_all(&self.text.as_bytes()[*start..*end])?,
+                entry => out.write_all(escape(&self.entry(entry)).as_bytes())?,
+            }
+            out.write_all(b"\n")?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+impl History for LazyHistory {
+    fn get(&self, index: usize, _dir: SearchDirection) -> Result<Option<SearchResult<'_>>> {
+        Ok(self.entries.get(index).map(|entry| SearchResult { entry: self.entry(entry), idx: index, pos: 0 }))
+    }
+
+    fn add(&mut self, line: &str) -> Result<bool> {
+        if self.ignore(line) {
+            return Ok(false);
+        }
+        self.added(line.to_string());
+        Ok(true)
+    }
+
+    fn add_owned(&mut self, line: String) -> Result<bool> {
+        if self.ignore(&line) {
+            return Ok(false);
+        }
+        self.added(line);
+        Ok(true)
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn set_max_len(&mut self, len: usize) -> Result<()> {
+        self.max_len = len;
+        if self.entries.len() > len {
+            self.entries.drain(..self.entries.len() - len);
+            self.compact();
+        }
+        self.new_entries = self.new_entries.min(len);
+        Ok(())
+    }
+
+    fn ignore_dups(&mut self, yes: bool) -> Result<()> {
+        self.ignore_dups = yes;
+        Ok(())
+    }
+
+    fn ignore_space(&mut self, yes: bool) {
+        self.ignore_space = yes;
+    }
+
+    fn save(&mut self, path: &Path) -> Result<()> {
+        if self.entries.is_empty() || self.new_entries == 0 {
+            return Ok(());
+        }
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        self.write(options.open(path)?, 0, true)?;
+        self.new_entries = 0;
+        Ok(())
+    }
+
+    fn append(&mut self, path: &Path) -> Result<()> {
+        if self.entries.is_empty() || self.new_entries == 0 {
+            return Ok(());
+        }
+        if !path.exists() {
+            return self.save(path);
+        }
+        let file = OpenOptions::new().append(true).open(path)?;
+        self.write(file, self.entries.len() - self.new_entries, false)?;
+        self.new_entries = 0;
+        Ok(())
+    }
+
+    fn load(&mut self, path: &Path) -> Result<()> {
+        let contents = fs::read_to_string(path)?;
+        let offset = self.text.len();
+        if offset == 0 {
+            self.text = contents;
+        } else {
+            self.text.push_str(&contents);
+        }
+
+        let mut pos = offset;
+        let mut escaped = false;
+        let mut first = true;
+        while pos < self.text.len() {
+            let line_end = self.text[pos..].find('\n').map_or(self.text.len(), |i| pos + i);
+            let (start, mut end) = (pos, line_end);
+            pos = line_end + 1;
+            if self.text[start..end].ends_with('\r') {
+                end -= 1;
+            }
+            if std::mem::take(&mut first) && &self.text[start..end] == FILE_VERSION_V2 {
+                escaped = true;
+                continue;
+            }
+            if start == end {
+                continue;
+            }
+            let entry = Entry::File { start, end, escaped };
+            if !self.ignore(&self.entry(&entry)) {
+                self.insert(entry);
+            }
+        }
+        self.new_entries = 0;
+        self.compact();
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.text.clear();
+        self.entries.clear();
+        self.new_entries = 0;
+        Ok(())
+    }
+
+    fn search(&self, term: &str, start: usize, dir: SearchDirection) -> Result<Option<SearchResult<'_>>> {
+        if term.is_empty() {
+            return Ok(None);
+        }
+        Ok(self.search_with(start, dir, |entry| entry.find(term)))
+    }
+
+    fn starts_with(&self, term: &str, start: usize, dir: SearchDirection) -> Result<Option<SearchResult<'_>>> {
+        if term.is_empty() {
+            return Ok(None);
+        }
+        Ok(self.search_with(start, dir, |entry| entry.starts_with(term).then_some(term.len())))
+    }
+}
+
+/// Appends entries to a history file from a background thread, batching
+/// those that arrive within an interval into one write.
+pub(super) struct Autosave {
+    sender: Option<mpsc::Sender<String>>,
+    flusher: Option<JoinHandle<()>>,
+}
+
+impl Autosave {
+    /// Starts appending to `path`, at most once per `interval`.
+    pub(super) fn start(path: PathBuf, interval: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel::<String>();
+        let flusher = thread::spawn(move || {
+            while let Ok(first) = receiver.recv() {
+                let mut batch = vec![first];
+                let deadline = Instant::now() + interval;
+                while let Ok(line) = receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    batch.push(line);
+                }
+                let _ = append_lines(&path, &batch);
+            }
+        });
+        Self { sender: Some(sender), flusher: Some(flusher) }
+    }
+
+    /// Queues `line` to be appended.
+    pub(super) fn push(&self, line: &str) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(line.to_string());
+        }
+    }
+}
+
+impl Drop for Autosave {
+    /// Writes what is still queued before returning.
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(flusher) = self.flusher.take() {
+            let _ = flusher.join();
+        }
+    }
+}
+
+/// Appends `lines` to the history file at `path` and syncs it once.
+fn append_lines(path: &Path, lines: &[String]) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.append(true).create(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let file = options.open(path)?;
+    let empty = file.metadata()?.len() == 0;
+    let mut out = BufWriter::new(&file);
+    if empty {
+        writeln!(out, "{FILE_VERSION_V2}")?;
+    }
+    for line in lines {
+        writeln!(out, "{}", escape(line))?;
+    }
+    out.flush()?;
+    drop(out);
+    file.sync_data()
+}
+
+/// Escapes newlines and backslashes the way rustyline writes entries.
+fn escape(line: &str) -> Cow<'_, str> {
+    if !line.contains(['\\', '\n']) {
+        return Cow::Borrowed(line);
+    }
+    Cow::Owned(line.replace('\\', r"\\").replace('\n', r"\n"))
+}
+
+/// Reverses [`escape`]. A line with an unknown escape is kept as written.
+fn unescape(raw: &str) -> Cow<'_, str> {
+    if !raw.contains('\\') {
+        return Cow::Borrowed(raw);
+    }
+    let mut line = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            line.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => line.push('\n'),
+            Some('\\') => line.push('\\'),
+            _ => return Cow::Borrowed(raw),
+        }
+    }
+    Cow::Owned(line)
+}
@@ -0,0 +1,42 @@
+//! Searching the history with a fuzzy picker, bound to Ctrl+R.
+
+use std::collections::HashSet;
+
+use rustyline::{Cmd, ConditionalEventHandler, Event, EventContext, RepeatCount};
+
+use super::history::LazyHistory;
+use super::placeholders::local_time;
+use super::reprompt::{self, Edited};
+use crate::locale::text;
+use crate::prompt::FuzzySelect;
+
+/// Opens the history picker, bound to Ctrl+R by
+/// [`Repl::with_history_search`](super::Repl::with_history_search).
+pub(super) struct SearchKey {
+    pub(super) edited: Edited,
+}
+
+impl ConditionalEventHandler for SearchKey {
+    fn handle(&self, _: &Event, _: RepeatCount, _: bool, ctx: &EventContext) -> Option<Cmd> {
+        Some(reprompt::search(&self.edited, ctx))
+    }
+}
+
+/// Lets the user pick an entry of `history`, newest first and each line
+/// once, searching for `query` to begin with. Returns `None` if the picker
+/// was dismissed or there is nothing to pick.
+pub(super) fn pick(history: &LazyHistory, query: &str) -> Option<String> {
+    let mut seen = HashSet::new();
+    let mut lines = Vec::new();
+    let mut details = Vec::new();
+    for (line, ran) in history.newest_first() {
+        if !seen.insert(line.clone()) {
+            continue;
+        }
+        lines.push(line.into_owned());
+        details.push(ran.map_or_else(String::new, |ran| format!("{} {}", local_time(ran.at), if ran.status == 0 { "✓" } else { "✗" })));
+    }
+    let picker = FuzzySelect::new(text("history.search", &[]), &lines).details(&details).query(query.trim()).threshold(0).keep_answer(false);
+    let index = picker.interact().ok()?;
+    lines.into_iter().nth(index)
+}
@@ -0,0 +1,436 @@
+//! Commands running in the background while the prompt stays available.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use super::{Clock, ReplPrinter, Rng, WorkingDir};
+use crate::locale::text;
+use crate::output::width::{display_width, pad, Align};
+
+/// The result of a background job: its output, or why it failed.
+pub type JobResult = std::result::Result<String, String>;
+
+/// Work that a [`CommandHandler`](super::CommandHandler) hands back for a
+/// command ending in `&`, run on its own thread.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use mycli::repl::Job;
+///
+/// let job = Job::new(|ctx| {
+///     for _ in 0..10 {
+///         if ctx.is_cancelled() {
+///             return Err("stopped".to_string());
+///         }
+///         std::thread::sleep(Duration::from_millis(10));
+///     }
+///     Ok("copied 10 files".to_string())
+/// });
+/// ```
+pub struct Job {
+    work: Work,
+}
+
+enum Work {
+    Once(Box<dyn FnOnce(&JobContext) -> JobResult + Send>),
+    Repeatable(Box<dyn Fn(&JobContext) -> JobResult + Send>),
+}
+
+impl fmt::Debug for Job {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Job").finish_non_exhaustive()
+    }
+}
+
+impl Job {
+    /// Creates a job running `work`.
+    ///
+    /// What `work` returns is printed when the job finishes, or when it is
+    /// brought to the foreground with `fg`.
+    pub fn new(work: impl FnOnce(&JobContext) -> JobResult + Send + 'static) -> Self {
+        Self { work: Work::Once(Box::new(work)) }
+    }
+
+    /// Creates a job running `work`, which can run again, as commands
+    /// scheduled with `every` do; see [`Builtin::Schedule`].
+    ///
+    /// [`Builtin::Schedule`]: super::Builtin::Schedule
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::repl::Job;
+    ///
+    /// let job = Job::repeatable(|ctx| Ok(format!("{} connections", 42)));
+    /// assert!(job.is_repeatable());
+    /// ```
+    pub fn repeatable(work: impl Fn(&JobContext) -> JobResult + Send + 'static) -> Self {
+        Self { work: Work::Repeatable(Box::new(work)) }
+    }
+
+    /// Returns `true` if the job was created with
+    /// [`repeatable`](Self::repeatable).
+    pub fn is_repeatable(&self) -> bool {
+        matches!(self.work, Work::Repeatable(_))
+    }
+
+    pub(super) fn run(self, context: &JobContext) -> JobResult {
+        match self.work {
+            Work::Once(work) => work(context),
+            Work::Repeatable(work) => work(context),
+        }
+    }
+
+    /// Runs the job and keeps it for another run, or returns `None` if it
+    /// is not repeatable.
+    pub(super) fn run_again(&self, context: &JobContext) -> Option<JobResult> {
+        match &self.work {
+            Work::Repeatable(work) => Some(work(context)),
+            Work::Once(_) => None,
+        }
+    }
+}
+
+/// Runs `job`, turning a panic into an error.
+fn run_caught(job: Job, context: &JobContext) -> JobResult {
+    panic::catch_unwind(AssertUnwindSafe(|| job.run(context))).unwrap_or_else(|_| Err("the job panicked".to_string()))
+}
+
+/// What a running [`Job`] can see of the REPL.
+#[derive(Debug)]
+pub struct JobContext {
+    id: usize,
+    cancelled: Arc<AtomicBool>,
+    printer: ReplPrinter,
+    label: Option<String>,
+    env: JobEnv,
+}
+
+/// What a job sees of the REPL that started it.
+#[derive(Debug, Clone)]
+pub(super) struct JobEnv {
+    pub(super) dry_run: bool,
+    pub(super) clock: Clock,
+    pub(super) rng: Rng,
+    pub(super) working_dir: WorkingDir,
+}
+
+impl JobContext {
+    /// Returns the number the job is listed under.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns `true` once the job was killed with `kill <id>` or the REPL
+    /// exited. Jobs should check it regularly and return early.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if the command was started in dry-run mode, and
+    /// should only describe what it would do. See
+    /// [`DryRun`](super::DryRun).
+    pub fn is_dry_run(&self) -> bool {
+        self.env.dry_run
+    }
+
+    /// Returns the REPL's clock, for timestamps that replay the same way
+    /// in tests.
+    pub fn clock(&self) -> &Clock {
+        &self.env.clock
+    }
+
+    /// Returns the REPL's random number generator, for IDs that replay
+    /// the same way in tests.
+    pub fn rng(&self) -> &Rng {
+        &self.env.rng
+    }
+
+    /// Returns the REPL's working directory, to resolve the paths the
+    /// command was given.
+    pub fn working_dir(&self) -> &WorkingDir {
+        &self.env.working_dir
+    }
+
+    /// Returns a printer for progress lines, which appear above the prompt.
+    pub fn printer(&self) -> &ReplPrinter {
+        &self.printer
+    }
+
+    /// Waits for `child` to exit, killing it if the job is cancelled
+    /// first, such as when its command [times out](super::Repl::with_timeout).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::process::Command;
+    /// use mycli::repl::Job;
+    ///
+    /// let job = Job::new(|ctx| {
+    ///     let mut child = Command::new("pg_dump").arg("app").spawn().map_err(|e| e.to_string())?;
+    ///     let status = ctx.wait(&mut child).map_err(|e| e.to_string())?;
+    ///     Ok(format!("pg_dump exited with {status}"))
+    /// });
+    /// ```
+    pub fn wait(&self, child: &mut std::process::Child) -> std::io::Result<std::process::ExitStatus> {
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(status);
+            }
+            if self.is_cancelled() {
+                child.kill()?;
+                return child.wait();
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Prints a progress line, labeled with the job's command when it runs
+    /// alongside others under `par`.
+    pub fn println(&self, line: &str) -> std::io::Result<()> {
+        match &self.label {
+            Some(label) => self.printer.println(&format!("{label} | {line}")),
+            None => self.printer.println(line),
+        }
+    }
+}
+
+/// How a [`CommandHandler`](super::CommandHandler) wants a command run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Execution {
+    /// The REPL waits for the command before showing the prompt again.
+    #[default]
+    Blocking,
+    /// The command runs on a worker thread, using the [`Job`] returned by
+    /// [`CommandHandler::background`](super::CommandHandler::background),
+    /// and the prompt comes back at once. Its output is printed above the
+    /// prompt when it finishes.
+    NonBlocking,
+    /// The REPL waits for the command, but runs it on a worker thread
+    /// using the [`Job`] returned by
+    /// [`CommandHandler::background`](super::CommandHandler::background),
+    /// so that the REPL's [timeout](super::Repl::with_timeout) can cancel
+    /// it.
+    Cancellable,
+}
+
+type Task = Box<dyn FnOnce() + Send>;
+
+/// A fixed number of threads running jobs in the order they were queued.
+struct Pool {
+    sender: mpsc::Sender<Task>,
+}
+
+impl Pool {
+    fn new(workers: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Task>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..workers.max(1) {
+            let receiver = receiver.clone();
+            thread::spawn(move || loop {
+                let task = receiver.lock().unwrap_or_else(|e| e.into_inner()).recv();
+                match task {
+                    Ok(task) => task(),
+                    Err(_) => break,
+                }
+            });
+        }
+        Self { sender }
+    }
+}
+
+/// How far a job has got, shared between it and the REPL.
+#[derive(Default)]
+struct Progress {
+    done: bool,
+    // Set while `fg` waits for the job, which then prints the result itself.
+    foreground: bool,
+    result: Option<JobResult>,
+}
+
+struct Entry {
+    id: usize,
+    command: String,
+    cancelled: Arc<AtomicBool>,
+    progress: Arc<(Mutex<Progress>, Condvar)>,
+}
+
+impl Entry {
+    fn is_finished(&self) -> bool {
+        lock(&self.progress.0).done
+    }
+}
+
+/// The background jobs of a REPL.
+#[derive(Default)]
+pub(super) struct Jobs {
+    entries: Vec<Entry>,
+    next_id: usize,
+    pool: Option<Pool>,
+}
+
+impl Jobs {
+    /// Runs later jobs on `workers` threads instead of one new thread each.
+    pub(super) fn use_pool(&mut self, workers: usize) {
+        self.pool = Some(Pool::new(workers));
+    }
+
+    /// Starts `job` for `command` and returns its number.
+    pub(super) fn spawn(&mut self, command: &str, job: Job, printer: ReplPrinter, env: JobEnv) -> usize {
+        self.reap();
+        self.next_id += 1;
+        let id = self.next_id;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let progress = Arc::new((Mutex::new(Progress::default()), Condvar::new()));
+        let context = JobContext { id, cancelled: cancelled.clone(), printer, label: None, env };
+        let label = command.to_string();
+        let shared = progress.clone();
+        let task = move || {
+            let result = run_caught(job, &context);
+            let mut state = lock(&shared.0);
+            state.done = true;
+            if state.foreground {
+                state.result = Some(result);
+                shared.1.notify_all();
+                return;
+            }
+            drop(state);
+            for line in report(id, &label, context.is_cancelled(), &result) {
+                let _ = context.printer.println(&line);
+            }
+        };
+        let task: Task = Box::new(task);
+        let unsent = match &self.pool {
+            Some(pool) => pool.sender.send(task).err().map(|err| err.0),
+            None => Some(task),
+        };
+        if let Some(task) = unsent {
+            thread::spawn(task);
+        }
+        self.entries.push(Entry { id, command: command.to_string(), cancelled, progress });
+        id
+    }
+
+    /// Returns the number and command of each job still running.
+    pub(super) fn list(&mut self) -> Vec<(usize, String)> {
+        self.reap();
+        self.entries.iter().map(|entry| (entry.id, entry.command.clone())).collect()
+    }
+
+    /// Waits for job `id` and returns the lines reporting how it ended, or
+    /// `None` if there is no such job.
+    pub(super) fn wait(&mut self, id: usize) -> Option<Vec<String>> {
+        let index = self.entries.iter().position(|entry| entry.id == id)?;
+        let entry = self.entries.remove(index);
+        let (state, finished) = &*entry.progress;
+        let mut state = lock(state);
+        if state.done {
+            // The job finished before `fg` and was already reported.
+            return Some(Vec::new());
+        }
+        state.foreground = true;
+        while !state.done {
+            state = finished.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+        let cancelled = entry.cancelled.load(Ordering::Relaxed);
+        Some(state.result.take().map_or_else(Vec::new, |result| report(id, &entry.command, cancelled, &result)))
+    }
+
+    /// Asks job `id` to stop. Returns its command, or `None` if there is no
+    /// such job.
+    pub(super) fn kill(&mut self, id: usize) -> Option<String> {
+        self.reap();
+        let entry = self.entries.iter().find(|entry| entry.id == id)?;
+        entry.cancelled.store(true, Ordering::Relaxed);
+        Some(entry.command.clone())
+    }
+
+    /// Asks every job to stop, without waiting for them.
+    pub(super) fn cancel_all(&mut self) {
+        for entry in self.entries.drain(..) {
+            entry.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Forgets the jobs that have finished; they reported themselves.
+    fn reap(&mut self) {
+        self.entries.retain(|entry| !entry.is_finished());
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Runs `job` on its own thread and waits for it for up to `timeout`. When
+/// the time is up, the job is cancelled and left to finish on its own, and
+/// `None` is returned.
+pub(super) fn run_with_timeout(job: Job, timeout: Duration, printer: ReplPrinter, env: JobEnv) -> Option<JobResult> {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let context = JobContext { id: 0, cancelled: cancelled.clone(), printer, label: None, env };
+    let (sender, result) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(run_caught(job, &context));
+    });
+    let result = result.recv_timeout(timeout).ok();
+    if result.is_none() {
+        cancelled.store(true, Ordering::Relaxed);
+    }
+    result
+}
+
+/// Runs `jobs` at the same time on up to `workers` threads and waits for
+/// all of them, printing each line of output labeled with its command as
+/// soon as it is available. A job that panics fails. Returns how many
+/// failed.
+pub(super) fn run_parallel(jobs: Vec<(String, Job)>, workers: usize, printer: &ReplPrinter, env: &JobEnv) -> usize {
+    let width = jobs.iter().map(|(command, _)| display_width(command)).max().unwrap_or(0);
+    let workers = workers.clamp(1, jobs.len().max(1));
+    let queue = Mutex::new(jobs.into_iter().enumerate());
+    let failed = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let next = lock(&queue).next();
+                let Some((index, (command, job))) = next else { break };
+                let label = pad(&command, width, Align::Left).into_owned();
+                let context = JobContext {
+                    id: index + 1,
+                    cancelled: Arc::new(AtomicBool::new(false)),
+                    printer: printer.clone(),
+                    label: Some(label.clone()),
+                    env: env.clone(),
+                };
+                let lines = match run_caught(job, &context) {
+                    Ok(output) => output.lines().map(|line| format!("{label} | {line}")).collect(),
+                    Err(err) => {
+                        failed.fetch_add(1, Ordering::Relaxed);
+                        vec![format!("{label} | {}", text("error", &[("message", &err)]))]
+                    }
+                };
+                for line in lines {
+                    let _ = printer.println(&line);
+                }
+            });
+        }
+    });
+    failed.into_inner()
+}
+
+/// The lines announcing that job `id` ended with `result`.
+fn report(id: usize, command: &str, cancelled: bool, result: &JobResult) -> Vec<String> {
+    let (status, output) = match result {
+        Ok(output) => (text("job.done", &[]), output.as_str()),
+        Err(err) if cancelled => (text("job.killed", &[]), err.as_str()),
+        Err(err) => (text("job.failed", &[]), err.as_str()),
+    };
+    let mut lines = vec![format!("[{id}] {status:<8}{command}")];
+    lines.extend(output.lines().map(str::to_string));
+    lines
+}
@@ -0,0 +1,98 @@
+//! Key binding names used by configuration files.
+
+use rustyline::{At, Cmd, KeyCode, KeyEvent, Modifiers, Movement, Word};
+
+/// Parses a key such as `ctrl-t`, `alt-f`, `f5` or `shift-tab`.
+pub(crate) fn parse_key(key: &str) -> Result<KeyEvent, String> {
+    let lower = key.trim().to_ascii_lowercase();
+    let mut modifiers = Modifiers::NONE;
+    let mut rest = lower.as_str();
+    loop {
+        if let Some(r) = rest.strip_prefix("ctrl-").or_else(|| rest.strip_prefix("c-")) {
+            modifiers |= Modifiers::CTRL;
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("alt-").or_else(|| rest.strip_prefix("m-")) {
+            modifiers |= Modifiers::ALT;
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("shift-") {
+            modifiers |= Modifiers::SHIFT;
+            rest = r;
+        } else {
+            break;
+        }
+    }
+
+    let code = match rest {
+        "enter" | "return" => KeyCode::Enter,
+        "tab" if modifiers.contains(Modifiers::SHIFT) => {
+            modifiers.remove(Modifiers::SHIFT);
+            KeyCode::BackTab
+        }
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "esc" | "escape" => KeyCode::Esc,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "space" => KeyCode::Char(' '),
+        f if f.len() > 1 && f.starts_with('f') && f[1..].parse::<u8>().is_ok_and(|n| (1..=24).contains(&n)) => {
+            KeyCode::F(f[1..].parse().unwrap_or(1))
+        }
+        c if c.chars().count() == 1 => {
+            // Keep the case the user wrote, so `alt-F` differs from `alt-f`.
+            let c = key.trim().chars().last().unwrap_or(' ');
+            return Ok(KeyEvent::new(c, modifiers));
+        }
+        _ => return Err(format!("unknown key `{}`", key.trim())),
+    };
+    Ok(KeyEvent(code, modifiers))
+}
+
+/// Parses an editing action such as `clear-screen` or `insert:git status`.
+pub(crate) fn parse_action(action: &str) -> Result<Cmd, String> {
+    if let Some(text) = action.strip_prefix("insert:") {
+        return Ok(Cmd::Insert(1, text.to_string()));
+    }
+    let cmd = match action.trim() {
+        "accept-line" => Cmd::AcceptLine,
+        "abort" => Cmd::Abort,
+        "beginning-of-line" => Cmd::Move(Movement::BeginningOfLine),
+        "end-of-line" => Cmd::Move(Movement::EndOfLine),
+        "backward-char" => Cmd::Move(Movement::BackwardChar(1)),
+        "forward-char" => Cmd::Move(Movement::ForwardChar(1)),
+        "backward-word" => Cmd::Move(Movement::BackwardWord(1, Word::Emacs)),
+        "forward-word" => Cmd::Move(Movement::ForwardWord(1, At::AfterEnd, Word::Emacs)),
+        "kill-line" => Cmd::Kill(Movement::EndOfLine),
+        "unix-line-discard" => Cmd::Kill(Movement::BeginningOfLine),
+        "kill-whole-line" => Cmd::Kill(Movement::WholeLine),
+        "backward-kill-word" => Cmd::Kill(Movement::BackwardWord(1, Word::Emacs)),
+        "kill-word" => Cmd::Kill(Movement::ForwardWord(1, At::AfterEnd, Word::Emacs)),
+        "previous-history" => Cmd::PreviousHistory,
+        "next-history" => Cmd::NextHistory,
+        "beginning-of-history" => Cmd::BeginningOfHistory,
+        "end-of-history" => Cmd::EndOfHistory,
+        "history-search-backward" => Cmd::HistorySearchBackward,
+        "history-search-forward" => Cmd::HistorySearchForward,
+        "reverse-search-history" => Cmd::ReverseSearchHistory,
+        "forward-search-history" => Cmd::ForwardSearchHistory,
+        "complete" => Cmd::Complete,
+        "complete-hint" => Cmd::CompleteHint,
+        "clear-screen" => Cmd::ClearScreen,
+        "transpose-chars" => Cmd::TransposeChars,
+        "upcase-word" => Cmd::UpcaseWord,
+        "downcase-word" => Cmd::DowncaseWord,
+        "capitalize-word" => Cmd::CapitalizeWord,
+        "undo" => Cmd::Undo(1),
+        "yank" => Cmd::Yank(1, rustyline::Anchor::Before),
+        "noop" => Cmd::Noop,
+        other => return Err(format!("unknown action `{other}`")),
+    };
+    Ok(cmd)
+}
@@ -0,0 +1,2546 @@
+//! A flexible REPL (Read-Eval-Print Loop) framework for building interactive CLI applications.
+//!
+//! # Quick Start
+//!
+//! ```no_run
+//! use mycli::repl::{Repl, CommandHandler, Context};
+//!
+//! struct Calculator;
+//!
+//! impl CommandHandler for Calculator {
+//!     fn handle(&mut self, command: &str, _: &Context) -> bool {
+//!         match command {
+//!             "quit" | "exit" => false,
+//!             cmd => {
+//!                 // Process command here
+//!                 println!("Processing: {}", cmd);
+//!                 true
+//!             }
+//!         }
+//!     }
+//! }
+//!
+//! fn main() -> mycli::repl::Result<()> {
+//!     let mut repl = Repl::new("calc> ", Calculator)?;
+//!     let _ = repl.load_history(".calc_history");
+//!     repl.run()?;
+//!     let _ = repl.save_history(".calc_history");
+//!     Ok(())
+//! }
+//! ```
+//!
+//! # Tracing
+//!
+//! With the `tracing` feature, every command runs inside a `command` span
+//! from the [`tracing`](https://docs.rs/tracing) crate, with the command's
+//! name, its arguments with secrets masked by the current
+//! [redactor](crate::redact), its duration in milliseconds and its
+//! outcome: `ok`, `exit`, `builtin`, `background`, `panicked` with
+//! [`Repl::with_catch_panics`], `refused` with
+//! [`Repl::with_allowed_commands`], `timeout` or `failed` with
+//! [`Repl::with_timeout`] or, in the async REPL, `interrupted` or `shutdown`. The framework also emits debug events, for instance when
+//! history is loaded.
+//! Install a subscriber to collect them.
+
+#[cfg(feature = "rustyline")]
+mod abbreviations;
+#[cfg(feature = "async")]
+mod async_repl;
+mod bench;
+mod builtins;
+mod clear;
+mod clock;
+mod context;
+mod dry_run;
+mod errors;
+#[cfg(feature = "clipboard")]
+mod clipboard;
+#[cfg(feature = "git")]
+mod git;
+mod help;
+#[cfg(feature = "rustyline")]
+mod history;
+#[cfg(all(feature = "rustyline", feature = "menu"))]
+mod history_search;
+mod jobs;
+mod osc;
+#[cfg(feature = "rustyline")]
+mod pairs;
+mod panics;
+mod placeholders;
+#[cfg(all(feature = "config", feature = "rustyline"))]
+pub(crate) mod keys;
+mod printer;
+mod reader;
+mod reference;
+mod repeat;
+#[cfg(feature = "rustyline")]
+mod reprompt;
+mod rewrite;
+mod rng;
+#[cfg(feature = "config")]
+mod reload;
+mod schedule;
+#[cfg(feature = "script")]
+mod script;
+#[cfg(feature = "config")]
+mod session;
+#[cfg(feature = "signing")]
+pub(crate) mod signing;
+#[cfg(feature = "rustyline")]
+mod snippets;
+mod status;
+mod summary;
+mod tokens;
+mod trace;
+#[cfg(feature = "update")]
+mod update;
+mod workdir;
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead};
+#[cfg(feature = "rustyline")]
+use std::path::Path;
+#[cfg(any(feature = "config", feature = "rustyline"))]
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::locale::text;
+use crate::output::theme::{self, Tone};
+use crate::output::{CodedError, Output, Verbosity};
+#[cfg(feature = "rustyline")]
+use crate::redact;
+
+#[cfg(feature = "async")]
+pub use async_repl::{AsyncCommandHandler, AsyncRepl, ShutdownHandle};
+pub use bench::{BenchReport, CommandStats, ReplBench};
+pub use builtins::Builtin;
+pub use clock::Clock;
+pub use context::{CancelToken, Context};
+pub use dry_run::DryRun;
+pub use errors::{DefaultErrorRenderer, ErrorRenderer};
+#[cfg(feature = "git")]
+pub use git::GitStatus;
+pub use help::{CommandInfo, Example, Registry};
+#[cfg(feature = "rustyline")]
+pub use history::LazyHistory;
+pub use jobs::{Execution, Job, JobContext, JobResult};
+#[cfg(feature = "rustyline")]
+pub use pairs::AutoPairs;
+pub use reader::{LineReader, ReadlineError, Result, StreamReader};
+pub use rewrite::{straight_quotes, strip_shell_prompt};
+pub use rng::Rng;
+pub use printer::ReplPrinter;
+pub use workdir::WorkingDir;
+#[cfg(feature = "config")]
+pub use session::SessionState;
+#[cfg(feature = "signing")]
+pub use signing::{ScriptKey, SignatureError};
+pub use status::{StatusBar, StatusPosition};
+pub use tokens::{join, quote, tokenize, Token, Tokens, UnterminatedQuote};
+
+
+/// A Read-Eval-Print Loop (REPL) implementation with customizable command handling.
+///
+/// The REPL handles:
+/// - Reading lines with editing capabilities (arrow keys, history navigation)
+/// - Empty line filtering (pressing Enter on empty input is ignored)
+/// - Signal handling (Ctrl+C continues, Ctrl+D exits)
+/// - Command history management
+///
+/// # Type Parameters
+///
+/// * `H` - A type that implements the `CommandHandler` trait to process commands
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::{Repl, CommandHandler, Context};
+///
+/// pub struct MyApp;
+///
+/// impl CommandHandler for MyApp {
+///     fn handle(&mut self, command: &str, _: &Context) -> bool {
+///         println!("Received: {}", command);
+///         command != "exit"
+///     }
+/// }
+///
+/// fn main() -> mycli::repl::Result<()> {
+///     let app = MyApp;
+///     let mut repl = Repl::new("MyApp> ", app)?;
+///     let _ = repl.load_history(".history");
+///     repl.run()?;
+///     let _ = repl.save_history(".history");
+///     Ok(())
+/// }
+/// ```
+pub struct Repl<H>
+where H: CommandHandler {
+    prompt: String,
+    handler: H,
+    #[cfg(feature = "rustyline")]
+    editor: history::LineEditor,
+    status: Option<StatusBar>,
+    output: Output,
+    printer: Option<ReplPrinter>,
+    errors: Box<dyn ErrorRenderer>,
+    aliases: HashMap<String, String>,
+    #[cfg(feature = "rustyline")]
+    history_file: Option<PathBuf>,
+    #[cfg(feature = "rustyline")]
+    autosave_interval: Option<Duration>,
+    #[cfg(feature = "rustyline")]
+    autosave: Option<history::Autosave>,
+    // The line Ctrl+L interrupted, to edit again after clearing the screen.
+    #[cfg(feature = "rustyline")]
+    cleared: clear::Cleared,
+    // The line a key binding changed, to edit again in place.
+    #[cfg(feature = "rustyline")]
+    edited: reprompt::Edited,
+    #[cfg(feature = "rustyline")]
+    abbreviations: abbreviations::Abbreviations,
+    #[cfg(feature = "rustyline")]
+    snippets: std::collections::BTreeMap<String, String>,
+    #[cfg(feature = "rustyline")]
+    filling: snippets::Filling,
+    builtins: HashSet<Builtin>,
+    commands: Registry,
+    // How long commands may run, unless the handler says otherwise.
+    timeout: Option<Duration>,
+    // The only commands that may run, when restricted.
+    allowed: Option<HashSet<String>>,
+    jobs: jobs::Jobs,
+    // The jobs started by `at` and `every`, some of which may have ended.
+    scheduled: HashSet<usize>,
+    // Reused for each line, so the loop does not allocate per command.
+    line: String,
+    parallelism: usize,
+    timing: bool,
+    // How long commands must take for `timing` to apply to them anyway.
+    report_time: Option<Duration>,
+    last_took: Option<Duration>,
+    dry_run: DryRun,
+    catch_panics: bool,
+    clock: Clock,
+    rng: Rng,
+    working_dir: WorkingDir,
+    cancel: CancelToken,
+    // Who a remote client authenticated as.
+    user: Option<String>,
+    placeholders: HashMap<String, Box<placeholders::Placeholder>>,
+    rewrites: Vec<Box<rewrite::Rewrite>>,
+    // 1 if the last command wrote error output, for `{status}`.
+    last_status: i32,
+    usage: summary::Usage,
+    #[cfg(feature = "git")]
+    git: git::Cache,
+    notify_after: Option<Duration>,
+    title: Option<String>,
+    marks: bool,
+    // Whether `run` is running, and whether it changed the window title,
+    // which must then be restored.
+    running: bool,
+    titled: bool,
+    #[cfg(feature = "config")]
+    settings: crate::config::Config,
+    #[cfg(feature = "config")]
+    live: Option<reload::LiveConfig>,
+    #[cfg(feature = "config")]
+    session_file: Option<PathBuf>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<crate::metrics::Metrics>,
+    #[cfg(feature = "audit")]
+    audit: Option<crate::audit::AuditLog>,
+    // Keys scripts must be signed with, when signatures are required.
+    #[cfg(feature = "signing")]
+    script_keys: Option<Vec<ScriptKey>>,
+    #[cfg(feature = "update")]
+    updater: Option<crate::update::Updater>,
+    #[cfg(feature = "clipboard")]
+    last_output: String,
+}
+
+/// Trait for handling commands in the REPL.
+///
+/// Implementors of this trait define how commands are processed when entered
+/// by the user in the REPL.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::{CommandHandler, Context};
+///
+/// struct EchoHandler;
+///
+/// impl CommandHandler for EchoHandler {
+///     fn handle(&mut self, command: &str, ctx: &Context) -> bool {
+///         if command == "quit" {
+///             return false;
+///         }
+///         let _ = ctx.output().println(&format!("Echo: {command}"));
+///         true
+///     }
+/// }
+/// ```
+pub trait CommandHandler {
+    /// Handles a command entered by the user.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command string to process
+    /// * `ctx` - What the command can use while it runs, such as the output
+    ///   and the working directory
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` to continue the REPL, `false` to exit
+    fn handle(&mut self, command: &str, ctx: &Context) -> bool;
+
+    /// Tells the REPL whether to wait for `command`.
+    ///
+    /// Returning [`Execution::NonBlocking`] runs the command like a line
+    /// ending in `&`, on a worker thread using the job returned by
+    /// [`background`](CommandHandler::background), so slow commands do not
+    /// hold up the prompt. Returning [`Execution::Cancellable`] runs it
+    /// the same way but waits for it, so that the REPL's
+    /// [timeout](Repl::with_timeout) applies. Returns
+    /// [`Execution::Blocking`] by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command string about to run
+    fn execution(&self, command: &str) -> Execution {
+        let _ = command;
+        Execution::Blocking
+    }
+
+    /// Starts `command` in the background, for lines ending in `&` when
+    /// [`Builtin::Jobs`] is enabled, and for commands whose
+    /// [`execution`](CommandHandler::execution) is non-blocking.
+    ///
+    /// The returned [`Job`] runs on its own thread while the user keeps
+    /// typing commands. Returns `None` by default, meaning the command
+    /// cannot run in the background.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command string, without the trailing `&`
+    ///
+    /// # Returns
+    ///
+    /// Returns the work to run, or `None` to reject the command
+    fn background(&mut self, command: &str) -> Option<Job> {
+        let _ = command;
+        None
+    }
+
+    /// Returns how long `command` may run before it is cancelled, in place
+    /// of the REPL's [default](Repl::with_timeout). Returns `None` by
+    /// default, meaning the REPL's default applies if the command is
+    /// [`Execution::Cancellable`].
+    ///
+    /// Only commands the handler can run as a [`Job`] can be cancelled;
+    /// returning a limit runs the command's job on a worker thread, as
+    /// [`Execution::Cancellable`] does. See [`Repl::with_timeout`].
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command string about to run
+    fn timeout(&self, command: &str) -> Option<Duration> {
+        let _ = command;
+        None
+    }
+
+    /// Adds the handler's own state, such as variables and the current
+    /// mode, to the state saved when the session ends.
+    ///
+    /// Called only when the REPL was given a
+    /// [session file](Repl::with_session_file). Does nothing by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state being saved, with the REPL's part filled in
+    #[cfg(feature = "config")]
+    fn save_session(&self, state: &mut SessionState) {
+        let _ = state;
+    }
+
+    /// Restores the state saved by [`save_session`] at the end of a
+    /// previous session. Does nothing by default.
+    ///
+    /// [`save_session`]: CommandHandler::save_session
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state read from the session file
+    #[cfg(feature = "config")]
+    fn restore_session(&mut self, state: &SessionState) {
+        let _ = state;
+    }
+
+    /// Called after the REPL switches to another configuration profile,
+    /// so the handler can pick up the profile's variables and connection
+    /// settings. Does nothing by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The profile now in use
+    /// * `config` - The settings with the profile applied
+    #[cfg(feature = "config")]
+    fn profile_changed(&mut self, name: &str, config: &crate::config::Config) {
+        let _ = (name, config);
+    }
+}
+
+
+impl <H: CommandHandler> Repl<H> {
+    /// Creates a new REPL instance with the specified prompt and command handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - The prompt string to display before each input (e.g., `"> "` or `"app> "`),
+    ///   which may contain [placeholders](Self::with_placeholder) such as `{cwd}`
+    /// * `handler` - The command handler that will process user input
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(Repl)` on success, or an error if the editor cannot be initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::repl::{Repl, CommandHandler, Context};
+    ///
+    /// struct MyHandler;
+    /// impl CommandHandler for MyHandler {
+    ///     fn handle(&mut self, command: &str, _: &Context) -> bool { true }
+    /// }
+    ///
+    /// let repl = Repl::new(">>> ", MyHandler).unwrap();
+    /// ```
+    pub fn new(prompt: impl Into<String>, handler: H, ) -> Result<Self> {
+        Ok(Self {
+            prompt: prompt.into(),
+            handler,
+            #[cfg(feature = "rustyline")]
+            editor: history::editor(rustyline::Config::default())?,
+            status: None,
+            output: Output::stdout(),
+            printer: None,
+            errors: Box::new(DefaultErrorRenderer),
+            aliases: HashMap::new(),
+            #[cfg(feature = "rustyline")]
+            history_file: None,
+            #[cfg(feature = "rustyline")]
+            autosave_interval: None,
+            #[cfg(feature = "rustyline")]
+            autosave: None,
+            #[cfg(feature = "rustyline")]
+            cleared: clear::Cleared::default(),
+            #[cfg(feature = "rustyline")]
+            edited: reprompt::Edited::default(),
+            #[cfg(feature = "rustyline")]
+            abbreviations: abbreviations::Abbreviations::default(),
+            #[cfg(feature = "rustyline")]
+            snippets: std::collections::BTreeMap::new(),
+            #[cfg(feature = "rustyline")]
+            filling: snippets::Filling::default(),
+            builtins: HashSet::new(),
+            commands: Registry::new(),
+            timeout: None,
+            allowed: None,
+            jobs: jobs::Jobs::default(),
+            scheduled: HashSet::new(),
+            line: String::new(),
+            parallelism: std::thread::available_parallelism().map_or(4, |n| n.get()),
+            timing: false,
+            report_time: None,
+            last_took: None,
+            dry_run: DryRun::new(),
+            catch_panics: false,
+            clock: Clock::system(),
+            rng: Rng::from_entropy(),
+            working_dir: WorkingDir::process(),
+            cancel: CancelToken::new(),
+            user: None,
+            placeholders: HashMap::new(),
+            rewrites: Vec::new(),
+            last_status: 0,
+            usage: summary::Usage::default(),
+            #[cfg(feature = "git")]
+            git: git::Cache::default(),
+            notify_after: None,
+            title: None,
+            marks: false,
+            running: false,
+            titled: false,
+            #[cfg(feature = "config")]
+            settings: crate::config::Config::default(),
+            #[cfg(feature = "config")]
+            live: None,
+            #[cfg(feature = "config")]
+            session_file: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+            #[cfg(feature = "audit")]
+            audit: None,
+            #[cfg(feature = "signing")]
+            script_keys: None,
+            #[cfg(feature = "update")]
+            updater: None,
+            #[cfg(feature = "clipboard")]
+            last_output: String::new() })
+    }
+
+    /// Applies settings from a configuration file.
+    ///
+    /// The configured prompt replaces the one given to [`Repl::new`], the
+    /// configured theme replaces the current [theme]
+    /// and the colors are applied to it, key
+    /// bindings are added to the line editor, aliases are expanded
+    /// before commands reach the handler and abbreviations as they are
+    /// typed. When a history file is configured,
+    /// history is loaded from it now and saved to it when [`run`] returns.
+    ///
+    /// History size and filtering settings recreate the line editor, so call
+    /// this before loading history yourself.
+    ///
+    /// [`run`]: Repl::run
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The settings to apply
+    ///
+    /// # Returns
+    ///
+    /// Returns the REPL with the settings applied, or an error if the line
+    /// editor cannot be recreated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::config::Config;
+    /// use mycli::repl::{Repl, CommandHandler, Context};
+    ///
+    /// struct MyHandler;
+    /// impl CommandHandler for MyHandler {
+    ///     fn handle(&mut self, command: &str, _: &Context) -> bool { true }
+    /// }
+    ///
+    /// let config = Config::parse("prompt = \"db> \"\n[aliases]\nq = \"quit\"").unwrap();
+    /// let repl = Repl::new("> ", MyHandler).unwrap().with_config(&config).unwrap();
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn with_config(mut self, config: &crate::config::Config) -> Result<Self> {
+        let live = reload::LiveConfig::new(config.clone(), self.prompt.clone());
+        if let Some(prompt) = &config.prompt {
+            self.prompt = prompt.clone();
+        }
+        if let Some(timing) = config.timing {
+            self.timing = timing;
+        }
+        if let Some(secs) = config.report_time {
+            self.report_time = Some(Duration::from_secs(secs)).filter(|threshold| !threshold.is_zero());
+        }
+        if let Some(dry_run) = config.dry_run {
+            self.dry_run.set(dry_run);
+        }
+        if let Some(secs) = config.command_timeout {
+            self.timeout = Some(Duration::from_secs(secs)).filter(|limit| !limit.is_zero());
+        }
+        if let Some(title) = &config.title {
+            self.title = Some(title.clone()).filter(|title| !title.is_empty());
+        }
+        if (config.theme.is_some() || !config.colors.is_empty())
+            && let Ok(theme) = config.theme((*theme::current()).clone())
+        {
+            theme::set_theme(theme);
+        }
+
+        #[cfg(feature = "rustyline")]
+        self.apply_editor_config(config)?;
+        self.aliases.extend(config.aliases.iter().map(|(k, v)| (k.clone(), v.clone())));
+        #[cfg(feature = "rustyline")]
+        {
+            self.add_abbreviations(config.abbreviations.clone());
+            self.add_snippets(config.snippets.clone());
+        }
+        #[cfg(feature = "rustyline")]
+        if let Some(file) = config.history_file() {
+            self = self.with_history_file(file);
+        }
+        self.settings.merge(config.clone());
+        self.live = Some(live);
+        Ok(self)
+    }
+
+    /// Applies the history settings and key bindings of a configuration to
+    /// the line editor.
+    #[cfg(all(feature = "config", feature = "rustyline"))]
+    fn apply_editor_config(&mut self, config: &crate::config::Config) -> Result<()> {
+        use rustyline::EventHandler;
+
+        let history = &config.history;
+        if history.max_size.is_some() || history.ignore_duplicates.is_some() || history.ignore_space.is_some() {
+            let mut builder = rustyline::Config::builder();
+            if let Some(size) = history.max_size {
+                builder = builder.max_history_size(size)?;
+            }
+            if let Some(ignore) = history.ignore_duplicates {
+                builder = builder.history_ignore_dups(ignore)?;
+            }
+            if let Some(ignore) = history.ignore_space {
+                builder = builder.history_ignore_space(ignore);
+            }
+            self.editor = history::editor(builder.build())?;
+        }
+
+        for (key, action) in &config.keybindings {
+            if let (Ok(key), Ok(cmd)) = (keys::parse_key(key), keys::parse_action(action)) {
+                self.editor.bind_sequence(key, EventHandler::Simple(cmd));
+            }
+        }
+        Ok(())
+    }
+
+    /// Watches a configuration file and applies changes to it while the
+    /// REPL runs.
+    ///
+    /// The file is checked before each prompt. When it has changed, its
+    /// prompt, colors and aliases replace the ones applied earlier and a
+    /// notice is printed; a file that no longer loads is reported and the
+    /// previous settings are kept. History and key binding changes take
+    /// effect the next time the application starts.
+    ///
+    /// Call this after [`with_config`] with the same file, so the settings
+    /// it applied are the ones a change replaces.
+    ///
+    /// [`with_config`]: Repl::with_config
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The configuration file to watch
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mycli::config::Config;
+    /// use mycli::repl::{Repl, CommandHandler, Context};
+    ///
+    /// struct MyHandler;
+    /// impl CommandHandler for MyHandler {
+    ///     fn handle(&mut self, command: &str, _: &Context) -> bool { true }
+    /// }
+    ///
+    /// let config = Config::load("myapp.toml").unwrap();
+    /// let mut repl = Repl::new("> ", MyHandler)
+    ///     .unwrap()
+    ///     .with_config(&config)
+    ///     .unwrap()
+    ///     .watch_config("myapp.toml");
+    /// repl.run().unwrap();
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn watch_config(mut self, path: impl Into<PathBuf>) -> Self {
+        let prompt = self.prompt.clone();
+        self.live
+            .get_or_insert_with(|| reload::LiveConfig::new(crate::config::Config::default(), prompt))
+            .watch(path.into());
+        self
+    }
+
+    /// Restores the previous session from a state file and saves this
+    /// session to it when [`run`] returns.
+    ///
+    /// Aliases defined during the session and the working directory are
+    /// restored now, as is anything the handler stored in
+    /// [`CommandHandler::save_session`]. A missing or unreadable state file
+    /// starts a fresh session, and a saved working directory that no longer
+    /// exists is skipped. The directory is the REPL's
+    /// [working directory](Self::with_working_dir), so give that first if
+    /// it is not the process's.
+    ///
+    /// [`run`]: Repl::run
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The state file, such as one in the
+    ///   [state directory](crate::dirs::AppDirs::state_file)
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::collections::BTreeMap;
+    /// use mycli::dirs::AppDirs;
+    /// use mycli::repl::{Repl, CommandHandler, Context, SessionState};
+    ///
+    /// #[derive(Default)]
+    /// struct Shell {
+    ///     vars: BTreeMap<String, String>,
+    /// }
+    ///
+    /// impl CommandHandler for Shell {
+    ///     fn handle(&mut self, command: &str, _: &Context) -> bool { command != "quit" }
+    ///
+    ///     fn save_session(&self, state: &mut SessionState) {
+    ///         state.variables = self.vars.clone();
+    ///     }
+    ///
+    ///     fn restore_session(&mut self, state: &SessionState) {
+    ///         self.vars = state.variables.clone();
+    ///     }
+    /// }
+    ///
+    /// let state = AppDirs::new("myapp").state_file("session.toml").unwrap();
+    /// let mut repl = Repl::new("> ", Shell::default()).unwrap().with_session_file(state);
+    /// repl.run().unwrap();
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn with_session_file(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        if let Ok(Some(state)) = SessionState::load(&path) {
+            self.aliases.extend(state.aliases.clone());
+            if let Some(cwd) = &state.cwd
+                && cwd.is_dir()
+            {
+                let _ = self.working_dir.change(cwd);
+            }
+            self.handler.restore_session(&state);
+        }
+        self.session_file = Some(path);
+        self
+    }
+
+    /// Switches to the configuration profile `name`.
+    ///
+    /// The profile's settings replace the top-level ones from the
+    /// configuration given to [`with_config`]: the prompt, colors, aliases
+    /// and timing change at once, and when the profile has its own history
+    /// file, the current history is saved and the profile's is loaded in its
+    /// place. The handler is then told through
+    /// [`CommandHandler::profile_changed`].
+    ///
+    /// [`with_config`]: Repl::with_config
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The profile to use, such as one named by a `--profile` flag
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if the configuration has no such profile, leaving
+    /// the current settings in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::config::Config;
+    /// use mycli::repl::{Repl, CommandHandler, Context};
+    ///
+    /// struct MyHandler;
+    /// impl CommandHandler for MyHandler {
+    ///     fn handle(&mut self, command: &str, _: &Context) -> bool { true }
+    /// }
+    ///
+    /// let config = Config::parse("[profiles.prod]\nprompt = \"PROD> \"").unwrap();
+    /// let mut repl = Repl::new("> ", MyHandler).unwrap().with_config(&config).unwrap();
+    /// repl.use_profile("prod").unwrap();
+    /// assert_eq!(repl.profile(), Some("prod"));
+    /// assert!(repl.use_profile("staging").is_err());
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn use_profile(&mut self, name: &str) -> std::result::Result<(), crate::config::ConfigError> {
+        let config = self.settings.profile(name)?;
+        let prompt = self.prompt.clone();
+        let live = self
+            .live
+            .get_or_insert_with(|| reload::LiveConfig::new(crate::config::Config::default(), prompt));
+        live.apply(config.clone(), &mut self.prompt, &mut self.aliases)?;
+        live.profile = Some(name.to_string());
+        #[cfg(feature = "rustyline")]
+        {
+            self.add_abbreviations(config.abbreviations.clone());
+            self.add_snippets(config.snippets.clone());
+        }
+        if let Some(timing) = config.timing {
+            self.timing = timing;
+        }
+        if let Some(secs) = config.report_time {
+            self.report_time = Some(Duration::from_secs(secs)).filter(|threshold| !threshold.is_zero());
+        }
+        if let Some(dry_run) = config.dry_run {
+            self.dry_run.set(dry_run);
+        }
+        if let Some(secs) = config.command_timeout {
+            self.timeout = Some(Duration::from_secs(secs)).filter(|limit| !limit.is_zero());
+        }
+        if let Some(title) = config.title.clone() {
+            self.set_title(title);
+        } else {
+            self.show_title(None);
+        }
+
+        #[cfg(feature = "rustyline")]
+        self.switch_history(config.history_file());
+        self.handler.profile_changed(name, &config);
+        Ok(())
+    }
+
+    /// Saves the history to its file and loads the one at `history`, when
+    /// a profile uses another file.
+    #[cfg(all(feature = "config", feature = "rustyline"))]
+    fn switch_history(&mut self, history: Option<PathBuf>) {
+        if history != self.history_file {
+            if let Some(old) = self.history_file.take() {
+                let _ = self.save_history(old);
+            }
+            let _ = self.editor.clear_history();
+            if let Some(file) = history {
+                let _ = self.load_history(&file);
+                self.history_file = Some(file);
+            }
+            if self.autosave.is_some() {
+                self.start_autosave();
+            }
+        }
+    }
+
+    /// Returns the configuration profile in use, if one was chosen with
+    /// [`use_profile`](Repl::use_profile).
+    #[cfg(feature = "config")]
+    pub fn profile(&self) -> Option<&str> {
+        self.live.as_ref().and_then(|live| live.profile.as_deref())
+    }
+
+    /// Rereads the watched configuration file now, as a `reload` command
+    /// would, and applies its prompt, colors and aliases.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if any of those changed, `Ok(false)` if none did
+    /// or no file is watched, or an error if the file cannot be loaded, in
+    /// which case the previous settings are kept.
+    #[cfg(feature = "config")]
+    pub fn reload_config(&mut self) -> std::result::Result<bool, crate::config::ConfigError> {
+        match &mut self.live {
+            Some(live) => live.reload(&mut self.prompt, &mut self.aliases),
+            None => Ok(false),
+        }
+    }
+
+    /// Enables a group of built-in commands.
+    ///
+    /// Built-ins are handled by the REPL before the command handler sees
+    /// the line, so enable only those whose names the application does not
+    /// use itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `builtin` - The built-in commands to enable
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// use mycli::repl::Builtin;
+    ///
+    /// // `set timing on`, `show colors`, `set --save prompt "db> "`
+    /// let repl = Repl::new("> ", MyHandler).unwrap().with_builtin(Builtin::Settings);
+    /// ```
+    pub fn with_builtin(mut self, builtin: Builtin) -> Self {
+        #[cfg(all(feature = "clipboard", feature = "rustyline"))]
+        if builtin == Builtin::Clipboard {
+            use rustyline::{EventHandler, KeyEvent};
+
+            self.editor.bind_sequence(KeyEvent::alt('w'), EventHandler::Conditional(Box::new(clipboard::CopyLine)));
+        }
+        self.builtins.insert(builtin);
+        self
+    }
+
+    /// Prints how long each command took after it finishes, such as
+    /// `took 1.2s`.
+    ///
+    /// # Arguments
+    ///
+    /// * `timing` - Whether to print the time
+    pub fn with_timing(mut self, timing: bool) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    /// Prints how long a command took after it finishes, such as
+    /// `took 4.2s`, when it took at least `threshold`, like zsh's
+    /// `REPORTTIME`. [`with_timing`](Self::with_timing) prints it after
+    /// every command instead. The `{took}` [placeholder](Self::with_placeholder)
+    /// shows how long the last command took either way.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - How long a command must take for the time to be printed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// use std::time::Duration;
+    ///
+    /// let repl = Repl::new("> ", MyHandler).unwrap().with_report_time(Duration::from_secs(5));
+    /// ```
+    pub fn with_report_time(mut self, threshold: Duration) -> Self {
+        self.report_time = Some(threshold);
+        self
+    }
+
+    /// Reads the time from `clock`, both to time commands and for the
+    /// command handler to read through a clone. See [`Clock`].
+    ///
+    /// # Arguments
+    ///
+    /// * `clock` - The clock, shared with the command handler
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Gives background jobs `rng` for their random numbers; the command
+    /// handler shares it through a clone. See [`Rng`].
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The generator, shared with the command handler
+    pub fn with_rng(mut self, rng: Rng) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// Formats the REPL's own errors with `renderer`, and lets it decide
+    /// which errors reading a line end the REPL. See [`ErrorRenderer`].
+    ///
+    /// # Arguments
+    ///
+    /// * `renderer` - How to report errors
+    pub fn with_error_renderer(mut self, renderer: impl ErrorRenderer + 'static) -> Self {
+        self.errors = Box::new(renderer);
+        self
+    }
+
+    /// Keeps the REPL running when the command handler panics, printing an
+    /// error instead, such as
+    /// ``Error: `report` failed unexpectedly: index out of bounds at src/report.rs:40:9``.
+    /// Off by default, so panics end the program as usual.
+    ///
+    /// This keeps long sessions, such as remote ones, from being torn down
+    /// by one bad command, and lets the session and history still be
+    /// saved. The handler's state may be left half-updated by the panic.
+    ///
+    /// # Arguments
+    ///
+    /// * `catch` - Whether to catch panics from the command handler
+    pub fn with_catch_panics(mut self, catch: bool) -> Self {
+        self.catch_panics = catch;
+        self
+    }
+
+    /// Uses `dry_run` as the REPL's dry-run flag, so the command handler
+    /// can check it through a clone. See [`DryRun`].
+    ///
+    /// A command ending in `--dry-run` runs with the flag on, without the
+    /// `--dry-run` word. Built-ins honor the flag too: `set --save` does
+    /// not write the configuration file, `kill` does not stop the job and
+    /// `copy` does not touch the clipboard; each says what it would do.
+    ///
+    /// # Arguments
+    ///
+    /// * `dry_run` - The flag, shared with the command handler
+    pub fn with_dry_run(mut self, dry_run: DryRun) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Fills in `{name}` in the prompt with what `value` returns, computed
+    /// again each time the prompt is shown.
+    ///
+    /// Besides the placeholders registered this way, prompts can contain:
+    ///
+    /// * `{cwd}` - the [working directory](Self::with_working_dir), with the
+    ///   home directory written as `~`;
+    /// * `{time}` - the local time, as `14:05:09`;
+    /// * `{status}` - `0` if the last command succeeded and `1` if it
+    ///   wrote error output;
+    /// * `{took}` - how long the last command took, such as `4.2s`;
+    /// * `{mode}` - `dry-run` while [dry-run mode](DryRun) is on, and
+    ///   nothing otherwise;
+    /// * `{git}` - with the `git` feature, the branch checked out in the
+    ///   working directory, followed by `*` if files were changed, and
+    ///   nothing outside a repository. See `GitStatus`.
+    ///
+    /// A registered placeholder replaces a built-in one of the same name,
+    /// for instance to show the application's own modes as `{mode}`.
+    /// Unknown placeholders are shown as they are.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name between the braces
+    /// * `value` - Computes the text shown in its place
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let queued = Arc::new(AtomicUsize::new(0));
+    /// let shown = queued.clone();
+    /// // Shows `deploy:3 ~/src [0]> `.
+    /// let repl = Repl::new("{app}:{queued} {cwd} [{status}]> ", MyHandler)
+    ///     .unwrap()
+    ///     .with_placeholder("app", || "deploy".to_string())
+    ///     .with_placeholder("queued", move || shown.load(Ordering::Relaxed).to_string());
+    /// ```
+    pub fn with_placeholder(mut self, name: impl Into<String>, value: impl Fn() -> String + Send + 'static) -> Self {
+        self.placeholders.insert(name.into(), Box::new(value));
+        self
+    }
+
+    /// Adds a hook that rewrites each line before it is split into words
+    /// and run, returning the new line or `None` to leave it unchanged.
+    /// Hooks run in the order they are added, each given the line as the
+    /// one before left it, and before aliases are expanded. A line that
+    /// ends up blank is not run.
+    ///
+    /// Lines are rewritten however they arrive, including from scripts and
+    /// remote clients, and history keeps them as they were typed. The
+    /// [`straight_quotes`] and [`strip_shell_prompt`] hooks tidy up
+    /// commands pasted from documents.
+    ///
+    /// # Arguments
+    ///
+    /// * `rewrite` - Returns the rewritten line, if it changes
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// use mycli::repl::{straight_quotes, strip_shell_prompt};
+    ///
+    /// // `$ deploy @web` pasted from a runbook runs `deploy service=web`.
+    /// let repl = Repl::new("> ", MyHandler)
+    ///     .unwrap()
+    ///     .with_rewrite(strip_shell_prompt)
+    ///     .with_rewrite(straight_quotes)
+    ///     .with_rewrite(|line| line.contains('@').then(|| line.replace('@', "service=")));
+    /// ```
+    pub fn with_rewrite(mut self, rewrite: impl Fn(&str) -> Option<String> + Send + 'static) -> Self {
+        self.rewrites.push(Box::new(rewrite));
+        self
+    }
+
+    /// Describes the application's commands for the `help` built-in,
+    /// enabled with [`Builtin::Help`]. `help` lists the commands with their
+    /// summaries, `help <command>` shows one with its usage and numbered
+    /// examples, and `help <command> --run-example <n>` runs an example in
+    /// [dry-run mode](DryRun), so users can try it without its side
+    /// effects.
+    ///
+    /// # Arguments
+    ///
+    /// * `commands` - The descriptions of the commands
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::output::{BufferSink, Output};
+    /// use mycli::repl::{Builtin, CommandHandler, CommandInfo, Context, DryRun, Registry, Repl};
+    /// # use mycli::repl::LineReader;
+    /// # struct Lines(std::vec::IntoIter<&'static str>);
+    /// # impl LineReader for Lines {
+    /// #     fn readline(&mut self, _: &str) -> mycli::repl::Result<String> {
+    /// #         self.0.next().map(String::from).ok_or(mycli::repl::ReadlineError::Eof)
+    /// #     }
+    /// # }
+    ///
+    /// struct Deployer {
+    ///     out: Output,
+    ///     dry_run: DryRun,
+    /// }
+    ///
+    /// impl CommandHandler for Deployer {
+    ///     fn handle(&mut self, command: &str, _: &Context) -> bool {
+    ///         let done = if self.dry_run.is_on() { "would run" } else { "ran" };
+    ///         let _ = self.out.println(&format!("{done} `{command}`"));
+    ///         true
+    ///     }
+    /// }
+    ///
+    /// let commands = Registry::new().command(
+    ///     CommandInfo::new("deploy", "Deploys a service")
+    ///         .with_usage("deploy <service> [--canary]")
+    ///         .with_example("deploy web", "Deploys web everywhere")
+    ///         .with_example("deploy web --canary", "Deploys web to the canary hosts only"),
+    /// );
+    /// let buffer = BufferSink::new();
+    /// let out = Output::new(buffer.clone());
+    /// let dry_run = DryRun::new();
+    /// let handler = Deployer { out: out.clone(), dry_run: dry_run.clone() };
+    /// let mut repl = Repl::new("> ", handler)
+    ///     .unwrap()
+    ///     .with_output(out)
+    ///     .with_dry_run(dry_run)
+    ///     .with_builtin(Builtin::Help)
+    ///     .with_commands(commands);
+    ///
+    /// // `Lines` is a `LineReader` that replays the lines it is given.
+    /// repl.run_with(&mut Lines(vec!["help deploy --run-example 2"].into_iter())).unwrap();
+    /// assert!(buffer.contents().ends_with("would run `deploy web --canary`\n"));
+    /// ```
+    pub fn with_commands(mut self, commands: Registry) -> Self {
+        self.commands = commands;
+        self
+    }
+
+    /// Uses `dir` as the directory commands run in, which the `cd` and
+    /// `pwd` built-ins of [`Builtin::WorkingDir`] change and show, so the
+    /// command handler can resolve paths through a clone. See
+    /// [`WorkingDir`]. The process's current directory is used by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory, shared with the command handler
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// use mycli::repl::{Builtin, WorkingDir};
+    ///
+    /// // Each session moves around on its own, starting in /.
+    /// let dir = WorkingDir::session("/")?;
+    /// let repl = Repl::new("{cwd}> ", MyHandler)
+    ///     .unwrap()
+    ///     .with_working_dir(dir)
+    ///     .with_builtin(Builtin::WorkingDir);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn with_working_dir(mut self, dir: WorkingDir) -> Self {
+        self.working_dir = dir;
+        self
+    }
+
+    /// Cancels commands that run longer than `timeout`, reporting an error
+    /// such as ``Error: `report` timed out after 30.0s`` and returning to
+    /// the prompt. [`CommandHandler::timeout`] can set another limit for
+    /// each command.
+    ///
+    /// The limit applies to commands whose
+    /// [`execution`](CommandHandler::execution) is
+    /// [`Execution::Cancellable`], and to those given their own limit by
+    /// [`CommandHandler::timeout`]. The REPL runs such a command's [`Job`]
+    /// from [`CommandHandler::background`] on a worker thread and waits for
+    /// it, and on timeout marks it [cancelled](JobContext::is_cancelled),
+    /// which also kills a child process the job waits for with
+    /// [`JobContext::wait`]. Other commands run through
+    /// [`CommandHandler::handle`] on the REPL's own thread, where they
+    /// cannot be stopped, and are not limited.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long commands may run
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use mycli::output::{BufferSink, Output};
+    /// use mycli::repl::{CommandHandler, Context, Execution, Job, Repl};
+    /// # use mycli::repl::LineReader;
+    /// # struct Lines(std::vec::IntoIter<&'static str>);
+    /// # impl LineReader for Lines {
+    /// #     fn readline(&mut self, _: &str) -> mycli::repl::Result<String> {
+    /// #         self.0.next().map(String::from).ok_or(mycli::repl::ReadlineError::Eof)
+    /// #     }
+    /// # }
+    ///
+    /// struct Reports;
+    ///
+    /// impl CommandHandler for Reports {
+    ///     fn handle(&mut self, _: &str, _: &Context) -> bool {
+    ///         true
+    ///     }
+    ///
+    ///     fn execution(&self, command: &str) -> Execution {
+    ///         if command == "report" { Execution::Cancellable } else { Execution::Blocking }
+    ///     }
+    ///
+    ///     fn background(&mut self, _: &str) -> Option<Job> {
+    ///         Some(Job::new(|ctx| {
+    ///             while !ctx.is_cancelled() {
+    ///                 std::thread::sleep(Duration::from_millis(10));
+    ///             }
+    ///             Err("cancelled".to_string())
+    ///         }))
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferSink::new();
+    /// let mut repl = Repl::new("> ", Reports).unwrap().with_output(Output::new(buffer.clone())).with_timeout(Duration::from_millis(50));
+    /// repl.run_with(&mut Lines(vec!["report", "status"].into_iter())).unwrap();
+    /// assert_eq!(buffer.contents(), "Error: `report` timed out after 50ms\n");
+    /// ```
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Only lets the commands named in `commands` run, refusing any other
+    /// line with an error such as ``Error: `drop` is not allowed here``.
+    ///
+    /// A line is judged by its first word after aliases are expanded, and
+    /// refused lines reach neither the command handler nor the built-ins,
+    /// so built-ins such as `jobs` or `set` must be listed to be usable.
+    /// Each command given to `par` is checked as well. List the command the
+    /// handler exits on, such as `quit`, or users can only leave with
+    /// Ctrl-D.
+    ///
+    /// # Arguments
+    ///
+    /// * `commands` - The names of the commands that may run
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { command != "quit" }
+    /// # }
+    /// // A limited shell for support staff.
+    /// let repl = Repl::new("support> ", MyHandler)
+    ///     .unwrap()
+    ///     .with_allowed_commands(["status", "logs", "help", "quit"]);
+    /// ```
+    pub fn with_allowed_commands(mut self, commands: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.allowed = Some(commands.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Records every command in `metrics`: how many times it ran, whether
+    /// it wrote error output and how long it took.
+    ///
+    /// # Arguments
+    ///
+    /// * `metrics` - The metrics to record in, usually shared with a server
+    ///   that exposes them
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// use mycli::metrics::Metrics;
+    ///
+    /// let metrics = Metrics::new("myapp");
+    /// let repl = Repl::new("> ", MyHandler).unwrap().with_metrics(metrics.clone());
+    /// ```
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: crate::metrics::Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Records every command in an audit log, with the user, the time and
+    /// its result code.
+    ///
+    /// A command that cannot be recorded still runs, and the failure is
+    /// reported as an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `log` - The audit log to record in
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// use mycli::audit::AuditLog;
+    ///
+    /// let log = AuditLog::file("/var/log/myapp/audit.log")?;
+    /// let repl = Repl::new("> ", MyHandler).unwrap().with_audit_log(log);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[cfg(feature = "audit")]
+    pub fn with_audit_log(mut self, log: crate::audit::AuditLog) -> Self {
+        self.audit = Some(log);
+        self
+    }
+
+    /// Only lets `source` run scripts signed with one of `keys`, for
+    /// production use where only approved runbooks may run.
+    ///
+    /// The signature of `deploy.rhai` is read from `deploy.rhai.minisig`,
+    /// as written by `minisign -S -m deploy.rhai`. Scripts without a
+    /// signature, or whose signature does not match, are refused with an
+    /// error and not run. Scripts given to [`Repl::run_script`] directly
+    /// are not checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - The keys scripts may be signed with; see [`ScriptKey`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// use mycli::repl::{Builtin, ScriptKey};
+    ///
+    /// let key: ScriptKey = "RWRaFzyeIUQL2OpKbGPinFIKvvVQexMuxfmVR3auvr57kkIe6mkURtIs".parse()?;
+    /// let repl = Repl::new("> ", MyHandler)
+    ///     .unwrap()
+    ///     .with_builtin(Builtin::Scripts)
+    ///     .with_script_keys([key]);
+    /// # Ok::<(), mycli::repl::SignatureError>(())
+    /// ```
+    #[cfg(feature = "signing")]
+    pub fn with_script_keys(mut self, keys: impl IntoIterator<Item = ScriptKey>) -> Self {
+        self.script_keys = Some(keys.into_iter().collect());
+        self
+    }
+
+    /// Looks for newer releases of the application with `updater`, as
+    /// described in the [`update`](crate::update) module.
+    ///
+    /// When [`run`] starts, a newer release is reported above the prompt,
+    /// checking at most as often as the updater's
+    /// [check interval](crate::update::Updater::with_check_interval)
+    /// allows and without holding up the prompt. With [`Builtin::Update`],
+    /// `update` installs it.
+    ///
+    /// [`run`]: Repl::run
+    ///
+    /// # Arguments
+    ///
+    /// * `updater` - Where to look for releases and how to download them
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// # fn download(url: &str) -> std::io::Result<Vec<u8>> { unimplemented!() }
+    /// use mycli::repl::Builtin;
+    /// use mycli::update::{ReleaseSource, Updater};
+    ///
+    /// let updater = Updater::new("1.2.0", ReleaseSource::GitHub("naromori/myapp".into()), download);
+    /// let repl = Repl::new("> ", MyHandler).unwrap().with_builtin(Builtin::Update).with_updater(updater);
+    /// ```
+    #[cfg(feature = "update")]
+    pub fn with_updater(mut self, updater: crate::update::Updater) -> Self {
+        self.updater = Some(updater);
+        self
+    }
+
+    /// Shows `name` in the window title while the REPL runs, followed by
+    /// the [profile](Repl::use_profile) in use and the running command,
+    /// such as `myapp [prod]: deploy`. The previous title is restored when
+    /// [`run`](Repl::run) returns, on terminals that keep a title stack.
+    ///
+    /// In tmux, the window is named the same way. The `title` setting of a
+    /// configuration file replaces `name`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name to show, usually the application's
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// let repl = Repl::new("> ", MyHandler).unwrap().with_title("myapp");
+    /// ```
+    pub fn with_title(mut self, name: impl Into<String>) -> Self {
+        self.title = Some(name.into()).filter(|title| !title.is_empty());
+        self
+    }
+
+    /// Marks where each prompt, command line and command output starts in
+    /// the terminal with OSC 133 escape sequences, as shells do for their
+    /// shell integration.
+    ///
+    /// Terminals such as WezTerm, kitty and iTerm2 use the marks to jump
+    /// between prompts, select the output of a command and show whether it
+    /// failed, which is when it wrote error output.
+    ///
+    /// # Arguments
+    ///
+    /// * `marks` - Whether to write the marks
+    pub fn with_shell_integration(mut self, marks: bool) -> Self {
+        self.marks = marks;
+        self
+    }
+
+    /// Sends a desktop notification when a command that ran for at least
+    /// `after` finishes, such as `deploy finished after 2m05s`, and rings
+    /// the bell.
+    ///
+    /// Notifications are sent through the terminal with the OSC 9 escape
+    /// sequence, supported by iTerm2, kitty, WezTerm and others, which show
+    /// them when their window is not focused. Other terminals only mark the
+    /// window or tab for the bell. Nothing is sent when the output is not a
+    /// terminal.
+    ///
+    /// # Arguments
+    ///
+    /// * `after` - How long a command must run to be notified about
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// use std::time::Duration;
+    ///
+    /// let repl = Repl::new("> ", MyHandler).unwrap().with_notifications(Duration::from_secs(10));
+    /// ```
+    pub fn with_notifications(mut self, after: Duration) -> Self {
+        self.notify_after = Some(after);
+        self
+    }
+
+    /// Runs background jobs and non-blocking commands on a pool of
+    /// `workers` threads, instead of starting a thread for each one.
+    ///
+    /// Jobs beyond the number of workers wait their turn; `jobs` lists them
+    /// as running from the moment they are queued.
+    ///
+    /// # Arguments
+    ///
+    /// * `workers` - The number of threads in the pool
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// use mycli::repl::{Context, Execution, Job};
+    ///
+    /// struct Deployer;
+    ///
+    /// impl CommandHandler for Deployer {
+    ///     fn handle(&mut self, command: &str, _: &Context) -> bool {
+    ///         command != "quit"
+    ///     }
+    ///
+    ///     fn execution(&self, command: &str) -> Execution {
+    ///         if command.starts_with("deploy ") { Execution::NonBlocking } else { Execution::Blocking }
+    ///     }
+    ///
+    ///     fn background(&mut self, command: &str) -> Option<Job> {
+    ///         let target = command.strip_prefix("deploy ")?.to_string();
+    ///         Some(Job::new(move |_| Ok(format!("deployed {target}"))))
+    ///     }
+    /// }
+    ///
+    /// let repl = Repl::new("> ", Deployer).unwrap().with_worker_pool(4);
+    /// ```
+    pub fn with_worker_pool(mut self, workers: usize) -> Self {
+        self.jobs.use_pool(workers);
+        self
+    }
+
+    /// Sets how many commands a `par` line runs at once. Defaults to the
+    /// number of CPUs.
+    ///
+    /// # Arguments
+    ///
+    /// * `workers` - The most commands to run at the same time
+    pub fn with_parallelism(mut self, workers: usize) -> Self {
+        self.parallelism = workers.max(1);
+        self
+    }
+
+    /// Sets where framework output, such as error messages, is written.
+    ///
+    /// Command handlers that print through a clone of the same [`Output`]
+    /// end up in the same place, which lets tests capture everything a
+    /// session printed.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The output handle to write to
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::output::{BufferSink, Output};
+    /// use mycli::repl::{Repl, CommandHandler, Context};
+    ///
+    /// struct Greeter {
+    ///     out: Output,
+    /// }
+    ///
+    /// impl CommandHandler for Greeter {
+    ///     fn handle(&mut self, command: &str, _: &Context) -> bool {
+    ///         let _ = self.out.println(&format!("hello, {command}"));
+    ///         true
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferSink::new();
+    /// let out = Output::new(buffer.clone());
+    /// let repl = Repl::new("> ", Greeter { out: out.clone() })
+    ///     .unwrap()
+    ///     .with_output(out);
+    /// ```
+    pub fn with_output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Returns a handle to the output used by the REPL.
+    pub fn output(&self) -> Output {
+        self.output.clone()
+    }
+
+    /// Returns the token commands see through [`Context::is_cancelled`],
+    /// for asking the command running at the time to stop from another
+    /// thread. It is cleared before each command.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone()
+    }
+
+    /// Sets which messages besides errors the REPL's output shows, such
+    /// as [`Verbosity::Quiet`] for `-q`. The REPL's own notes, such as
+    /// that the configuration was reloaded, are hidden when quiet, and so
+    /// are [warnings](Output::warn) from the command handler printed
+    /// through a clone of the output.
+    ///
+    /// Applies to the output the REPL has when called; call it after
+    /// [`with_output`](Self::with_output).
+    ///
+    /// # Arguments
+    ///
+    /// * `verbosity` - What to show besides errors
+    pub fn with_verbosity(self, verbosity: Verbosity) -> Self {
+        self.output.set_verbosity(verbosity);
+        self
+    }
+
+    /// Returns a handle for printing from other threads while the prompt
+    /// is shown.
+    ///
+    /// Lines printed through it appear above the prompt, which is redrawn
+    /// with whatever the user has typed so far, so notifications and log
+    /// lines from background work do not mangle the input line. All calls
+    /// return clones of the same handle.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// use std::time::Duration;
+    ///
+    /// let mut repl = Repl::new("> ", MyHandler).unwrap();
+    /// let printer = repl.printer();
+    /// std::thread::spawn(move || loop {
+    ///     std::thread::sleep(Duration::from_secs(5));
+    ///     let _ = printer.println("heartbeat");
+    /// });
+    /// repl.run().unwrap();
+    /// ```
+    pub fn printer(&mut self) -> ReplPrinter {
+        #[cfg(feature = "rustyline")]
+        let printer = self.printer.get_or_insert_with(|| ReplPrinter::new(&mut self.editor, self.output.clone()));
+        #[cfg(not(feature = "rustyline"))]
+        let printer = self.printer.get_or_insert_with(|| ReplPrinter::fallback(self.output.clone()));
+        printer.clone()
+    }
+
+    /// Adds a status line that is redrawn after each command.
+    ///
+    /// Ctrl+L clears the screen and draws the bar again, keeping the line
+    /// being edited, and so does the `clear` built-in of [`Builtin::Clear`].
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The status bar providing the fields to display
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler, StatusBar};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// let repl = Repl::new("> ", MyHandler)
+    ///     .unwrap()
+    ///     .with_status_bar(StatusBar::new(|| vec!["mode: normal".into()]));
+    /// ```
+    pub fn with_status_bar(mut self, status: StatusBar) -> Self {
+        #[cfg(feature = "rustyline")]
+        {
+            use rustyline::{EventHandler, KeyEvent};
+
+            let key = clear::ClearKey(self.cleared.clone());
+            self.editor.bind_sequence(KeyEvent::ctrl('L'), EventHandler::Conditional(Box::new(key)));
+        }
+        self.status = Some(status);
+        self
+    }
+
+    /// Closes brackets and quotes as they are typed, as described for
+    /// [`AutoPairs`]. Off by default, since not every application's input
+    /// is code.
+    ///
+    /// # Arguments
+    ///
+    /// * `pairs` - The pairs to complete
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// use mycli::repl::AutoPairs;
+    ///
+    /// // `query "select (1)"` with half the typing.
+    /// let repl = Repl::new("> ", MyHandler).unwrap().with_auto_pairs(AutoPairs::default());
+    /// ```
+    #[cfg(feature = "rustyline")]
+    pub fn with_auto_pairs(mut self, pairs: AutoPairs) -> Self {
+        use rustyline::{EventHandler, KeyCode, KeyEvent, Modifiers};
+
+        let keys: Vec<char> = pairs.keys().collect();
+        let handler = || EventHandler::Conditional(Box::new(pairs::PairKey { pairs: pairs.clone(), edited: self.edited.clone() }));
+        for key in keys {
+            self.editor.bind_sequence(KeyEvent::new(key, Modifiers::NONE), handler());
+        }
+        self.editor.bind_sequence(KeyEvent(KeyCode::Backspace, Modifiers::NONE), handler());
+        self
+    }
+
+    /// Adds an abbreviation, which expands in place when space is typed
+    /// after it as the first word of a line, or when the line is entered
+    /// with nothing else on it.
+    ///
+    /// Unlike an [alias](Repl::with_config), the expansion is shown on the
+    /// line, so the user can see and edit it before running the command,
+    /// and history holds the command as it ran. Abbreviations can also be
+    /// configured in the `[abbreviations]` table.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The word to expand
+    /// * `expansion` - The text that replaces it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// // Typing `k ` leaves `kubectl ` on the line.
+    /// let repl = Repl::new("> ", MyHandler)
+    ///     .unwrap()
+    ///     .with_abbreviation("k", "kubectl")
+    ///     .with_abbreviation("gco", "git checkout");
+    /// ```
+    #[cfg(feature = "rustyline")]
+    pub fn with_abbreviation(mut self, name: impl Into<String>, expansion: impl Into<String>) -> Self {
+        self.add_abbreviations([(name.into(), expansion.into())]);
+        self
+    }
+
+    /// Adds abbreviations, binding space to expand them the first time.
+    #[cfg(feature = "rustyline")]
+    fn add_abbreviations(&mut self, added: impl IntoIterator<Item = (String, String)>) {
+        use rustyline::{EventHandler, KeyEvent, Modifiers};
+
+        let mut map = self.abbreviations.lock().unwrap_or_else(|e| e.into_inner());
+        let unbound = map.is_empty();
+        map.extend(added);
+        if unbound && !map.is_empty() {
+            let key = abbreviations::SpaceKey { abbreviations: self.abbreviations.clone(), edited: self.edited.clone() };
+            self.editor.bind_sequence(KeyEvent::new(' ', Modifiers::NONE), EventHandler::Conditional(Box::new(key)));
+        }
+    }
+
+    /// Adds a snippet, a command template that the `snippet` built-in of
+    /// [`Builtin::Snippets`] puts on the line to edit at the next prompt.
+    ///
+    /// Fields of the template are written `<name>` or `<name:default>`.
+    /// The first field is replaced by its default with the cursor after
+    /// it, and Tab moves on to the next one; fields left when the line is
+    /// entered are replaced by their defaults. Snippets can also be
+    /// configured in the `[snippets]` table.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name given to `snippet`
+    /// * `template` - The command line to insert
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// use mycli::repl::Builtin;
+    ///
+    /// // `snippet deploy` leaves `deploy staging ` to edit, then Tab moves
+    /// // to the version.
+    /// let repl = Repl::new("> ", MyHandler)
+    ///     .unwrap()
+    ///     .with_builtin(Builtin::Snippets)
+    ///     .with_snippet("deploy", "deploy <env:staging> <version>");
+    /// ```
+    #[cfg(feature = "rustyline")]
+    pub fn with_snippet(mut self, name: impl Into<String>, template: impl Into<String>) -> Self {
+        self.add_snippets([(name.into(), template.into())]);
+        self
+    }
+
+    /// Adds snippets, binding Tab to move between their fields the first
+    /// time.
+    #[cfg(feature = "rustyline")]
+    fn add_snippets(&mut self, added: impl IntoIterator<Item = (String, String)>) {
+        use rustyline::{EventHandler, KeyCode, KeyEvent, Modifiers};
+
+        let unbound = self.snippets.is_empty();
+        self.snippets.extend(added);
+        if unbound && !self.snippets.is_empty() {
+            let key = snippets::TabKey { filling: self.filling.clone(), edited: self.edited.clone() };
+            self.editor.bind_sequence(KeyEvent(KeyCode::Tab, Modifiers::NONE), EventHandler::Conditional(Box::new(key)));
+        }
+    }
+
+    /// Keeps command history in a file: history is loaded from it now and
+    /// saved to it when [`run`] returns.
+    ///
+    /// Commands enter the history with their secrets masked by the current
+    /// [redactor](crate::redact), so they are never saved as typed.
+    ///
+    /// [`run`]: Repl::run
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the history file, which need not exist yet
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// use mycli::dirs::AppDirs;
+    ///
+    /// let history = AppDirs::new("myapp").state_file("history").unwrap();
+    /// let mut repl = Repl::new("> ", MyHandler).unwrap().with_history_file(history);
+    /// repl.run().unwrap();
+    /// ```
+    #[cfg(feature = "rustyline")]
+    pub fn with_history_file(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let _ = self.load_history(&path);
+        self.history_file = Some(path);
+        self
+    }
+
+    /// Appends each command to the [history file](Repl::with_history_file)
+    /// soon after it is entered, instead of only when [`run`](Repl::run)
+    /// returns, so history survives a crash or a killed terminal.
+    ///
+    /// Commands are written by a background thread in batches: the first
+    /// command after a quiet period starts a batch, and everything entered
+    /// within `interval` of it is written and synced to disk together, so a
+    /// burst of commands costs one write.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - How long to collect commands before writing them
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// use std::time::Duration;
+    ///
+    /// let mut repl = Repl::new("> ", MyHandler)
+    ///     .unwrap()
+    ///     .with_history_file(".my_app_history")
+    ///     .with_history_autosave(Duration::from_secs(2));
+    /// repl.run().unwrap();
+    /// ```
+    #[cfg(feature = "rustyline")]
+    pub fn with_history_autosave(mut self, interval: Duration) -> Self {
+        self.autosave_interval = Some(interval);
+        self
+    }
+
+    /// Replaces Ctrl+R's search with a [fuzzy picker](crate::prompt::FuzzySelect)
+    /// over the whole history, newest first and each command once, starting
+    /// from the line being edited. Commands entered during the session show
+    /// when they were entered and whether they failed. The chosen command is
+    /// put on the line to edit, and Escape returns to the line as it was.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// let mut repl = Repl::new("> ", MyHandler)
+    ///     .unwrap()
+    ///     .with_history_file(".my_app_history")
+    ///     .with_history_search();
+    /// repl.run().unwrap();
+    /// ```
+    #[cfg(all(feature = "rustyline", feature = "menu"))]
+    pub fn with_history_search(mut self) -> Self {
+        use rustyline::{EventHandler, KeyEvent};
+
+        let key = history_search::SearchKey { edited: self.edited.clone() };
+        self.editor.bind_sequence(KeyEvent::ctrl('R'), EventHandler::Conditional(Box::new(key)));
+        self
+    }
+
+    /// (Re)starts appending history to the current history file.
+    #[cfg(feature = "rustyline")]
+    fn start_autosave(&mut self) {
+        self.autosave = None;
+        if let (Some(interval), Some(file)) = (self.autosave_interval, &self.history_file) {
+            self.autosave = Some(history::Autosave::start(file.clone(), interval));
+        }
+    }
+
+    /// Loads command history from a file.
+    ///
+    /// This allows users to access previously entered commands across sessions
+    /// using the up/down arrow keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the history file
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if the file cannot be read.
+    /// It's safe to ignore errors if the file doesn't exist yet.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// let mut repl = Repl::new("> ", MyHandler).unwrap();
+    /// let _ = repl.load_history(".my_app_history");
+    /// ```
+    #[cfg(feature = "rustyline")]
+    pub fn load_history(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let loaded = self.editor.load_history(path);
+        debug_event!(path = %path.display(), ok = loaded.is_ok(), entries = rustyline::history::History::len(self.editor.history()), "loaded history");
+        loaded
+    }
+
+    /// Saves command history to a file.
+    ///
+    /// This should typically be called when the REPL exits to persist
+    /// the command history for future sessions.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path where the history file should be saved
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if the file cannot be written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// let mut repl = Repl::new("> ", MyHandler).unwrap();
+    /// // ... run the REPL ...
+    /// let _ = repl.save_history(".my_app_history");
+    /// ```
+    #[cfg(feature = "rustyline")]
+    pub fn save_history(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let saved = self.editor.save_history(path);
+        debug_event!(path = %path.display(), ok = saved.is_ok(), "saved history");
+        saved
+    }
+
+    /// Starts the REPL loop, processing commands until termination.
+    ///
+    /// The loop continues until:
+    /// - The command handler returns `false`
+    /// - The user presses Ctrl+D (EOF)
+    /// - A readline error occurs that the [`ErrorRenderer`] treats as fatal
+    ///
+    /// Ctrl+C (Interrupt) is caught and ignored, allowing the REPL to continue.
+    /// Empty commands (whitespace-only input) are ignored.
+    ///
+    /// Lines are read with a line editor. On a dumb terminal, such as an
+    /// Emacs shell buffer (`TERM=dumb`), and without the `rustyline`
+    /// feature, they are read from stdin as they are typed, with no editing
+    /// or history, by a [`StreamReader`].
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` when the REPL exits normally, or an error if a
+    /// critical readline error occurs.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { true }
+    /// # }
+    /// let mut repl = Repl::new("> ", MyHandler).unwrap();
+    /// repl.run().unwrap();
+    /// ```
+    pub fn run(&mut self) -> Result<()> {
+        #[cfg(feature = "rustyline")]
+        if !reader::is_dumb_terminal() {
+            return self.run_loop(None);
+        }
+        let mut stdin = StreamReader::new(io::stdin(), self.output.clone());
+        self.run_loop(Some(&mut stdin))
+    }
+
+    /// Runs the REPL loop as [`run`](Self::run) does, reading lines from
+    /// `reader` instead of the terminal.
+    ///
+    /// Lines still go through history, aliases and built-ins, and the loop
+    /// ends the same way: when the command handler returns `false`, or on
+    /// EOF or another read error. Interrupts are ignored.
+    pub fn run_with(&mut self, reader: &mut dyn LineReader) -> Result<()> {
+        self.run_loop(Some(reader))
+    }
+
+    /// Runs the REPL loop as [`run`](Self::run) does, over `reader` and
+    /// `writer` instead of the terminal, for embedding it in a PTY, a test
+    /// harness or a network stream.
+    ///
+    /// Prompts and all output, errors included, are written to `writer`,
+    /// which replaces the REPL's output. To keep an output set up with
+    /// [`with_output`](Self::with_output), give a [`StreamReader`] over it
+    /// to [`run_with`](Self::run_with) instead. The loop ends at the end of
+    /// `reader`, or when the command handler returns `false`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Where lines are read from
+    /// * `writer` - Where prompts and output are written
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, ctx: &mycli::repl::Context) -> bool {
+    /// #         let _ = ctx.output().println(&format!("ran {command}"));
+    /// #         true
+    /// #     }
+    /// # }
+    /// let (mut from_repl, writer) = std::io::pipe()?;
+    /// let mut repl = Repl::new("> ", MyHandler).unwrap();
+    /// repl.run_on("status\n".as_bytes(), writer).unwrap();
+    /// drop(repl);
+    ///
+    /// let mut text = String::new();
+    /// std::io::Read::read_to_string(&mut from_repl, &mut text)?;
+    /// assert_eq!(text, "> ran status\n> ");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn run_on(&mut self, reader: impl io::Read, writer: impl io::Write + Send + 'static) -> Result<()> {
+        self.output = Output::new(crate::output::WriterSink::new(writer));
+        // Job output goes to `writer` too, not to the line editor's terminal.
+        self.print_through_output();
+        let mut reader = StreamReader::new(reader, self.output.clone());
+        self.run_with(&mut reader)
+    }
+
+    /// Adds `line` to the history, and to the history file when it is saved
+    /// as commands are entered.
+    #[cfg(feature = "rustyline")]
+    fn add_history_entry(&mut self, line: &str) {
+        // Untrimmed at the start, so `ignore_space` can see it.
+        let entry = redact::current().redact(line.trim_end()).into_owned();
+        if self.editor.add_history_entry(&entry).unwrap_or(false)
+            && let Some(autosave) = &self.autosave
+        {
+            autosave.push(&entry);
+        }
+    }
+
+    /// Runs the loop, reading lines from `reader`, or from the line editor
+    /// if there is none.
+    fn run_loop(&mut self, mut reader: Option<&mut dyn LineReader>) -> Result<()> {
+        #[cfg(feature = "rustyline")]
+        self.start_autosave();
+        self.running = true;
+        crate::output::term::keep_watching();
+        #[cfg(feature = "update")]
+        self.check_for_update();
+        self.show_title(None);
+        loop {
+            if let Some(status) = &mut self.status {
+                let _ = status.draw();
+            }
+            #[cfg(feature = "config")]
+            self.reload_if_changed();
+
+            if self.marks {
+                let _ = osc::mark("A");
+            }
+            let prompt = placeholders::expand(&self.prompt, |name| self.placeholder(name));
+            let readline = match reader.as_deref_mut() {
+                Some(reader) => reader.readline(&prompt),
+                #[cfg(feature = "rustyline")]
+                None => reprompt::read_line(&mut self.editor, &self.cleared, &self.edited, &prompt)
+                    .map(|line| abbreviations::expand(&self.abbreviations, &line).unwrap_or(line))
+                    .map(|line| snippets::finish(&self.filling, line)),
+                #[cfg(not(feature = "rustyline"))]
+                None => Err(ReadlineError::Eof),
+            };
+
+            match readline {
+                Ok(line) => {
+                    let cmd = line.trim();
+
+                    if cmd.is_empty() {
+                        continue;
+                    }
+
+                    #[cfg(feature = "rustyline")]
+                    self.add_history_entry(&line);
+                    #[cfg(all(feature = "rustyline", feature = "menu"))]
+                    let entered = self.clock.now();
+                    self.show_title(Some(cmd));
+                    if self.marks {
+                        let _ = osc::mark("C");
+                    }
+                    let errors = self.output.errors_written();
+                    let keep_going = self.execute(cmd);
+                    if self.marks {
+                        let failed = self.output.errors_written() > errors;
+                        let _ = osc::mark(if failed { "D;1" } else { "D;0" });
+                    }
+                    #[cfg(all(feature = "rustyline", feature = "menu"))]
+                    self.editor.history_mut().ran(history::Ran { at: entered, status: self.last_status });
+                    self.show_title(None);
+                    if !keep_going {
+                        break;
+                    }
+                }
+                Err(ReadlineError::Interrupted) => {
+                    #[cfg(feature = "rustyline")]
+                    if self.cleared.lock().unwrap_or_else(|e| e.into_inner()).is_some() {
+                        self.clear_screen();
+                    } else {
+                        self.filling.store(false, std::sync::atomic::Ordering::Relaxed);
+                    }
+                    continue;
+                }
+                Err(ReadlineError::Eof) => {
+                    break;
+                }
+                Err(err) => {
+                    let _ = self.output.eprintln(&self.errors.render_readline(&err));
+                    if self.errors.is_fatal(&err) {
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.jobs.cancel_all();
+        if let Some(status) = &mut self.status {
+            let _ = status.clear();
+        }
+        #[cfg(feature = "rustyline")]
+        {
+            // Waits for queued history lines before the file is rewritten.
+            self.autosave = None;
+            if let Some(file) = self.history_file.clone() {
+                let _ = self.save_history(file);
+            }
+        }
+        #[cfg(feature = "config")]
+        self.save_session();
+        self.running = false;
+        if std::mem::take(&mut self.titled) {
+            let _ = osc::restore_title();
+        }
+        Ok(())
+    }
+
+    /// Shows the title with the running `command`, if a title is set and
+    /// the REPL is running, saving the terminal's own title first.
+    fn show_title(&mut self, command: Option<&str>) {
+        let Some(title) = self.title.as_deref().filter(|_| self.running) else { return };
+        let mut title = title.to_string();
+        #[cfg(feature = "config")]
+        if let Some(profile) = self.profile() {
+            title = format!("{title} [{profile}]");
+        }
+        if let Some(command) = command {
+            let name = command.split_whitespace().next().unwrap_or(command);
+            title = format!("{title}: {name}");
+        }
+        if !self.titled {
+            self.titled = osc::save_title().is_ok();
+        }
+        let _ = osc::set_title(&title);
+    }
+
+    /// Changes the title to `title`; an empty one restores the terminal's
+    /// own.
+    #[cfg(feature = "config")]
+    fn set_title(&mut self, title: String) {
+        self.title = Some(title).filter(|title| !title.is_empty());
+        if self.title.is_none() {
+            if std::mem::take(&mut self.titled) {
+                let _ = osc::restore_title();
+            }
+            return;
+        }
+        self.show_title(None);
+    }
+
+    /// Runs commands read from `input`, one per line, without prompting,
+    /// as if the user had typed them.
+    ///
+    /// Blank lines are skipped and nothing is added to the history. The
+    /// line buffer is reused, so feeding large scripts allocates only
+    /// when an alias is expanded or a command does so itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Where to read the commands from, such as stdin or a file
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` at the end of `input` or when the handler returns
+    /// `false`, or an error if reading fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mycli::repl::{Repl, CommandHandler};
+    /// # struct MyHandler;
+    /// # impl CommandHandler for MyHandler {
+    /// #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { command != "quit" }
+    /// # }
+    /// let script = "status\n\nquit\nnever run\n";
+    /// let mut repl = Repl::new("> ", MyHandler).unwrap();
+    /// repl.run_batch(script.as_bytes()).unwrap();
+    /// ```
+    pub fn run_batch(&mut self, mut input: impl BufRead) -> io::Result<()> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                break;
+            }
+            let cmd = line.trim();
+            if !cmd.is_empty() && !self.execute(cmd) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Gives commands `user`, the identity a remote client authenticated
+    /// as, through [`Context::user`], and records them in the audit log as
+    /// run by that user.
+    #[cfg(feature = "remote")]
+    pub(crate) fn authenticated_as(&mut self, user: &str) {
+        self.user = Some(user.to_string());
+        #[cfg(feature = "audit")]
+        if let Some(log) = self.audit.take() {
+            self.audit = Some(log.with_user(user));
+        }
+    }
+
+    /// Returns the prompt shown before each command.
+    #[cfg(any(feature = "remote", feature = "tui"))]
+    pub(crate) fn prompt_text(&self) -> Cow<'_, str> {
+        placeholders::expand(&self.prompt, |name| self.placeholder(name))
+    }
+
+    /// Prints from other threads through the REPL's output, for when the
+    /// line editor does not own the terminal.
+    pub(crate) fn print_through_output(&mut self) {
+        self.printer = Some(ReplPrinter::fallback(self.output.clone()));
+    }
+
+    /// Reads the time from `clock` instead of the system's.
+    #[cfg(feature = "testing")]
+    pub(crate) fn use_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    /// Returns the prompt shown before each line.
+    #[cfg(feature = "testing")]
+    pub(crate) fn prompt(&self) -> Cow<'_, str> {
+        placeholders::expand(&self.prompt, |name| self.placeholder(name))
+    }
+
+    /// Returns the text of the prompt placeholder `{name}`, or `None` if
+    /// there is no such placeholder.
+    fn placeholder(&self, name: &str) -> Option<String> {
+        if let Some(value) = self.placeholders.get(name) {
+            return Some(value());
+        }
+        Some(match name {
+            "cwd" => self.working_dir.display(),
+            "time" => placeholders::local_time(self.clock.now()),
+            "status" => self.last_status.to_string(),
+            "took" => self.last_took.map(format_elapsed).unwrap_or_default(),
+            "mode" => if self.dry_run.is_on() { "dry-run" } else { "" }.to_string(),
+            #[cfg(feature = "git")]
+            "git" => self.git.get(self.working_dir.get()).map(|status| status.to_string()).unwrap_or_default(),
+            _ => return None,
+        })
+    }
+
+    /// Reports `error` as error output, formatted by the error renderer.
+    fn print_error(&self, error: CodedError) {
+        let _ = self.output.report_as(&error, &self.errors.render_error(&error));
+    }
+
+    /// Returns `true` if `cmd` may run, and reports it as refused
+    /// otherwise.
+    fn allows(&self, cmd: &str) -> bool {
+        let Some(allowed) = &self.allowed else { return true };
+        let name = cmd.split_whitespace().next().unwrap_or(cmd);
+        if allowed.contains(name) {
+            return true;
+        }
+        self.print_error(CodedError::new("command.not_allowed", text("command.not_allowed", &[("command", &name)])).detail("command", name));
+        false
+    }
+
+    /// Returns what background jobs started now see of the REPL.
+    fn job_env(&self) -> jobs::JobEnv {
+        jobs::JobEnv {
+            dry_run: self.dry_run.is_on(),
+            clock: self.clock.clone(),
+            rng: self.rng.clone(),
+            working_dir: self.working_dir.clone(),
+        }
+    }
+
+    /// Stops the background jobs and the command running, when a remote
+    /// session ends.
+    #[cfg(any(feature = "remote", feature = "rpc"))]
+    pub(crate) fn cancel_jobs(&mut self) {
+        self.jobs.cancel_all();
+        self.cancel.cancel();
+    }
+
+    /// Runs one trimmed, non-empty line after rewriting it and expanding
+    /// aliases. Returns `false` if the REPL should exit.
+    pub(crate) fn execute(&mut self, line: &str) -> bool {
+        let line = rewrite::apply(&self.rewrites, line);
+        let line = line.trim();
+        if line.is_empty() {
+            return true;
+        }
+        let mut cmd = std::mem::take(&mut self.line);
+        expand_alias_into(&self.aliases, line, &mut cmd);
+        let keep_going = self.dispatch(&cmd);
+        self.line = cmd;
+        keep_going
+    }
+
+    fn dispatch(&mut self, cmd: &str) -> bool {
+        let span = trace::CommandSpan::new(cmd);
+        let started = self.clock.instant();
+        let errors = self.output.errors_written();
+        let (keep_going, outcome) = span.in_scope(|| {
+            #[cfg(feature = "clipboard")]
+            if self.builtins.contains(&Builtin::Clipboard) && cmd.split_whitespace().next() != Some("copy") {
+                return self.run_keeping_output(cmd);
+            }
+            self.run_command(cmd)
+        });
+        let took = self.clock.instant() - started;
+        let failed = self.output.errors_written() > errors;
+        self.last_status = i32::from(failed);
+        self.last_took = Some(took);
+        self.usage.record(cmd, took, failed);
+        #[cfg(feature = "git")]
+        self.git.clear();
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.observe(cmd, took, failed);
+        }
+        #[cfg(feature = "audit")]
+        if let Some(log) = &self.audit
+            && let Err(err) = log.record(cmd, i32::from(failed))
+        {
+            self.print_error(CodedError::new("audit.write_failed", text("audit.write_failed", &[("error", &err)])));
+        }
+        let report = self.timing || self.report_time.is_some_and(|threshold| took >= threshold);
+        if report && matches!(outcome, "ok" | "exit") {
+            let took = text("command.took", &[("elapsed", &format_elapsed(took))]);
+            let _ = self.output.eprintln(&theme::current().paint(Tone::Muted, &took));
+        }
+        let _ = self.output.flush();
+        if let Some(after) = self.notify_after
+            && took >= after
+            && outcome != "background"
+            && self.output.is_terminal()
+        {
+            let name = cmd.split_whitespace().next().unwrap_or(cmd);
+            let ended = if failed { "command.failed_after" } else { "command.finished_after" };
+            let _ = osc::notify(&text(ended, &[("command", &name), ("elapsed", &format_elapsed(took))]));
+        }
+        span.finish(outcome);
+        keep_going
+    }
+
+    /// Runs `cmd` and returns whether to keep going and how it ended.
+    fn run_command(&mut self, cmd: &str) -> (bool, &'static str) {
+        if let Some(cmd) = dry_run::strip_flag(cmd) {
+            let was_on = self.dry_run.replace(true);
+            let ran = self.run_command(&cmd);
+            self.dry_run.set(was_on);
+            return ran;
+        }
+        if !self.allows(cmd) {
+            return (true, "refused");
+        }
+        if self.run_builtin(cmd) {
+            debug_event!("ran as a built-in");
+            return (true, "builtin");
+        }
+        let execution = self.handler.execution(cmd);
+        if execution == Execution::NonBlocking
+            && let Some(job) = self.handler.background(cmd)
+        {
+            let printer = self.printer();
+            self.jobs.spawn(cmd, job, printer, self.job_env());
+            debug_event!("started in the background");
+            return (true, "background");
+        }
+        // Only a timeout the handler gives this command, or the REPL's for a
+        // cancellable one, moves it onto a worker thread.
+        if let Some(limit) = self.handler.timeout(cmd).or(self.timeout.filter(|_| execution == Execution::Cancellable))
+            && let Some(job) = self.handler.background(cmd)
+        {
+            return self.run_with_timeout(cmd, job, limit);
+        }
+        self.cancel.reset();
+        let ctx = Context {
+            output: Cow::Borrowed(&self.output),
+            working_dir: Cow::Borrowed(&self.working_dir),
+            cancel: Cow::Borrowed(&self.cancel),
+            dry_run: self.dry_run.is_on(),
+            allowed: self.allowed.as_ref(),
+            user: self.user.as_deref(),
+            #[cfg(feature = "config")]
+            config: Cow::Borrowed(&self.settings),
+        };
+        if self.catch_panics {
+            return match panics::catch(|| self.handler.handle(cmd, &ctx)) {
+                Ok(keep_going) => (keep_going, if keep_going { "ok" } else { "exit" }),
+                Err(panic) => {
+                    let name = cmd.split_whitespace().next().unwrap_or(cmd);
+                    self.print_error(CodedError::new("command.panicked", text("command.panicked", &[("command", &name), ("panic", &panic)])).detail("command", name));
+                    (true, "panicked")
+                }
+            };
+        }
+        let keep_going = self.handler.handle(cmd, &ctx);
+        (keep_going, if keep_going { "ok" } else { "exit" })
+    }
+
+    /// Runs `job` for `cmd` in the foreground, cancelling it after `limit`.
+    fn run_with_timeout(&mut self, cmd: &str, job: Job, limit: Duration) -> (bool, &'static str) {
+        let printer = self.printer();
+        match jobs::run_with_timeout(job, limit, printer, self.job_env()) {
+            Some(Ok(output)) => {
+                if !output.is_empty() {
+                    let _ = self.output.println(output.trim_end_matches('\n'));
+                }
+                (true, "ok")
+            }
+            Some(Err(err)) => {
+                let name = cmd.split_whitespace().next().unwrap_or(cmd);
+                self.print_error(CodedError::new("command.failed", text("command.failed", &[("command", &name), ("error", &err)])).detail("command", name));
+                (true, "failed")
+            }
+            None => {
+                let name = cmd.split_whitespace().next().unwrap_or(cmd);
+                let message = text("command.timed_out", &[("command", &name), ("timeout", &format_elapsed(limit))]);
+                self.print_error(CodedError::new("command.timed_out", message).detail("command", name));
+                (true, "timeout")
+            }
+        }
+    }
+
+    #[cfg(feature = "config")]
+    fn save_session(&self) {
+        let Some(path) = &self.session_file else { return };
+        let configured = self.live.as_ref().map(|live| &live.applied().aliases);
+        let mut state = SessionState {
+            aliases: self
+                .aliases
+                .iter()
+                .filter(|(name, expansion)| configured.and_then(|c| c.get(*name)) != Some(*expansion))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            cwd: Some(self.working_dir.get()).filter(|cwd| !cwd.as_os_str().is_empty()),
+            ..SessionState::default()
+        };
+        self.handler.save_session(&mut state);
+        if let Err(err) = state.save(path) {
+            self.print_error(CodedError::new("session.save_failed", text("session.save_failed", &[("error", &err)])).detail("path", path.display()));
+        }
+    }
+
+    #[cfg(feature = "config")]
+    fn reload_if_changed(&mut self) {
+        let Some(live) = &self.live else { return };
+        if !live.changed() {
+            return;
+        }
+        let path = live.path.clone().unwrap_or_default();
+        match self.reload_config() {
+            Ok(true) => {
+                if let Some(live) = &self.live {
+                    self.settings.theme = live.applied().theme.clone();
+                    self.settings.colors = live.applied().colors.clone();
+                }
+                if let Some(file) = self.live.as_ref().and_then(|live| live.file()) {
+                    self.settings.profiles = file.profiles.clone();
+                }
+                let _ = self.output.info(&text("config.reloaded", &[("path", &path.display())]));
+            }
+            Ok(false) => {}
+            Err(err) => {
+                self.print_error(CodedError::new("config.reload_failed", err.to_string()).detail("path", path.display()));
+            }
+        }
+    }
+
+}
+
+/// Replaces the first word of `line` with its alias expansion, if it has one.
+fn expand_alias<'a>(aliases: &HashMap<String, String>, line: &'a str) -> Cow<'a, str> {
+    let (word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    match aliases.get(word) {
+        Some(expansion) if rest.is_empty() => Cow::Owned(expansion.clone()),
+        Some(expansion) => Cow::Owned(format!("{expansion} {rest}")),
+        None => Cow::Borrowed(line),
+    }
+}
+
+/// Writes `line` into `buf`, with the first word replaced by its alias
+/// expansion if it has one.
+fn expand_alias_into(aliases: &HashMap<String, String>, line: &str, buf: &mut String) {
+    buf.clear();
+    let (word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    match aliases.get(word) {
+        Some(expansion) => {
+            buf.push_str(expansion);
+            if !rest.is_empty() {
+                buf.push(' ');
+                buf.push_str(rest);
+            }
+        }
+        None => buf.push_str(line),
+    }
+}
+
+/// Formats `elapsed` with a precision that suits its size, such as `850ms`,
+/// `4.2s` or `2m05s`.
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs_f64();
+    if secs < 1.0 {
+        format!("{}ms", elapsed.as_millis())
+    } else if secs < 60.0 {
+        format!("{secs:.1}s")
+    } else {
+        let secs = elapsed.as_secs();
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
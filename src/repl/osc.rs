@@ -0,0 +1,115 @@
+//! Escape sequences sent straight to the terminal, such as clipboard
+//! updates and notifications.
+//!
+//! They go to stdout rather than through the REPL's output, so they are
+//! never stripped or captured, and only when stdout is a terminal. Inside
+//! tmux they are wrapped to pass through to the outer terminal.
+
+use std::io::{self, IsTerminal, Write};
+
+/// Writes the OSC sequence with the payload `payload`, such as `9;done`.
+pub(super) fn send(payload: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    if !stdout.is_terminal() {
+        return Err(io::Error::other("there is no terminal to send it through"));
+    }
+    let sequence = format!("\x1b]{payload}\x07");
+    if in_tmux() {
+        write!(stdout, "\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))?;
+    } else {
+        stdout.write_all(sequence.as_bytes())?;
+    }
+    stdout.flush()
+}
+
+/// Rings the bell and shows `message` as a desktop notification, with
+/// OSC 9. Terminals that support it show notifications while their window
+/// is not focused; the others at least mark the window or tab.
+pub(super) fn notify(message: &str) -> io::Result<()> {
+    let message: String = message.chars().filter(|c| !c.is_control()).collect();
+    send(&format!("9;{message}"))?;
+    let mut stdout = io::stdout();
+    stdout.write_all(b"\x07")?;
+    stdout.flush()
+}
+
+/// Clears the screen and moves the cursor to its top left corner.
+pub(super) fn clear_screen() -> io::Result<()> {
+    let mut stdout = io::stdout();
+    if !stdout.is_terminal() {
+        return Ok(());
+    }
+    stdout.write_all(b"\x1b[H\x1b[2J")?;
+    stdout.flush()
+}
+
+/// Moves the cursor up `rows` rows and erases everything from there.
+#[cfg(feature = "rustyline")]
+pub(super) fn erase_rows(rows: usize) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    if !stdout.is_terminal() {
+        return Ok(());
+    }
+    write!(stdout, "\x1b[{rows}A\r\x1b[J")?;
+    stdout.flush()
+}
+
+/// Sets the window title, and the window name when running in tmux.
+pub(super) fn set_title(title: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    if !stdout.is_terminal() {
+        return Ok(());
+    }
+    let title: String = title.chars().filter(|c| !c.is_control()).collect();
+    write!(stdout, "\x1b]2;{title}\x07")?;
+    if in_tmux() {
+        write!(stdout, "\x1bk{title}\x1b\\")?;
+    }
+    stdout.flush()
+}
+
+/// Saves the window title on the terminal's title stack, to be restored by
+/// [`restore_title`].
+pub(super) fn save_title() -> io::Result<()> {
+    let mut stdout = io::stdout();
+    if !stdout.is_terminal() {
+        return Ok(());
+    }
+    stdout.write_all(b"\x1b[22;0t")?;
+    stdout.flush()
+}
+
+/// Restores the window title saved by [`save_title`], and lets tmux name
+/// the window again.
+pub(super) fn restore_title() -> io::Result<()> {
+    let mut stdout = io::stdout();
+    if !stdout.is_terminal() {
+        return Ok(());
+    }
+    stdout.write_all(b"\x1b[23;0t")?;
+    stdout.flush()?;
+    if in_tmux() {
+        // Naming the window turned automatic renaming off for it.
+        let _ = std::process::Command::new("tmux")
+            .args(["set-window-option", "-u", "automatic-rename"])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status();
+    }
+    Ok(())
+}
+
+/// Writes the OSC 133 shell integration mark `mark`, such as `A` before
+/// the prompt. tmux understands these itself, so they are not wrapped.
+pub(super) fn mark(mark: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    if !stdout.is_terminal() {
+        return Ok(());
+    }
+    write!(stdout, "\x1b]133;{mark}\x07")?;
+    stdout.flush()
+}
+
+fn in_tmux() -> bool {
+    std::env::var_os("TMUX").is_some_and(|tmux| !tmux.is_empty())
+}
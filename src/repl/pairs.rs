@@ -0,0 +1,154 @@
+//! Closing brackets and quotes as they are typed.
+
+use rustyline::{Cmd, ConditionalEventHandler, Event, EventContext, InputMode, KeyCode, KeyEvent, Movement, RepeatCount};
+
+use super::reprompt::{self, Edited};
+
+/// The pairs of characters that [`Repl::with_auto_pairs`] completes while
+/// a line is edited.
+///
+/// Typing an opening character also inserts its closing one after the
+/// cursor, typing a closing character right before the same one steps over
+/// it, and Backspace between an empty pair deletes both. The closing
+/// character is only added before whitespace, the end of the line or
+/// another closing character, and a quote only after something other than
+/// a letter or digit, so `don't` and `f(x` typed before text are left
+/// alone.
+///
+/// The default pairs suit commands that take code or quoted arguments:
+/// `()`, `[]`, `{}`, `""` and `''`. Applications whose input is mostly
+/// prose can start from [`AutoPairs::new`] and add only the pairs they
+/// want.
+///
+/// [`Repl::with_auto_pairs`]: super::Repl::with_auto_pairs
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::AutoPairs;
+///
+/// // Brackets only, leaving apostrophes in sentences alone.
+/// let pairs = AutoPairs::new().pair('(', ')').pair('[', ']');
+/// assert_eq!(pairs.closing('('), Some(')'));
+/// assert_eq!(pairs.closing('\''), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoPairs {
+    pairs: Vec<(char, char)>,
+}
+
+impl Default for AutoPairs {
+    fn default() -> Self {
+        Self::new().pair('(', ')').pair('[', ']').pair('{', '}').pair('"', '"').pair('\'', '\'')
+    }
+}
+
+impl AutoPairs {
+    /// Creates a set without any pairs.
+    pub fn new() -> Self {
+        Self { pairs: Vec::new() }
+    }
+
+    /// Adds the pair `open` and `close`, which may be the same character,
+    /// as quotes are.
+    pub fn pair(mut self, open: char, close: char) -> Self {
+        self.pairs.retain(|&(o, _)| o != open);
+        self.pairs.push((open, close));
+        self
+    }
+
+    /// Returns the character that closes `open`, if it is the opening
+    /// character of a pair.
+    pub fn closing(&self, open: char) -> Option<char> {
+        self.pairs.iter().find(|&&(o, _)| o == open).map(|&(_, close)| close)
+    }
+
+    fn is_closing(&self, c: char) -> bool {
+        self.pairs.iter().any(|&(_, close)| close == c)
+    }
+
+    /// Returns the keys to bind: the opening and closing characters.
+    pub(super) fn keys(&self) -> impl Iterator<Item = char> + '_ {
+        let mut keys: Vec<char> = self.pairs.iter().flat_map(|&(open, close)| [open, close]).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        keys.into_iter()
+    }
+
+    /// Returns what typing `typed` does to `line` with the cursor at
+    /// `pos`, or `None` if it is inserted as usual.
+    fn edit(&self, line: &str, pos: usize, typed: Typed) -> Option<Action> {
+        let (before, after) = line.split_at(pos);
+        let prev = before.chars().next_back();
+        let next = after.chars().next();
+        match typed {
+            Typed::Backspace => {
+                let prev = prev?;
+                if self.closing(prev)? != next? {
+                    return None;
+                }
+                let start = pos - prev.len_utf8();
+                let end = pos + next?.len_utf8();
+                Some(Action::Edit(format!("{}{}", &line[..start], &line[end..]), start))
+            }
+            Typed::Char(c) => {
+                if next == Some(c) && self.is_closing(c) {
+                    return Some(Action::Skip);
+                }
+                let close = self.closing(c)?;
+                if next.is_some_and(|next| !next.is_whitespace() && !self.is_closing(next)) {
+                    return None;
+                }
+                if close == c && prev.is_some_and(|prev| prev.is_alphanumeric() || prev == c) {
+                    return None;
+                }
+                let mut edited = String::with_capacity(line.len() + c.len_utf8() + close.len_utf8());
+                edited.push_str(before);
+                edited.push(c);
+                edited.push(close);
+                edited.push_str(after);
+                Some(Action::Edit(edited, pos + c.len_utf8()))
+            }
+        }
+    }
+}
+
+enum Typed {
+    Char(char),
+    Backspace,
+}
+
+enum Action {
+    /// Step over the closing character at the cursor.
+    Skip,
+    /// Edit the line again as the given text, with the cursor at the given
+    /// position.
+    Edit(String, usize),
+}
+
+/// Completes pairs, bound to their characters and Backspace.
+///
+/// Stepping over a closing character is a cursor move; the other changes
+/// insert or delete on both sides of the cursor, which the line editor
+/// cannot do in one step, so they go through [`reprompt`].
+pub(super) struct PairKey {
+    pub(super) pairs: AutoPairs,
+    pub(super) edited: Edited,
+}
+
+impl ConditionalEventHandler for PairKey {
+    fn handle(&self, event: &Event, _: RepeatCount, _: bool, ctx: &EventContext) -> Option<Cmd> {
+        if ctx.input_mode() != InputMode::Insert {
+            return None;
+        }
+        let typed = match event.get(0)? {
+            KeyEvent(KeyCode::Backspace, _) => Typed::Backspace,
+            KeyEvent(KeyCode::Char(c), _) => Typed::Char(*c),
+            _ => return None,
+        };
+        match self.pairs.edit(ctx.line(), ctx.pos(), typed)? {
+            Action::Skip => Some(Cmd::Move(Movement::ForwardChar(1))),
+            Action::Edit(line, pos) => Some(reprompt::edit(&self.edited, ctx, line, pos)),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! Placeholders in the prompt, such as `{cwd}`, filled in each time it is
+//! shown.
+
+use std::borrow::Cow;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Computes the text of a placeholder registered with
+/// [`Repl::with_placeholder`](super::Repl::with_placeholder).
+pub(super) type Placeholder = dyn Fn() -> String + Send;
+
+/// Fills in the `{name}` placeholders in `template` with `value(name)`,
+/// leaving those it returns `None` for as they are.
+pub(super) fn expand<'a>(template: &'a str, value: impl Fn(&str) -> Option<String>) -> Cow<'a, str> {
+    if !template.contains('{') {
+        return Cow::Borrowed(template);
+    }
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let filled = after
+            .find('}')
+            .map(|end| &after[..end])
+            .filter(|name| !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.')))
+            .and_then(|name| Some((name, value(name)?)));
+        match filled {
+            Some((name, text)) => {
+                expanded.push_str(&text);
+                rest = &after[name.len() + 1..];
+            }
+            None => {
+                expanded.push('{');
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    Cow::Owned(expanded)
+}
+
+/// Formats `time` as `HH:MM:SS` in the local time zone, or in UTC where
+/// the time zone is not known.
+pub(super) fn local_time(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    #[cfg(unix)]
+    {
+        let since = secs as libc::time_t;
+        // SAFETY: `tm` is plain data and `localtime_r` only writes into it.
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        if !unsafe { libc::localtime_r(&since, &mut tm) }.is_null() {
+            return format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec);
+        }
+    }
+    format!("{:02}:{:02}:{:02}", secs / 3600 % 24, secs / 60 % 60, secs % 60)
+}
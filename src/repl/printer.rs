@@ -0,0 +1,110 @@
+//! Printing from other threads while the prompt is shown.
+
+use std::fmt;
+use std::io;
+#[cfg(feature = "rustyline")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "rustyline")]
+use rustyline::ExternalPrinter;
+
+#[cfg(feature = "rustyline")]
+use super::history::LineEditor;
+use crate::locale::text;
+#[cfg(feature = "rustyline")]
+use crate::output::ansi;
+use crate::output::{Output, Verbosity};
+
+/// A handle for printing complete lines while the user may be typing.
+///
+/// Lines printed while the prompt is shown appear above it, and the prompt
+/// and the partly typed input are drawn again below them. Lines printed
+/// while a command runs are written like any other output. The handle is
+/// `Send`, and clones print through the same terminal, so background
+/// threads and tasks such as log tailers can each keep one.
+///
+/// When the REPL is not attached to a terminal, lines go to the REPL's
+/// [`Output`] instead.
+///
+/// Obtain one with [`Repl::printer`](super::Repl::printer).
+#[derive(Clone)]
+pub struct ReplPrinter {
+    #[cfg(feature = "rustyline")]
+    external: Option<Arc<Mutex<Box<dyn ExternalPrinter + Send>>>>,
+    output: Output,
+}
+
+impl fmt::Debug for ReplPrinter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReplPrinter").field("terminal", &self.on_terminal()).finish_non_exhaustive()
+    }
+}
+
+impl ReplPrinter {
+    #[cfg(feature = "rustyline")]
+    pub(super) fn new(editor: &mut LineEditor, output: Output) -> Self {
+        let external = editor
+            .create_external_printer()
+            .ok()
+            .map(|printer| Arc::new(Mutex::new(Box::new(printer) as Box<dyn ExternalPrinter + Send>)));
+        Self { external, output }
+    }
+
+    /// A printer that writes to `output`, for when there is no editor to
+    /// print through.
+    pub(super) fn fallback(output: Output) -> Self {
+        Self {
+            #[cfg(feature = "rustyline")]
+            external: None,
+            output,
+        }
+    }
+
+    /// Returns `true` if lines are printed above the prompt of a line
+    /// editor.
+    #[cfg(feature = "rustyline")]
+    fn on_terminal(&self) -> bool {
+        self.external.is_some()
+    }
+
+    #[cfg(not(feature = "rustyline"))]
+    fn on_terminal(&self) -> bool {
+        false
+    }
+
+    /// Prints `line` followed by a newline without disturbing the prompt.
+    pub fn println(&self, line: &str) -> io::Result<()> {
+        #[cfg(feature = "rustyline")]
+        if let Some(external) = &self.external {
+            let line = if self.output.color().strips(true) { ansi::strip(line) } else { line.into() };
+            let mut printer = external.lock().unwrap_or_else(|e| e.into_inner());
+            return printer.print(format!("{line}\n")).map_err(|err| match err {
+                super::ReadlineError::Io(err) => err,
+                err => io::Error::other(err),
+            });
+        }
+        self.output.println(line)
+    }
+
+    /// Prints `line` as error output. On a terminal it appears above the
+    /// prompt like [`println`](ReplPrinter::println).
+    pub fn eprintln(&self, line: &str) -> io::Result<()> {
+        if !self.on_terminal() {
+            return self.output.eprintln(line);
+        }
+        self.println(line)
+    }
+
+    /// Prints `message` as a [warning](Output::warn), unless the REPL's
+    /// output is quiet. On a terminal it appears above the prompt like
+    /// [`println`](ReplPrinter::println).
+    pub fn warn(&self, message: &str) -> io::Result<()> {
+        if !self.on_terminal() {
+            return self.output.warn(message);
+        }
+        if self.output.verbosity() < Verbosity::Normal {
+            return Ok(());
+        }
+        self.println(&text("warning", &[("message", &message)]))
+    }
+}
@@ -0,0 +1,141 @@
+//! Where the REPL loop reads its lines from.
+
+use std::io::{BufRead, BufReader, Read};
+
+#[cfg(feature = "rustyline")]
+use rustyline::history::History;
+#[cfg(feature = "rustyline")]
+use rustyline::{Editor, Helper};
+
+use crate::output::Output;
+
+#[cfg(feature = "rustyline")]
+pub use rustyline::error::ReadlineError;
+#[cfg(feature = "rustyline")]
+pub use rustyline::Result;
+
+/// An error reading a line, when the REPL is built without the `rustyline`
+/// feature. With it, this is rustyline's own error type.
+#[cfg(not(feature = "rustyline"))]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ReadlineError {
+    /// Reading failed.
+    Io(std::io::Error),
+    /// The input ended, as with Ctrl+D.
+    Eof,
+    /// The user pressed Ctrl+C.
+    Interrupted,
+}
+
+#[cfg(not(feature = "rustyline"))]
+impl std::fmt::Display for ReadlineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadlineError::Io(err) => err.fmt(f),
+            ReadlineError::Eof => f.write_str("EOF"),
+            ReadlineError::Interrupted => f.write_str("Interrupted"),
+        }
+    }
+}
+
+#[cfg(not(feature = "rustyline"))]
+impl std::error::Error for ReadlineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadlineError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(not(feature = "rustyline"))]
+impl From<std::io::Error> for ReadlineError {
+    fn from(err: std::io::Error) -> Self {
+        ReadlineError::Io(err)
+    }
+}
+
+/// The result of reading a line, or of setting up the REPL.
+#[cfg(not(feature = "rustyline"))]
+pub type Result<T> = std::result::Result<T, ReadlineError>;
+
+/// A source of lines for [`Repl::run_with`](super::Repl::run_with).
+///
+/// Implemented for rustyline's `Editor`, which [`Repl::run`](super::Repl::run)
+/// reads from, and for [`StreamReader`]. Other readers let the loop run
+/// without a terminal; the `testing` feature has a `MockReader` that
+/// replays scripted input.
+pub trait LineReader {
+    /// Shows `prompt` and reads a line. Ctrl+C is reported as
+    /// [`ReadlineError::Interrupted`] and Ctrl+D as [`ReadlineError::Eof`].
+    fn readline(&mut self, prompt: &str) -> Result<String>;
+}
+
+#[cfg(feature = "rustyline")]
+impl<H: Helper, I: History> LineReader for Editor<H, I> {
+    fn readline(&mut self, prompt: &str) -> Result<String> {
+        Editor::readline(self, prompt)
+    }
+}
+
+/// Returns `true` if the terminal cannot be driven by a line editor, as
+/// with `TERM=dumb` or in an Emacs shell buffer, so lines are read with a
+/// plain [`StreamReader`] instead.
+#[cfg(any(feature = "rustyline", feature = "async"))]
+pub(super) fn is_dumb_terminal() -> bool {
+    std::env::var_os("TERM").is_some_and(|term| term == "dumb")
+        || std::env::var("INSIDE_EMACS").is_ok_and(|emacs| emacs.contains("comint"))
+}
+
+/// Reads lines from any [`Read`], such as a pipe, a socket or the slave
+/// side of a PTY, showing prompts on an [`Output`].
+///
+/// There is no line editing or completion: the stream is expected to
+/// deliver whole lines, as a terminal in canonical mode does. The end of
+/// the stream is reported as [`ReadlineError::Eof`].
+/// [`Repl::run`](super::Repl::run) reads stdin with one on a dumb terminal,
+/// such as an Emacs shell buffer, and without the `rustyline` feature.
+/// [`Repl::run_on`](super::Repl::run_on) uses one with the output it writes
+/// to; create one yourself to keep an output set up with
+/// [`Repl::with_output`](super::Repl::with_output).
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::{BufferSink, Output};
+/// use mycli::repl::{LineReader, StreamReader};
+///
+/// let buffer = BufferSink::new();
+/// let mut reader = StreamReader::new("status\r\n".as_bytes(), Output::new(buffer.clone()));
+/// assert_eq!(reader.readline("> ").unwrap(), "status");
+/// assert!(reader.readline("> ").is_err());
+/// assert_eq!(buffer.contents(), "> > ");
+/// ```
+#[derive(Debug)]
+pub struct StreamReader<R> {
+    reader: BufReader<R>,
+    output: Output,
+}
+
+impl<R: Read> StreamReader<R> {
+    /// Creates a reader reading lines from `reader` and writing prompts to
+    /// `output`.
+    pub fn new(reader: R, output: Output) -> Self {
+        Self { reader: BufReader::new(reader), output }
+    }
+}
+
+impl<R: Read> LineReader for StreamReader<R> {
+    fn readline(&mut self, prompt: &str) -> Result<String> {
+        self.output.print(prompt)?;
+        self.output.flush()?;
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(ReadlineError::Eof);
+        }
+        let len = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(len);
+        Ok(line)
+    }
+}
@@ -0,0 +1,140 @@
+//! Reference documentation generated from a [`Registry`], so that the
+//! docs shipped with a release describe the commands it has.
+
+use super::{CommandInfo, Registry};
+
+impl Registry {
+    /// Returns a man page in section 1 for `program`, in troff, with a
+    /// section describing each command: its summary, usage, description
+    /// and examples.
+    ///
+    /// # Arguments
+    ///
+    /// * `program` - The name of the program, used as the page's title
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::repl::{CommandInfo, Registry};
+    ///
+    /// let commands = Registry::new().command(
+    ///     CommandInfo::new("deploy", "Deploys a service")
+    ///         .with_usage("deploy <service> [--canary]")
+    ///         .with_example("deploy web --canary", "Deploys web to the canary hosts only"),
+    /// );
+    /// let page = commands.generate_man("myapp");
+    /// assert!(page.starts_with(".TH \"MYAPP\" \"1\"\n"));
+    /// assert!(page.contains(".SS deploy\nDeploys a service\n"));
+    /// assert!(page.contains(".B deploy web \\-\\-canary\n"));
+    /// // `man -l` shows it, or save it as `myapp.1` in the release.
+    /// ```
+    pub fn generate_man(&self, program: &str) -> String {
+        let mut page = format!(".TH \"{}\" \"1\"\n.SH NAME\n{} \\- commands\n.SH COMMANDS\n", roff(&program.to_uppercase()), roff(program));
+        for info in self.iter() {
+            page.push_str(&format!(".SS {}\n{}\n", roff(info.name()), roff_text(info.summary())));
+            if let Some(usage) = info.usage() {
+                page.push_str(&format!(".PP\n.B {}\n", roff(usage)));
+            }
+            for paragraph in paragraphs(info) {
+                page.push_str(&format!(".PP\n{}\n", roff_text(&paragraph)));
+            }
+            if !info.examples().is_empty() {
+                page.push_str(".PP\nExamples:\n");
+                for example in info.examples() {
+                    page.push_str(&format!(".TP\n.B {}\n{}\n", roff(example.command()), roff_text(example.description())));
+                }
+            }
+        }
+        page
+    }
+
+    /// Returns a Markdown reference for `program`, with a section for each
+    /// command: its summary, usage, description and examples.
+    ///
+    /// # Arguments
+    ///
+    /// * `program` - The name of the program, used in the title
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::repl::{CommandInfo, Registry};
+    ///
+    /// let commands = Registry::new().command(
+    ///     CommandInfo::new("deploy", "Deploys a service")
+    ///         .with_usage("deploy <service> [--canary]")
+    ///         .with_example("deploy web --canary", "Deploys web to the canary hosts only"),
+    /// );
+    /// let reference = commands.generate_markdown("myapp");
+    /// assert!(reference.starts_with("# myapp commands\n\n## deploy\n\nDeploys a service\n"));
+    /// assert!(reference.contains("```text\ndeploy web --canary\n```\n"));
+    /// ```
+    pub fn generate_markdown(&self, program: &str) -> String {
+        let mut reference = format!("# {program} commands\n");
+        for info in self.iter() {
+            reference.push_str(&format!("\n## {}\n\n{}\n", info.name(), info.summary()));
+            if let Some(usage) = info.usage() {
+                reference.push_str(&format!("\n{}", fenced(usage)));
+            }
+            for paragraph in paragraphs(info) {
+                reference.push_str(&format!("\n{paragraph}\n"));
+            }
+            if !info.examples().is_empty() {
+                reference.push_str("\n### Examples\n");
+                for example in info.examples() {
+                    if !example.description().is_empty() {
+                        reference.push_str(&format!("\n{}\n", example.description()));
+                    }
+                    reference.push_str(&format!("\n{}", fenced(example.command())));
+                }
+            }
+        }
+        reference
+    }
+}
+
+/// Splits the description of `info` into paragraphs at blank lines.
+fn paragraphs(info: &CommandInfo) -> Vec<String> {
+    let description = info.description().unwrap_or("");
+    let mut paragraphs = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    for line in description.lines().map(str::trim).chain([""]) {
+        if line.is_empty() {
+            if !paragraph.is_empty() {
+                paragraphs.push(paragraph.join("\n"));
+                paragraph.clear();
+            }
+        } else {
+            paragraph.push(line);
+        }
+    }
+    paragraphs
+}
+
+/// Escapes `text` for a troff line: backslashes, hyphens and double
+/// quotes, which troff would otherwise take as escapes, dashes and quoted
+/// arguments.
+fn roff(text: &str) -> String {
+    text.replace('\\', "\\e").replace('-', "\\-").replace('"', "\\(dq")
+}
+
+/// Escapes `text` for troff filler text, where a line starting with `.`
+/// or `'` would also be taken as a request.
+fn roff_text(text: &str) -> String {
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| {
+            let line = roff(line);
+            if line.starts_with(['.', '\'']) { format!("\\&{line}") } else { line }
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Returns `text` in a fenced code block, with a fence longer than any run
+/// of backticks in it.
+fn fenced(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{fence}text\n{text}\n{fence}\n")
+}
@@ -0,0 +1,125 @@
+//! Picking up configuration changes while a session is running.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::config::{Config, ConfigError};
+use crate::output::theme::{self, Theme};
+
+/// The configuration a REPL has applied, and what it replaced.
+pub(crate) struct LiveConfig {
+    /// The file watched for changes, if any.
+    pub(crate) path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    /// The profile applied on top of the file, if any.
+    pub(crate) profile: Option<String>,
+    file: Option<Config>,
+    applied: Config,
+    prompt: String,
+    theme: Arc<Theme>,
+}
+
+impl LiveConfig {
+    /// Records `applied` as the configuration in effect over the
+    /// application's own `prompt` and the current theme.
+    pub(crate) fn new(applied: Config, prompt: String) -> Self {
+        Self {
+            path: None,
+            modified: None,
+            profile: None,
+            file: None,
+            applied,
+            prompt,
+            theme: theme::current(),
+        }
+    }
+
+    /// Returns the whole file as last reread, if it has been.
+    pub(crate) fn file(&self) -> Option<&Config> {
+        self.file.as_ref()
+    }
+
+    /// Returns the configuration in effect.
+    pub(crate) fn applied(&self) -> &Config {
+        &self.applied
+    }
+
+    /// Starts watching `path`, treating its current contents as applied.
+    pub(crate) fn watch(&mut self, path: PathBuf) {
+        self.modified = modified(&path);
+        self.path = Some(path);
+    }
+
+    /// Records that `key` was set to `value` in the watched file by the REPL
+    /// itself, so the change is not reloaded.
+    pub(crate) fn saved(&mut self, key: &str, value: &str) {
+        if let Some(path) = &self.path {
+            self.modified = modified(path);
+        }
+        let _ = self.applied.set(key, value);
+    }
+
+    /// Returns whether the watched file has changed since it was last read.
+    pub(crate) fn changed(&self) -> bool {
+        self.path.as_ref().is_some_and(|path| modified(path) != self.modified)
+    }
+
+    /// Rereads the watched file and applies its prompt, colors and aliases,
+    /// with the current profile on top.
+    ///
+    /// Returns `Ok(false)` when nothing that can be applied live changed.
+    pub(crate) fn reload(
+        &mut self,
+        prompt: &mut String,
+        aliases: &mut HashMap<String, String>,
+    ) -> Result<bool, ConfigError> {
+        let Some(path) = self.path.clone() else {
+            return Ok(false);
+        };
+        self.modified = modified(&path);
+        let file = Config::load(&path)?;
+        let config = match &self.profile {
+            Some(profile) => file.profile(profile)?,
+            None => file.clone(),
+        };
+        self.file = Some(file);
+        self.apply(config, prompt, aliases)
+    }
+
+    /// Applies the prompt, colors and aliases of `config` in place of those
+    /// applied before.
+    ///
+    /// Returns `Ok(false)` when none of them changed.
+    pub(crate) fn apply(
+        &mut self,
+        config: Config,
+        prompt: &mut String,
+        aliases: &mut HashMap<String, String>,
+    ) -> Result<bool, ConfigError> {
+        if config.prompt == self.applied.prompt
+            && config.theme == self.applied.theme
+            && config.colors == self.applied.colors
+            && config.aliases == self.applied.aliases
+        {
+            self.applied = config;
+            return Ok(false);
+        }
+
+        if config.theme != self.applied.theme || config.colors != self.applied.colors {
+            theme::set_theme(config.theme((*self.theme).clone())?);
+        }
+        *prompt = config.prompt.clone().unwrap_or_else(|| self.prompt.clone());
+        for name in self.applied.aliases.keys() {
+            aliases.remove(name);
+        }
+        aliases.extend(config.aliases.iter().map(|(k, v)| (k.clone(), v.clone())));
+        self.applied = config;
+        Ok(true)
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
@@ -0,0 +1,78 @@
+//! Running a command several times, with the `repeat` built-in.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::schedule::parse_interval;
+use super::{expand_alias, CommandHandler, Repl};
+use crate::locale::text;
+use crate::output::CodedError;
+
+const USAGE: &str = "repeat <count> [--delay <interval>] [--index <name>] <command>";
+
+impl<H: CommandHandler> Repl<H> {
+    /// Runs `repeat <count> [--delay <interval>] [--index <name>] <command>`.
+    pub(super) fn repeat_command(&mut self, args: &str) {
+        let (count, mut rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        let Ok(count) = count.parse::<u64>() else {
+            let _ = self.output.eprintln(&text("usage", &[("usage", &USAGE)]));
+            return;
+        };
+        let mut delay = Duration::ZERO;
+        let mut index = None;
+        loop {
+            rest = rest.trim_start();
+            let (flag, after) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            let (value, after) = after.trim_start().split_once(char::is_whitespace).unwrap_or((after.trim_start(), ""));
+            match flag {
+                "--delay" => {
+                    let Some(interval) = parse_interval(value) else {
+                        self.print_error(CodedError::new("schedule.bad_interval", text("schedule.bad_interval", &[("interval", &value)])).detail("interval", value));
+                        return;
+                    };
+                    delay = interval;
+                }
+                "--index" if !value.is_empty() => index = Some(format!("{{{value}}}")),
+                _ => break,
+            }
+            rest = after;
+        }
+        if rest.is_empty() {
+            let _ = self.output.eprintln(&text("usage", &[("usage", &USAGE)]));
+            return;
+        }
+        let rest = expand_alias(&self.aliases, rest).into_owned();
+        if !self.allows(&rest) {
+            return;
+        }
+
+        self.cancel.reset();
+        for i in 1..=count {
+            if i > 1 && !self.pause(delay) {
+                break;
+            }
+            let command = match &index {
+                Some(placeholder) => rest.replace(placeholder.as_str(), &i.to_string()),
+                None => rest.to_string(),
+            };
+            let (keep_going, _) = self.run_command(&command);
+            if !keep_going || self.cancel.is_cancelled() {
+                break;
+            }
+        }
+    }
+
+    /// Waits for `delay` unless the REPL's cancel token is set first.
+    /// Returns `false` if it was.
+    fn pause(&self, delay: Duration) -> bool {
+        let until = Instant::now() + delay;
+        while !self.cancel.is_cancelled() {
+            let left = until.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return true;
+            }
+            thread::sleep(left.min(Duration::from_millis(50)));
+        }
+        false
+    }
+}
@@ -0,0 +1,97 @@
+//! Changing the line being edited from a key binding, by prompting again
+//! in place.
+//!
+//! Key bindings can only move the cursor, or insert or delete on one side
+//! of it. A binding that needs more, such as replacing a word and leaving
+//! the cursor after it, stores the new line in an [`Edited`] slot and
+//! interrupts reading; [`read_line`] then erases the line and reads again,
+//! starting from the new one.
+
+use std::sync::{Arc, Mutex};
+
+use rustyline::{Cmd, EventContext};
+
+use super::clear::Cleared;
+use super::history::LineEditor;
+#[cfg(feature = "menu")]
+use super::history_search;
+use super::{osc, ReadlineError, Result};
+use crate::output::term;
+use crate::output::width::display_width;
+
+/// A line changed by a key binding, to be edited again in place of the one
+/// shown.
+#[derive(Debug)]
+pub(super) struct Edit {
+    /// The line as it was shown, to know how many rows to erase.
+    shown: String,
+    line: String,
+    pos: usize,
+    /// Whether to pick a history entry to edit instead, searching for the
+    /// line.
+    #[cfg(feature = "menu")]
+    search: bool,
+}
+
+/// The line a key binding changed, left for [`read_line`].
+pub(super) type Edited = Arc<Mutex<Option<Edit>>>;
+
+/// Leaves `line`, with the cursor at `pos`, to be edited in place of the
+/// one in `ctx`, and returns the command that interrupts reading it.
+pub(super) fn edit(edited: &Edited, ctx: &EventContext, line: String, pos: usize) -> Cmd {
+    let edit = Edit {
+        shown: ctx.line().to_string(),
+        line,
+        pos,
+        #[cfg(feature = "menu")]
+        search: false,
+    };
+    *edited.lock().unwrap_or_else(|e| e.into_inner()) = Some(edit);
+    Cmd::Interrupt
+}
+
+/// Leaves the line in `ctx` to have a history entry picked in its place,
+/// and returns the command that interrupts reading it.
+#[cfg(feature = "menu")]
+pub(super) fn search(edited: &Edited, ctx: &EventContext) -> Cmd {
+    let edit = Edit { shown: ctx.line().to_string(), line: ctx.line().to_string(), pos: ctx.pos(), search: true };
+    *edited.lock().unwrap_or_else(|e| e.into_inner()) = Some(edit);
+    Cmd::Interrupt
+}
+
+/// Reads a line with `editor`, starting from the one Ctrl+L interrupted,
+/// and prompting again in place with each line a key binding changed or
+/// picked from the history.
+pub(super) fn read_line(editor: &mut LineEditor, cleared: &Cleared, edited: &Edited, prompt: &str) -> Result<String> {
+    let mut initial = cleared.lock().unwrap_or_else(|e| e.into_inner()).take();
+    loop {
+        let read = match &initial {
+            Some((line, pos)) => editor.readline_with_initial(prompt, line.split_at(*pos)),
+            None => editor.readline(prompt),
+        };
+        let edit = edited.lock().unwrap_or_else(|e| e.into_inner()).take();
+        match (read, edit) {
+            (Err(ReadlineError::Interrupted), Some(edit)) => {
+                // The editor moved below the line; erase the rows it took.
+                let _ = osc::erase_rows(rows(prompt, &edit.shown, term::width()));
+                #[cfg(feature = "menu")]
+                if edit.search
+                    && let Some(line) = history_search::pick(editor.history(), &edit.line)
+                {
+                    let pos = line.len();
+                    initial = Some((line, pos));
+                    continue;
+                }
+                initial = Some((edit.line, edit.pos));
+            }
+            (read, _) => return read,
+        }
+    }
+}
+
+/// Returns how many rows `prompt` followed by `line` takes on a terminal
+/// `cols` wide.
+fn rows(prompt: &str, line: &str, cols: usize) -> usize {
+    let text = format!("{prompt}{line}");
+    text.split('\n').map(|row| display_width(row) / cols.max(1) + 1).sum()
+}
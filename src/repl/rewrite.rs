@@ -0,0 +1,66 @@
+//! Rewriting lines before they run, with the hooks added by
+//! [`Repl::with_rewrite`](super::Repl::with_rewrite), and rewrites for
+//! text pasted from documents.
+
+use std::borrow::Cow;
+
+/// A hook that returns a line rewritten, or `None` to leave it as it is.
+pub(super) type Rewrite = dyn Fn(&str) -> Option<String> + Send;
+
+/// Passes `line` through each of `rewrites` in turn.
+pub(super) fn apply<'a>(rewrites: &[Box<Rewrite>], line: &'a str) -> Cow<'a, str> {
+    let mut line = Cow::Borrowed(line);
+    for rewrite in rewrites {
+        if let Some(rewritten) = rewrite(&line) {
+            line = Cow::Owned(rewritten);
+        }
+    }
+    line
+}
+
+/// Replaces curly quotes, which word processors and documentation sites
+/// put in place of typed ones, with straight quotes, so that pasted
+/// commands quote their arguments as intended. Returns `None` if `line`
+/// has none.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::straight_quotes;
+///
+/// assert_eq!(straight_quotes("grep “not found” ‘app.log’").as_deref(), Some(r#"grep "not found" 'app.log'"#));
+/// assert_eq!(straight_quotes("grep error app.log"), None);
+/// ```
+pub fn straight_quotes(line: &str) -> Option<String> {
+    const CURLY: [char; 8] = ['‘', '’', '‚', '‛', '“', '”', '„', '‟'];
+    if !line.contains(CURLY) {
+        return None;
+    }
+    let straight = line.chars().map(|c| match c {
+        '‘' | '’' | '‚' | '‛' => '\'',
+        '“' | '”' | '„' | '‟' => '"',
+        c => c,
+    });
+    Some(straight.collect())
+}
+
+/// Removes a shell prompt, `$` or `%` followed by whitespace, from the
+/// start of `line`, as left by commands copied from runbooks and
+/// terminal sessions. Returns `None` if `line` does not start with one.
+///
+/// `#` is left alone, although it is the prompt of a root shell, since
+/// lines starting with it are usually comments.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::strip_shell_prompt;
+///
+/// assert_eq!(strip_shell_prompt("$ deploy web --canary").as_deref(), Some("deploy web --canary"));
+/// assert_eq!(strip_shell_prompt("deploy $HOME"), None);
+/// ```
+pub fn strip_shell_prompt(line: &str) -> Option<String> {
+    let trimmed = line.trim_start();
+    let rest = trimmed.strip_prefix(['$', '%'])?;
+    rest.starts_with(char::is_whitespace).then(|| rest.trim_start().to_string())
+}
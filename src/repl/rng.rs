@@ -0,0 +1,86 @@
+//! Random numbers as commands see them.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Where commands get random numbers, such as for generating IDs.
+///
+/// Seeded from the operating system unless the REPL is given a
+/// [seeded](Self::from_seed) one with [`Repl::with_rng`], which makes the
+/// numbers the same on every run so that a recorded session replays byte
+/// for byte. Clones share the same sequence. Background jobs get the
+/// REPL's generator through [`JobContext::rng`].
+///
+/// The numbers are not suitable for keys, tokens or anything else that
+/// must be hard to guess.
+///
+/// [`Repl::with_rng`]: super::Repl::with_rng
+/// [`JobContext::rng`]: super::JobContext::rng
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::Rng;
+///
+/// let a = Rng::from_seed(7);
+/// let b = Rng::from_seed(7);
+/// assert_eq!(a.next_u64(), b.next_u64());
+/// assert_eq!(a.id(8), b.id(8));
+/// assert!(a.below(6) < 6);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Rng(Arc<AtomicU64>);
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
+
+impl Rng {
+    /// Creates a generator that produces the same numbers for the same
+    /// `seed`.
+    pub fn from_seed(seed: u64) -> Self {
+        Self(Arc::new(AtomicU64::new(seed)))
+    }
+
+    /// Creates a generator seeded from the operating system.
+    pub fn from_entropy() -> Self {
+        Self::from_seed(RandomState::new().build_hasher().finish())
+    }
+
+    /// Returns the next number.
+    pub fn next_u64(&self) -> u64 {
+        // SplitMix64.
+        let mut z = self.0.fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::Relaxed).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number below `bound`, which must not be zero.
+    pub fn below(&self, bound: u64) -> u64 {
+        assert!(bound > 0, "bound must not be zero");
+        // Rejects the top values that would make small results likelier.
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let n = self.next_u64();
+            if n < zone {
+                return n % bound;
+            }
+        }
+    }
+
+    /// Returns `len` random lowercase hexadecimal digits, for IDs.
+    pub fn id(&self, len: usize) -> String {
+        let mut id = String::with_capacity(len);
+        while id.len() < len {
+            let n = self.next_u64();
+            id.push_str(&format!("{n:016x}"));
+        }
+        id.truncate(len);
+        id
+    }
+}
@@ -0,0 +1,139 @@
+//! Commands run later or repeatedly, with the `at`, `every` and `schedule`
+//! built-ins.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{expand_alias, CommandHandler, Job, JobContext, Repl};
+use crate::locale::text;
+use crate::output::CodedError;
+
+impl<H: CommandHandler> Repl<H> {
+    /// Runs `at <delay> <command>` or `every <interval> <command>`, given
+    /// the built-in's name and the rest of the line.
+    pub(super) fn schedule_at(&mut self, word: &str, args: &str) {
+        let repeat = word == "every";
+        let (when, command) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        let command = expand_alias(&self.aliases, command.trim()).into_owned();
+        if command.is_empty() {
+            let usage = if repeat { "every <interval> <command>" } else { "at <delay> <command>" };
+            let _ = self.output.eprintln(&text("usage", &[("usage", &usage)]));
+            return;
+        }
+        let Some(period) = parse_interval(when).filter(|period| !repeat || !period.is_zero()) else {
+            self.print_error(CodedError::new("schedule.bad_interval", text("schedule.bad_interval", &[("interval", &when)])).detail("interval", when));
+            return;
+        };
+        if !self.allows(&command) {
+            return;
+        }
+        let Some(job) = self.handler.background(&command) else {
+            self.print_error(CodedError::new("job.cannot_background", text("job.cannot_background", &[("command", &command)])).detail("command", &command));
+            return;
+        };
+        if repeat && !job.is_repeatable() {
+            self.print_error(CodedError::new("schedule.not_repeatable", text("schedule.not_repeatable", &[("command", &command)])).detail("command", &command));
+            return;
+        }
+        let scheduled = if repeat { every(period, job) } else { at(period, job) };
+        let label = format!("{word} {when} {command}");
+        let printer = self.printer();
+        let id = self.jobs.spawn(&label, scheduled, printer, self.job_env());
+        self.scheduled.insert(id);
+        let _ = self.output.println(&format!("[{id}] {label}"));
+    }
+
+    /// Runs `schedule [list | cancel <id>]`.
+    pub(super) fn schedule_command(&mut self, args: &str) {
+        let (sub, id) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        match (sub, id.trim()) {
+            ("" | "list", "") => {
+                let scheduled: Vec<_> = self.jobs.list().into_iter().filter(|(id, _)| self.scheduled.contains(id)).collect();
+                self.scheduled.retain(|id| scheduled.iter().any(|(running, _)| running == id));
+                if scheduled.is_empty() {
+                    let _ = self.output.println(&text("schedule.none", &[]));
+                }
+                for (id, command) in scheduled {
+                    let _ = self.output.println(&text("job.running", &[("id", &id), ("command", &command)]));
+                }
+            }
+            ("cancel", id) if !id.is_empty() => {
+                let Some(id) = super::builtins::parse_job_id(id) else {
+                    let _ = self.output.eprintln(&text("usage", &[("usage", &"schedule cancel <id>")]));
+                    return;
+                };
+                match self.scheduled.contains(&id).then(|| self.jobs.kill(id)).flatten() {
+                    Some(command) => {
+                        self.scheduled.remove(&id);
+                        let _ = self.output.println(&text("job.stopping", &[("id", &id), ("command", &command)]));
+                    }
+                    None => {
+                        self.print_error(CodedError::new("job.not_found", text("job.not_found", &[("id", &id)])).detail("id", id));
+                    }
+                }
+            }
+            _ => {
+                let _ = self.output.eprintln(&text("usage", &[("usage", &"schedule [list | cancel <id>]")]));
+            }
+        }
+    }
+}
+
+/// A job running `job` once `delay` has passed.
+fn at(delay: Duration, job: Job) -> Job {
+    Job::new(move |ctx| {
+        if !sleep(ctx, delay) {
+            return Err(text("command.interrupted", &[]));
+        }
+        job.run(ctx)
+    })
+}
+
+/// A job running the repeatable `job` every `interval` until it is
+/// cancelled, printing what each run returns.
+fn every(interval: Duration, job: Job) -> Job {
+    Job::new(move |ctx| {
+        let mut next = Instant::now() + interval;
+        while sleep(ctx, next.saturating_duration_since(Instant::now())) {
+            next += interval;
+            let Some(result) = job.run_again(ctx) else { break };
+            let lines = match result {
+                Ok(output) => output.lines().map(str::to_string).collect(),
+                Err(err) => vec![text("error", &[("message", &err)])],
+            };
+            for line in lines {
+                let _ = ctx.println(&line);
+            }
+        }
+        Err(text("command.interrupted", &[]))
+    })
+}
+
+/// Waits for `duration` unless the job is cancelled first. Returns `false`
+/// if it was.
+fn sleep(ctx: &JobContext, duration: Duration) -> bool {
+    let until = Instant::now() + duration;
+    while !ctx.is_cancelled() {
+        let left = until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        thread::sleep(left.min(Duration::from_millis(50)));
+    }
+    false
+}
+
+/// Parses a delay or interval such as `500ms`, `30s`, `5m` or `1h`; a bare
+/// number is in seconds.
+pub(super) fn parse_interval(text: &str) -> Option<Duration> {
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number.parse().ok()?;
+    match unit {
+        "ms" => Some(Duration::from_millis(number)),
+        "" | "s" => Some(Duration::from_secs(number)),
+        "m" => Some(Duration::from_secs(number.checked_mul(60)?)),
+        "h" => Some(Duration::from_secs(number.checked_mul(3600)?)),
+        _ => None,
+    }
+}
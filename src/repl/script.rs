@@ -0,0 +1,133 @@
+//! Scripts around REPL commands, written in [Rhai](https://rhai.rs).
+
+use std::sync::mpsc;
+use std::thread;
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Scope};
+
+use super::{CommandHandler, Repl};
+use crate::output::Output;
+
+/// A command a script asks the REPL to run.
+struct Request {
+    command: String,
+    // Whether the command's output is also printed.
+    echo: bool,
+    reply: mpsc::Sender<Map>,
+}
+
+impl<H: CommandHandler> Repl<H> {
+    /// Runs the [Rhai](https://rhai.rs) script `source`, which can run
+    /// commands of this REPL and act on what they print.
+    ///
+    /// Commands go through the same steps as typed lines: aliases,
+    /// built-ins and the command handler. Scripts run them with two
+    /// functions:
+    ///
+    /// * `run(command)` runs `command`, printing its output as usual.
+    /// * `capture(command)` runs `command` without printing anything.
+    ///
+    /// Both return a map with the regular `output` and the `errors` the
+    /// command printed, without color codes, and `exit`, which is `true`
+    /// when the handler asked to exit. After that, the script's commands are
+    /// no longer run. `print` writes to the REPL's output, and the script
+    /// sees `args` as an array of strings.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The script.
+    /// * `args` - The values of `args` in the script.
+    ///
+    /// # Returns
+    ///
+    /// An error if the script does not compile or fails while running.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::output::{BufferSink, Output};
+    /// use mycli::repl::{CommandHandler, Context, Repl};
+    ///
+    /// struct Pinger {
+    ///     out: Output,
+    /// }
+    ///
+    /// impl CommandHandler for Pinger {
+    ///     fn handle(&mut self, command: &str, _: &Context) -> bool {
+    ///         let host = command.trim_start_matches("ping ");
+    ///         let _ = self.out.println(if host == "db" { "down" } else { "up" });
+    ///         true
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferSink::new();
+    /// let out = Output::new(buffer.clone());
+    /// let mut repl = Repl::new("> ", Pinger { out: out.clone() }).unwrap().with_output(out);
+    ///
+    /// let script = r#"
+    ///     for host in args {
+    ///         let result = capture("ping " + host);
+    ///         if result.output.contains("down") {
+    ///             print(host + " is down");
+    ///         }
+    ///     }
+    /// "#;
+    /// repl.run_script(script, &["web", "db"]).unwrap();
+    /// assert_eq!(buffer.contents(), "db is down\n");
+    /// ```
+    pub fn run_script(&mut self, source: &str, args: &[&str]) -> Result<(), Box<EvalAltResult>> {
+        let (sender, requests) = mpsc::channel();
+        let output = self.output.clone();
+        let source = source.to_string();
+        let args: Array = args.iter().map(|arg| Dynamic::from(arg.to_string())).collect();
+        // The script runs on its own thread and sends its commands back
+        // here, where the REPL can run them.
+        let script = thread::spawn(move || {
+            let engine = engine(sender, output);
+            let mut scope = Scope::new();
+            scope.push("args", args);
+            engine.run_with_scope(&mut scope, &source)
+        });
+
+        let mut ended = false;
+        for request in requests {
+            let command = request.command.trim();
+            let (mut out, mut err) = (String::new(), String::new());
+            if !ended && !command.is_empty() {
+                let output = self.output.clone();
+                let keep_going;
+                (keep_going, out, err) = output.record(request.echo, || self.execute(command));
+                ended = !keep_going;
+            }
+            let mut result = Map::new();
+            result.insert("output".into(), out.into());
+            result.insert("errors".into(), err.into());
+            result.insert("exit".into(), ended.into());
+            let _ = request.reply.send(result);
+        }
+        script.join().unwrap_or_else(|_| Err("the script panicked".into()))
+    }
+}
+
+/// Creates the engine for a script, sending its commands to `sender` and
+/// its printing to `output`.
+fn engine(sender: mpsc::Sender<Request>, output: Output) -> Engine {
+    let mut engine = Engine::new();
+    let out = output.clone();
+    engine.on_print(move |line| {
+        let _ = out.println(line);
+    });
+    engine.on_debug(move |line, _, _| {
+        let _ = output.eprintln(line);
+    });
+    for (name, echo) in [("run", true), ("capture", false)] {
+        let sender = sender.clone();
+        engine.register_fn(name, move |command: &str| -> Result<Map, Box<EvalAltResult>> {
+            let (reply, result) = mpsc::channel();
+            let request = Request { command: command.to_string(), echo, reply };
+            sender.send(request).map_err(|_| "the REPL stopped running commands")?;
+            Ok(result.recv().map_err(|_| "the REPL stopped running commands")?)
+        });
+    }
+    engine
+}
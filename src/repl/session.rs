@@ -0,0 +1,77 @@
+//! Session state saved on exit and restored on the next start.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::ConfigError;
+
+/// What a session leaves behind for the next one.
+///
+/// The REPL fills in its aliases and the working directory, and command
+/// handlers add their own variables and mode through
+/// [`CommandHandler::save_session`] and restore them in
+/// [`CommandHandler::restore_session`].
+///
+/// [`CommandHandler::save_session`]: super::CommandHandler::save_session
+/// [`CommandHandler::restore_session`]: super::CommandHandler::restore_session
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::SessionState;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("session.toml");
+///
+/// let mut state = SessionState::default();
+/// state.variables.insert("region".into(), "eu-west-1".into());
+/// state.mode = Some("admin".into());
+/// state.save(&path).unwrap();
+///
+/// assert_eq!(SessionState::load(&path).unwrap(), Some(state));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionState {
+    /// Session variables set by the application.
+    pub variables: BTreeMap<String, String>,
+    /// Aliases defined during the session, not counting those from the
+    /// configuration file.
+    pub aliases: BTreeMap<String, String>,
+    /// The mode the application was in, such as a selected database.
+    pub mode: Option<String>,
+    /// The working directory.
+    pub cwd: Option<PathBuf>,
+}
+
+impl SessionState {
+    /// Reads the state saved at `path`, or returns `None` if there is none.
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<Self>, ConfigError> {
+        let path = path.as_ref();
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => return Err(ConfigError::Io { path: path.to_path_buf(), source }),
+        };
+        toml::from_str(&text).map(Some).map_err(|err| ConfigError::Parse {
+            path: Some(path.to_path_buf()),
+            message: err.to_string().trim_end().to_string(),
+        })
+    }
+
+    /// Writes the state to `path`, replacing what was saved there before.
+    ///
+    /// The state is written to a temporary file that is then renamed, so an
+    /// interrupted save leaves the previous state intact.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let text = toml::to_string(self).map_err(io::Error::other)?;
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, text)?;
+        std::fs::rename(&tmp, path)
+    }
+}
@@ -0,0 +1,141 @@
+//! Checking [minisign](https://jedisct1.github.io/minisign/) signatures of
+//! scripts before they run.
+
+use std::fmt;
+use std::str::FromStr;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use blake2::{Blake2b512, Digest};
+use ed25519_dalek::{Signature, VerifyingKey};
+
+/// A minisign public key, allowed to sign scripts.
+///
+/// Parsed from the contents of a `minisign.pub` file, or from just its
+/// second line, the one `minisign -G` prints for `-P`.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::{ScriptKey, SignatureError};
+///
+/// let key: ScriptKey = "RWRaFzyeIUQL2OpKbGPinFIKvvVQexMuxfmVR3auvr57kkIe6mkURtIs".parse().unwrap();
+///
+/// let script = "run(\"status\");\n";
+/// let signature = "untrusted comment: signature from minisign secret key
+/// RURaFzyeIUQL2NO3ALbu20sVNiB6tXEr+/e0YN5nxJWBapD18JgRT87NqIielBurYypcVeP6z9myVAm7qRw+CmYz/nv/teHVGQo=
+/// trusted comment: timestamp:1700000000\tfile:check.rhai\thashed
+/// MpKgOLRdIDb4WxjQHLEf/CUQGf7mLVSrofOyPmJ4xNQm6D8c6+woIFX9aYoX/DvX70YAIIjvsx5ZV8QIM7+eBA==
+/// ";
+/// assert!(key.verify(script.as_bytes(), signature).is_ok());
+///
+/// let changed = "run(\"drop everything\");\n";
+/// assert!(matches!(key.verify(changed.as_bytes(), signature), Err(SignatureError::Invalid)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptKey {
+    id: [u8; 8],
+    key: VerifyingKey,
+}
+
+impl ScriptKey {
+    /// Checks that `signature`, the contents of a `.minisig` file, was
+    /// made for `content` with this key, including its trusted comment.
+    pub fn verify(&self, content: &[u8], signature: &str) -> Result<(), SignatureError> {
+        self.trusted_comment(content, signature).map(|_| ())
+    }
+
+    /// Verifies like [`verify`](Self::verify), returning the trusted
+    /// comment the signature vouches for.
+    fn trusted_comment<'a>(&self, content: &[u8], signature: &'a str) -> Result<&'a str, SignatureError> {
+        let mut lines = signature.lines().map(str::trim_end);
+        let malformed = || SignatureError::Malformed;
+        lines.next().filter(|line| line.starts_with("untrusted comment:")).ok_or_else(malformed)?;
+        let signed = decode(lines.next().ok_or_else(malformed)?, 74)?;
+        let comment = lines.next().and_then(|line| line.strip_prefix("trusted comment: ")).ok_or_else(malformed)?;
+        let global = decode(lines.next().ok_or_else(malformed)?, 64)?;
+
+        let (algorithm, rest) = signed.split_at(2);
+        let (id, bytes) = rest.split_at(8);
+        if id != self.id {
+            return Err(SignatureError::UnknownKey);
+        }
+        let signature = Signature::from_slice(bytes).map_err(|_| malformed())?;
+        match algorithm {
+            b"ED" => self.key.verify_strict(&Blake2b512::digest(content), &signature),
+            b"Ed" => self.key.verify_strict(content, &signature),
+            _ => return Err(malformed()),
+        }
+        .map_err(|_| SignatureError::Invalid)?;
+
+        let mut trusted = bytes.to_vec();
+        trusted.extend_from_slice(comment.as_bytes());
+        let global = Signature::from_slice(&global).map_err(|_| malformed())?;
+        self.key.verify_strict(&trusted, &global).map_err(|_| SignatureError::Invalid)?;
+        Ok(comment)
+    }
+}
+
+impl FromStr for ScriptKey {
+    type Err = SignatureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let line = s
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with("untrusted comment:"))
+            .ok_or(SignatureError::Malformed)?;
+        let bytes = decode(line, 42)?;
+        if &bytes[..2] != b"Ed" {
+            return Err(SignatureError::Malformed);
+        }
+        let key: [u8; 32] = bytes[10..].try_into().map_err(|_| SignatureError::Malformed)?;
+        Ok(Self {
+            id: bytes[2..10].try_into().map_err(|_| SignatureError::Malformed)?,
+            key: VerifyingKey::from_bytes(&key).map_err(|_| SignatureError::Malformed)?,
+        })
+    }
+}
+
+/// Why a script's signature was not accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The key or signature is not in minisign's format.
+    Malformed,
+    /// The script was signed with a key that is not allowed.
+    UnknownKey,
+    /// The signature does not match the script or its trusted comment,
+    /// which were changed after signing.
+    Invalid,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SignatureError::Malformed => "not a minisign signature",
+            SignatureError::UnknownKey => "signed with a key that is not allowed",
+            SignatureError::Invalid => "the signature does not match",
+        })
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/// Checks `signature` against each of `keys`, succeeding with its trusted
+/// comment if one of them made it.
+pub(crate) fn verify<'a>(keys: &[ScriptKey], content: &[u8], signature: &'a str) -> Result<&'a str, SignatureError> {
+    let mut result = Err(SignatureError::UnknownKey);
+    for key in keys {
+        match key.trusted_comment(content, signature) {
+            Ok(comment) => return Ok(comment),
+            Err(SignatureError::UnknownKey) => {}
+            Err(err) => result = Err(err),
+        }
+    }
+    result
+}
+
+/// Decodes the base64 `line`, which must hold `len` bytes.
+fn decode(line: &str, len: usize) -> Result<Vec<u8>, SignatureError> {
+    STANDARD.decode(line.trim()).ok().filter(|bytes| bytes.len() == len).ok_or(SignatureError::Malformed)
+}
@@ -0,0 +1,107 @@
+//! Snippets: command templates with fields to fill in, inserted with the
+//! `snippet` built-in.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use rustyline::{Cmd, ConditionalEventHandler, Event, EventContext, InputMode, RepeatCount};
+
+use super::reprompt::{self, Edited};
+use super::{CommandHandler, Repl};
+use crate::locale::text;
+use crate::output::CodedError;
+
+/// Set while the line being edited came from a snippet, so Tab moves
+/// between its fields and the fields left are filled in when it is
+/// entered.
+pub(super) type Filling = Arc<AtomicBool>;
+
+/// A field of a snippet, written `<name>` or `<name:default>`.
+struct Field<'a> {
+    start: usize,
+    end: usize,
+    default: &'a str,
+}
+
+/// Returns the first field in `line` starting at or after byte `from`.
+fn find_field(line: &str, mut from: usize) -> Option<Field<'_>> {
+    while let Some(offset) = line.get(from..)?.find('<') {
+        let start = from + offset;
+        let rest = &line[start + 1..];
+        if let Some(len) = rest.find('>') {
+            let (name, default) = rest[..len].split_once(':').unwrap_or((&rest[..len], ""));
+            if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') && !default.contains('<') {
+                return Some(Field { start, end: start + len + 2, default });
+            }
+        }
+        from = start + 1;
+    }
+    None
+}
+
+/// Replaces `field` in `line` with its default, returning the new line and
+/// the position right after the default.
+fn fill(line: &str, field: &Field) -> (String, usize) {
+    let filled = format!("{}{}{}", &line[..field.start], field.default, &line[field.end..]);
+    (filled, field.start + field.default.len())
+}
+
+/// Returns `template` as the line to edit, with its first field filled in
+/// and the cursor after it.
+pub(super) fn start(template: &str) -> (String, usize) {
+    match find_field(template, 0) {
+        Some(field) => fill(template, &field),
+        None => (template.to_string(), template.len()),
+    }
+}
+
+/// Fills in the fields left in `line` if it came from a snippet.
+pub(super) fn finish(filling: &Filling, mut line: String) -> String {
+    if filling.swap(false, Ordering::Relaxed) {
+        while let Some(field) = find_field(&line, 0) {
+            line = fill(&line, &field).0;
+        }
+    }
+    line
+}
+
+/// Moves to the next field of a snippet, bound to Tab once snippets are
+/// added. Tab completes as usual on other lines and once no field is left.
+pub(super) struct TabKey {
+    pub(super) filling: Filling,
+    pub(super) edited: Edited,
+}
+
+impl ConditionalEventHandler for TabKey {
+    fn handle(&self, _: &Event, _: RepeatCount, _: bool, ctx: &EventContext) -> Option<Cmd> {
+        if !self.filling.load(Ordering::Relaxed) || ctx.input_mode() != InputMode::Insert {
+            return None;
+        }
+        let line = ctx.line();
+        let field = find_field(line, ctx.pos()).or_else(|| find_field(line, 0))?;
+        let (line, pos) = fill(line, &field);
+        Some(reprompt::edit(&self.edited, ctx, line, pos))
+    }
+}
+
+impl<H: CommandHandler> Repl<H> {
+    /// Lists the snippets, or leaves the one named `name` to be edited at
+    /// the next prompt.
+    pub(super) fn snippet_command(&mut self, name: &str) {
+        if name.is_empty() {
+            if self.snippets.is_empty() {
+                let _ = self.output.println(&text("snippet.none", &[]));
+            }
+            for (name, template) in &self.snippets {
+                let _ = self.output.println(&format!("{name} = {template}"));
+            }
+            return;
+        }
+        let Some(template) = self.snippets.get(name) else {
+            self.print_error(CodedError::new("snippet.not_found", text("snippet.not_found", &[("name", &name)])).detail("name", name));
+            return;
+        };
+        *self.cleared.lock().unwrap_or_else(|e| e.into_inner()) = Some(start(template));
+        self.filling.store(true, Ordering::Relaxed);
+    }
+}
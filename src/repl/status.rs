@@ -0,0 +1,201 @@
+//! A persistent status line displayed alongside the prompt.
+
+use std::io::{self, IsTerminal, Write};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::locale::text;
+use crate::output::term::{self, ResizeWatch};
+use crate::output::accessible;
+use crate::output::width::{fit, Align};
+
+/// Where the status line is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatusPosition {
+    /// Printed on its own line right before every prompt.
+    #[default]
+    AbovePrompt,
+    /// Pinned to the last row of the terminal, below the scrolling output.
+    ///
+    /// Falls back to [`StatusPosition::AbovePrompt`] when the terminal size
+    /// cannot be determined.
+    Bottom,
+}
+
+/// A status line showing application-provided fields such as the current
+/// connection, mode, or number of pending jobs.
+///
+/// The fields are requested again every time the line is redrawn, which
+/// happens after each command. When the terminal is resized, a bar pinned
+/// to the bottom is drawn again at once for the new size, with the fields
+/// it last showed.
+///
+/// In the [accessible mode](crate::output::accessible) the bar is neither
+/// pinned nor highlighted: it is printed as a `Status:` line, and only when
+/// its text has changed since it was last printed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::cell::Cell;
+/// use std::rc::Rc;
+/// use mycli::repl::{Repl, CommandHandler, Context, StatusBar, StatusPosition};
+///
+/// struct Counter(Rc<Cell<usize>>);
+///
+/// impl CommandHandler for Counter {
+///     fn handle(&mut self, _command: &str, _: &Context) -> bool {
+///         self.0.set(self.0.get() + 1);
+///         true
+///     }
+/// }
+///
+/// let count = Rc::new(Cell::new(0));
+/// let shared = count.clone();
+/// let bar = StatusBar::new(move || vec![
+///     "db: connected".to_string(),
+///     format!("commands: {}", shared.get()),
+/// ])
+/// .position(StatusPosition::Bottom);
+///
+/// let mut repl = Repl::new("> ", Counter(count)).unwrap().with_status_bar(bar);
+/// repl.run().unwrap();
+/// ```
+pub struct StatusBar {
+    fields: Box<dyn FnMut() -> Vec<String>>,
+    position: StatusPosition,
+    separator: String,
+    // The bottom bar, shared with the resize callback.
+    bottom: Arc<Mutex<Bottom>>,
+    resize: Option<ResizeWatch>,
+    // Text last printed in the accessible mode.
+    announced: Option<String>,
+}
+
+impl StatusBar {
+    /// Creates a status bar whose fields are produced by `fields`.
+    ///
+    /// The bar is drawn above the prompt with fields separated by `" | "`.
+    pub fn new(fields: impl FnMut() -> Vec<String> + 'static) -> Self {
+        Self {
+            fields: Box::new(fields),
+            position: StatusPosition::AbovePrompt,
+            separator: " | ".to_string(),
+            bottom: Arc::default(),
+            resize: None,
+            announced: None,
+        }
+    }
+
+    /// Sets where the status bar is drawn.
+    pub fn position(mut self, position: StatusPosition) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Sets the string placed between fields.
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Renders the current fields as a single line fitting `width` columns.
+    pub fn render(&mut self, width: usize) -> String {
+        let line = (self.fields)().join(&self.separator);
+        fit(&line, width, Align::Left)
+    }
+
+    /// Redraws the status line. Does nothing when stdout is not a terminal.
+    pub(crate) fn draw(&mut self) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        if !stdout.is_terminal() {
+            return Ok(());
+        }
+
+        if accessible::is_enabled() {
+            let line = (self.fields)().join(&self.separator);
+            if self.announced.as_ref() != Some(&line) {
+                writeln!(stdout, "{}", text("status", &[("status", &line)]))?;
+                self.announced = Some(line);
+            }
+            return stdout.flush();
+        }
+
+        match (self.position, term::size()) {
+            (StatusPosition::Bottom, Some((cols, rows))) if rows > 1 => {
+                let line = (self.fields)().join(&self.separator);
+                let bottom = self.bottom.clone();
+                self.resize.get_or_insert_with(|| {
+                    term::on_resize(move |cols, rows| {
+                        let mut bottom = lock(&bottom);
+                        if bottom.region.is_some() {
+                            let _ = bottom.draw(&mut io::stdout().lock(), cols, rows);
+                        }
+                    })
+                });
+                let mut bottom = lock(&self.bottom);
+                bottom.line = line;
+                bottom.draw(&mut stdout, cols, rows)?;
+            }
+            (_, size) => {
+                let line = self.render(size.map_or_else(term::width, |(cols, _)| cols));
+                writeln!(stdout, "\x1b[7m{}\x1b[0m", line.trim_end())?;
+            }
+        }
+        stdout.flush()
+    }
+
+    /// Forgets what was drawn, after the screen was cleared, so the next
+    /// [`draw`](Self::draw) sets the bar up again.
+    pub(crate) fn forget(&mut self) {
+        lock(&self.bottom).region = None;
+        self.announced = None;
+    }
+
+    /// Removes the bar and restores the full scroll region.
+    pub(crate) fn clear(&mut self) -> io::Result<()> {
+        self.resize = None;
+        let Some((_, rows)) = lock(&self.bottom).region.take() else {
+            return Ok(());
+        };
+        let mut stdout = io::stdout();
+        write!(stdout, "\x1b7\x1b[r\x1b[{rows};1H\x1b[2K\x1b8")?;
+        stdout.flush()
+    }
+}
+
+impl Drop for StatusBar {
+    fn drop(&mut self) {
+        let _ = self.clear();
+    }
+}
+
+/// What the bottom bar shows, kept to draw it again when the terminal is
+/// resized.
+#[derive(Debug, Default)]
+struct Bottom {
+    line: String,
+    // Terminal size the scroll region was set up for.
+    region: Option<(usize, usize)>,
+}
+
+impl Bottom {
+    /// Draws the bar on the last row, first confining scrolling above it
+    /// if the size changed.
+    fn draw(&mut self, out: &mut impl Write, cols: usize, rows: usize) -> io::Result<()> {
+        if rows < 2 {
+            return Ok(());
+        }
+        if self.region != Some((cols, rows)) {
+            // Make room for the bar, then confine scrolling above it.
+            write!(out, "\n\x1b[1A\x1b7\x1b[1;{}r\x1b8", rows - 1)?;
+            self.region = Some((cols, rows));
+        }
+        let line = fit(&self.line, cols, Align::Left);
+        write!(out, "\x1b7\x1b[{rows};1H\x1b[2K\x1b[7m{line}\x1b[0m\x1b8")?;
+        out.flush()
+    }
+}
+
+fn lock(bottom: &Mutex<Bottom>) -> MutexGuard<'_, Bottom> {
+    bottom.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,59 @@
+//! What the session has run so far, reported by the `summary` built-in.
+
+use std::time::Duration;
+
+use super::{format_elapsed, CommandHandler, Repl};
+use crate::locale::text;
+
+/// How many of the slowest commands are kept.
+const SLOWEST: usize = 5;
+
+/// Counts of the commands run in the session.
+#[derive(Debug, Default)]
+pub(super) struct Usage {
+    commands: u64,
+    failures: u64,
+    total: Duration,
+    // The slowest commands, slowest first, with secrets masked.
+    slowest: Vec<(String, Duration)>,
+}
+
+impl Usage {
+    /// Counts `cmd`, which took `took` and wrote error output if `failed`.
+    pub(super) fn record(&mut self, cmd: &str, took: Duration, failed: bool) {
+        self.commands += 1;
+        self.failures += u64::from(failed);
+        self.total += took;
+        if self.slowest.len() == SLOWEST && self.slowest.last().is_some_and(|&(_, slowest)| slowest >= took) {
+            return;
+        }
+        let at = self.slowest.partition_point(|&(_, slower)| slower >= took);
+        self.slowest.insert(at, (crate::redact::current().redact(cmd).into_owned(), took));
+        self.slowest.truncate(SLOWEST);
+    }
+}
+
+impl<H: CommandHandler> Repl<H> {
+    /// Runs `summary`, printing how many commands the session has run, how
+    /// many failed, the time spent in them and the slowest ones.
+    pub(super) fn summary_command(&mut self, args: &str) {
+        if !args.is_empty() {
+            let _ = self.output.eprintln(&text("usage", &[("usage", &"summary")]));
+            return;
+        }
+        let usage = &self.usage;
+        let mut lines = vec![
+            text("summary.commands", &[("count", &usage.commands)]),
+            text("summary.failures", &[("count", &usage.failures)]),
+            text("summary.time", &[("elapsed", &format_elapsed(usage.total))]),
+        ];
+        if !usage.slowest.is_empty() {
+            lines.push(text("summary.slowest", &[]));
+            let width = usage.slowest.iter().map(|(_, took)| format_elapsed(*took).len()).max().unwrap_or(0);
+            lines.extend(usage.slowest.iter().map(|(cmd, took)| format!("  {:>width$}  {cmd}", format_elapsed(*took))));
+        }
+        for line in lines {
+            let _ = self.output.println(&line);
+        }
+    }
+}
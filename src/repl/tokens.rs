@@ -0,0 +1,172 @@
+//! Splitting command lines into words without copying them.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::ops::Range;
+
+use crate::output::Diagnostic;
+
+/// A word of a command line, borrowed from the line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    /// The word, without the quotes around it.
+    pub text: &'a str,
+    /// Where the word is written in the line, quotes included, in bytes.
+    pub span: Range<usize>,
+    /// Whether the word was quoted.
+    pub quoted: bool,
+}
+
+/// A quote that is opened but never closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnterminatedQuote {
+    /// The quote character, `"` or `'`.
+    pub quote: char,
+    /// Where the quote is in the line, in bytes.
+    pub start: usize,
+}
+
+impl fmt::Display for UnterminatedQuote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unterminated {} at column {}", self.quote, self.start + 1)
+    }
+}
+
+impl std::error::Error for UnterminatedQuote {}
+
+impl UnterminatedQuote {
+    /// Returns the error as a [`Diagnostic`] underlining the quoted text
+    /// in `line`, the line that was tokenized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::repl::tokenize;
+    ///
+    /// let line = "say 'hi there";
+    /// let err = tokenize(line).find_map(Result::err).unwrap();
+    /// assert_eq!(
+    ///     err.diagnostic(line).to_string(),
+    ///     "Error: unterminated '\n \
+    ///      | say 'hi there\n \
+    ///      |     ^^^^^^^^^ this quote is never closed\n \
+    ///      = help: add a closing ' at the end of the word"
+    /// );
+    /// ```
+    pub fn diagnostic(&self, line: &str) -> Diagnostic {
+        Diagnostic::new(format!("unterminated {}", self.quote), line, self.start..line.len())
+            .label("this quote is never closed")
+            .help(format!("add a closing {} at the end of the word", self.quote))
+    }
+}
+
+/// An iterator over the words of a command line, created by [`tokenize`].
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    line: &'a str,
+    pos: usize,
+}
+
+/// Splits `line` into words separated by whitespace.
+///
+/// A word starting with `"` or `'` runs to the matching quote and may
+/// contain whitespace; quotes elsewhere are part of the word. There are no
+/// escapes, so every word is a slice of `line` and nothing is allocated.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::tokenize;
+///
+/// let words: Vec<&str> = tokenize(r#"grep "two words" 'it''s' a"b"#)
+///     .map(|token| token.unwrap().text)
+///     .collect();
+/// assert_eq!(words, ["grep", "two words", "it", "s", "a\"b"]);
+///
+/// let err = tokenize("say 'hi").nth(1).unwrap().unwrap_err();
+/// assert_eq!(err.start, 4);
+/// ```
+pub fn tokenize(line: &str) -> Tokens<'_> {
+    Tokens { line, pos: 0 }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Result<Token<'a>, UnterminatedQuote>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.line[self.pos..];
+        let start = self.pos + (rest.len() - rest.trim_start().len());
+        let rest = &self.line[start..];
+        let first = rest.chars().next()?;
+
+        if first == '"' || first == '\'' {
+            let Some(len) = rest[1..].find(first) else {
+                self.pos = self.line.len();
+                return Some(Err(UnterminatedQuote { quote: first, start }));
+            };
+            let end = start + 1 + len + 1;
+            self.pos = end;
+            return Some(Ok(Token { text: &self.line[start + 1..end - 1], span: start..end, quoted: true }));
+        }
+
+        let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let end = start + len;
+        self.pos = end;
+        Some(Ok(Token { text: &self.line[start..end], span: start..end, quoted: false }))
+    }
+}
+
+impl FusedIterator for Tokens<'_> {}
+
+/// Quotes `word` so that [`tokenize`] reads it back as one word.
+///
+/// Words without whitespace that do not start with a quote are returned
+/// as they are; others are wrapped in whichever quote they do not contain.
+/// Returns `None` for a word that needs quoting but contains both quotes,
+/// since there are no escapes.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::quote;
+///
+/// assert_eq!(quote("plain").unwrap(), "plain");
+/// assert_eq!(quote("two words").unwrap(), "\"two words\"");
+/// assert_eq!(quote("say \"hi\"").unwrap(), "'say \"hi\"'");
+/// assert_eq!(quote(""), Some("\"\"".into()));
+/// assert_eq!(quote("it's \"both\""), None);
+/// ```
+pub fn quote(word: &str) -> Option<Cow<'_, str>> {
+    let bare = !word.is_empty() && !word.starts_with(['"', '\'']) && !word.contains(char::is_whitespace);
+    if bare {
+        return Some(Cow::Borrowed(word));
+    }
+    let quote = ['"', '\''].into_iter().find(|&q| !word.contains(q))?;
+    Some(Cow::Owned(format!("{quote}{word}{quote}")))
+}
+
+/// Joins `words` into a command line that [`tokenize`] splits back into
+/// the same words, quoting them with [`quote`]. Returns `None` if a word
+/// cannot be quoted.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::{join, tokenize};
+///
+/// let line = join(&["grep", "two words", "-n"]).unwrap();
+/// assert_eq!(line, "grep \"two words\" -n");
+/// let words: Vec<&str> = tokenize(&line).map(|token| token.unwrap().text).collect();
+/// assert_eq!(words, ["grep", "two words", "-n"]);
+/// ```
+pub fn join<S: AsRef<str>>(words: &[S]) -> Option<String> {
+    let mut line = String::new();
+    for word in words {
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&quote(word.as_ref())?);
+    }
+    Some(line)
+}
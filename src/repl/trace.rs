@@ -0,0 +1,70 @@
+//! Spans for the commands a REPL runs, with the `tracing` feature.
+//!
+//! Without the feature everything here compiles to nothing, so callers
+//! need no `cfg` of their own.
+
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+/// The span of one command, carrying its name, its arguments with secrets
+/// masked by the current [redactor](crate::redact), how long it took and how it ended.
+pub(crate) struct CommandSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "tracing")]
+    started: Instant,
+}
+
+impl CommandSpan {
+    /// Creates the span of `command`, as typed after alias expansion.
+    pub(crate) fn new(command: &str) -> Self {
+        #[cfg(feature = "tracing")]
+        {
+            let (name, args) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+            let span = tracing::info_span!(
+                "command",
+                command = name,
+                args = %crate::redact::current().redact(args.trim()),
+                duration_ms = tracing::field::Empty,
+                outcome = tracing::field::Empty,
+            );
+            Self { span, started: Instant::now() }
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            let _ = command;
+            Self {}
+        }
+    }
+
+    /// Runs `f` inside the span.
+    pub(crate) fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        #[cfg(feature = "tracing")]
+        return self.span.in_scope(f);
+        #[cfg(not(feature = "tracing"))]
+        f()
+    }
+
+    /// Runs `future` inside the span whenever it is polled.
+    #[cfg(feature = "async")]
+    pub(crate) fn instrument<F: std::future::Future>(&self, future: F) -> impl std::future::Future<Output = F::Output> {
+        #[cfg(feature = "tracing")]
+        return tracing::Instrument::instrument(future, self.span.clone());
+        #[cfg(not(feature = "tracing"))]
+        future
+    }
+
+    /// Records how the command ended, such as `ok` or `exit`, and how long
+    /// it took, and closes the span.
+    pub(crate) fn finish(self, outcome: &'static str) {
+        #[cfg(feature = "tracing")]
+        {
+            let took = self.started.elapsed().as_secs_f64() * 1000.0;
+            self.span.record("duration_ms", took);
+            self.span.record("outcome", outcome);
+            self.span.in_scope(|| tracing::debug!("command finished"));
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = outcome;
+    }
+}
@@ -0,0 +1,62 @@
+//! Reporting and installing newer releases, with the `update` built-in.
+
+use super::{CommandHandler, Repl};
+use crate::locale::text;
+use crate::output::CodedError;
+use crate::update::{Release, Updater};
+
+impl<H: CommandHandler> Repl<H> {
+    /// Checks for a newer release on another thread, when one is due, and
+    /// reports it above the prompt.
+    pub(super) fn check_for_update(&mut self) {
+        let Some(updater) = self.updater.clone() else { return };
+        let printer = self.printer();
+        std::thread::spawn(move || {
+            if let Ok(Some(release)) = updater.check_due() {
+                let _ = printer.println(&available(&updater, &release));
+            }
+        });
+    }
+
+    /// Runs `update [check]`.
+    pub(super) fn update_command(&mut self, args: &str) {
+        let Some(updater) = self.updater.clone() else { return };
+        let install = match args {
+            "" => true,
+            "check" => false,
+            _ => {
+                let _ = self.output.eprintln(&text("usage", &[("usage", &"update [check]")]));
+                return;
+            }
+        };
+        let release = match updater.check() {
+            Ok(Some(release)) => release,
+            Ok(None) => {
+                let _ = self.output.println(&text("update.latest", &[("current", &updater.current_version())]));
+                return;
+            }
+            Err(err) => {
+                self.print_error(CodedError::new("update.failed", text("update.failed", &[("error", &err)])));
+                return;
+            }
+        };
+        if !install {
+            let _ = self.output.println(&available(&updater, &release));
+        } else if self.dry_run.is_on() {
+            let _ = self.output.println(&text("update.would_install", &[("version", &release.version)]));
+        } else {
+            match updater.install(&release) {
+                Ok(_) => {
+                    let _ = self.output.println(&text("update.installed", &[("version", &release.version)]));
+                }
+                Err(err) => {
+                    self.print_error(CodedError::new("update.failed", text("update.failed", &[("error", &err)])).detail("version", &release.version));
+                }
+            }
+        }
+    }
+}
+
+fn available(updater: &Updater, release: &Release) -> String {
+    text("update.available", &[("version", &release.version), ("current", &updater.current_version())])
+}
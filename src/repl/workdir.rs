@@ -0,0 +1,138 @@
+//! The directory commands run in.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::dirs;
+
+/// The working directory that the `cd` and `pwd` built-ins change and
+/// show.
+///
+/// It is either the process's current directory, shared by everything in
+/// the program, or a virtual one belonging to a single session, which
+/// suits servers where each client should move around on its own. Clones
+/// share the directory. Give one to [`Repl::with_working_dir`] and keep
+/// another in the command handler to [`resolve`](Self::resolve) the paths
+/// commands are given; background jobs see it through
+/// [`JobContext::working_dir`]. A prompt containing `{cwd}` shows it, with
+/// the home directory written as `~`.
+///
+/// [`Repl::with_working_dir`]: super::Repl::with_working_dir
+/// [`JobContext::working_dir`]: super::JobContext::working_dir
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::WorkingDir;
+///
+/// let root = std::env::temp_dir();
+/// let dir = WorkingDir::session(&root)?;
+/// std::fs::create_dir_all(root.join("mycli-workdir-example"))?;
+///
+/// dir.change("mycli-workdir-example")?;
+/// assert!(dir.get().ends_with("mycli-workdir-example"));
+/// assert!(dir.resolve("notes.txt").ends_with("mycli-workdir-example/notes.txt"));
+///
+/// dir.change("..")?;
+/// assert_eq!(dir.get(), root.canonicalize()?);
+/// assert!(dir.change("no-such-directory").is_err());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct WorkingDir(Arc<Mutex<State>>);
+
+#[derive(Debug)]
+struct State {
+    /// The session's own directory, or `None` to use the process's.
+    current: Option<PathBuf>,
+    previous: Option<PathBuf>,
+}
+
+impl Default for WorkingDir {
+    fn default() -> Self {
+        Self::process()
+    }
+}
+
+impl WorkingDir {
+    /// Uses the process's current directory, so `cd` changes it for the
+    /// whole program, including child processes started afterwards.
+    pub fn process() -> Self {
+        Self(Arc::new(Mutex::new(State { current: None, previous: None })))
+    }
+
+    /// Uses a directory of the session's own, starting at `path`, so `cd`
+    /// leaves the process's current directory alone.
+    pub fn session(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = checked(path.as_ref().to_path_buf())?;
+        Ok(Self(Arc::new(Mutex::new(State { current: Some(path), previous: None }))))
+    }
+
+    /// Returns the directory.
+    pub fn get(&self) -> PathBuf {
+        self.lock().current()
+    }
+
+    /// Changes to `path`, relative to the directory, after expanding a
+    /// leading `~`. Returns the new directory, or an error if `path` is not
+    /// a directory.
+    pub fn change(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let mut state = self.lock();
+        let from = state.current();
+        let to = checked(from.join(dirs::expand_home(path.as_ref())))?;
+        match &mut state.current {
+            Some(current) => *current = to.clone(),
+            None => std::env::set_current_dir(&to)?,
+        }
+        state.previous = Some(from);
+        Ok(to)
+    }
+
+    /// Changes back to the directory before the last change, as `cd -`
+    /// does. Returns the new directory, or `None` if it never changed.
+    pub fn back(&self) -> Option<io::Result<PathBuf>> {
+        let previous = self.lock().previous.clone()?;
+        Some(self.change(previous))
+    }
+
+    /// Returns `path` relative to the directory, after expanding a leading
+    /// `~`. Absolute paths are returned as they are.
+    pub fn resolve(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.get().join(dirs::expand_home(path.as_ref()))
+    }
+
+    /// Returns the directory as shown in prompts, with the home directory
+    /// written as `~`.
+    pub fn display(&self) -> String {
+        let path = self.get();
+        match dirs::home_dir().and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+            Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+            Some(rest) => Path::new("~").join(rest).display().to_string(),
+            None => path.display().to_string(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl State {
+    fn current(&self) -> PathBuf {
+        match &self.current {
+            Some(path) => path.clone(),
+            None => std::env::current_dir().unwrap_or_default(),
+        }
+    }
+}
+
+/// Returns `path` without `.`, `..` or symbolic links, if it is a
+/// directory.
+fn checked(path: PathBuf) -> io::Result<PathBuf> {
+    let path = path.canonicalize()?;
+    if !path.is_dir() {
+        return Err(io::Error::new(io::ErrorKind::NotADirectory, "not a directory"));
+    }
+    Ok(path)
+}
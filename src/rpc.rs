@@ -0,0 +1,316 @@
+//! Driving a REPL from another program with JSON-RPC.
+//!
+//! An [`RpcSession`] reads [JSON-RPC 2.0](https://www.jsonrpc.org/specification)
+//! requests, one per line, and answers each with a response on its own
+//! line, so editor plugins, bots and scripts can run commands and tell
+//! their output apart from their errors. Commands go through the same
+//! steps as typed lines: aliases, built-ins and the command handler.
+//!
+//! The only method is `execute`, whose parameter is the command line,
+//! either by name or as the only element of an array:
+//!
+//! ```text
+//! --> {"jsonrpc": "2.0", "id": 1, "method": "execute", "params": {"command": "status"}}
+//! <-- {"id":1,"jsonrpc":"2.0","result":{"errors":"","exit":false,"failures":[],"output":"all workers running\n"}}
+//! --> {"jsonrpc": "2.0", "id": 2, "method": "execute", "params": ["kill 7"]}
+//! <-- {"id":2,"jsonrpc":"2.0","result":{"errors":"Error: no job 7\n","exit":false,"failures":[{"code":"job.not_found","details":{"id":"7"},"message":"no job 7"}],"output":""}}
+//! --> {"jsonrpc": "2.0", "id": 3, "method": "execute", "params": ["quit"]}
+//! <-- {"id":3,"jsonrpc":"2.0","result":{"errors":"","exit":true,"failures":[],"output":""}}
+//! ```
+//!
+//! `output` and `errors` hold what the command printed as regular output
+//! and as errors, without color codes. `failures` lists the errors that
+//! were [reported](crate::output::Output::report) with a stable code,
+//! with their message and details, so scripts can branch on the code
+//! rather than on the wording. `exit` is `true` when the handler
+//! ended the session, after which no more requests are read. Requests
+//! without an `id` are notifications: they run, but get no response and
+//! their output is dropped.
+//! Malformed requests get the error responses the specification defines.
+//!
+//! Output printed by background jobs after their command has returned is
+//! included in the response to the next request.
+//!
+//! # Error codes
+//!
+//! The REPL reports its own errors with these codes:
+//!
+//! | Code | Details | Reported when |
+//! |------|---------|---------------|
+//! | `audit.write_failed` | | the audit log cannot be written |
+//! | `clipboard.empty` | | `copy` has nothing to copy |
+//! | `clipboard.failed` | | `copy` cannot reach the clipboard |
+//! | `clipboard.no_terminal` | | `copy` runs without a terminal |
+//! | `command.failed` | `command` | a command run with a timeout fails before it |
+//! | `command.not_allowed` | `command` | a command is not among the allowed ones |
+//! | `command.panicked` | `command` | a handler panics while panics are caught |
+//! | `command.timed_out` | `command` | a command runs longer than its timeout |
+//! | `config.invalid_setting` | `key` | `set` is given an unknown key or a bad value |
+//! | `config.no_file` | | `set --save` has no configuration file |
+//! | `config.profile_failed` | `profile` | `profile use` cannot switch profiles |
+//! | `config.reload_failed` | `path` | the changed configuration file is invalid |
+//! | `config.save_failed` | `path` | `set --save` cannot write the file |
+//! | `dir.change_failed` | `path` | `cd` is given a path that is not a directory |
+//! | `dir.no_home` | | `cd` has no home directory to change to |
+//! | `dir.no_previous` | | `cd -` has no previous directory |
+//! | `help.no_example` | `name`, `number` | `help --run-example` is given a number with no example |
+//! | `help.unknown_command` | `name` | `help` is given a command that is not described |
+//! | `input.unterminated_quote` | `quote`, `offset` | the arguments of `source` leave a quote open |
+//! | `job.cannot_background` | `command` | the handler cannot run a command with `&`, `at` or `every` |
+//! | `job.cannot_parallel` | `command` | the handler cannot run a command in `par` |
+//! | `job.none_running` | | `fg` has no job to wait for |
+//! | `job.not_found` | `id` | `fg` or `kill` is given an unknown job |
+//! | `script.bad_signature` | `path` | a script's signature does not match an allowed key |
+//! | `script.failed` | `path` | a script run with `source` fails |
+//! | `script.unreadable` | `path` | `source` cannot read the script |
+//! | `script.unsigned` | `path` | a script has no signature when signatures are required |
+//! | `schedule.bad_interval` | `interval` | `at`, `every` or `repeat --delay` is given something other than a duration |
+//! | `schedule.not_repeatable` | `command` | the handler's job for a command given to `every` cannot run again |
+//! | `session.save_failed` | `path` | the session cannot be saved on exit |
+//! | `snippet.not_found` | `name` | `snippet` is given an unknown name |
+//! | `update.failed` | `version` | `update` cannot check for or install a release |
+//!
+//! Codes are only ever added to this list, and details only ever added to
+//! a code. Errors printed with [`Output::eprintln`](crate::output::Output::eprintln)
+//! appear in `errors` only.
+//!
+//! # Examples
+//!
+//! ```
+//! use mycli::output::Output;
+//! use mycli::repl::{CommandHandler, Context, Repl};
+//! use mycli::rpc::RpcSession;
+//!
+//! struct Daemon {
+//!     out: Output,
+//! }
+//!
+//! impl CommandHandler for Daemon {
+//!     fn handle(&mut self, command: &str, _: &Context) -> bool {
+//!         let _ = self.out.println(&format!("ran {command}"));
+//!         true
+//!     }
+//! }
+//!
+//! let mut session = RpcSession::new(|out| Repl::new("", Daemon { out })).unwrap();
+//! let requests = r#"{"jsonrpc": "2.0", "id": 1, "method": "execute", "params": ["status"]}"#;
+//! let mut responses = Vec::new();
+//! session.run(requests.as_bytes(), &mut responses).unwrap();
+//!
+//! let response = String::from_utf8(responses).unwrap();
+//! assert!(response.contains(r#""output":"ran status\n""#));
+//! ```
+
+use std::io::{self, BufRead, Write};
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::output::{CodedError, Output, OutputSink};
+use crate::repl::{CommandHandler, Repl};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// A REPL answering JSON-RPC requests instead of reading from a terminal.
+///
+/// See the [module documentation](self) for the protocol.
+pub struct RpcSession<H: CommandHandler> {
+    repl: Repl<H>,
+    captured: Capture,
+    ended: bool,
+}
+
+impl<H: CommandHandler> RpcSession<H> {
+    /// Creates a session around the REPL that `factory` returns.
+    ///
+    /// The factory is given the [`Output`] whose text ends up in responses;
+    /// the command handler should write to it instead of stdout. What
+    /// background jobs print after their command has returned is in the
+    /// response to the next request.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::repl::{Builtin, CommandHandler, Context, Job, Repl};
+    /// use mycli::rpc::RpcSession;
+    ///
+    /// struct Backups;
+    ///
+    /// impl CommandHandler for Backups {
+    ///     fn handle(&mut self, _: &str, _: &Context) -> bool {
+    ///         true
+    ///     }
+    ///
+    ///     fn background(&mut self, _: &str) -> Option<Job> {
+    ///         Some(Job::new(|_| Ok("backed up 3 databases".to_string())))
+    ///     }
+    /// }
+    ///
+    /// let mut session = RpcSession::new(|_| Ok(Repl::new("", Backups)?.with_builtin(Builtin::Jobs))).unwrap();
+    /// let started = session.respond(r#"{"jsonrpc": "2.0", "id": 1, "method": "execute", "params": ["backup &"]}"#).unwrap();
+    /// assert_eq!(started["result"]["output"], "[1] backup\n");
+    ///
+    /// std::thread::sleep(std::time::Duration::from_millis(200));
+    /// let next = session.respond(r#"{"jsonrpc": "2.0", "id": 2, "method": "execute", "params": ["jobs"]}"#).unwrap();
+    /// assert!(next["result"]["output"].as_str().unwrap().contains("backed up 3 databases"));
+    /// ```
+    pub fn new<F>(factory: F) -> crate::repl::Result<Self>
+    where
+        F: FnOnce(Output) -> crate::repl::Result<Repl<H>>,
+    {
+        let captured = Capture::default();
+        let output = Output::new(captured.clone());
+        let mut repl = factory(output.clone())?.with_output(output);
+        // Job output goes into the next response, not to the terminal.
+        repl.print_through_output();
+        Ok(Self { repl, captured, ended: false })
+    }
+
+    /// Answers requests read from stdin on stdout until stdin is closed or
+    /// the handler ends the session.
+    pub fn run_stdio(&mut self) -> io::Result<()> {
+        self.run(io::stdin().lock(), io::stdout().lock())
+    }
+
+    /// Answers requests read from `input` on `output` until `input` ends or
+    /// the handler ends the session.
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.respond(&line) {
+                serde_json::to_writer(&mut output, &response)?;
+                output.write_all(b"\n")?;
+                output.flush()?;
+            }
+            if self.ended {
+                break;
+            }
+        }
+        self.repl.cancel_jobs();
+        Ok(())
+    }
+
+    /// Runs the request in `line` and returns the response to send, if any.
+    pub fn respond(&mut self, line: &str) -> Option<Value> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(err) => return Some(error(Value::Null, PARSE_ERROR, &err.to_string())),
+        };
+        let Some(fields) = request.as_object() else {
+            return Some(error(Value::Null, INVALID_REQUEST, "a request must be an object"));
+        };
+        let id = fields.get("id").cloned();
+        let reply = |response: Value| id.is_some().then_some(response);
+        let error_id = id.clone().unwrap_or(Value::Null);
+
+        if fields.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+            return Some(error(error_id, INVALID_REQUEST, "`jsonrpc` must be \"2.0\""));
+        }
+        let Some(method) = fields.get("method").and_then(Value::as_str) else {
+            return Some(error(error_id, INVALID_REQUEST, "`method` must be a string"));
+        };
+        if method != "execute" {
+            return reply(error(error_id, METHOD_NOT_FOUND, &format!("unknown method `{method}`")));
+        }
+        let Some(command) = command(fields.get("params")) else {
+            return reply(error(error_id, INVALID_PARAMS, "expected the command as `command` or `[command]`"));
+        };
+
+        let result = self.run_command(command);
+        reply(json!({ "jsonrpc": "2.0", "id": error_id, "result": result }))
+    }
+
+    /// Returns `true` once the handler has ended the session.
+    pub fn has_ended(&self) -> bool {
+        self.ended
+    }
+
+    /// Returns the REPL, for instance to inspect its handler afterwards.
+    pub fn into_inner(self) -> Repl<H> {
+        self.repl
+    }
+
+    /// Runs `command` and returns what it printed.
+    pub(crate) fn run_command(&mut self, command: &str) -> Outcome {
+        let command = command.trim();
+        if !command.is_empty() && !self.ended {
+            self.ended = !self.repl.execute(command);
+        }
+        let Captured { output, errors, failures } = self.captured.take();
+        Outcome { output, errors, failures, exit: self.ended }
+    }
+}
+
+/// What a command printed, as sent in responses.
+#[derive(Serialize)]
+pub(crate) struct Outcome {
+    output: String,
+    errors: String,
+    failures: Vec<Value>,
+    exit: bool,
+}
+
+/// Returns the command line given in `params`.
+fn command(params: Option<&Value>) -> Option<&str> {
+    match params? {
+        Value::Object(params) => params.get("command")?.as_str(),
+        Value::Array(params) if params.len() == 1 => params[0].as_str(),
+        _ => None,
+    }
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Collects regular output, errors and coded errors separately until a
+/// response takes them.
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Captured>>);
+
+#[derive(Default)]
+struct Captured {
+    output: String,
+    errors: String,
+    failures: Vec<Value>,
+}
+
+impl Capture {
+    fn lock(&self) -> MutexGuard<'_, Captured> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn take(&self) -> Captured {
+        mem::take(&mut *self.lock())
+    }
+}
+
+impl OutputSink for Capture {
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        self.lock().output.push_str(s);
+        Ok(())
+    }
+
+    fn write_err(&mut self, s: &str) -> io::Result<()> {
+        self.lock().errors.push_str(s);
+        Ok(())
+    }
+
+    fn write_error(&mut self, error: &CodedError, text: &str) -> io::Result<()> {
+        let details: serde_json::Map<String, Value> =
+            error.details().iter().map(|(key, value)| (key.clone(), Value::from(value.as_str()))).collect();
+        let mut captured = self.lock();
+        captured.errors.push_str(text);
+        captured.errors.push('\n');
+        captured.failures.push(json!({ "code": error.code(), "message": error.message(), "details": details }));
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+//! Passwords and tokens kept in the operating system's keyring.
+//!
+//! Secrets are stored in the macOS Keychain, the Windows Credential Manager
+//! or the Secret Service (GNOME Keyring, KWallet) on Linux, instead of in
+//! plain text in a configuration file. Each secret is identified by a
+//! service name, usually the application's name, and an account.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use mycli::prompt::Password;
+//! use mycli::secrets::Keyring;
+//!
+//! let keyring = Keyring::new("myapp");
+//! // Asks only the first time; later runs read the stored token.
+//! let token = keyring.login("api-token", Password::new("API token"))?;
+//!
+//! // A `logout` command forgets it again.
+//! keyring.delete("api-token")?;
+//! # Ok::<(), mycli::secrets::SecretError>(())
+//! ```
+
+use std::fmt;
+
+use crate::prompt::{self, Password};
+
+/// An error reading or writing the keyring.
+#[derive(Debug)]
+pub enum SecretError {
+    /// There is no keyring to use, for example because no Secret Service
+    /// is running in this session.
+    Unavailable(String),
+    /// The keyring refused or failed the operation.
+    Access(String),
+    /// Asking for the secret failed.
+    Prompt(prompt::Error),
+}
+
+impl fmt::Display for SecretError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretError::Unavailable(message) => write!(f, "no keyring available: {message}"),
+            SecretError::Access(message) => write!(f, "keyring error: {message}"),
+            SecretError::Prompt(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SecretError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SecretError::Prompt(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<prompt::Error> for SecretError {
+    fn from(err: prompt::Error) -> Self {
+        SecretError::Prompt(err)
+    }
+}
+
+impl From<keyring::Error> for SecretError {
+    fn from(err: keyring::Error) -> Self {
+        match err {
+            keyring::Error::NoStorageAccess(_) | keyring::Error::PlatformFailure(_) => {
+                SecretError::Unavailable(err.to_string())
+            }
+            err => SecretError::Access(err.to_string()),
+        }
+    }
+}
+
+/// The secrets of one service in the system keyring.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keyring {
+    service: String,
+}
+
+impl Keyring {
+    /// Opens the secrets stored for `service`.
+    pub fn new(service: impl Into<String>) -> Self {
+        Self { service: service.into() }
+    }
+
+    /// Returns the secret stored for `account`, or `None` if there is none.
+    pub fn get(&self, account: &str) -> Result<Option<String>, SecretError> {
+        match self.entry(account)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Stores `secret` for `account`, replacing any previous one.
+    pub fn set(&self, account: &str, secret: &str) -> Result<(), SecretError> {
+        Ok(self.entry(account)?.set_password(secret)?)
+    }
+
+    /// Removes the secret stored for `account`. Returns `false` if there
+    /// was none.
+    pub fn delete(&self, account: &str) -> Result<bool, SecretError> {
+        match self.entry(account)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the secret stored for `account`, or asks for it with
+    /// `password` and stores the answer for next time.
+    pub fn login(&self, account: &str, password: Password) -> Result<String, SecretError> {
+        if let Some(secret) = self.get(account)? {
+            return Ok(secret);
+        }
+        let secret = password.interact()?;
+        self.set(account, &secret)?;
+        Ok(secret)
+    }
+
+    fn entry(&self, account: &str) -> Result<keyring::Entry, SecretError> {
+        Ok(keyring::Entry::new(&self.service, account)?)
+    }
+}
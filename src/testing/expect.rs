@@ -0,0 +1,290 @@
+//! Driving a running REPL the way a user at the keyboard would.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use super::Captured;
+use crate::output::Output;
+use crate::repl::{CommandHandler, LineReader, ReadlineError, Repl};
+
+/// Runs a REPL on its own thread and talks to it like `expect` talks to a
+/// terminal: send a line, wait for some text, press Ctrl+C.
+///
+/// The REPL runs its full loop with [`Repl::run_with`], so Ctrl+C and
+/// Ctrl+D behave as they do at the terminal, and lines sent while a command
+/// is running answer the [`prompt`](crate::prompt) widgets it shows. What
+/// the terminal would display is captured without escape sequences: each
+/// prompt with the line typed after it, the commands' output, and the
+/// widgets' questions and answers.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::Output;
+/// use mycli::repl::{CommandHandler, Context, Repl};
+/// use mycli::testing::Expect;
+///
+/// struct Db(Output);
+///
+/// impl CommandHandler for Db {
+///     fn handle(&mut self, command: &str, _: &Context) -> bool {
+///         match command {
+///             "quit" => return false,
+///             "connect db" => {
+///                 let _ = self.0.println("Connected");
+///             }
+///             _ => {
+///                 let _ = self.0.eprintln(&format!("unknown command `{command}`"));
+///             }
+///         }
+///         true
+///     }
+/// }
+///
+/// let mut session = Expect::spawn(|out| Repl::new("db> ", Db(out)))?;
+/// session.expect_contains("db> ");
+/// session.send("connect db").expect_contains("Connected");
+/// session.send_ctrl_c().send("quit").expect_exit();
+/// # Ok::<(), mycli::repl::ReadlineError>(())
+/// ```
+pub struct Expect {
+    input: Option<Sender<Input>>,
+    captured: Captured,
+    cursor: usize,
+    timeout: Duration,
+    done: Receiver<crate::repl::Result<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// What the test types.
+enum Input {
+    Line(String),
+    Interrupt,
+    Eof,
+}
+
+impl Expect {
+    /// Starts the REPL created by `factory` on a new thread. `factory` is
+    /// given the [`Output`] whose text is captured; command handlers should
+    /// print through it.
+    pub fn spawn<H, F>(factory: F) -> crate::repl::Result<Self>
+    where
+        H: CommandHandler,
+        F: FnOnce(Output) -> crate::repl::Result<Repl<H>> + Send + 'static,
+    {
+        let captured = Captured::default();
+        let output = Output::new(captured.clone());
+        let (input, keys) = mpsc::channel();
+        let (ready_tx, ready) = mpsc::channel();
+        let (done_tx, done) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            let mut repl = match factory(output.clone()) {
+                Ok(repl) => repl.with_output(output.clone()),
+                Err(err) => {
+                    let _ = ready_tx.send(Err(err));
+                    return;
+                }
+            };
+            repl.print_through_output();
+            let _ = ready_tx.send(Ok(()));
+            let mut keyboard = Keyboard(Rc::new(Keys { input: keys, pending: RefCell::default(), output }));
+            #[cfg(feature = "prompt-core")]
+            crate::prompt::scripted::install(Some(keyboard.0.clone()));
+            let result = repl.run_with(&mut keyboard);
+            let _ = done_tx.send(result);
+        });
+        match ready.recv() {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => return Err(err),
+            // The factory panicked; show its panic rather than a new one.
+            Err(_) => std::panic::resume_unwind(thread.join().err().unwrap_or_else(|| Box::new("REPL thread ended"))),
+        }
+        Ok(Self {
+            input: Some(input),
+            captured,
+            cursor: 0,
+            timeout: Duration::from_secs(5),
+            done,
+            thread: Some(thread),
+        })
+    }
+
+    /// Sets how long [`expect_contains`](Self::expect_contains) and
+    /// [`expect_exit`](Self::expect_exit) wait before failing. Defaults to
+    /// 5 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Types `line` and presses Enter.
+    pub fn send(&mut self, line: &str) -> &mut Self {
+        self.push(Input::Line(line.to_string()))
+    }
+
+    /// Presses Ctrl+C. At the REPL's prompt this discards the line; in a
+    /// prompt widget it cancels the widget.
+    pub fn send_ctrl_c(&mut self) -> &mut Self {
+        self.push(Input::Interrupt)
+    }
+
+    /// Presses Ctrl+D, which ends the REPL when it is waiting for a
+    /// command.
+    pub fn send_eof(&mut self) -> &mut Self {
+        self.push(Input::Eof)
+    }
+
+    /// Waits until `text` is displayed after the text matched by the
+    /// previous expectation, and moves past it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` does not show up within the
+    /// [`timeout`](Self::timeout), showing what was displayed instead.
+    #[track_caller]
+    pub fn expect_contains(&mut self, text: &str) -> &mut Self {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let found = {
+                let transcript = self.captured.lock();
+                let unread = &transcript.all[self.cursor..];
+                if Instant::now() >= deadline && !unread.contains(text) {
+                    panic!("timed out waiting for {text:?}; displayed since the last match:\n{unread}");
+                }
+                unread.find(text)
+            };
+            if let Some(at) = found {
+                self.cursor += at + text.len();
+                return self;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    /// Waits for the REPL loop to end, as it does after `quit` or Ctrl+D.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the loop is still running after the
+    /// [`timeout`](Self::timeout), or if it ended with an error.
+    #[track_caller]
+    pub fn expect_exit(&mut self) -> &mut Self {
+        match self.done.recv_timeout(self.timeout) {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => panic!("the REPL ended with an error: {err}"),
+            Err(RecvTimeoutError::Timeout) => {
+                panic!("timed out waiting for the REPL to exit; displayed:\n{}", self.output())
+            }
+            Err(RecvTimeoutError::Disconnected) => self.join(),
+        }
+        self
+    }
+
+    /// Returns everything displayed so far.
+    pub fn output(&self) -> String {
+        self.captured.lock().all.clone()
+    }
+
+    fn push(&mut self, input: Input) -> &mut Self {
+        if let Some(sender) = &self.input {
+            let _ = sender.send(input);
+        }
+        self
+    }
+
+    /// Waits for the thread, passing on a panic from a command handler.
+    fn join(&mut self) {
+        if let Some(thread) = self.thread.take()
+            && let Err(panic) = thread.join()
+            && !thread::panicking()
+        {
+            std::panic::resume_unwind(panic);
+        }
+    }
+}
+
+impl Drop for Expect {
+    /// Ends the session as if the terminal was closed, and waits for the
+    /// REPL to finish.
+    fn drop(&mut self) {
+        self.input = None;
+        self.join();
+    }
+}
+
+/// The keys sent by the test, shared by the REPL's line reader and the
+/// prompt widgets.
+struct Keys {
+    input: Receiver<Input>,
+    /// What is left of a line a widget read key by key.
+    pending: RefCell<VecDeque<char>>,
+    output: Output,
+}
+
+impl Keys {
+    /// Returns the next input, or EOF once the test has ended.
+    fn next(&self) -> Input {
+        self.input.recv().unwrap_or(Input::Eof)
+    }
+
+    /// Returns the rest of a line a widget started reading, if it did.
+    fn rest_of_line(&self) -> Option<String> {
+        let mut pending = self.pending.borrow_mut();
+        if pending.is_empty() {
+            return None;
+        }
+        Some(pending.drain(..).take_while(|&c| c != '\n').collect())
+    }
+}
+
+/// The REPL's [`LineReader`], which echoes the prompt and the line as the
+/// terminal would.
+struct Keyboard(Rc<Keys>);
+
+impl LineReader for Keyboard {
+    fn readline(&mut self, prompt: &str) -> crate::repl::Result<String> {
+        let _ = self.0.output.print(prompt);
+        let pending = self.0.rest_of_line();
+        let line = match pending.map_or_else(|| self.0.next(), Input::Line) {
+            Input::Line(line) => line,
+            Input::Interrupt => {
+                let _ = self.0.output.print("^C\n");
+                return Err(ReadlineError::Interrupted);
+            }
+            Input::Eof => {
+                let _ = self.0.output.print("\n");
+                return Err(ReadlineError::Eof);
+            }
+        };
+        let _ = self.0.output.println(&line);
+        Ok(line)
+    }
+}
+
+#[cfg(feature = "prompt-core")]
+impl crate::prompt::scripted::ScriptedTerminal for Keys {
+    fn write(&self, s: &str) {
+        let _ = self.output.print(s);
+    }
+
+    fn read_key(&self) -> std::io::Result<console::Key> {
+        use console::Key;
+
+        let mut pending = self.pending.borrow_mut();
+        if pending.is_empty() {
+            match self.next() {
+                Input::Line(line) => pending.extend(line.chars().chain(['\n'])),
+                Input::Interrupt => return Ok(Key::CtrlC),
+                Input::Eof => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+            }
+        }
+        Ok(match pending.pop_front() {
+            Some('\n') | None => Key::Enter,
+            Some(c) => Key::Char(c),
+        })
+    }
+}
@@ -0,0 +1,104 @@
+//! Comparing output against golden files.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::output::ansi;
+
+/// The environment variable that makes [`Golden::assert`] rewrite golden
+/// files instead of comparing against them.
+pub const UPDATE_VAR: &str = "UPDATE_GOLDEN";
+
+/// A file holding the output a test expects.
+///
+/// Output is normalized before it is compared or written: escape sequences
+/// are removed and `\r\n` becomes `\n`, so colors and platforms do not
+/// matter. When the output changes on purpose, run the tests with
+/// `UPDATE_GOLDEN=1` to write the new output to the files.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::testing::Golden;
+///
+/// let path = std::env::temp_dir().join("mycli-golden-help.txt");
+/// Golden::new(&path).update(true).assert("Usage: deploy <env>\r\n");
+/// Golden::new(&path).assert("\x1b[1mUsage:\x1b[0m deploy <env>\n");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Golden {
+    path: PathBuf,
+    update: bool,
+}
+
+impl Golden {
+    /// Creates a golden file at `path`, updated when `UPDATE_GOLDEN` is set
+    /// to anything but `0`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let update = std::env::var_os(UPDATE_VAR).is_some_and(|v| !v.is_empty() && v != "0");
+        Self { path: path.into(), update }
+    }
+
+    /// Sets whether [`assert`](Self::assert) writes the file instead of
+    /// comparing against it, overriding `UPDATE_GOLDEN`.
+    pub fn update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Checks `actual` against the file, or writes it there in update mode.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the output differs, showing the first line that does, or
+    /// if the file is missing or cannot be read or written.
+    #[track_caller]
+    pub fn assert(&self, actual: &str) {
+        let actual = normalize(actual);
+        if self.update {
+            if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+                fs::create_dir_all(dir).unwrap_or_else(|e| panic!("cannot create {}: {e}", dir.display()));
+            }
+            fs::write(&self.path, &actual).unwrap_or_else(|e| panic!("cannot write {}: {e}", self.path.display()));
+            return;
+        }
+        let expected = match fs::read_to_string(&self.path) {
+            Ok(expected) => normalize(&expected),
+            Err(e) => panic!("cannot read golden file {}: {e}; run with {UPDATE_VAR}=1 to create it", self.path.display()),
+        };
+        if expected != actual {
+            panic!("{}", mismatch(&self.path, &expected, &actual));
+        }
+    }
+}
+
+/// Removes escape sequences and carriage returns before line feeds.
+fn normalize(s: &str) -> String {
+    ansi::strip(s).replace("\r\n", "\n")
+}
+
+fn mismatch(path: &Path, expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    let (want, got) = loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(want), Some(got)) if want == got => line += 1,
+            (want, got) => break (want, got),
+        }
+    };
+    let mut msg = format!("output differs from golden file {}", path.display());
+    let show = |l: Option<&str>| l.map_or("<end of output>".to_string(), |l| format!("{l:?}"));
+    let _ = write!(msg, " at line {line}\n  expected: {}\n    actual: {}", show(want), show(got));
+    if want.is_none() && got.is_none() {
+        msg.push_str("\n(the outputs differ in their trailing newline)");
+    }
+    let _ = write!(msg, "\n\n--- actual output ---\n{actual}\nrun with {UPDATE_VAR}=1 to accept it");
+    msg
+}
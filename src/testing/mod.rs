@@ -0,0 +1,412 @@
+//! Driving a REPL from tests, without a terminal.
+//!
+//! [`Repl::run`] reads from the terminal through the line editor, which
+//! makes it hard to test. A [`ReplTester`] feeds lines to a REPL instead,
+//! through the same path typed commands take: alias expansion, built-ins,
+//! the command handler, timing, metrics and the audit log. Everything
+//! printed is captured, and commands are timed on a [`FakeClock`] that only
+//! moves when the test advances it, so output such as `took 2.0s` can be
+//! asserted on.
+//!
+//! To test the loop itself, including what Ctrl+C and Ctrl+D do, give
+//! [`Repl::run_with`] a [`MockReader`] that replays lines, interrupts and
+//! EOF in order. For interactive flows, such as a command asking for
+//! confirmation, [`Expect`] runs the REPL on its own thread and answers
+//! prompt widgets as they are shown.
+//!
+//! For property tests of the parsing layer, [`arbitrary_lines`] generates
+//! random command lines from a seeded [`Rng`](crate::repl::Rng), and
+//! [`check_tokens`] and [`check_round_trip`] check what the tokenizer
+//! promises about them.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::Duration;
+//! use mycli::output::Output;
+//! use mycli::repl::{CommandHandler, Context, Repl};
+//! use mycli::testing::{FakeClock, ReplTester};
+//!
+//! struct Deployer {
+//!     out: Output,
+//!     clock: FakeClock,
+//! }
+//!
+//! impl CommandHandler for Deployer {
+//!     fn handle(&mut self, command: &str, _: &Context) -> bool {
+//!         match command {
+//!             "quit" => return false,
+//!             "deploy" => {
+//!                 self.clock.advance(Duration::from_secs(2));
+//!                 let _ = self.out.println("deployed");
+//!             }
+//!             _ => {
+//!                 let _ = self.out.eprintln(&format!("Error: unknown command `{command}`"));
+//!             }
+//!         }
+//!         true
+//!     }
+//! }
+//!
+//! let clock = FakeClock::new();
+//! let mut tester = ReplTester::new(|out| {
+//!     Ok(Repl::new("> ", Deployer { out, clock: clock.clone() })?.with_timing(true))
+//! })?
+//! .with_clock(clock.clone());
+//!
+//! tester.send("deploy");
+//! assert_eq!(tester.take_output(), "deployed\ntook 2.0s\n");
+//!
+//! tester.run_script("frobnicate\nquit\nnever run\n");
+//! assert!(tester.errors().starts_with("Error: unknown command `frobnicate`\n"));
+//! assert!(tester.has_exited());
+//! # Ok::<(), mycli::repl::ReadlineError>(())
+//! ```
+//!
+//! [`ReplTester::assert_golden`] compares a script's transcript against a
+//! [`Golden`] file, which keeps formatting from changing unnoticed:
+//!
+//! ```no_run
+//! # use mycli::output::Output;
+//! # use mycli::repl::{CommandHandler, Repl};
+//! # use mycli::testing::ReplTester;
+//! # struct Echo(Output);
+//! # impl CommandHandler for Echo {
+//! #     fn handle(&mut self, command: &str, _: &mycli::repl::Context) -> bool { let _ = self.0.println(command); true }
+//! # }
+//! let mut tester = ReplTester::new(|out| Repl::new("> ", Echo(out)))?;
+//! // `UPDATE_GOLDEN=1 cargo test` writes the file.
+//! tester.assert_golden("status\nlist users\n", "tests/golden/echo.txt");
+//! # Ok::<(), mycli::repl::ReadlineError>(())
+//! ```
+//!
+//! Driving the loop with a [`MockReader`]:
+//!
+//! ```
+//! # use mycli::output::Output;
+//! # use mycli::repl::{CommandHandler, Context, Repl};
+//! use mycli::testing::{MockReader, ReplTester};
+//!
+//! struct Echo(Output);
+//!
+//! impl CommandHandler for Echo {
+//!     fn handle(&mut self, command: &str, _: &Context) -> bool {
+//!         let _ = self.0.println(command);
+//!         true
+//!     }
+//! }
+//!
+//! let mut tester = ReplTester::new(|out| Repl::new("> ", Echo(out)))?;
+//! let mut reader = MockReader::new().line("status").interrupt().line("list").eof().line("unread");
+//! tester.repl_mut().run_with(&mut reader)?;
+//!
+//! assert_eq!(tester.output(), "status\nlist\n");
+//! assert_eq!(reader.remaining(), 1);
+//! assert_eq!(reader.prompts(), ["> ", "> ", "> ", "> "]);
+//! # Ok::<(), mycli::repl::ReadlineError>(())
+//! ```
+
+mod expect;
+mod golden;
+mod props;
+
+use std::collections::VecDeque;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::output::{Output, OutputSink};
+use crate::repl::{Clock, CommandHandler, LineReader, ReadlineError, Repl};
+
+pub use expect::Expect;
+pub use golden::{Golden, UPDATE_VAR};
+pub use props::{arbitrary_line, arbitrary_lines, arbitrary_words, check_round_trip, check_tokens};
+
+/// A clock that stands still until it is advanced; clones share the same
+/// time.
+///
+/// This is a [manual](Clock::manual) [`Clock`] starting at the current
+/// time; [`clock`](Self::clock) returns it for command handlers that read
+/// timestamps.
+#[derive(Debug, Clone)]
+pub struct FakeClock {
+    start: Instant,
+    clock: Clock,
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeClock {
+    /// Creates a clock reading the current time.
+    pub fn new() -> Self {
+        let clock = Clock::manual(SystemTime::now());
+        Self { start: clock.instant(), clock }
+    }
+
+    /// Moves the clock forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        self.clock.advance(by);
+    }
+
+    /// Returns how far the clock was advanced since it was created.
+    pub fn elapsed(&self) -> Duration {
+        self.clock.instant() - self.start
+    }
+
+    /// Returns the time the clock reads.
+    pub fn now(&self) -> Instant {
+        self.clock.instant()
+    }
+
+    /// Returns the clock as a [`Clock`], sharing its time.
+    pub fn clock(&self) -> Clock {
+        self.clock.clone()
+    }
+}
+
+/// A [`LineReader`] that replays scripted input, for driving
+/// [`Repl::run_with`] in tests.
+///
+/// Reads return the queued events in order; once they run out, every read
+/// returns EOF as if Ctrl+D was pressed.
+#[derive(Debug, Default)]
+pub struct MockReader {
+    events: VecDeque<Event>,
+    prompts: Vec<String>,
+}
+
+#[derive(Debug)]
+enum Event {
+    Line(String),
+    Interrupt,
+    Eof,
+}
+
+impl MockReader {
+    /// Creates a reader with nothing queued.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a reader that returns each of `lines`, then EOF.
+    pub fn from_lines<I>(lines: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self { events: lines.into_iter().map(|line| Event::Line(line.into())).collect(), prompts: Vec::new() }
+    }
+
+    /// Queues `line`, as if it was typed and Enter pressed.
+    pub fn line(mut self, line: impl Into<String>) -> Self {
+        self.events.push_back(Event::Line(line.into()));
+        self
+    }
+
+    /// Queues a Ctrl+C.
+    pub fn interrupt(mut self) -> Self {
+        self.events.push_back(Event::Interrupt);
+        self
+    }
+
+    /// Queues a Ctrl+D. Events after it are only read if the reader is
+    /// used again.
+    pub fn eof(mut self) -> Self {
+        self.events.push_back(Event::Eof);
+        self
+    }
+
+    /// Returns the prompts shown so far, one per read.
+    pub fn prompts(&self) -> &[String] {
+        &self.prompts
+    }
+
+    /// Returns how many queued events have not been read yet.
+    pub fn remaining(&self) -> usize {
+        self.events.len()
+    }
+}
+
+impl LineReader for MockReader {
+    fn readline(&mut self, prompt: &str) -> crate::repl::Result<String> {
+        self.prompts.push(prompt.to_string());
+        match self.events.pop_front() {
+            Some(Event::Line(line)) => Ok(line),
+            Some(Event::Interrupt) => Err(ReadlineError::Interrupted),
+            Some(Event::Eof) | None => Err(ReadlineError::Eof),
+        }
+    }
+}
+
+/// Runs lines through a [`Repl`] as if they were typed, capturing what it
+/// prints.
+///
+/// See the [module documentation](self) for an example.
+pub struct ReplTester<H>
+where H: CommandHandler {
+    repl: Repl<H>,
+    captured: Captured,
+    clock: FakeClock,
+    exited: bool,
+}
+
+impl<H: CommandHandler> ReplTester<H> {
+    /// Creates the tester. `factory` creates the REPL to test, given the
+    /// [`Output`] whose text is captured; command handlers should print
+    /// through it.
+    pub fn new<F>(factory: F) -> crate::repl::Result<Self>
+    where
+        F: FnOnce(Output) -> crate::repl::Result<Repl<H>>,
+    {
+        let captured = Captured::default();
+        let output = Output::new(captured.clone());
+        let mut repl = factory(output.clone())?.with_output(output);
+        repl.print_through_output();
+        let clock = FakeClock::new();
+        repl.use_clock(clock.clock());
+        Ok(Self { repl, captured, clock, exited: false })
+    }
+
+    /// Times commands on `clock`, for handlers that advance it, instead of
+    /// the tester's own.
+    pub fn with_clock(mut self, clock: FakeClock) -> Self {
+        self.repl.use_clock(clock.clock());
+        self.clock = clock;
+        self
+    }
+
+    /// Runs `line` as if it was typed at the prompt. Blank lines are
+    /// skipped, as are all lines once the REPL has exited.
+    pub fn send(&mut self, line: &str) -> &mut Self {
+        let cmd = line.trim();
+        if !self.exited && !cmd.is_empty() && !self.repl.execute(cmd) {
+            self.exited = true;
+        }
+        self
+    }
+
+    /// Runs each of `lines` with [`send`](Self::send).
+    pub fn run<I>(&mut self, lines: I) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        for line in lines {
+            self.send(line.as_ref());
+        }
+        self
+    }
+
+    /// Runs `script`, one command per line.
+    pub fn run_script(&mut self, script: &str) -> &mut Self {
+        self.run(script.lines())
+    }
+
+    /// Runs `script` and returns its transcript: each command after the
+    /// prompt, as it would appear in the terminal, followed by what it
+    /// printed. Output printed before the script is left out.
+    pub fn transcript(&mut self, script: &str) -> String {
+        self.take_output();
+        let mut transcript = String::new();
+        for line in script.lines() {
+            let cmd = line.trim();
+            if self.exited || cmd.is_empty() {
+                continue;
+            }
+            transcript.push_str(&self.repl.prompt());
+            transcript.push_str(cmd);
+            transcript.push('\n');
+            self.send(cmd);
+            transcript.push_str(&self.take_output());
+        }
+        transcript
+    }
+
+    /// Runs `script` and checks its [transcript](Self::transcript) against
+    /// the [`Golden`] file at `path`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the transcript differs from the file, as
+    /// [`Golden::assert`] does.
+    #[track_caller]
+    pub fn assert_golden(&mut self, script: &str, path: impl Into<PathBuf>) {
+        let transcript = self.transcript(script);
+        Golden::new(path).assert(&transcript);
+    }
+
+    /// Returns everything printed so far, regular and error output in the
+    /// order it was written.
+    pub fn output(&self) -> String {
+        self.captured.lock().all.clone()
+    }
+
+    /// Returns the error output printed so far.
+    pub fn errors(&self) -> String {
+        self.captured.lock().errors.clone()
+    }
+
+    /// Returns everything printed so far, as [`output`](Self::output)
+    /// does, and forgets it.
+    pub fn take_output(&mut self) -> String {
+        let mut captured = self.captured.lock();
+        captured.errors.clear();
+        std::mem::take(&mut captured.all)
+    }
+
+    /// Returns `true` once the command handler has returned `false`, as it
+    /// does for commands like `quit`.
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    /// Returns the clock commands are timed on.
+    pub fn clock(&self) -> &FakeClock {
+        &self.clock
+    }
+
+    /// Returns the REPL being tested.
+    pub fn repl(&self) -> &Repl<H> {
+        &self.repl
+    }
+
+    /// Returns the REPL being tested, for changing it between commands.
+    pub fn repl_mut(&mut self) -> &mut Repl<H> {
+        &mut self.repl
+    }
+}
+
+#[derive(Default)]
+struct Transcript {
+    all: String,
+    errors: String,
+}
+
+/// Captures output, keeping errors apart as well.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Transcript>>);
+
+impl Captured {
+    fn lock(&self) -> MutexGuard<'_, Transcript> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl OutputSink for Captured {
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        self.lock().all.push_str(s);
+        Ok(())
+    }
+
+    fn write_err(&mut self, s: &str) -> io::Result<()> {
+        let mut transcript = self.lock();
+        transcript.all.push_str(s);
+        transcript.errors.push_str(s);
+        Ok(())
+    }
+}
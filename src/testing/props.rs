@@ -0,0 +1,150 @@
+//! Random command lines and the properties parsing them must keep.
+
+use crate::repl::{join, tokenize, Rng};
+
+/// Characters random lines are made of: plain letters, the characters the
+/// tokenizer treats specially, and a few that are more than one byte.
+const ALPHABET: &[char] = &[
+    'a', 'b', 'c', 'x', 'y', 'z', '0', '1', '-', '=', '.', '/', '&', '|', ' ', ' ', ' ', '\t', '"', '"', '\'', '\'',
+    'é', 'λ', '日', '\u{3000}', '🦀',
+];
+
+/// Returns a random command line of up to 40 characters, often with
+/// unbalanced quotes and unusual whitespace.
+///
+/// Use the lines to check that code reading input copes with anything,
+/// such as with [`check_tokens`], or by running them through a
+/// [`ReplTester`](super::ReplTester) to see that the dispatcher never
+/// panics. Seed `rng` with [`Rng::from_seed`] to get the same lines on
+/// every run.
+pub fn arbitrary_line(rng: &Rng) -> String {
+    let len = rng.below(41) as usize;
+    (0..len).map(|_| ALPHABET[rng.below(ALPHABET.len() as u64) as usize]).collect()
+}
+
+/// Returns an endless series of [random lines](arbitrary_line).
+///
+/// # Examples
+///
+/// ```
+/// use mycli::output::Output;
+/// use mycli::repl::{CommandHandler, Context, Repl, Rng};
+/// use mycli::testing::{arbitrary_lines, ReplTester};
+///
+/// struct Echo(Output);
+///
+/// impl CommandHandler for Echo {
+///     fn handle(&mut self, command: &str, _: &Context) -> bool {
+///         let _ = self.0.println(command);
+///         true
+///     }
+/// }
+///
+/// let mut tester = ReplTester::new(|out| Repl::new("> ", Echo(out)))?;
+/// tester.run(arbitrary_lines(&Rng::from_seed(1)).take(500));
+/// assert!(!tester.has_exited());
+/// # Ok::<(), mycli::repl::ReadlineError>(())
+/// ```
+pub fn arbitrary_lines(rng: &Rng) -> impl Iterator<Item = String> {
+    let rng = rng.clone();
+    std::iter::repeat_with(move || arbitrary_line(&rng))
+}
+
+/// Returns up to 6 random words that [`join`] can always quote: each
+/// contains at most one kind of quote.
+pub fn arbitrary_words(rng: &Rng) -> Vec<String> {
+    let count = rng.below(7) as usize;
+    (0..count)
+        .map(|_| {
+            let word = arbitrary_line(rng);
+            let keep = if rng.below(2) == 0 { '"' } else { '\'' };
+            word.chars().filter(|&c| c == keep || (c != '"' && c != '\'')).take(12).collect()
+        })
+        .collect()
+}
+
+/// Checks what [`tokenize`] promises about any `line`: each token's span
+/// lies within the line, after the previous one, and holds the token's
+/// text, with quotes around it if it was quoted; only whitespace is left
+/// between and after tokens; and an unterminated quote ends the tokens.
+///
+/// Returns a description of the first broken property.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::Rng;
+/// use mycli::testing::{arbitrary_lines, check_tokens};
+///
+/// for line in arbitrary_lines(&Rng::from_seed(7)).take(1000) {
+///     check_tokens(&line).unwrap_or_else(|err| panic!("{line:?}: {err}"));
+/// }
+/// ```
+pub fn check_tokens(line: &str) -> Result<(), String> {
+    let mut end = 0;
+    let mut tokens = tokenize(line);
+    for token in tokens.by_ref() {
+        let token = match token {
+            Ok(token) => token,
+            Err(err) => {
+                if line.get(err.start..).and_then(|rest| rest.chars().next()) != Some(err.quote) {
+                    return Err(format!("no {} at byte {} for the unterminated quote", err.quote, err.start));
+                }
+                end = line.len();
+                break;
+            }
+        };
+        let span = token.span.clone();
+        let Some(written) = line.get(span.clone()) else {
+            return Err(format!("span {span:?} is not within the line"));
+        };
+        if span.start < end || !line[end..span.start].chars().all(char::is_whitespace) {
+            return Err(format!("span {span:?} does not follow the previous token"));
+        }
+        let expected = if token.quoted { written.get(1..written.len() - 1) } else { Some(written) };
+        if expected != Some(token.text) {
+            return Err(format!("span {span:?} holds {written:?}, not the token {:?}", token.text));
+        }
+        end = span.end;
+    }
+    if tokens.next().is_some() {
+        return Err("tokens continue after an unterminated quote".to_string());
+    }
+    if !line[end..].chars().all(char::is_whitespace) {
+        return Err(format!("text after byte {end} is not in any token"));
+    }
+    Ok(())
+}
+
+/// Checks that joining the words of `line` with [`join`] and tokenizing
+/// the result gives the same words. Lines with an unterminated quote, and
+/// words that cannot be quoted, pass trivially.
+///
+/// Returns a description of the difference.
+///
+/// # Examples
+///
+/// ```
+/// use mycli::repl::{join, Rng};
+/// use mycli::testing::{arbitrary_words, check_round_trip};
+///
+/// let rng = Rng::from_seed(3);
+/// for _ in 0..1000 {
+///     let line = join(&arbitrary_words(&rng)).unwrap();
+///     check_round_trip(&line).unwrap_or_else(|err| panic!("{line:?}: {err}"));
+/// }
+/// ```
+pub fn check_round_trip(line: &str) -> Result<(), String> {
+    let Ok(words) = tokenize(line).map(|token| token.map(|token| token.text)).collect::<Result<Vec<_>, _>>() else {
+        return Ok(());
+    };
+    let Some(joined) = join(&words) else {
+        return Ok(());
+    };
+    let again: Result<Vec<_>, _> = tokenize(&joined).map(|token| token.map(|token| token.text)).collect();
+    match again {
+        Ok(again) if again == words => Ok(()),
+        Ok(again) => Err(format!("{joined:?} splits into {again:?}, not {words:?}")),
+        Err(err) => Err(format!("{joined:?} does not tokenize: {err}")),
+    }
+}
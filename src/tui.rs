@@ -0,0 +1,500 @@
+//! Embedding a REPL in a [ratatui](https://ratatui.rs) interface.
+//!
+//! A [`ReplWidget`] runs commands through a [`Repl`], with its aliases,
+//! built-ins and command handler, but takes its keys from the application
+//! and draws itself into a ratatui layout instead of owning the terminal:
+//! the output of past commands scrolls above an input line with the
+//! prompt, and Tab opens a popup with the completions of the word being
+//! typed. Dashboards can put it next to their other widgets.
+//!
+//! Output is kept as plain text, with errors drawn in red. Background jobs
+//! and other threads may print at any time; their lines appear the next
+//! time the widget is drawn.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use mycli::output::Output;
+//! use mycli::repl::{CommandHandler, Context, Repl};
+//! use mycli::tui::ReplWidget;
+//! use ratatui::crossterm::event::{self, Event};
+//!
+//! struct Dashboard {
+//!     out: Output,
+//! }
+//!
+//! impl CommandHandler for Dashboard {
+//!     fn handle(&mut self, command: &str, _: &Context) -> bool {
+//!         let _ = self.out.println(&format!("ran {command}"));
+//!         command != "quit"
+//!     }
+//! }
+//!
+//! let commands = ["status", "deploy", "quit"];
+//! let mut widget = ReplWidget::new(|out| Repl::new("> ", Dashboard { out }))?.with_completer(move |word| {
+//!     commands.iter().filter(|c| c.starts_with(word)).map(|c| c.to_string()).collect()
+//! });
+//! let mut terminal = ratatui::init();
+//! while !widget.has_exited() {
+//!     terminal.draw(|frame| {
+//!         let area = frame.area();
+//!         frame.render_widget(&widget, area);
+//!         frame.set_cursor_position(widget.cursor_position(area));
+//!     })?;
+//!     if let Event::Key(key) = event::read()? {
+//!         widget.handle_key(key);
+//!     }
+//! }
+//! ratatui::restore();
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use ratatui::buffer::Buffer;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Position, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Clear, List, ListState, Paragraph, StatefulWidget, Widget};
+
+use crate::output::theme::{self, Tone};
+use crate::output::width::display_width;
+use crate::output::{Output, OutputSink};
+use crate::repl::{CommandHandler, Repl};
+
+// The most completions the popup shows at once; it scrolls for more.
+const POPUP_ROWS: usize = 8;
+
+type Completer = Box<dyn FnMut(&str) -> Vec<String> + Send>;
+
+/// A REPL drawn as a ratatui widget.
+///
+/// See the [module documentation](self) for an example.
+pub struct ReplWidget<H: CommandHandler> {
+    repl: Repl<H>,
+    scrollback: Scrollback,
+    input: String,
+    // A byte offset into `input`, always on a character boundary.
+    cursor: usize,
+    history: Vec<String>,
+    // The entry of `history` being shown, and the input it replaced.
+    browsing: Option<(usize, String)>,
+    completer: Option<Completer>,
+    popup: Option<Popup>,
+    // How many lines the scrollback is scrolled up from the bottom.
+    scroll: usize,
+    exited: bool,
+}
+
+struct Popup {
+    candidates: Vec<String>,
+    selected: usize,
+}
+
+impl<H: CommandHandler> ReplWidget<H> {
+    /// Creates the widget. `factory` creates the REPL that runs the
+    /// commands, given the [`Output`] whose text is shown in the widget.
+    pub fn new<F>(factory: F) -> crate::repl::Result<Self>
+    where
+        F: FnOnce(Output) -> crate::repl::Result<Repl<H>>,
+    {
+        let scrollback = Scrollback::default();
+        let output = Output::new(scrollback.clone());
+        let mut repl = factory(output.clone())?.with_output(output);
+        repl.print_through_output();
+        Ok(Self {
+            repl,
+            scrollback,
+            input: String::new(),
+            cursor: 0,
+            history: Vec::new(),
+            browsing: None,
+            completer: None,
+            popup: None,
+            scroll: 0,
+            exited: false,
+        })
+    }
+
+    /// Completes words with `completer`, which is given the word before the
+    /// cursor, possibly empty, and returns the words that can replace it.
+    pub fn with_completer(mut self, completer: impl FnMut(&str) -> Vec<String> + Send + 'static) -> Self {
+        self.completer = Some(Box::new(completer));
+        self
+    }
+
+    /// Keeps at most `lines` lines of output, 10,000 by default.
+    pub fn with_max_lines(self, lines: usize) -> Self {
+        self.scrollback.lock().max = lines.max(1);
+        self
+    }
+
+    /// Handles a key pressed by the user. Returns `false` once the REPL
+    /// has exited, because the handler returned `false` or Ctrl-D was
+    /// pressed on an empty line.
+    ///
+    /// Enter runs the line, Tab completes, Up and Down go through the
+    /// history, Page Up and Page Down scroll the output and Ctrl-L clears
+    /// it; the usual editing keys work on the line.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if self.exited || key.kind == KeyEventKind::Release {
+            return !self.exited;
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if self.popup.is_some() && self.handle_popup_key(key) {
+            return true;
+        }
+        match key.code {
+            KeyCode::Enter => self.submit(),
+            KeyCode::Tab => self.complete(),
+            KeyCode::Char('d') if ctrl && self.input.is_empty() => self.exited = true,
+            KeyCode::Char('c') if ctrl => self.set_input(String::new()),
+            KeyCode::Char('u') if ctrl => {
+                self.input.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            KeyCode::Char('l') if ctrl => {
+                let mut scrollback = self.scrollback.lock();
+                scrollback.lines.clear();
+                scrollback.partial = None;
+                self.scroll = 0;
+            }
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.input.len(),
+            KeyCode::Char(c) if !ctrl => {
+                self.input.insert(self.cursor, c);
+                self.cursor += c.len_utf8();
+            }
+            KeyCode::Backspace => {
+                if let Some(c) = self.input[..self.cursor].chars().next_back() {
+                    self.cursor -= c.len_utf8();
+                    self.input.remove(self.cursor);
+                }
+            }
+            KeyCode::Delete if self.cursor < self.input.len() => {
+                self.input.remove(self.cursor);
+            }
+            KeyCode::Left => {
+                if let Some(c) = self.input[..self.cursor].chars().next_back() {
+                    self.cursor -= c.len_utf8();
+                }
+            }
+            KeyCode::Right => {
+                if let Some(c) = self.input[self.cursor..].chars().next() {
+                    self.cursor += c.len_utf8();
+                }
+            }
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.input.len(),
+            KeyCode::Up => self.browse_history(true),
+            KeyCode::Down => self.browse_history(false),
+            KeyCode::PageUp => {
+                let lines = self.scrollback.lock().iter().count();
+                self.scroll = (self.scroll + 10).min(lines.saturating_sub(1));
+            }
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(10),
+            _ => {}
+        }
+        !self.exited
+    }
+
+    /// Runs `line` as if it had been typed and Enter pressed. Returns
+    /// `false` if the REPL exited.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mycli::output::Output;
+    /// use mycli::repl::{CommandHandler, Context, Repl};
+    /// use mycli::tui::ReplWidget;
+    ///
+    /// struct Echo(Output);
+    /// impl CommandHandler for Echo {
+    ///     fn handle(&mut self, command: &str, _: &Context) -> bool {
+    ///         let _ = self.0.println(command);
+    ///         true
+    ///     }
+    /// }
+    ///
+    /// let mut widget = ReplWidget::new(|out| Repl::new("> ", Echo(out))).unwrap();
+    /// widget.submit_line("hello");
+    /// assert_eq!(widget.lines(), ["> hello", "hello"]);
+    /// ```
+    pub fn submit_line(&mut self, line: &str) -> bool {
+        self.set_input(line.to_string());
+        self.submit();
+        !self.exited
+    }
+
+    /// Returns whether the REPL has exited.
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    /// Returns the line being typed.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Returns the output shown above the input line, one string per line.
+    pub fn lines(&self) -> Vec<String> {
+        self.scrollback.lock().iter().map(|(line, _)| line.to_string()).collect()
+    }
+
+    /// Returns where the terminal cursor belongs when the widget is drawn
+    /// in `area`, for [`Frame::set_cursor_position`](ratatui::Frame::set_cursor_position).
+    pub fn cursor_position(&self, area: Rect) -> Position {
+        let (column, _) = self.input_columns(area.width);
+        Position::new(area.x + column, area.y + area.height.saturating_sub(1))
+    }
+
+    /// Returns the REPL running the commands.
+    pub fn repl(&self) -> &Repl<H> {
+        &self.repl
+    }
+
+    /// Returns the REPL running the commands, mutably.
+    pub fn repl_mut(&mut self) -> &mut Repl<H> {
+        &mut self.repl
+    }
+
+    fn set_input(&mut self, input: String) {
+        self.cursor = input.len();
+        self.input = input;
+        self.popup = None;
+    }
+
+    fn submit(&mut self) {
+        let line = std::mem::take(&mut self.input);
+        self.cursor = 0;
+        self.browsing = None;
+        self.scroll = 0;
+        self.scrollback.lock().push(format!("{}{line}", self.repl.prompt_text()), Kind::Echo);
+        let cmd = line.trim();
+        if cmd.is_empty() {
+            return;
+        }
+        if self.history.last().is_none_or(|last| last != cmd) {
+            self.history.push(cmd.to_string());
+        }
+        if !self.repl.execute(cmd) {
+            self.exited = true;
+        }
+    }
+
+    fn browse_history(&mut self, back: bool) {
+        let index = match (&self.browsing, back) {
+            (None, true) => self.history.len().checked_sub(1),
+            (None, false) => return,
+            (Some((index, _)), true) => Some(index.saturating_sub(1)),
+            (Some((index, _)), false) => Some(index + 1).filter(|&index| index < self.history.len()),
+        };
+        match index {
+            Some(index) => {
+                let typed = self.browsing.take().map_or_else(|| self.input.clone(), |(_, typed)| typed);
+                self.set_input(self.history[index].clone());
+                self.browsing = Some((index, typed));
+            }
+            None => {
+                if let Some((_, typed)) = self.browsing.take() {
+                    self.set_input(typed);
+                }
+            }
+        }
+    }
+
+    /// Returns where the word before the cursor starts.
+    fn word_start(&self) -> usize {
+        self.input[..self.cursor].rfind(char::is_whitespace).map_or(0, |i| i + 1)
+    }
+
+    fn complete(&mut self) {
+        let start = self.word_start();
+        let Some(completer) = &mut self.completer else { return };
+        let candidates = completer(&self.input[start..self.cursor]);
+        match candidates.len() {
+            0 => {}
+            1 => self.accept(&candidates[0]),
+            _ => self.popup = Some(Popup { candidates, selected: 0 }),
+        }
+    }
+
+    /// Replaces the word before the cursor with `word`.
+    fn accept(&mut self, word: &str) {
+        let start = self.word_start();
+        self.input.replace_range(start..self.cursor, word);
+        self.cursor = start + word.len();
+        self.popup = None;
+    }
+
+    /// Handles `key` while the completion popup is open. Returns whether
+    /// the key was used by the popup.
+    fn handle_popup_key(&mut self, key: KeyEvent) -> bool {
+        let Some(popup) = &mut self.popup else { return false };
+        let count = popup.candidates.len();
+        match key.code {
+            KeyCode::Tab | KeyCode::Down => popup.selected = (popup.selected + 1) % count,
+            KeyCode::BackTab | KeyCode::Up => popup.selected = (popup.selected + count - 1) % count,
+            KeyCode::Enter => {
+                let word = popup.candidates[popup.selected].clone();
+                self.accept(&word);
+            }
+            KeyCode::Esc => self.popup = None,
+            _ => {
+                // Any other key closes the popup and is handled as usual.
+                self.popup = None;
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns the column of the cursor within an input line `width`
+    /// columns wide, and how many columns of the line are scrolled out of
+    /// view to its left.
+    fn input_columns(&self, width: u16) -> (u16, u16) {
+        let before = display_width(&self.repl.prompt_text()) + display_width(&self.input[..self.cursor]);
+        let width = usize::from(width.max(1));
+        let hidden = (before + 1).saturating_sub(width);
+        (to_u16(before - hidden), to_u16(hidden))
+    }
+}
+
+impl<H: CommandHandler> Widget for &ReplWidget<H> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height == 0 || area.width == 0 {
+            return;
+        }
+        let output_area = Rect { height: area.height - 1, ..area };
+        let input_area = Rect { y: area.y + area.height - 1, height: 1, ..area };
+
+        let scrollback = self.scrollback.lock();
+        let rows = usize::from(output_area.height);
+        let end = scrollback.iter().count().saturating_sub(self.scroll);
+        let lines: Vec<Line> = scrollback
+            .iter()
+            .take(end)
+            .skip(end.saturating_sub(rows))
+            .map(|(line, kind)| Line::styled(line.to_string(), kind.style()))
+            .collect();
+        drop(scrollback);
+        // Short output sits right above the input line, like in a terminal.
+        let top = output_area.y + output_area.height - to_u16(lines.len());
+        Paragraph::new(lines).render(Rect { y: top, height: output_area.bottom() - top, ..output_area }, buf);
+
+        let (_, hidden) = self.input_columns(input_area.width);
+        let line = Line::from(vec![
+            Span::styled(self.repl.prompt_text(), theme::current().style(Tone::Accent)),
+            Span::raw(self.input.as_str()),
+        ]);
+        Paragraph::new(line).scroll((0, hidden)).render(input_area, buf);
+
+        if let Some(popup) = &self.popup {
+            let column = display_width(&self.repl.prompt_text()) + display_width(&self.input[..self.word_start()]);
+            let column = to_u16(column).saturating_sub(hidden).saturating_sub(1);
+            let longest = popup.candidates.iter().map(|c| display_width(c)).max().unwrap_or(0);
+            let width = to_u16(longest + 2).min(area.width);
+            let height = to_u16(popup.candidates.len().min(POPUP_ROWS) + 2).min(output_area.height);
+            let x = (area.x + column).min(area.right().saturating_sub(width));
+            let rect = Rect { x, y: input_area.y - height, width, height };
+            Clear.render(rect, buf);
+            let list = List::new(popup.candidates.iter().map(String::as_str))
+                .block(Block::bordered())
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+            let mut state = ListState::default().with_selected(Some(popup.selected));
+            StatefulWidget::render(list, rect, buf, &mut state);
+        }
+    }
+}
+
+fn to_u16(n: usize) -> u16 {
+    u16::try_from(n).unwrap_or(u16::MAX)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Echo,
+    Output,
+    Error,
+}
+
+impl Kind {
+    fn style(self) -> Style {
+        match self {
+            Kind::Echo => theme::current().style(Tone::Accent).into(),
+            Kind::Output => Style::new(),
+            Kind::Error => theme::current().style(Tone::Error).into(),
+        }
+    }
+}
+
+struct Lines {
+    lines: VecDeque<(String, Kind)>,
+    // The last line, until its newline is written.
+    partial: Option<(String, Kind)>,
+    max: usize,
+}
+
+impl Lines {
+    /// Returns the lines, including the last one if it is incomplete.
+    fn iter(&self) -> impl Iterator<Item = (&str, Kind)> {
+        self.lines.iter().chain(&self.partial).map(|(line, kind)| (line.as_str(), *kind))
+    }
+
+    fn push(&mut self, line: String, kind: Kind) {
+        if let Some(partial) = self.partial.take() {
+            self.lines.push_back(partial);
+        }
+        self.lines.push_back((line, kind));
+        while self.lines.len() > self.max {
+            self.lines.pop_front();
+        }
+    }
+
+    fn write(&mut self, s: &str, kind: Kind) {
+        let mut rest = s;
+        while let Some((line, after)) = rest.split_once('\n') {
+            let mut text = match self.partial.take() {
+                Some((partial, _)) => partial,
+                None => String::new(),
+            };
+            text.push_str(line.strip_suffix('\r').unwrap_or(line));
+            self.push(text, kind);
+            rest = after;
+        }
+        if !rest.is_empty() {
+            self.partial.get_or_insert_with(|| (String::new(), kind)).0.push_str(rest);
+        }
+    }
+}
+
+/// The sink of the widget's output.
+#[derive(Clone)]
+struct Scrollback(Arc<Mutex<Lines>>);
+
+impl Default for Scrollback {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(Lines { lines: VecDeque::new(), partial: None, max: 10_000 })))
+    }
+}
+
+impl Scrollback {
+    fn lock(&self) -> MutexGuard<'_, Lines> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl OutputSink for Scrollback {
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        self.lock().write(s, Kind::Output);
+        Ok(())
+    }
+
+    fn write_err(&mut self, s: &str) -> io::Result<()> {
+        self.lock().write(s, Kind::Error);
+        Ok(())
+    }
+}
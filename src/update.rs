@@ -0,0 +1,359 @@
+//! Checking for newer releases of the application and installing them.
+//!
+//! An [`Updater`] asks a [`ReleaseSource`] for the latest release, either
+//! the latest GitHub release of a repository or a JSON document at a URL
+//! of your own:
+//!
+//! ```text
+//! {"version": "1.4.0", "url": "https://example.com/myapp-1.4.0", "signature": "https://example.com/myapp-1.4.0.minisig"}
+//! ```
+//!
+//! For GitHub releases, the binary is the asset whose name contains the
+//! operating system and architecture, such as `myapp-linux-x86_64`, or the
+//! one given to [`Updater::with_asset`], and its signature is the asset of
+//! the same name with `.minisig` added.
+//!
+//! Binaries are only installed with a valid
+//! [minisign](https://jedisct1.github.io/minisign/) signature from one of
+//! the keys given to [`Updater::with_key`], so a compromised download
+//! server cannot ship its own binary. The signature's trusted comment must
+//! also name the release's version, which must be newer than the running
+//! one, so the server cannot pass off an older signed binary as a newer
+//! release either:
+//!
+//! ```text
+//! minisign -S -m myapp-linux-x86_64 -t "version:1.4.0"
+//! ```
+//!
+//! The crate has no HTTP client of its own: the updater downloads through
+//! the function it is given, so applications keep the client, proxy
+//! settings and certificates they already use.
+//!
+//! With [`Repl::with_updater`](crate::repl::Repl::with_updater), the REPL
+//! reports newer releases when it starts and the `update` built-in
+//! installs them.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use mycli::dirs::AppDirs;
+//! use mycli::repl::ScriptKey;
+//! use mycli::update::{ReleaseSource, Updater};
+//!
+//! fn download(url: &str) -> std::io::Result<Vec<u8>> {
+//!     // With the application's HTTP client.
+//! #   unimplemented!()
+//! }
+//!
+//! let key: ScriptKey = "RWRaFzyeIUQL2OpKbGPinFIKvvVQexMuxfmVR3auvr57kkIe6mkURtIs".parse()?;
+//! let updater = Updater::new(env!("CARGO_PKG_VERSION"), ReleaseSource::GitHub("naromori/myapp".into()), download)
+//!     .with_key(key)
+//!     .with_check_interval(AppDirs::new("myapp").state_file("update-check")?, Duration::from_secs(24 * 60 * 60));
+//!
+//! if let Some(release) = updater.check()? {
+//!     println!("installing {}", release.version);
+//!     updater.install(&release)?;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+
+use crate::repl::signing;
+use crate::repl::{ScriptKey, SignatureError};
+
+/// Where an [`Updater`] looks for the latest release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReleaseSource {
+    /// The latest release of a GitHub repository, written `owner/name`.
+    GitHub(String),
+    /// A URL answering with a JSON object holding the `version`, and the
+    /// `url` and `signature` URLs of the binary.
+    Url(String),
+}
+
+impl ReleaseSource {
+    fn url(&self) -> String {
+        match self {
+            ReleaseSource::GitHub(repo) => format!("https://api.github.com/repos/{repo}/releases/latest"),
+            ReleaseSource::Url(url) => url.clone(),
+        }
+    }
+}
+
+/// A release newer than the running version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    /// The version, without a leading `v`.
+    pub version: String,
+    /// Where to download the binary.
+    pub url: String,
+    /// Where to download the binary's minisign signature.
+    pub signature_url: String,
+}
+
+type Fetch = dyn Fn(&str) -> io::Result<Vec<u8>> + Send + Sync;
+
+/// Checks for and installs newer releases of the application.
+///
+/// See the [module documentation](self) for where releases come from.
+/// Clones share the download function.
+#[derive(Clone)]
+pub struct Updater {
+    current: String,
+    source: ReleaseSource,
+    fetch: Arc<Fetch>,
+    keys: Vec<ScriptKey>,
+    asset: Option<String>,
+    check_file: Option<(PathBuf, Duration)>,
+}
+
+impl fmt::Debug for Updater {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Updater").field("current", &self.current).field("source", &self.source).finish_non_exhaustive()
+    }
+}
+
+impl Updater {
+    /// Creates an updater for the running version `current`, usually
+    /// `env!("CARGO_PKG_VERSION")`, downloading with `fetch`.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The version running now
+    /// * `source` - Where to look for the latest release
+    /// * `fetch` - Returns the body of a successful GET request to a URL
+    pub fn new(current: impl Into<String>, source: ReleaseSource, fetch: impl Fn(&str) -> io::Result<Vec<u8>> + Send + Sync + 'static) -> Self {
+        Self { current: current.into(), source, fetch: Arc::new(fetch), keys: Vec::new(), asset: None, check_file: None }
+    }
+
+    /// Allows binaries signed with `key`. Nothing is installed until at
+    /// least one key is given.
+    pub fn with_key(mut self, key: ScriptKey) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Downloads the GitHub release asset named `name` instead of the one
+    /// named after the operating system and architecture.
+    pub fn with_asset(mut self, name: impl Into<String>) -> Self {
+        self.asset = Some(name.into());
+        self
+    }
+
+    /// Limits [`check_due`](Self::check_due) to one check per `interval`,
+    /// remembering the time of the last one in `file`.
+    pub fn with_check_interval(mut self, file: impl Into<PathBuf>, interval: Duration) -> Self {
+        self.check_file = Some((file.into(), interval));
+        self
+    }
+
+    /// Returns the version running now.
+    pub fn current_version(&self) -> &str {
+        &self.current
+    }
+
+    /// Returns the latest release if it is newer than the running version.
+    pub fn check(&self) -> Result<Option<Release>, UpdateError> {
+        let body = (self.fetch)(&self.source.url()).map_err(UpdateError::Fetch)?;
+        let json: Value = serde_json::from_slice(&body).map_err(|err| UpdateError::Malformed(err.to_string()))?;
+        let field = |name: &str| json.get(name).and_then(Value::as_str).ok_or_else(|| UpdateError::Malformed(format!("no `{name}` in the release")));
+        let version = match &self.source {
+            ReleaseSource::GitHub(_) => field("tag_name")?.trim_start_matches('v'),
+            ReleaseSource::Url(_) => field("version")?,
+        };
+        if compare_versions(version, &self.current) != Ordering::Greater {
+            return Ok(None);
+        }
+        let (url, signature_url) = match &self.source {
+            ReleaseSource::GitHub(_) => self.github_asset(&json)?,
+            ReleaseSource::Url(_) => (field("url")?.to_string(), field("signature")?.to_string()),
+        };
+        Ok(Some(Release { version: version.to_string(), url, signature_url }))
+    }
+
+    /// Checks like [`check`](Self::check), unless the last check was less
+    /// than the interval given to
+    /// [`with_check_interval`](Self::with_check_interval) ago, when it
+    /// returns `None` without a request.
+    pub fn check_due(&self) -> Result<Option<Release>, UpdateError> {
+        let Some((file, interval)) = &self.check_file else {
+            return self.check();
+        };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let last = fs::read_to_string(file).ok().and_then(|text| text.trim().parse::<u64>().ok());
+        if last.is_some_and(|last| now.saturating_sub(last) < interval.as_secs()) {
+            return Ok(None);
+        }
+        if let Some(dir) = file.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(file, now.to_string());
+        self.check()
+    }
+
+    /// Downloads `release`, checks its signature and replaces the running
+    /// binary with it. The new version runs from the next start. Returns
+    /// the path of the replaced binary.
+    pub fn install(&self, release: &Release) -> Result<PathBuf, UpdateError> {
+        if self.keys.is_empty() {
+            return Err(UpdateError::NoKey);
+        }
+        let binary = (self.fetch)(&release.url).map_err(UpdateError::Fetch)?;
+        let signature = (self.fetch)(&release.signature_url).map_err(UpdateError::Fetch)?;
+        let signature = String::from_utf8(signature).map_err(|_| UpdateError::Signature(SignatureError::Malformed))?;
+        let comment = signing::verify(&self.keys, &binary, &signature).map_err(UpdateError::Signature)?;
+        // The release information is not signed, so only the version in the
+        // signature's trusted comment can rule out an older binary.
+        let signed = signed_version(comment).unwrap_or_default();
+        if signed.is_empty() || compare_versions(signed, &release.version) != Ordering::Equal || compare_versions(signed, &self.current) != Ordering::Greater {
+            return Err(UpdateError::Version(signed.to_string()));
+        }
+
+        let path = std::env::current_exe().map_err(UpdateError::Install)?;
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let new = path.with_file_name(format!(".{name}.new"));
+        let written = fs::write(&new, &binary)
+            .and_then(|()| fs::metadata(&path))
+            .and_then(|metadata| fs::set_permissions(&new, metadata.permissions()))
+            .and_then(|()| replace(&new, &path));
+        if let Err(err) = written {
+            let _ = fs::remove_file(&new);
+            return Err(UpdateError::Install(err));
+        }
+        Ok(path)
+    }
+
+    /// Returns the URLs of the binary and its signature among the assets
+    /// of a GitHub release.
+    fn github_asset(&self, json: &Value) -> Result<(String, String), UpdateError> {
+        let assets: Vec<(&str, &str)> = json
+            .get("assets")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|asset| Some((asset.get("name")?.as_str()?, asset.get("browser_download_url")?.as_str()?)))
+            .collect();
+        let wanted = |name: &str| match &self.asset {
+            Some(asset) => name == asset,
+            None => name.contains(std::env::consts::OS) && name.contains(std::env::consts::ARCH) && !name.ends_with(".minisig"),
+        };
+        let (name, url) = assets.iter().find(|(name, _)| wanted(name)).ok_or(UpdateError::NoAsset)?;
+        let signature = format!("{name}.minisig");
+        let (_, signature_url) = assets.iter().find(|(name, _)| *name == signature).ok_or(UpdateError::Signature(SignatureError::Malformed))?;
+        Ok((url.to_string(), signature_url.to_string()))
+    }
+}
+
+/// Returns the version named by a `version:` field of a minisign trusted
+/// comment, whose fields are separated by tabs.
+fn signed_version(comment: &str) -> Option<&str> {
+    comment.split('\t').find_map(|field| field.trim().strip_prefix("version:")).map(str::trim)
+}
+
+/// Moves `new` over `path`. Windows cannot replace a running binary, but
+/// can rename it out of the way first.
+fn replace(new: &Path, path: &Path) -> io::Result<()> {
+    #[cfg(windows)]
+    {
+        let old = path.with_extension("old");
+        let _ = fs::remove_file(&old);
+        fs::rename(path, &old)?;
+    }
+    fs::rename(new, path)
+}
+
+/// Compares two versions such as `1.10.0` and `v1.9.2`, number by number.
+/// A pre-release such as `2.0.0-beta.1` comes before the release itself.
+///
+/// # Examples
+///
+/// ```
+/// use std::cmp::Ordering;
+/// use mycli::update::compare_versions;
+///
+/// assert_eq!(compare_versions("1.10.0", "v1.9.2"), Ordering::Greater);
+/// assert_eq!(compare_versions("2.0.0-beta.1", "2.0.0"), Ordering::Less);
+/// assert_eq!(compare_versions("1.2", "1.2.0"), Ordering::Equal);
+/// ```
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn parse(version: &str) -> (Vec<u64>, Option<&str>) {
+        let version = version.trim().trim_start_matches('v');
+        let version = version.split_once('+').map_or(version, |(version, _)| version);
+        let (numbers, pre) = match version.split_once('-') {
+            Some((numbers, pre)) => (numbers, Some(pre)),
+            None => (version, None),
+        };
+        let mut numbers: Vec<u64> = numbers.split('.').map(|part| part.parse().unwrap_or(0)).collect();
+        while numbers.last() == Some(&0) {
+            numbers.pop();
+        }
+        (numbers, pre)
+    }
+    let (a_numbers, a_pre) = parse(a);
+    let (b_numbers, b_pre) = parse(b);
+    a_numbers.cmp(&b_numbers).then_with(|| match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => a.cmp(b),
+    })
+}
+
+/// Why checking for or installing a release failed.
+#[derive(Debug)]
+pub enum UpdateError {
+    /// The release source or a download could not be reached.
+    Fetch(io::Error),
+    /// The release source answered with something other than a release.
+    Malformed(String),
+    /// The GitHub release has no binary for this operating system and
+    /// architecture.
+    NoAsset,
+    /// No key was given with [`Updater::with_key`], so nothing may be
+    /// installed.
+    NoKey,
+    /// The binary's signature is missing or does not match.
+    Signature(SignatureError),
+    /// The signature's trusted comment does not name the release's
+    /// version, or names one that is not newer than the running version,
+    /// as an older binary served as a newer one would. Holds the version
+    /// it names, empty if none.
+    Version(String),
+    /// The new binary could not be written in place of the running one.
+    Install(io::Error),
+}
+
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateError::Fetch(err) => write!(f, "cannot download the release: {err}"),
+            UpdateError::Malformed(message) => write!(f, "unexpected release information: {message}"),
+            UpdateError::NoAsset => write!(f, "the release has no binary for {}-{}", std::env::consts::OS, std::env::consts::ARCH),
+            UpdateError::NoKey => f.write_str("no key to check the release's signature with"),
+            UpdateError::Signature(err) => write!(f, "the release's signature was not accepted: {err}"),
+            UpdateError::Version(signed) if signed.is_empty() => f.write_str("the release's signature does not name its version"),
+            UpdateError::Version(signed) => write!(f, "the release's signature is for version {signed}, not a newer one"),
+            UpdateError::Install(err) => write!(f, "cannot replace the binary: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for UpdateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UpdateError::Fetch(err) | UpdateError::Install(err) => Some(err),
+            UpdateError::Signature(err) => Some(err),
+            _ => None,
+        }
+    }
+}